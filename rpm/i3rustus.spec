@@ -21,7 +21,7 @@ Lightweight implementation of i3status in rust
 %files
 %license LICENSE
 %license LICENSE.dependencies
-%doc README.md
+%doc README.md examples
 %{_bindir}/i3rustus
 
 %prep
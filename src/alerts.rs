@@ -0,0 +1,90 @@
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::config::{AlertSink, WidgetId};
+use crate::i3_status::CONFIG;
+use crate::utils::secrets::resolve_secret;
+
+// Requests to slow servers should not pile up
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// Gotify priorities go from 0 to 10, everything above 7 is shown as urgent
+const GOTIFY_PRIORITY: u8 = 8;
+
+fn agent() -> ureq::Agent {
+    ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build()
+}
+
+fn send(sink: &AlertSink, id: &WidgetId, message: &str) -> Result<(), String> {
+    match sink {
+        AlertSink::Command(command) => {
+            let status = Command::new("sh")
+                .args(["-c", command])
+                .env("I3RUSTUS_ALERT", message)
+                .status()
+                .map_err(|error| error.to_string())?;
+            if !status.success() {
+                return Err(format!("\"{}\" failed with {}", command, status));
+            }
+        }
+        AlertSink::Webhook(url) => {
+            let body = json!({
+                "widget": &*id.name,
+                "instance": id.instance.as_deref(),
+                "message": message,
+            });
+            agent()
+                .post(url)
+                .set("Content-Type", "application/json")
+                .send_string(&body.to_string())
+                .map_err(|error| error.to_string())?;
+        }
+        AlertSink::Ntfy(url) => {
+            agent()
+                .post(url)
+                .set("Title", "i3rustus")
+                .set("Priority", "urgent")
+                .set("Tags", "warning")
+                .send_string(message)
+                .map_err(|error| error.to_string())?;
+        }
+        AlertSink::Gotify { url, token } => {
+            let body = json!({
+                "title": "i3rustus",
+                "message": message,
+                "priority": GOTIFY_PRIORITY,
+            });
+            agent()
+                .post(&format!("{}/message", url.trim_end_matches('/')))
+                .set(
+                    "X-Gotify-Key",
+                    &resolve_secret(token).map_err(|error| error.to_string())?,
+                )
+                .set("Content-Type", "application/json")
+                .send_string(&body.to_string())
+                .map_err(|error| error.to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+// Send an alert to all configured sinks
+// Sinks may be slow or unreachable, so this does not block the widget
+pub fn send_alert(id: &WidgetId, message: String) {
+    let sinks = CONFIG.alert_sinks();
+    if sinks.is_empty() {
+        return;
+    }
+
+    let id = id.clone();
+    thread::spawn(move || {
+        for sink in &sinks {
+            if let Err(error) = send(sink, &id, &message) {
+                log::error!("Could not send alert for {}: {}", id, error);
+            }
+        }
+    });
+}
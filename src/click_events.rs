@@ -0,0 +1,75 @@
+use serde::Deserialize;
+use std::io::{stdin, BufRead};
+use std::thread;
+
+use crate::config::WidgetId;
+use crate::i3_status::{I3Status, WidgetExecutors, GROUP};
+use crate::signals::request_refresh;
+use crate::widget_executor::ClickWidget;
+
+pub const BUTTON_LEFT: u8 = 1;
+pub const BUTTON_MIDDLE: u8 = 2;
+pub const BUTTON_RIGHT: u8 = 3;
+pub const SCROLL_UP: u8 = 4;
+pub const SCROLL_DOWN: u8 = 5;
+
+// A click event as it is sent by i3bar
+// See https://i3wm.org/docs/i3bar-protocol.html#_click_events
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClickEvent {
+    // Name of the widget that was clicked
+    pub name: String,
+    // Instance of the widget that was clicked, only set for widgets with an instance
+    #[serde(default)]
+    pub instance: Option<String>,
+    // X11 button ID (1 = left, 2 = middle, 3 = right, 4 / 5 = scroll up / down)
+    pub button: u8,
+    // Modifiers that were pressed during the click (e.g. "Shift", "Mod4")
+    #[serde(default)]
+    pub modifiers: Vec<String>,
+}
+
+// i3bar sends an endless JSON array, where every click event is on its own line
+// The first line only contains the opening bracket and every following event
+// is prefixed with a comma
+fn parse_click_event(line: &str) -> Option<ClickEvent> {
+    let line = line.trim().trim_start_matches(',');
+    if line.is_empty() || line == "[" {
+        return None;
+    }
+
+    match serde_json::from_str(line) {
+        Ok(event) => Some(event),
+        Err(error) => {
+            log::warn!("Could not parse click event \"{}\": {}", line, error);
+            None
+        }
+    }
+}
+
+// Read click events from stdin and forward them to the executor of the clicked widget
+// Reading stdin is blocking, so this runs in a dedicated thread
+pub fn listen_for_click_events(widget_executors: WidgetExecutors) {
+    thread::spawn(move || {
+        for line in stdin().lock().lines().map_while(Result::ok) {
+            if let Some(event) = parse_click_event(&line) {
+                let id = WidgetId::new(&event.name, event.instance.as_deref());
+                // Clone the address, so the config can be reloaded while the click is handled
+                let executor = widget_executors.read().unwrap().get(&id).cloned();
+                match executor {
+                    // Groups have no executor, clicking them expands or collapses them
+                    None if &*id.name == GROUP => {
+                        I3Status::toggle_group(&id);
+                        request_refresh();
+                    }
+                    Some(executor) => {
+                        executor.do_send(ClickWidget(event));
+                        // Show the result of the click right away
+                        request_refresh();
+                    }
+                    None => log::warn!("Received click event for unknown widget {}", id),
+                }
+            }
+        }
+    });
+}
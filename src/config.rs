@@ -1,8 +1,863 @@
-pub(crate) const GREEN: &str = "#08FF00";
-pub(crate) const RED: &str = "#FF0000";
-pub(crate) const YELLOW_WARNING: &str = "#FED037";
-pub(crate) const YELLOW: &str = "#E5DE00";
-pub(crate) const NEUTRAL: &str = "#FFFFFF";
+use crate::i3_status::I3Status;
+use crate::icons::IconSet;
+use crate::sandbox::sandboxed_widgets;
+use crate::theme::{Color, Palette, Theme};
+use crate::utils::intern::intern;
+use crate::utils::suggest::{closest, suggest_for_serde_error};
+use chrono::{NaiveTime, Timelike, Weekday};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::env;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::fs::read_to_string;
+use std::io::ErrorKind;
+use std::mem::replace;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+// Settings of the config file, None until it was loaded or if there is no config file
+// A reload swaps in new settings, callers that still hold the previous ones keep them alive
+// until they are done, so getters return owned values instead of references into them
+static FILE_SETTINGS: RwLock<Option<Arc<FileSettings>>> = RwLock::new(None);
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Markup {
+    // full_text is interpreted as pango markup
+    // Without markup, full_text is shown as it is
+    Pango,
+}
+
+// i3bar accepts either a width in pixels or a text whose width is used
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum MinWidth {
+    Pixels(u32),
+    Text(String),
+}
+
+// Alignment of the text if it is shorter than min_width
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+// Options that are added to the block of a widget when it is printed
+#[derive(Default, Serialize)]
+pub struct BlockConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub markup: Option<Markup>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_width: Option<MinWidth>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub align: Option<Align>,
+    // Whether a separator line is drawn after the block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub separator: Option<bool>,
+    // Gap in pixels between this block and the next one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub separator_block_width: Option<u32>,
+    // Background color of the block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<String>,
+    // Border color of the block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub border: Option<String>,
+    // Border widths in pixels, i3bar defaults to 1 if a border color is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub border_top: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub border_bottom: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub border_left: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub border_right: Option<u32>,
+    // Grow min_width to the widest text the block had so far, so the blocks next to it
+    // stay in place even with a proportional font. The width is estimated from bar_font_size
+    #[serde(skip_serializing)]
+    pub stable_width: bool,
+    // Leave the widget out of the bar when it returns an error, instead of showing "<name>: n/a"
+    #[serde(skip_serializing)]
+    pub hide_on_error: bool,
+    // Leave the widget out of the bar when it has nothing to show
+    // (e.g. no text or a network device that is down)
+    #[serde(skip_serializing)]
+    pub hide_when_empty: bool,
+}
+
+// A limit for the value of a widget, e.g. { above = 99 }
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Threshold {
+    // Crossed when the value drops below the limit
+    Below(f32),
+    // Crossed when the value rises above the limit
+    Above(f32),
+}
+
+impl Threshold {
+    pub fn is_crossed(&self, value: f32) -> bool {
+        match self {
+            Threshold::Below(limit) => value < *limit,
+            Threshold::Above(limit) => value > *limit,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleAction {
+    // The widget is not shown at all
+    Hide,
+    // The short_text of the widget is shown instead of full_text
+    Minimal,
+}
+
+// Time window in which some widgets are hidden or shown in their minimal form
+pub struct Schedule {
+    pub widgets: Vec<String>,
+    // Start and end of the time window
+    // If the end is before the start, the window spans midnight
+    pub from: NaiveTime,
+    pub to: NaiveTime,
+    pub action: ScheduleAction,
+}
+
+impl Schedule {
+    pub fn is_active(&self, now: NaiveTime) -> bool {
+        if self.from <= self.to {
+            self.from <= now && now < self.to
+        } else {
+            now >= self.from || now < self.to
+        }
+    }
+}
+
+// Wall clock time at which the alarm widget rings
+#[derive(Clone)]
+pub struct Alarm {
+    // Shown while the alarm rings, and passed to the alarm command as ALARM_LABEL
+    pub label: String,
+    // (hour, minute) in local time
+    pub time: (u32, u32),
+    // Days on which the alarm rings, bit 0 is Monday and bit 6 is Sunday
+    pub weekdays: u8,
+}
+
+impl Alarm {
+    pub fn rings_on(&self, weekday: Weekday) -> bool {
+        self.weekdays & (1 << weekday.num_days_from_monday()) != 0
+    }
+}
+
+// What a click action does with a variable, e.g. { toggle = "on_call" } or
+// { set = ["focus", "deep"] }
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum VariableAction {
+    // Switch the variable between "true" and "false"
+    Toggle(String),
+    // Set the variable to a fixed value
+    Set(String, String),
+}
+
+// Changes a variable when a widget is clicked with the given button
+// e.g. { widget = "time", button = 2, action = { toggle = "on_call" } }
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClickAction {
+    // "name" for every instance of a widget or "name:instance"
+    pub widget: String,
+    // Button of i3bar, 1 is left, 2 is middle and 3 is right
+    pub button: u8,
+    pub action: VariableAction,
+}
+
+// Recolors widgets while a variable has a certain value
+// e.g. { variable = "on_call", value = "true", widgets = ["time", "network"], color = "warning" }
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VariableColor {
+    pub variable: String,
+    pub value: String,
+    // "name" for every instance of a widget or "name:instance"
+    pub widgets: Vec<String>,
+    pub color: Color,
+}
+
+// Text color that follows the value of a widget instead of its own color
+// The colors are spread evenly between min and max, values in between are interpolated
+#[derive(Clone)]
+pub struct ColorGradient {
+    pub min: f32,
+    pub max: f32,
+    // Hex values, e.g. ["#00FF00", "#FFFF00", "#FF0000"]
+    pub colors: Vec<String>,
+}
+
+// Where an alert is sent when a widget crosses its urgent threshold
+// e.g. { ntfy = "https://ntfy.sh/my-topic" } in alert_sinks
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum AlertSink {
+    // Shell command, the alert message is passed in $I3RUSTUS_ALERT
+    Command(String),
+    // URL that receives the alert as JSON in a POST request
+    Webhook(String),
+    // ntfy topic URL, e.g. "https://ntfy.sh/my-topic"
+    Ntfy(String),
+    // Gotify server URL and application token, resolved with utils::secrets
+    // e.g. { gotify = { url = "https://gotify.example.com", token = "env:GOTIFY_TOKEN" } }
+    Gotify { url: String, token: String },
+}
+
+impl AlertSink {
+    fn check(&self) -> Result<(), String> {
+        match self {
+            AlertSink::Command(_) => Ok(()),
+            AlertSink::Webhook(url) | AlertSink::Ntfy(url) | AlertSink::Gotify { url, .. } => {
+                if url.starts_with("http://") || url.starts_with("https://") {
+                    Ok(())
+                } else {
+                    Err(format!("Invalid URL {}, expected http:// or https://", url))
+                }
+            }
+        }
+    }
+}
+
+// Sound that is played once when a widget crosses the threshold
+// The sound is played again only after the value recovered in between
+// e.g. { widget = "battery:BAT1", threshold = { above = 99 }, file = "/usr/share/sounds/alsa/Front_Center.wav" }
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SoundAlert {
+    // "name" or "name:instance", only that exact widget plays the sound
+    pub widget: String,
+    pub threshold: Threshold,
+    // Path to a PCM encoded WAV file
+    pub file: String,
+}
+
+// A value that is collected on a remote host
+// e.g. metrics = ["load", { disk = "/" }, { temperature = "thermal_zone0" }]
+#[derive(Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RemoteMetric {
+    // One minute load average
+    Load,
+    // Used space of the file system that contains the path
+    Disk(String),
+    // Temperature of a thermal zone, e.g. "thermal_zone0"
+    Temperature(String),
+}
+
+// A host that is monitored over SSH
+pub struct RemoteHost {
+    // Host as it is passed to ssh, so aliases from ~/.ssh/config work
+    pub host: String,
+    pub metrics: Vec<RemoteMetric>,
+}
+
+// A value that is polled from a device over SNMP, e.g.
+// { label = "WAN ↓", oid = "1.3.6.1.2.1.31.1.1.1.6.2", scale = 0.000008, unit = " Mbit/s", counter = true }
+#[derive(Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SnmpOid {
+    pub label: String,
+    pub oid: String,
+    // Factor the value is multiplied with, e.g. 0.000008 to get Mbit from bytes
+    #[serde(default = "SnmpOid::default_scale")]
+    pub scale: f64,
+    #[serde(default)]
+    pub unit: String,
+    // Counters only grow, so the change per second is shown instead of the value
+    #[serde(default)]
+    pub counter: bool,
+}
+
+impl SnmpOid {
+    fn default_scale() -> f64 {
+        1.0
+    }
+}
+
+// A device that is polled over SNMP v2c
+pub struct SnmpTarget {
+    // Address including the port, e.g. "192.168.1.1:161"
+    pub address: String,
+    pub community: String,
+    pub oids: Vec<SnmpOid>,
+}
+
+// A topic of a MQTT broker that is shown in the bar, e.g.
+// { topic = "zigbee2mqtt/living_room", path = "$.temperature", format = "🌡 {value:.1}°C" }
+#[derive(Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct MqttTopic {
+    // Topic filter, may contain the wildcards "+" and "#"
+    pub topic: String,
+    // JSONPath of the value in JSON payloads, e.g. "$.temperature"
+    // The whole payload is used if not set
+    pub path: Option<String>,
+    // Template with the placeholder {value}, e.g. "{value:.1}°C"
+    #[serde(default = "MqttTopic::default_format")]
+    pub format: String,
+}
+
+impl MqttTopic {
+    fn default_format() -> String {
+        String::from("{value}")
+    }
+}
+
+pub struct MqttBroker {
+    // Address including the port, e.g. "localhost:1883"
+    pub address: String,
+    // Username and password, the password is resolved with utils::secrets
+    pub credentials: Option<(String, String)>,
+    pub topics: Vec<MqttTopic>,
+}
+
+// An entity of Home Assistant that is shown in the bar
+pub struct HomeAssistantEntity {
+    // e.g. "climate.living_room" or "switch.coffee_machine"
+    pub entity_id: String,
+    // The instance name if not set
+    pub label: String,
+    // Toggle the entity with a left click (e.g. switches and lights)
+    pub toggle: bool,
+}
+
+// A value computed from the values of other widgets, see utils::expression
+pub struct DerivedExpression {
+    // "derived" or the instance name if not set
+    pub label: String,
+    // e.g. "memory.value > 80 && cpu_percentage.value > 50"
+    pub expression: String,
+}
+
+// API of the software that controls a 3D printer
+pub enum PrinterApi {
+    // API key, resolved with utils::secrets (e.g. "env:OCTOPRINT_API_KEY")
+    OctoPrint { api_key: String },
+    // Moonraker is the API server of Klipper
+    Moonraker,
+}
+
+pub struct Printer {
+    // Base URL, e.g. "http://octopi.local"
+    pub url: String,
+    pub api: PrinterApi,
+}
+
+// API of the DNS server that blocks ads
+#[derive(Clone)]
+pub enum AdBlockerApi {
+    // Password of the web interface (Pi-hole v6), empty if none is set
+    PiHole { password: String },
+    AdGuardHome { username: String, password: String },
+}
+
+// Passwords are resolved with utils::secrets (e.g. "env:PIHOLE_PASSWORD")
+pub struct AdBlockerServer {
+    // Base URL, e.g. "http://pi.hole"
+    pub url: String,
+    pub api: AdBlockerApi,
+}
+
+// A WebDAV account with a storage quota (e.g. Nextcloud)
+pub struct WebDavAccount {
+    // WebDAV URL of the user's files,
+    // e.g. "https://cloud.example.com/remote.php/dav/files/alice/" for Nextcloud
+    pub url: String,
+    pub username: String,
+    // Resolved with utils::secrets (e.g. "env:NEXTCLOUD_APP_PASSWORD")
+    pub password: String,
+}
+
+// Where the transfer widget gets the progress of a job from
+pub enum TransferSource {
+    // File that receives the output of "rsync --info=progress2"
+    // e.g. rsync -a --info=progress2 src/ dst/ > /tmp/rsync-progress.log
+    ProgressFile(String),
+    // Remote control API of rclone, started with --rc (e.g. "http://localhost:5572")
+    // Username and password are only needed if --rc-user and --rc-pass are set,
+    // the password is resolved with utils::secrets
+    Rclone {
+        url: String,
+        credentials: Option<(String, String)>,
+    },
+}
+
+// How the task widget recognizes that a long running task is running
+pub enum TaskMarker {
+    // File that contains the PID of the task
+    PidFile(String),
+    // Part of the command line of a process, e.g. "cargo build"
+    Process(String),
+    // Variable that is "true" while the task runs, e.g. set by a click action
+    Variable(String),
+}
+
+// Zone of a time instance
+pub enum TimeZoneSetting {
+    // Seconds east of UTC, the same all year
+    Offset(i32),
+    // Name of a zoneinfo file, e.g. "Europe/Berlin", which knows about daylight saving time
+    Name(String),
+}
+
+// Shell command of a script instance, see widgets/script.rs for its output
+pub struct ScriptCommand {
+    pub command: String,
+    // Seconds between two runs, 0 runs the command only at the start and after clicks
+    pub interval: u64,
+    // Whether the command prints a JSON object instead of lines
+    pub json: bool,
+}
+
+// Identifies a widget in the bar
+// The instance is only needed if the same widget is shown more than once (e.g. two disks)
+// Names and instances are interned, so cloning an id (e.g. for the blocks of every update)
+// does not allocate
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WidgetId {
+    pub name: Arc<str>,
+    pub instance: Option<Arc<str>>,
+}
+
+impl WidgetId {
+    pub fn new(name: &str, instance: Option<&str>) -> Self {
+        Self {
+            name: intern(name),
+            instance: instance.map(intern),
+        }
+    }
+
+    // Widgets are declared as "name" or "name:instance"
+    pub fn parse(widget: &str) -> Self {
+        match widget.split_once(':') {
+            Some((name, instance)) => Self::new(name, Some(instance)),
+            None => Self::new(widget, None),
+        }
+    }
+
+    // Name of a variable that belongs to this widget (e.g. "disk.home.value")
+    pub fn variable_name(&self, field: &str) -> String {
+        match &self.instance {
+            Some(instance) => format!("{}.{}.{}", self.name, instance, field),
+            None => format!("{}.{}", self.name, field),
+        }
+    }
+}
+
+impl Display for WidgetId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match &self.instance {
+            Some(instance) => write!(f, "{}:{}", self.name, instance),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+// Several widgets that are shown as one block with a shared label and color
+pub struct GroupConfig {
+    // The instance name if not set
+    pub label: String,
+    // Widgets of the group, declared as "name" or "name:instance"
+    pub widgets: Vec<String>,
+    // Fixed color of the group, by default it takes the most severe color of its widgets
+    pub color: Option<Color>,
+    // Clicking the group shows every widget as its own block
+    pub expandable: bool,
+}
+
+// A [[schedules]] table, e.g.
+// widgets = ["focus_tracker"], from = "18:00", to = "08:00", action = "hide"
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScheduleFile {
+    widgets: Vec<String>,
+    from: String,
+    to: String,
+    action: ScheduleAction,
+}
+
+impl ScheduleFile {
+    fn schedule(self) -> Result<Schedule, String> {
+        let time = |time: &str| {
+            NaiveTime::parse_from_str(time, "%H:%M")
+                .map_err(|_| format!("[[schedules]] needs times like \"18:00\", not \"{}\"", time))
+        };
+        Ok(Schedule {
+            from: time(&self.from)?,
+            to: time(&self.to)?,
+            widgets: self.widgets,
+            action: self.action,
+        })
+    }
+}
+
+// An alarm of the alarm widget, e.g.
+// { label = "Wake up", time = "07:00", weekdays = ["mon", "tue", "wed", "thu", "fri"] }
+#[derive(Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct AlarmSection {
+    label: String,
+    time: String,
+    // Every day if not set
+    weekdays: Option<Vec<String>>,
+}
+
+impl AlarmSection {
+    fn alarm(&self) -> Result<Alarm, String> {
+        let time = NaiveTime::parse_from_str(&self.time, "%H:%M")
+            .map_err(|_| format!("alarms need times like \"07:00\", not \"{}\"", self.time))?;
+        let weekdays = match &self.weekdays {
+            Some(days) => days.iter().try_fold(0, |weekdays, day| {
+                let day: Weekday = day
+                    .parse()
+                    .map_err(|_| format!("Unknown weekday {}, expected e.g. \"mon\"", day))?;
+                Ok::<u8, String>(weekdays | 1 << day.num_days_from_monday())
+            })?,
+            None => 0b1111111,
+        };
+        Ok(Alarm {
+            label: self.label.clone(),
+            time: (time.hour(), time.minute()),
+            weekdays,
+        })
+    }
+}
+
+// Either the name of a built-in theme or the hex values of a custom palette
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ThemeFile {
+    Name(String),
+    Palette {
+        neutral: String,
+        good: String,
+        accent: String,
+        warning: String,
+        critical: String,
+    },
+}
+
+#[derive(Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct GradientSection {
+    min: f32,
+    max: f32,
+    colors: Vec<String>,
+}
+
+impl GradientSection {
+    fn gradient(&self) -> Result<ColorGradient, String> {
+        if self.min >= self.max {
+            return Err(String::from("min has to be smaller than max"));
+        }
+        if self.colors.len() < 2 {
+            return Err(String::from("at least two colors are needed"));
+        }
+        if let Some(invalid) = self.colors.iter().find(|color| !is_hex_color(color)) {
+            return Err(format!("Invalid color {}, expected #RRGGBB", invalid));
+        }
+        Ok(ColorGradient {
+            min: self.min,
+            max: self.max,
+            colors: self.colors.clone(),
+        })
+    }
+}
+
+// API that a printer or ad blocker instance talks to, e.g. api = "moonraker"
+#[derive(Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum ApiSection {
+    #[serde(rename = "octoprint")]
+    OctoPrint,
+    Moonraker,
+    #[serde(rename = "pihole")]
+    PiHole,
+    #[serde(rename = "adguard_home")]
+    AdGuardHome,
+}
+
+// min_width = 120 or min_width = "CPU:100%"
+#[derive(Deserialize, PartialEq)]
+#[serde(untagged)]
+enum MinWidthSection {
+    Pixels(u32),
+    Text(String),
+}
+
+impl MinWidthSection {
+    fn min_width(&self) -> MinWidth {
+        match self {
+            MinWidthSection::Pixels(pixels) => MinWidth::Pixels(*pixels),
+            MinWidthSection::Text(text) => MinWidth::Text(text.clone()),
+        }
+    }
+}
+
+// Options of a single widget, everything that is not set uses the compiled in default
+// Options that every widget of a kind shares are set in [widgets.<name>],
+// options of a single instance in [widgets."<name>:<instance>"]
+#[derive(Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct WidgetSection {
+    // Template for the text, the placeholders are listed next to the default format of the widget
+    format: Option<String>,
+    short_format: Option<String>,
+    // The widget becomes urgent when its value drops below / rises above the limit
+    urgent_below: Option<f32>,
+    urgent_above: Option<f32>,
+    // Device of the battery, wireless and ethernet widgets (e.g. "BAT1" or "wlan0")
+    device: Option<String>,
+    // Mount point of a disk instance
+    mount_point: Option<String>,
+    // Offset to UTC in seconds or zoneinfo name (e.g. "America/New_York") of a time instance
+    utc_offset: Option<i32>,
+    time_zone: Option<String>,
+    // Show the time widget with AM/PM instead of 24 hours
+    twelve_hour: Option<bool>,
+    // Show the UTC time after the local time in the time widget
+    show_utc: Option<bool>,
+    // Names of the time widget for %A and %B, from Monday to Sunday and from January to December
+    // %a and %b show their first three characters
+    day_names: Option<Vec<String>>,
+    month_names: Option<Vec<String>>,
+    // e.g. gradient = { min = 40, max = 90, colors = ["#A3BE8C", "#EBCB8B", "#BF616A"] }
+    gradient: Option<GradientSection>,
+    // Shell command, interval (in seconds) and output format of a script instance
+    // The alarm widget runs its command when an alarm rings, an empty command runs nothing
+    command: Option<String>,
+    // Enable and disable command of every step of the presentation mode widget
+    // e.g. commands = [["powerprofilesctl set performance", "powerprofilesctl set balanced"]]
+    commands: Option<Vec<(String, String)>>,
+    interval: Option<u64>,
+    json: Option<bool>,
+    // Seconds that a timer instance counts down from, or that a pomodoro work phase lasts
+    duration: Option<u64>,
+    // Seconds that the short and long breaks of the pomodoro widget last
+    break_duration: Option<u64>,
+    long_break_duration: Option<u64>,
+    // Show a global IPv6 address of the network widgets instead of the first one
+    prefer_global_ipv6: Option<bool>,
+    // Signal strength in percent below which the wireless widget is shown as warning or critical
+    signal_warning: Option<u8>,
+    signal_critical: Option<u8>,
+    // Passed as JSON object to a plugin instance
+    options: Option<toml::Table>,
+    // Widgets with a lower priority are shortened or hidden first when the bar is too wide
+    priority: Option<u8>,
+    // Scale factor that each output should run at and the one of all other outputs, e.g.
+    // scales = { "eDP-1" = 2, "HDMI-1" = 1.25 } in [widgets.display_scale]
+    scales: Option<HashMap<String, f32>>,
+    default_scale: Option<f32>,
+    // Alarms of the alarm widget, see AlarmSection
+    alarms: Option<Vec<AlarmSection>>,
+    // Minutes by which a click snoozes a ringing alarm, and after which an alarm that
+    // nobody reacted to stops ringing
+    snooze_minutes: Option<u32>,
+    ring_minutes: Option<u32>,
+    // Backlights of the brightness widget (e.g. ["intel_backlight"])
+    devices: Option<Vec<String>>,
+    // Options of the block, see BlockConfig
+    markup: Option<Markup>,
+    min_width: Option<MinWidthSection>,
+    align: Option<Align>,
+    separator: Option<bool>,
+    separator_block_width: Option<u32>,
+    background: Option<String>,
+    border: Option<String>,
+    border_top: Option<u32>,
+    border_bottom: Option<u32>,
+    border_left: Option<u32>,
+    border_right: Option<u32>,
+    stable_width: Option<bool>,
+    hide_on_error: Option<bool>,
+    hide_when_empty: Option<bool>,
+    // Host of a remote instance as it is passed to ssh, and the values that are collected there
+    host: Option<String>,
+    metrics: Option<Vec<RemoteMetric>>,
+    // Address of a SNMP instance including the port, its community ("public" if not set)
+    // and the values that are polled
+    address: Option<String>,
+    community: Option<String>,
+    oids: Option<Vec<SnmpOid>>,
+    // Login of a service, passwords are resolved with utils::secrets (e.g. "env:MQTT_PASSWORD")
+    username: Option<String>,
+    password: Option<String>,
+    // Topics of a MQTT instance, the broker is set in address
+    topics: Option<Vec<MqttTopic>>,
+    // Base URL of a service, e.g. "http://homeassistant.local:8123"
+    url: Option<String>,
+    // Access token of a service, resolved with utils::secrets (e.g. "env:HASS_TOKEN")
+    token: Option<String>,
+    // File with the output of "rsync --info=progress2" of a transfer instance,
+    // rclone instances set the url of the remote control API instead
+    progress_file: Option<String>,
+    // How a task instance recognizes its task, the first one that is set is used
+    pid_file: Option<String>,
+    process: Option<String>,
+    variable: Option<String>,
+    // API of a printer or ad blocker instance and the key of the OctoPrint API
+    api: Option<ApiSection>,
+    api_key: Option<String>,
+    // Entity of a Home Assistant instance, e.g. "switch.coffee_machine"
+    entity: Option<String>,
+    // Toggle the entity of a Home Assistant instance with a left click
+    toggle: Option<bool>,
+    // Shown in front of a group, Home Assistant entity or derived value, the instance name if
+    // not set
+    label: Option<String>,
+    // Expression of a derived widget, e.g. "memory.value > 80 && cpu_percentage.value > 50"
+    expression: Option<String>,
+    // Widgets and color of a group, e.g. widgets = ["wireless", "ethernet"]
+    // Without a color, the group takes the most severe color of its widgets
+    widgets: Option<Vec<String>>,
+    color: Option<Color>,
+    // Clicking the label of a group shows every widget as its own block
+    expandable: Option<bool>,
+}
+
+// Content of the config file ($XDG_CONFIG_HOME/i3rustus/config.toml)
+// Example:
+//
+// order = ["battery", "disk:home", "time"]
+// theme = "nord"
+//
+// [widgets.battery]
+// device = "BAT1"
+// urgent_below = 15
+//
+// [widgets."disk:home"]
+// mount_point = "/home"
+//
+// [widgets."script:updates"]
+// command = "checkupdates | wc -l"
+// interval = 3600
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    // Widgets in the order they are shown, declared as "name" or "name:instance"
+    order: Option<Vec<String>>,
+    theme: Option<ThemeFile>,
+    // Width of the bar in pixels that the blocks may take, see max_bar_width
+    max_width: Option<u32>,
+    // Battery percentage that starts low power mode, see low_power_below()
+    low_power_below: Option<f32>,
+    // Restrict the files and system calls after starting, see sandbox()
+    sandbox: Option<bool>,
+    // Serve org.i3rustus on the session bus, see dbus_interface()
+    dbus: Option<bool>,
+    // Time windows in which widgets are hidden or minimized, see schedules()
+    #[serde(default)]
+    schedules: Vec<ScheduleFile>,
+    // Widgets whose values are written to metrics.csv, see recorded_widgets()
+    #[serde(default)]
+    recorded_widgets: Vec<String>,
+    // Glyphs of the icons, see icon_set(), icon_override() and icon_ramp()
+    icons: Option<IconSet>,
+    #[serde(default)]
+    icon_overrides: HashMap<String, String>,
+    #[serde(default)]
+    icon_ramps: HashMap<String, Vec<String>>,
+    // i2c devices of external monitors, e.g. ["/dev/i2c-4"], see ddc_i2c_devices()
+    #[serde(default)]
+    ddc_i2c_devices: Vec<String>,
+    // Where alerts are sent, see alert_sinks()
+    #[serde(default)]
+    alert_sinks: Vec<AlertSink>,
+    // Sounds that are played when widgets cross thresholds, see sound_alerts()
+    #[serde(default)]
+    sound_alerts: Vec<SoundAlert>,
+    // Variables that clicks change and the colors that follow them, see click_actions()
+    // and variable_colors()
+    #[serde(default)]
+    click_actions: Vec<ClickAction>,
+    #[serde(default)]
+    variable_colors: Vec<VariableColor>,
+    #[serde(default)]
+    widgets: HashMap<String, WidgetSection>,
+}
+
+// Validated settings of the config file
+struct FileSettings {
+    widget_order: Option<Vec<WidgetId>>,
+    theme: Option<Theme>,
+    max_width: Option<u32>,
+    low_power_below: Option<f32>,
+    sandbox: Option<bool>,
+    dbus: Option<bool>,
+    schedules: Vec<Schedule>,
+    recorded_widgets: Vec<String>,
+    icons: Option<IconSet>,
+    icon_overrides: HashMap<String, String>,
+    icon_ramps: HashMap<String, Vec<String>>,
+    ddc_i2c_devices: Vec<String>,
+    alert_sinks: Vec<AlertSink>,
+    sound_alerts: Vec<SoundAlert>,
+    click_actions: Vec<ClickAction>,
+    variable_colors: Vec<VariableColor>,
+    // Sections by "name" or "name:instance", shared with the getters that read them
+    widgets: HashMap<String, Arc<WidgetSection>>,
+    // Validated gradients of the sections
+    gradients: HashMap<String, ColorGradient>,
+}
+
+// Name of a widget that is declared as "name" or "name:instance"
+pub fn widget_name(widget: &str) -> &str {
+    widget.split(':').next().unwrap_or(widget)
+}
+
+pub fn is_hex_color(value: &str) -> bool {
+    value.len() == 7
+        && value.starts_with('#')
+        && value[1..]
+            .chars()
+            .all(|character| character.is_ascii_hexdigit())
+}
+
+impl ThemeFile {
+    fn theme(self) -> Result<Theme, String> {
+        match self {
+            ThemeFile::Name(name) => {
+                Theme::from_name(&name).ok_or_else(|| match closest(&name, Theme::NAMES) {
+                    Some(theme) => format!("Unknown theme {}, did you mean `{}`?", name, theme),
+                    None => format!("Unknown theme {}", name),
+                })
+            }
+            ThemeFile::Palette {
+                neutral,
+                good,
+                accent,
+                warning,
+                critical,
+            } => {
+                let colors = [neutral, good, accent, warning, critical];
+                if let Some(invalid) = colors.iter().find(|color| !is_hex_color(color)) {
+                    return Err(format!("Invalid color {}, expected #RRGGBB", invalid));
+                }
+                let [neutral, good, accent, warning, critical] = colors.map(Cow::Owned);
+                Ok(Theme::Custom(Palette {
+                    neutral,
+                    good,
+                    accent,
+                    warning,
+                    critical,
+                }))
+            }
+        }
+    }
+}
 
 pub struct Config;
 
@@ -11,33 +866,1131 @@ impl Config {
         Config {}
     }
 
-    pub fn get_wifi_device_name(&self) -> &str {
-        "wlp3s0"
+    // Colors of the bar, widgets only choose the meaning of a color (e.g. Color::Critical)
+    pub fn theme(&self) -> Theme {
+        Self::file_settings()
+            .and_then(|settings| settings.theme.clone())
+            .unwrap_or(Theme::Default)
+    }
+
+    fn file_settings() -> Option<Arc<FileSettings>> {
+        FILE_SETTINGS.read().unwrap().clone()
+    }
+
+    // Options of a widget from the config file, e.g. "battery" or "disk:home"
+    fn widget_section(widget: &str) -> Option<Arc<WidgetSection>> {
+        Self::file_settings()?.widgets.get(widget).cloned()
+    }
+
+    // Sections that apply to a widget, "[widgets.\"disk:home\"]" before "[widgets.disk]"
+    fn widget_sections(widget: &str) -> impl Iterator<Item = Arc<WidgetSection>> + '_ {
+        [widget, widget_name(widget)]
+            .into_iter()
+            .filter_map(Self::widget_section)
+    }
+
+    // Instances of a widget that have a section in the config file, e.g. every "time:<instance>"
+    pub fn configured_instances(&self, name: &str) -> Vec<WidgetId> {
+        let Some(settings) = Self::file_settings() else {
+            return Vec::new();
+        };
+        let mut instances: Vec<WidgetId> = settings
+            .widgets
+            .keys()
+            .map(|widget| WidgetId::parse(widget))
+            .filter(|id| &*id.name == name && id.instance.is_some())
+            .collect();
+        instances.sort_by_key(|id| id.to_string());
+        instances
+    }
+
+    // Follows the XDG base directory specification
+    pub fn file_path(&self) -> PathBuf {
+        let directory = match env::var("XDG_CONFIG_HOME") {
+            Ok(config_home) if !config_home.is_empty() => PathBuf::from(config_home),
+            _ => PathBuf::from(env::var("HOME").unwrap_or_default()).join(".config"),
+        };
+
+        directory.join("i3rustus").join("config.toml")
+    }
+
+    // Serve org.i3rustus on the session bus, see dbus.rs
+    // Every process of the session can call it, so it has to be turned on with dbus = true
+    // It is started with the bar, changing this needs a restart
+    pub fn dbus_interface(&self) -> bool {
+        Self::file_settings()
+            .and_then(|settings| settings.dbus)
+            .unwrap_or(false)
+    }
+
+    // Socket for runtime commands, see ipc.rs
+    // Without a runtime directory there is no safe place for it, so it is not created
+    pub fn ipc_socket_path(&self) -> Option<PathBuf> {
+        match env::var("XDG_RUNTIME_DIR") {
+            Ok(runtime_directory) if !runtime_directory.is_empty() => {
+                Some(PathBuf::from(runtime_directory).join("i3rustus.sock"))
+            }
+            _ => None,
+        }
+    }
+
+    // Confine i3rustus with Landlock and seccomp once the config file is loaded, so parsing
+    // the data of other hosts can't be used to read or change other files, see sandbox/mod.rs
+    // The files are chosen for the widgets at startup, changing the order needs a restart
+    // then, and the commands that widgets run are confined as well
+    pub fn sandbox(&self) -> bool {
+        Self::file_settings()
+            .and_then(|settings| settings.sandbox)
+            .unwrap_or(false)
+    }
+
+    // Shared libraries of "plugin:<name>" widgets are loaded from here
+    pub fn plugin_directory(&self) -> PathBuf {
+        self.file_path().with_file_name("plugins")
+    }
+
+    // (Re)load the config file, the previous settings are kept if it is invalid
+    // A missing config file is not an error, the compiled in defaults are used then
+    // Returns the widget sections ("name" or "name:instance") that were added, changed or
+    // removed, so their widgets can be created again with the new options
+    pub fn load_file(&self) -> Result<Vec<String>, String> {
+        let path = self.file_path();
+        let settings = match read_to_string(&path) {
+            Ok(content) => {
+                let file: ConfigFile = toml::from_str(&content).map_err(|error| {
+                    let message = format!("{}: {}", path.display(), error);
+                    match suggest_for_serde_error(error.message()) {
+                        Some(suggestion) => format!("{}\n{}", message.trim_end(), suggestion),
+                        None => message,
+                    }
+                })?;
+                for widget in &file.recorded_widgets {
+                    I3Status::check_widget_name(&WidgetId::parse(widget).name)
+                        .map_err(|error| format!("recorded_widgets: {}", error))?;
+                }
+                for sink in &file.alert_sinks {
+                    sink.check()
+                        .map_err(|error| format!("alert_sinks: {}", error))?;
+                }
+                for alert in &file.sound_alerts {
+                    I3Status::check_widget_name(&WidgetId::parse(&alert.widget).name)
+                        .map_err(|error| format!("sound_alerts: {}", error))?;
+                }
+                for action in &file.click_actions {
+                    I3Status::check_widget_name(&WidgetId::parse(&action.widget).name)
+                        .map_err(|error| format!("click_actions: {}", error))?;
+                }
+                for widget in file.variable_colors.iter().flat_map(|color| &color.widgets) {
+                    I3Status::check_widget_name(&WidgetId::parse(widget).name)
+                        .map_err(|error| format!("variable_colors: {}", error))?;
+                }
+                let mut gradients = HashMap::new();
+                for (widget, section) in &file.widgets {
+                    if section.urgent_below.is_some() && section.urgent_above.is_some() {
+                        return Err(format!(
+                            "[widgets.\"{}\"] can only set one of urgent_below and urgent_above",
+                            widget
+                        ));
+                    }
+                    if let Some(invalid) = [&section.background, &section.border]
+                        .into_iter()
+                        .flatten()
+                        .find(|color| !is_hex_color(color))
+                    {
+                        return Err(format!(
+                            "[widgets.\"{}\"] Invalid color {}, expected #RRGGBB",
+                            widget, invalid
+                        ));
+                    }
+                    if let Some(scale) = section
+                        .scales
+                        .iter()
+                        .flat_map(|scales| scales.values())
+                        .chain(&section.default_scale)
+                        .find(|scale| **scale <= 0.0)
+                    {
+                        return Err(format!(
+                            "[widgets.\"{}\"] Invalid scale {}, expected a factor above 0",
+                            widget, scale
+                        ));
+                    }
+                    for alarm in section.alarms.iter().flatten() {
+                        alarm
+                            .alarm()
+                            .map_err(|error| format!("[widgets.\"{}\"] {}", widget, error))?;
+                    }
+                    if let Some(gradient) = &section.gradient {
+                        let gradient = gradient.gradient().map_err(|error| {
+                            format!("[widgets.\"{}\"] gradient: {}", widget, error)
+                        })?;
+                        gradients.insert(widget.clone(), gradient);
+                    }
+                }
+                let settings = FileSettings {
+                    widget_order: file.order.map(|widgets| {
+                        widgets
+                            .iter()
+                            .map(|widget| WidgetId::parse(widget))
+                            .collect()
+                    }),
+                    theme: file.theme.map(ThemeFile::theme).transpose()?,
+                    max_width: file.max_width,
+                    low_power_below: file.low_power_below,
+                    sandbox: file.sandbox,
+                    dbus: file.dbus,
+                    schedules: file
+                        .schedules
+                        .into_iter()
+                        .map(ScheduleFile::schedule)
+                        .collect::<Result<_, _>>()?,
+                    recorded_widgets: file.recorded_widgets,
+                    icons: file.icons,
+                    icon_overrides: file.icon_overrides,
+                    icon_ramps: file.icon_ramps,
+                    ddc_i2c_devices: file.ddc_i2c_devices,
+                    alert_sinks: file.alert_sinks,
+                    sound_alerts: file.sound_alerts,
+                    click_actions: file.click_actions,
+                    variable_colors: file.variable_colors,
+                    widgets: file
+                        .widgets
+                        .into_iter()
+                        .map(|(widget, section)| (widget, Arc::new(section)))
+                        .collect(),
+                    gradients,
+                };
+                Some(Arc::new(settings))
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => None,
+            Err(error) => return Err(format!("{}: {}", path.display(), error)),
+        };
+        // The sandbox only allows the files of the widgets that the bar was started with,
+        // so the old settings are kept as a whole if the widgets changed
+        if sandboxed_widgets()
+            .is_some_and(|widgets| widgets != Self::widget_order_of(settings.as_deref()))
+        {
+            return Err(
+                "the widgets can't be changed in the sandbox, restart the bar to show the new ones"
+                    .to_string(),
+            );
+        }
+        let previous = replace(&mut *FILE_SETTINGS.write().unwrap(), settings.clone());
+        let (previous, settings) = (previous.as_deref(), settings.as_deref());
+        let section = |settings: Option<&FileSettings>, widget: &str| {
+            settings.and_then(|settings| settings.widgets.get(widget).cloned())
+        };
+        let mut changed: Vec<String> = [previous, settings]
+            .into_iter()
+            .flatten()
+            .flat_map(|settings| settings.widgets.keys())
+            .filter(|widget| section(previous, widget) != section(settings, widget))
+            .cloned()
+            .collect();
+        // The devices are opened when the widgets are created
+        let ddc_i2c_devices = |settings: Option<&FileSettings>| {
+            settings.map_or(Vec::new(), |settings| settings.ddc_i2c_devices.clone())
+        };
+        if ddc_i2c_devices(previous) != ddc_i2c_devices(settings) {
+            changed.extend(["brightness", "external_monitor"].map(String::from));
+        }
+        changed.sort();
+        changed.dedup();
+
+        Ok(changed)
+    }
+
+    // Glyphs that are used for icons, the ASCII set works with every font
+    // e.g. icons = "nerd_font"
+    pub fn icon_set(&self) -> IconSet {
+        Self::file_settings()
+            .and_then(|settings| settings.icons)
+            .unwrap_or(IconSet::Default)
+    }
+
+    // Replaces a single icon of the icon set, e.g. battery_charging = "+" in [icon_overrides]
+    pub fn icon_override(&self, icon: &str) -> Option<String> {
+        Self::file_settings()?.icon_overrides.get(icon).cloned()
+    }
+
+    // Replaces the glyphs of a ramp, ordered from 0% to 100%
+    // e.g. battery = ["[  ]", "[= ]", "[==]"] in [icon_ramps]
+    pub fn icon_ramp(&self, ramp: &str) -> Option<Vec<String>> {
+        Self::file_settings()?
+            .icon_ramps
+            .get(ramp)
+            .filter(|glyphs| !glyphs.is_empty())
+            .cloned()
+    }
+
+    // Device that is set in the section of a widget, e.g. [widgets.battery]
+    fn device_name(widget_name: &str, default: &str) -> String {
+        Self::widget_section(widget_name)
+            .and_then(|section| section.device.clone())
+            .unwrap_or_else(|| default.to_string())
+    }
+
+    pub fn get_wifi_device_name(&self) -> String {
+        Self::device_name("wireless", "wlp3s0")
+    }
+
+    pub fn get_ethernet_device_name(&self) -> String {
+        Self::device_name("ethernet", "enp5s0")
     }
 
-    pub fn get_ethernet_device_name(&self) -> &str {
-        "enp5s0"
+    // {ipv6} of the ethernet and wireless widgets shows a global address if there is one,
+    // otherwise the first address of the interface (usually the link local one)
+    pub fn prefer_global_ipv6(&self, widget_name: &str) -> bool {
+        Self::widget_section(widget_name)
+            .and_then(|section| section.prefer_global_ipv6)
+            .unwrap_or(true)
     }
 
-    pub fn battery_device_name(&self) -> &str {
-        "BAT0"
+    // Percentages of the signal strength below which the wireless widget turns warning and
+    // critical, about -75 dBm and -88 dBm
+    pub fn signal_thresholds(&self) -> (u8, u8) {
+        let section = Self::widget_section("wireless");
+        (
+            section
+                .as_ref()
+                .and_then(|section| section.signal_warning)
+                .unwrap_or(50),
+            section
+                .as_ref()
+                .and_then(|section| section.signal_critical)
+                .unwrap_or(25),
+        )
     }
 
-    pub fn brightness_device_name(&self) -> &str {
-        "amdgpu_bl1"
+    pub fn battery_device_name(&self) -> String {
+        Self::device_name("battery", "BAT0")
     }
 
-    pub fn widget_order(&self) -> Vec<String> {
-        vec![
-            String::from("wireless"),
-            String::from("ethernet"),
-            String::from("battery"),
-            String::from("brightness"),
-            String::from("cpu_load"),
-            String::from("cpu_percentage"),
-            String::from("memory"),
-            String::from("disk"),
-            String::from("time"),
+    // i2c devices of external monitors that are controlled through DDC/CI,
+    // shared by the brightness and external_monitor widgets
+    pub fn ddc_i2c_devices(&self) -> Vec<String> {
+        Self::file_settings()
+            .map(|settings| settings.ddc_i2c_devices.clone())
+            .unwrap_or_default()
+    }
+
+    // Backlights that are shown and controlled together by the brightness widget
+    pub fn brightness_device_names(&self) -> Vec<String> {
+        Self::widget_section("brightness")
+            .and_then(|section| section.devices.clone())
+            .unwrap_or_else(|| vec![String::from("amdgpu_bl1")])
+    }
+
+    // USB vendor IDs of security keys (Yubico, Nitrokey, Feitian)
+    pub fn security_key_vendor_ids(&self) -> &[&str] {
+        &["1050", "20a0", "096e"]
+    }
+
+    // File that is created by a notify hook while a security key waits for a touch
+    // It is kept in the runtime directory, so other users can't fake a touch request
+    pub fn security_key_touch_flag_path(&self) -> Option<PathBuf> {
+        match env::var("XDG_RUNTIME_DIR") {
+            Ok(runtime_directory) if !runtime_directory.is_empty() => {
+                Some(PathBuf::from(runtime_directory).join("i3rustus-touch-request"))
+            }
+            _ => None,
+        }
+    }
+
+    // Should match default-cache-ttl in gpg-agent.conf (in seconds)
+    pub fn gpg_agent_cache_ttl(&self) -> u64 {
+        600
+    }
+
+    // How often gpg-agent is asked for cached passphrases (in seconds)
+    pub fn gpg_agent_poll_interval(&self) -> u64 {
+        5
+    }
+
+    // Commands that are run when presentation mode is enabled / disabled, from commands in
+    // [widgets.presentation_mode]
+    // The disable command of every step has to revert its enable command
+    pub fn presentation_mode_commands(&self) -> Vec<(String, String)> {
+        if let Some(commands) =
+            Self::widget_section("presentation_mode").and_then(|section| section.commands.clone())
+        {
+            return commands;
+        }
+
+        [
+            // Inhibit screen saver and display power management
+            ("xset s off -dpms", "xset s on +dpms"),
+            // Do not disturb
+            ("dunstctl set-paused true", "dunstctl set-paused false"),
+            // Mute notification sounds
+            (
+                "gsettings set org.gnome.desktop.sound event-sounds false",
+                "gsettings set org.gnome.desktop.sound event-sounds true",
+            ),
+        ]
+        .map(|(enable, disable)| (enable.to_string(), disable.to_string()))
+        .to_vec()
+    }
+
+    // The block of a widget is marked as urgent when its value crosses this threshold
+    // Alerts are sent once when a widget crosses its urgent threshold
+    // e.g. alert_sinks = [{ ntfy = "https://ntfy.sh/my-topic" }] to get a push notification on
+    // the phone
+    pub fn alert_sinks(&self) -> Vec<AlertSink> {
+        Self::file_settings().map_or(Vec::new(), |settings| settings.alert_sinks.clone())
+    }
+
+    // Sounds that are played through PulseAudio or PipeWire when a widget crosses a threshold
+    // e.g. a sound when the battery is full, from sound_alerts in the config file:
+    // [{ widget = "battery", threshold = { above = 99 }, file = "/usr/share/sounds/alsa/Front_Center.wav" }]
+    pub fn sound_alerts(&self) -> Vec<SoundAlert> {
+        Self::file_settings().map_or(Vec::new(), |settings| settings.sound_alerts.clone())
+    }
+
+    // Sounds are muted while this variable is "true"
+    // Presentation mode also pauses notifications, so it doubles as do not disturb
+    pub fn sound_mute_variable(&self) -> &str {
+        "presentation_mode.enabled"
+    }
+
+    // Threshold of a widget, e.g. "battery" or "disk:home"
+    pub fn urgent_threshold(&self, widget: &str) -> Option<Threshold> {
+        if let Some(threshold) = Self::widget_sections(widget).find_map(|section| {
+            match (section.urgent_below, section.urgent_above) {
+                (Some(limit), _) => Some(Threshold::Below(limit)),
+                (_, Some(limit)) => Some(Threshold::Above(limit)),
+                _ => None,
+            }
+        }) {
+            return Some(threshold);
+        }
+
+        match widget_name(widget) {
+            // Battery percentage
+            "battery" => Some(Threshold::Below(10.0)),
+            // Available disk space in percent
+            "disk" => Some(Threshold::Below(5.0)),
+            _ => None,
+        }
+    }
+
+    // Gradient of a widget, e.g. "memory" or "disk:home", from gradient in its section
+    pub fn color_gradient(&self, widget: &str) -> Option<ColorGradient> {
+        Self::file_settings().and_then(|settings| {
+            [widget, widget_name(widget)]
+                .iter()
+                .find_map(|widget| settings.gradients.get(*widget))
+                .cloned()
+        })
+    }
+
+    // CPU time in milliseconds that a widget may use per minute, widgets that use more
+    // are logged and shown by the self_monitor widget
+    pub fn widget_cpu_budget_ms(&self) -> u64 {
+        500
+    }
+
+    // Battery percentage below which low power mode starts while the battery discharges
+    // None disables low power mode, it has to be turned on in the config file
+    pub fn low_power_below(&self) -> Option<f32> {
+        Self::file_settings().and_then(|settings| settings.low_power_below)
+    }
+
+    // In low power mode, widgets are only updated every this many ticks
+    pub fn low_power_tick_factor(&self) -> u64 {
+        5
+    }
+
+    // Widgets that are still updated every tick in low power mode
+    pub fn low_power_exempt_widgets(&self) -> &[&'static str] {
+        &["time"]
+    }
+
+    // Seconds without keyboard or mouse input after which the user counts as idle
+    pub fn idle_timeout(&self) -> u64 {
+        300
+    }
+
+    // Track the active time per i3 workspace
+    pub fn focus_track_workspaces(&self) -> bool {
+        true
+    }
+
+    // Seconds of continuous activity after which a break is due
+    pub fn break_reminder_interval(&self) -> u64 {
+        20 * 60
+    }
+
+    // Seconds without input that count as a break
+    pub fn break_reminder_break_length(&self) -> u64 {
+        20
+    }
+
+    // Command that is run once when a break is due
+    pub fn break_reminder_command(&self) -> Option<&str> {
+        Some("notify-send 'Take a break' 'Look at something 20 feet away for 20 seconds'")
+    }
+
+    // Scale factor that each output (e.g. "eDP-1") should run at, from scales in
+    // [widgets.display_scale]
+    // The display_scale widget shows a warning for outputs with another scale
+    pub fn output_scales(&self) -> HashMap<String, f32> {
+        Self::widget_section("display_scale")
+            .and_then(|section| section.scales.clone())
+            .unwrap_or_default()
+    }
+
+    // Expected scale factor of outputs that are not listed in output_scales
+    pub fn default_output_scale(&self) -> f32 {
+        Self::widget_section("display_scale")
+            .and_then(|section| section.default_scale)
+            .unwrap_or(1.0)
+    }
+
+    // Returns the expected scale factor of the given output
+    pub fn expected_output_scale(&self, output: &str) -> f32 {
+        self.output_scales()
+            .get(output)
+            .copied()
+            .unwrap_or_else(|| self.default_output_scale())
+    }
+
+    // Tags of the interval that a click on the timewarrior widget starts
+    pub fn timewarrior_tags(&self) -> &[&'static str] {
+        &["work"]
+    }
+
+    // Alarms of the alarm widget, from alarms in [widgets.alarm]
+    // There are none by default, the widget shows "off" then
+    pub fn alarms(&self) -> Vec<Alarm> {
+        Self::widget_section("alarm")
+            .and_then(|section| {
+                let alarms = section.alarms.as_ref()?;
+                // Checked when the config file is loaded
+                Some(
+                    alarms
+                        .iter()
+                        .filter_map(|alarm| alarm.alarm().ok())
+                        .collect(),
+                )
+            })
+            .unwrap_or_default()
+    }
+
+    // Command that is run every time an alarm starts ringing, also after a snooze
+    pub fn alarm_command(&self) -> Option<String> {
+        Self::widget_section("alarm")
+            .and_then(|section| section.command.clone())
+            .or_else(|| {
+                Some(String::from(
+                    "notify-send --urgency=critical 'Alarm' \"$ALARM_LABEL\"",
+                ))
+            })
+            .filter(|command| !command.is_empty())
+    }
+
+    // Minutes by which a click snoozes a ringing alarm
+    pub fn alarm_snooze_minutes(&self) -> i64 {
+        Self::widget_section("alarm")
+            .and_then(|section| section.snooze_minutes)
+            .map_or(9, i64::from)
+    }
+
+    // Minutes after which an alarm that nobody reacted to stops ringing
+    pub fn alarm_ring_minutes(&self) -> i64 {
+        Self::widget_section("alarm")
+            .and_then(|section| section.ring_minutes)
+            .map_or(30, i64::from)
+    }
+
+    // Time windows in which widgets are hidden or minimized, from [[schedules]] in the
+    // config file
+    // Returns the action of the first active schedule for the given widget
+    pub fn scheduled_action(&self, widget_name: &str, now: NaiveTime) -> Option<ScheduleAction> {
+        Self::file_settings()?
+            .schedules
+            .iter()
+            .find(|schedule| {
+                schedule.widgets.iter().any(|widget| widget == widget_name)
+                    && schedule.is_active(now)
+            })
+            .map(|schedule| schedule.action)
+    }
+
+    // Click actions that change variables, these work for every widget
+    // e.g. a manual on-call flag that a middle click on the clock switches:
+    // click_actions = [{ widget = "time", button = 2, action = { toggle = "on_call" } }]
+    pub fn click_actions(&self) -> Vec<ClickAction> {
+        Self::file_settings().map_or(Vec::new(), |settings| settings.click_actions.clone())
+    }
+
+    // Colors that override the color of widgets depending on variables
+    // e.g. the clock and the network in the warning color while on call:
+    // variable_colors = [{ variable = "on_call", value = "true", widgets = ["time", "network"],
+    // color = "warning" }]
+    pub fn variable_colors(&self) -> Vec<VariableColor> {
+        Self::file_settings().map_or(Vec::new(), |settings| settings.variable_colors.clone())
+    }
+
+    // Label and expression of a derived widget, e.g. "derived" or "derived:busy"
+    // Variables are the values of other widgets (e.g. "memory.value") or named variables
+    pub fn derived_expression(&self, widget: &str) -> Option<DerivedExpression> {
+        let section = Self::widget_section(widget)?;
+        Some(DerivedExpression {
+            expression: section.expression.clone()?,
+            label: section.label.clone().unwrap_or_else(|| {
+                WidgetId::parse(widget)
+                    .instance
+                    .map_or_else(|| String::from("derived"), |instance| instance.to_string())
+            }),
+        })
+    }
+
+    // Widgets whose values are appended to metrics.csv in the state directory, from
+    // recorded_widgets in the config file, e.g. ["battery", "cpu_percentage", "memory"]
+    pub fn recorded_widgets(&self) -> Vec<String> {
+        Self::file_settings()
+            .map(|settings| settings.recorded_widgets.clone())
+            .unwrap_or_default()
+    }
+
+    // Size in bytes after which metrics.csv is moved to metrics.csv.1, replacing the
+    // previous one, so at most twice this size is kept
+    pub fn metrics_max_size(&self) -> u64 {
+        10 * 1024 * 1024
+    }
+
+    // Seconds between two records
+    pub fn recording_interval(&self) -> u64 {
+        60
+    }
+
+    // Wrap placeholder values with Hebrew or Arabic text in Unicode directional isolates,
+    // so they don't reorder the rest of the block. Disable it for fonts that draw the
+    // control characters as boxes
+    pub fn isolate_right_to_left_text(&self) -> bool {
+        true
+    }
+
+    // Appended to texts that were truncated by a placeholder like "{title:.30}"
+    // e.g. "..." for fonts without "…" or "" to cut the text without a hint
+    pub fn truncation_ellipsis(&self) -> &str {
+        "…"
+    }
+
+    // Appended to the last text of a widget that is shown again because the widget
+    // did not answer in time, None shows the text without a hint
+    pub fn stale_marker(&self) -> Option<&str> {
+        Some(" ⌛")
+    }
+
+    // Size of the bar font in pixels (e.g. 12 for a 9pt font at 96 DPI)
+    // Only used to estimate the width of texts for stable_width
+    pub fn bar_font_size(&self) -> f32 {
+        12.0
+    }
+
+    // Width in pixels that the blocks may take, e.g. the width of the screen minus the tray
+    // and the workspace buttons. When the blocks are wider (estimated like stable_width),
+    // blocks of low priority widgets show their short text, and if that is not enough they
+    // are hidden until the blocks fit. None lets the bar grow as wide as it wants
+    pub fn max_bar_width(&self) -> Option<u32> {
+        Self::file_settings().and_then(|settings| settings.max_width)
+    }
+
+    // Priority of a widget ("name" or "name:instance") when the bar is too wide
+    // Widgets with the lowest priority are shortened and hidden first
+    pub fn widget_priority(&self, widget: &str) -> u8 {
+        if let Some(priority) = Self::widget_sections(widget).find_map(|section| section.priority) {
+            return priority;
+        }
+
+        match widget_name(widget) {
+            // The clock is the last thing that should disappear
+            "time" => 100,
+            "battery" => 50,
+            _ => 10,
+        }
+    }
+
+    // Block options of a widget, e.g. "wireless" or "disk:home"
+    // Every option of [widgets."disk:home"] replaces the one of [widgets.disk], which replaces
+    // the compiled in default
+    pub fn block_config(&self, widget: &str) -> BlockConfig {
+        let defaults = Self::default_block_config(widget);
+        let sections = || Self::widget_sections(widget);
+        BlockConfig {
+            markup: sections()
+                .find_map(|section| section.markup)
+                .or(defaults.markup),
+            min_width: sections()
+                .find_map(|section| section.min_width.as_ref().map(MinWidthSection::min_width))
+                .or(defaults.min_width),
+            align: sections()
+                .find_map(|section| section.align)
+                .or(defaults.align),
+            separator: sections()
+                .find_map(|section| section.separator)
+                .or(defaults.separator),
+            separator_block_width: sections()
+                .find_map(|section| section.separator_block_width)
+                .or(defaults.separator_block_width),
+            background: sections()
+                .find_map(|section| section.background.clone())
+                .or(defaults.background),
+            border: sections()
+                .find_map(|section| section.border.clone())
+                .or(defaults.border),
+            border_top: sections()
+                .find_map(|section| section.border_top)
+                .or(defaults.border_top),
+            border_bottom: sections()
+                .find_map(|section| section.border_bottom)
+                .or(defaults.border_bottom),
+            border_left: sections()
+                .find_map(|section| section.border_left)
+                .or(defaults.border_left),
+            border_right: sections()
+                .find_map(|section| section.border_right)
+                .or(defaults.border_right),
+            stable_width: sections()
+                .find_map(|section| section.stable_width)
+                .unwrap_or(defaults.stable_width),
+            hide_on_error: sections()
+                .find_map(|section| section.hide_on_error)
+                .unwrap_or(defaults.hide_on_error),
+            hide_when_empty: sections()
+                .find_map(|section| section.hide_when_empty)
+                .unwrap_or(defaults.hide_when_empty),
+        }
+    }
+
+    // Block options of a widget without a section in the config file
+    fn default_block_config(widget: &str) -> BlockConfig {
+        match widget_name(widget) {
+            // Wireless and ethernet belong together, so don't separate them
+            "wireless" => BlockConfig {
+                separator: Some(false),
+                separator_block_width: Some(6),
+                ..Default::default()
+            },
+            // Avoid that the whole bar shifts when the usage changes the number of digits
+            "cpu_percentage" => BlockConfig {
+                min_width: Some(MinWidth::Text(String::from("CPU:100%"))),
+                align: Some(Align::Right),
+                ..Default::default()
+            },
+            // Transfers and tasks only show up while they are running
+            "transfer" | "task" => BlockConfig {
+                hide_when_empty: true,
+                ..Default::default()
+            },
+            _ => BlockConfig::default(),
+        }
+    }
+
+    // Mount point of a disk instance, e.g. "disk:home"
+    // The instance name is shown as label in the bar
+    pub fn disk_mount_point(&self, instance: &str) -> Option<String> {
+        if let Some(mount_point) = Self::widget_section(&format!("disk:{}", instance))
+            .and_then(|section| section.mount_point.clone())
+        {
+            return Some(mount_point);
+        }
+
+        match instance {
+            "root" => Some(String::from("/")),
+            "home" => Some(String::from("/home")),
+            _ => None,
+        }
+    }
+
+    // Zone of a time instance, e.g. "time:utc"
+    // The instance name is shown as label in the bar
+    pub fn time_zone(&self, instance: &str) -> Option<TimeZoneSetting> {
+        if let Some(section) = Self::widget_section(&format!("time:{}", instance)) {
+            if let Some(name) = &section.time_zone {
+                return Some(TimeZoneSetting::Name(name.clone()));
+            }
+            if let Some(offset) = section.utc_offset {
+                return Some(TimeZoneSetting::Offset(offset));
+            }
+        }
+
+        match instance {
+            "utc" => Some(TimeZoneSetting::Offset(0)),
+            _ => None,
+        }
+    }
+
+    // Show the {time} and {hour_minute} placeholders of a clock with AM/PM
+    // Clocks read [widgets."time:<instance>"] first, then [widgets.time]
+    pub fn twelve_hour_clock(&self, clock: &WidgetId) -> bool {
+        Self::widget_sections(&clock.to_string())
+            .find_map(|section| section.twelve_hour)
+            .unwrap_or(false)
+    }
+
+    // Show the UTC time after the format of a clock, e.g. "13:37 / 11:37Z"
+    pub fn time_show_utc(&self, clock: &WidgetId) -> bool {
+        Self::widget_sections(&clock.to_string())
+            .find_map(|section| section.show_utc)
+            .unwrap_or(false)
+    }
+
+    // Weekday names of a clock, from Monday to Sunday
+    // chrono's English names are used if not set
+    pub fn day_names(&self, clock: &WidgetId) -> Option<Vec<String>> {
+        Self::widget_sections(&clock.to_string()).find_map(|section| section.day_names.clone())
+    }
+
+    // Month names of a clock, from January to December
+    pub fn month_names(&self, clock: &WidgetId) -> Option<Vec<String>> {
+        Self::widget_sections(&clock.to_string()).find_map(|section| section.month_names.clone())
+    }
+
+    // Seconds that a timer instance counts down from, e.g. "timer:tea"
+    pub fn timer_duration(&self, instance: &str) -> Option<u64> {
+        if let Some(duration) = Self::widget_section(&format!("timer:{}", instance))
+            .and_then(|section| section.duration)
+        {
+            return Some(duration);
+        }
+
+        match instance {
+            "tea" => Some(4 * 60),
+            _ => None,
+        }
+    }
+
+    // Seconds of a work phase of the pomodoro widget
+    pub fn pomodoro_work_duration(&self) -> u64 {
+        Self::widget_section("pomodoro")
+            .and_then(|section| section.duration)
+            .unwrap_or(25 * 60)
+    }
+
+    // Seconds of a short break of the pomodoro widget
+    pub fn pomodoro_break_duration(&self) -> u64 {
+        Self::widget_section("pomodoro")
+            .and_then(|section| section.break_duration)
+            .unwrap_or(5 * 60)
+    }
+
+    // Seconds of a long break of the pomodoro widget
+    pub fn pomodoro_long_break_duration(&self) -> u64 {
+        Self::widget_section("pomodoro")
+            .and_then(|section| section.long_break_duration)
+            .unwrap_or(15 * 60)
+    }
+
+    // A long break follows every this many finished work phases
+    pub fn pomodoro_long_break_every(&self) -> u32 {
+        4
+    }
+
+    // Command that is run when a phase of the pomodoro widget is over
+    // The next phase (work, break or long break) is passed as POMODORO_PHASE
+    pub fn pomodoro_command(&self) -> Option<&str> {
+        Some("notify-send 'Pomodoro' \"Time for $POMODORO_PHASE\"")
+    }
+
+    // Zoneinfo files of named time zones are read from here
+    pub fn zoneinfo_directory(&self) -> &'static str {
+        "/usr/share/zoneinfo"
+    }
+
+    // Zoneinfo file of the local time zone when TZ is not set, usually a symlink into
+    // the zoneinfo directory that timedatectl set-timezone changes
+    pub fn localtime_path(&self) -> &'static str {
+        "/etc/localtime"
+    }
+
+    // Remote host of a remote instance, e.g. "remote:homelab"
+    // The instance name is shown as label in the bar, only the load is collected without metrics
+    pub fn remote_host(&self, instance: &str) -> Option<RemoteHost> {
+        let section = Self::widget_section(&format!("remote:{}", instance))?;
+        Some(RemoteHost {
+            host: section.host.clone()?,
+            metrics: section
+                .metrics
+                .clone()
+                .unwrap_or_else(|| vec![RemoteMetric::Load]),
+        })
+    }
+
+    // Seconds between two collections on a remote host
+    pub fn remote_interval(&self) -> u64 {
+        30
+    }
+
+    // Device of a SNMP instance, e.g. "snmp:router"
+    pub fn snmp_target(&self, instance: &str) -> Option<SnmpTarget> {
+        let section = Self::widget_section(&format!("snmp:{}", instance))?;
+        Some(SnmpTarget {
+            address: section.address.clone()?,
+            community: section
+                .community
+                .clone()
+                .unwrap_or_else(|| String::from("public")),
+            oids: section.oids.clone()?,
+        })
+    }
+
+    // Seconds between two SNMP requests
+    pub fn snmp_interval(&self) -> u64 {
+        10
+    }
+
+    // Broker of a MQTT instance, e.g. "mqtt:home"
+    pub fn mqtt_broker(&self, instance: &str) -> Option<MqttBroker> {
+        let section = Self::widget_section(&format!("mqtt:{}", instance))?;
+        Some(MqttBroker {
+            address: section.address.clone()?,
+            credentials: Self::credentials(&section),
+            topics: section.topics.clone()?,
+        })
+    }
+
+    // Username and password of a section, only if the username is set
+    fn credentials(section: &WidgetSection) -> Option<(String, String)> {
+        let password = section.password.clone().unwrap_or_default();
+        Some((section.username.clone()?, password))
+    }
+
+    // Base URL of Home Assistant, from [widgets.home_assistant]
+    pub fn home_assistant_url(&self) -> String {
+        Self::widget_section("home_assistant")
+            .and_then(|section| section.url.clone())
+            .unwrap_or_else(|| String::from("http://homeassistant.local:8123"))
+    }
+
+    // Long-lived access token, resolved with utils::secrets
+    pub fn home_assistant_token(&self) -> String {
+        Self::widget_section("home_assistant")
+            .and_then(|section| section.token.clone())
+            .unwrap_or_else(|| String::from("env:HASS_TOKEN"))
+    }
+
+    // Entity of a Home Assistant instance, e.g. "home_assistant:thermostat"
+    pub fn home_assistant_entity(&self, instance: &str) -> Option<HomeAssistantEntity> {
+        let section = Self::widget_section(&format!("home_assistant:{}", instance))?;
+        Some(HomeAssistantEntity {
+            entity_id: section.entity.clone()?,
+            label: section
+                .label
+                .clone()
+                .unwrap_or_else(|| instance.to_string()),
+            toggle: section.toggle.unwrap_or(false),
+        })
+    }
+
+    // Seconds between two requests to Home Assistant
+    pub fn home_assistant_interval(&self) -> u64 {
+        30
+    }
+
+    // 3D printer of a printer instance, e.g. "printer:voron"
+    pub fn printer(&self, instance: &str) -> Option<Printer> {
+        let section = Self::widget_section(&format!("printer:{}", instance))?;
+        let api = match section.api? {
+            ApiSection::OctoPrint => PrinterApi::OctoPrint {
+                api_key: section.api_key.clone()?,
+            },
+            ApiSection::Moonraker => PrinterApi::Moonraker,
+            ApiSection::PiHole | ApiSection::AdGuardHome => return None,
+        };
+        Some(Printer {
+            url: section.url.clone()?,
+            api,
+        })
+    }
+
+    // Seconds between two requests to a 3D printer
+    pub fn printer_interval(&self) -> u64 {
+        10
+    }
+
+    // DNS server of an ad blocker instance, e.g. "ad_blocker:pihole"
+    pub fn ad_blocker(&self, instance: &str) -> Option<AdBlockerServer> {
+        let section = Self::widget_section(&format!("ad_blocker:{}", instance))?;
+        let password = section.password.clone().unwrap_or_default();
+        let api = match section.api? {
+            ApiSection::PiHole => AdBlockerApi::PiHole { password },
+            ApiSection::AdGuardHome => AdBlockerApi::AdGuardHome {
+                username: section.username.clone()?,
+                password,
+            },
+            ApiSection::OctoPrint | ApiSection::Moonraker => return None,
+        };
+        Some(AdBlockerServer {
+            url: section.url.clone()?,
+            api,
+        })
+    }
+
+    // Seconds between two requests to an ad blocker
+    pub fn ad_blocker_interval(&self) -> u64 {
+        30
+    }
+
+    // How long blocking is disabled when an ad blocker widget is clicked
+    pub fn ad_blocker_pause(&self) -> u64 {
+        5 * 60
+    }
+
+    // Base URL of the Syncthing GUI / REST API
+    pub fn syncthing_url(&self) -> &str {
+        "http://localhost:8384"
+    }
+
+    // API key from the GUI settings, resolved with utils::secrets
+    pub fn syncthing_api_key(&self) -> &str {
+        "env:SYNCTHING_API_KEY"
+    }
+
+    // Seconds between two requests to Syncthing
+    pub fn syncthing_interval(&self) -> u64 {
+        30
+    }
+
+    // Account of a WebDAV instance, e.g. "webdav:nextcloud"
+    // The instance name is shown as label in the bar
+    pub fn webdav_account(&self, instance: &str) -> Option<WebDavAccount> {
+        let section = Self::widget_section(&format!("webdav:{}", instance))?;
+        Some(WebDavAccount {
+            url: section.url.clone()?,
+            username: section.username.clone()?,
+            password: section.password.clone().unwrap_or_default(),
+        })
+    }
+
+    // Seconds between two quota requests, quotas change slowly
+    pub fn webdav_interval(&self) -> u64 {
+        300
+    }
+
+    // Progress source of a transfer instance, e.g. "transfer:backup"
+    // The instance name is shown as label in the bar
+    pub fn transfer_source(&self, instance: &str) -> Option<TransferSource> {
+        let section = Self::widget_section(&format!("transfer:{}", instance))?;
+        if let Some(path) = &section.progress_file {
+            return Some(TransferSource::ProgressFile(path.clone()));
+        }
+        Some(TransferSource::Rclone {
+            url: section.url.clone()?,
+            credentials: Self::credentials(&section),
+        })
+    }
+
+    // Seconds between two requests to the rclone API
+    pub fn transfer_interval(&self) -> u64 {
+        2
+    }
+
+    // Marker of a task instance, e.g. "task:build"
+    // The instance name is shown as label in the bar
+    pub fn task_marker(&self, instance: &str) -> Option<TaskMarker> {
+        let section = Self::widget_section(&format!("task:{}", instance))?;
+        section
+            .pid_file
+            .clone()
+            .map(TaskMarker::PidFile)
+            .or_else(|| section.process.clone().map(TaskMarker::Process))
+            .or_else(|| section.variable.clone().map(TaskMarker::Variable))
+    }
+
+    // Seconds the duration of a finished task stays in the bar
+    pub fn task_flash_duration(&self) -> u64 {
+        10
+    }
+
+    // Command of a script instance, e.g. "script:updates"
+    // Scripts can also be declared in the config file
+    pub fn script(&self, instance: &str) -> Option<ScriptCommand> {
+        if let Some(section) = Self::widget_section(&format!("script:{}", instance)) {
+            if let Some(command) = &section.command {
+                return Some(ScriptCommand {
+                    command: command.clone(),
+                    interval: section.interval.unwrap_or(self.script_interval()),
+                    json: section.json.unwrap_or(false),
+                });
+            }
+        }
+
+        match instance {
+            "kernel" => Some(ScriptCommand {
+                command: String::from("uname -r"),
+                interval: 0,
+                json: false,
+            }),
+            _ => None,
+        }
+    }
+
+    // Options of a plugin instance as JSON object, e.g. "plugin:weather"
+    pub fn plugin_options(&self, instance: &str) -> String {
+        Self::widget_section(&format!("plugin:{}", instance))
+            .and_then(|section| serde_json::to_string(section.options.as_ref()?).ok())
+            .unwrap_or_else(|| String::from("{}"))
+    }
+
+    // Seconds between two runs of a script without an interval
+    pub fn script_interval(&self) -> u64 {
+        5
+    }
+
+    // Group that is declared as "group:<instance>" in the widget order, e.g.
+    // [widgets."group:net"]
+    // label = "NET:"
+    // widgets = ["wireless", "ethernet"]
+    pub fn group(&self, instance: &str) -> Option<GroupConfig> {
+        let section = Self::widget_section(&format!("group:{}", instance))?;
+        Some(GroupConfig {
+            label: section
+                .label
+                .clone()
+                .unwrap_or_else(|| instance.to_string()),
+            widgets: section.widgets.clone()?,
+            color: section.color,
+            expandable: section.expandable.unwrap_or(false),
+        })
+    }
+
+    // Template for the text of a widget, e.g. "memory" or "remote:homelab"
+    // The placeholders of every widget are listed next to its default format
+    pub fn format(&self, widget: &str) -> Option<String> {
+        Self::widget_sections(widget).find_map(|section| section.format.clone())
+    }
+
+    // Template for the short text of a widget, used when the bar runs out of space
+    pub fn short_format(&self, widget: &str) -> Option<String> {
+        Self::widget_sections(widget).find_map(|section| section.short_format.clone())
+    }
+
+    // Widgets are declared as "name" or "name:instance"
+    // The same widget can be shown several times with different instances
+    pub fn widget_order(&self) -> Vec<WidgetId> {
+        Self::widget_order_of(Self::file_settings().as_deref())
+    }
+
+    fn widget_order_of(settings: Option<&FileSettings>) -> Vec<WidgetId> {
+        if let Some(widget_order) = settings.and_then(|settings| settings.widget_order.clone()) {
+            return widget_order;
+        }
+
+        [
+            "wireless",
+            "ethernet",
+            "battery",
+            "brightness",
+            "cpu_load",
+            "cpu_percentage",
+            "memory",
+            "disk:root",
+            "time",
         ]
+        .iter()
+        .map(|widget| WidgetId::parse(widget))
+        .collect()
     }
 }
@@ -0,0 +1,131 @@
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fs::read_to_string;
+use std::path::Path;
+use toml::Spanned;
+
+use crate::config::WidgetId;
+use crate::i3_status::{I3Status, CONFIG};
+
+// Where the widgets are declared in the config file, everything else is ignored
+#[derive(Deserialize)]
+struct Locations {
+    #[serde(default)]
+    order: Option<Vec<Spanned<String>>>,
+    #[serde(default)]
+    widgets: HashMap<Spanned<String>, IgnoredAny>,
+}
+
+// Widgets that read a device from sysfs, with the directory of their devices
+const DEVICES: [(&str, &str); 3] = [
+    ("battery", "/sys/class/power_supply"),
+    ("wireless", "/sys/class/net"),
+    ("ethernet", "/sys/class/net"),
+];
+
+// Line of a byte offset in the config file, starting at 1
+fn line_number(content: &str, offset: usize) -> usize {
+    content[..offset.min(content.len())].matches('\n').count() + 1
+}
+
+// Validate the config file (and the compiled in defaults it overrides)
+// Returns one message per problem, prefixed with the location in the file if it has one
+pub fn check_config() -> Vec<String> {
+    let path = CONFIG.file_path();
+    let prefix = |line: Option<usize>| match line {
+        Some(line) => format!("{}:{}", path.display(), line),
+        None => String::from("default config"),
+    };
+    // Syntax errors, unknown keys and invalid values, the message contains the position
+    if let Err(error) = CONFIG.load_file() {
+        return vec![error];
+    }
+
+    let content = read_to_string(&path).unwrap_or_default();
+    let locations = match toml::from_str::<Locations>(&content) {
+        Ok(locations) => locations,
+        // There is no config file, only the defaults are checked
+        Err(_) => Locations {
+            order: None,
+            widgets: HashMap::new(),
+        },
+    };
+    let order: Vec<(WidgetId, Option<usize>)> = match &locations.order {
+        Some(order) => order
+            .iter()
+            .map(|widget| {
+                (
+                    WidgetId::parse(widget.get_ref()),
+                    Some(line_number(&content, widget.span().start)),
+                )
+            })
+            .collect(),
+        None => CONFIG
+            .widget_order()
+            .into_iter()
+            .map(|id| (id, None))
+            .collect(),
+    };
+
+    let mut errors = Vec::new();
+    let mut seen = HashSet::new();
+    for (id, line) in &order {
+        if let Err(error) = I3Status::check_widget(id) {
+            errors.push(format!("{}: order: {}", prefix(*line), error));
+        }
+        if !seen.insert(id) {
+            errors.push(format!(
+                "{}: order: {} is declared more than once, use an instance",
+                prefix(*line),
+                id
+            ));
+        }
+    }
+
+    // A section of an unknown widget is most likely a typo
+    for widget in locations.widgets.keys() {
+        let id = WidgetId::parse(widget.get_ref());
+        if id.instance.is_none() && I3Status::needs_instance(&id.name) {
+            continue;
+        }
+        let line = Some(line_number(&content, widget.span().start));
+        if let Err(error) = I3Status::check_widget(&id) {
+            errors.push(format!("{}: [widgets.\"{}\"]: {}", prefix(line), id, error));
+        }
+    }
+
+    // The devices of the BSDs don't show up in a directory
+    let devices: &[(&str, &str)] = if cfg!(target_os = "linux") {
+        &DEVICES
+    } else {
+        &[]
+    };
+    for &(widget_name, directory) in devices {
+        let Some((_, line)) = order.iter().find(|(id, _)| &*id.name == widget_name) else {
+            continue;
+        };
+        let device = match widget_name {
+            "battery" => CONFIG.battery_device_name(),
+            "wireless" => CONFIG.get_wifi_device_name(),
+            _ => CONFIG.get_ethernet_device_name(),
+        };
+        if !Path::new(directory).join(&device).exists() {
+            let line = locations
+                .widgets
+                .keys()
+                .find(|widget| widget.get_ref() == widget_name)
+                .map(|widget| line_number(&content, widget.span().start))
+                .or(*line);
+            errors.push(format!(
+                "{}: [widgets.{}] device: {} does not exist in {}",
+                prefix(line),
+                widget_name,
+                device,
+                directory
+            ));
+        }
+    }
+
+    errors
+}
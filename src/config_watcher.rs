@@ -0,0 +1,21 @@
+use std::io::Error as IOError;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::signals::request_refresh;
+use crate::utils::file_watcher::watch_file;
+
+// Set when the config file was written, the main loop reloads the config when it is set
+static CONFIG_CHANGED: AtomicBool = AtomicBool::new(false);
+
+// Watch the config file for changes with inotify
+pub fn watch_config_file(path: &Path) -> Result<(), IOError> {
+    watch_file(path, || {
+        CONFIG_CHANGED.store(true, Ordering::SeqCst);
+        request_refresh();
+    })
+}
+
+pub fn take_config_change() -> bool {
+    CONFIG_CHANGED.swap(false, Ordering::SeqCst)
+}
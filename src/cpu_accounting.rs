@@ -0,0 +1,86 @@
+use libc::{clock_gettime, timespec, CLOCK_THREAD_CPUTIME_ID};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::WidgetId;
+use crate::i3_status::CONFIG;
+
+// CPU time is summed up over this long, and compared to the budget per minute afterwards
+const WINDOW: Duration = Duration::from_secs(60);
+
+// CPU time per minute of every widget in its last full window, by "name:instance"
+static CPU_TIMES: Mutex<BTreeMap<String, Duration>> = Mutex::new(BTreeMap::new());
+
+// CPU time that the calling thread used so far
+// Every executor runs in its own thread, so this only counts the time of the widget
+pub fn thread_cpu_time() -> Duration {
+    let mut time = timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    if unsafe { clock_gettime(CLOCK_THREAD_CPUTIME_ID, &mut time) } != 0 {
+        return Duration::ZERO;
+    }
+    Duration::new(time.tv_sec as u64, time.tv_nsec as u32)
+}
+
+// CPU time that an executor spent in its widget
+// Background threads that a widget starts (e.g. for HTTP requests) are not counted
+pub struct CpuAccount {
+    used: Duration,
+    window_start: Instant,
+    // Whether the widget was over its budget in the last window, so it is only logged once
+    over_budget: bool,
+}
+
+impl CpuAccount {
+    pub fn new() -> Self {
+        Self {
+            used: Duration::ZERO,
+            window_start: Instant::now(),
+            over_budget: false,
+        }
+    }
+
+    pub fn add(&mut self, id: &WidgetId, used: Duration) {
+        self.used += used;
+        let elapsed = self.window_start.elapsed();
+        if elapsed < WINDOW {
+            return;
+        }
+
+        // Windows can be longer than a minute for widgets that are not updated every second
+        let per_minute = self
+            .used
+            .mul_f64(WINDOW.as_secs_f64() / elapsed.as_secs_f64());
+        let budget = Duration::from_millis(CONFIG.widget_cpu_budget_ms());
+        let over_budget = per_minute > budget;
+        if over_budget && !self.over_budget {
+            log::warn!(
+                "{} used {:?} of CPU time per minute, more than its budget of {:?}",
+                id,
+                per_minute,
+                budget
+            );
+        }
+        self.over_budget = over_budget;
+        CPU_TIMES.lock().unwrap().insert(id.to_string(), per_minute);
+        self.used = Duration::ZERO;
+        self.window_start = Instant::now();
+    }
+}
+
+// CPU time per minute of every widget that was running for at least a minute
+pub fn cpu_times() -> Vec<(String, Duration)> {
+    CPU_TIMES
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(id, time)| (id.clone(), *time))
+        .collect()
+}
+
+pub fn forget_cpu_time(id: &WidgetId) {
+    CPU_TIMES.lock().unwrap().remove(&id.to_string());
+}
@@ -0,0 +1,937 @@
+use std::env;
+use std::io::{Error as IOError, ErrorKind, Read, Write};
+#[cfg(target_os = "linux")]
+use std::os::linux::net::SocketAddrExt;
+#[cfg(target_os = "linux")]
+use std::os::unix::net::SocketAddr;
+use std::os::unix::net::UnixStream;
+use std::thread;
+use std::time::Duration;
+
+use crate::config::{is_hex_color, Markup, WidgetId};
+use crate::i3_status::{WidgetExecutors, CONFIG};
+use crate::output::escape_markup;
+use crate::overrides::{clear_override, set_override};
+use crate::signals::{request_refresh, request_render, set_paused};
+use crate::theme::Color;
+use crate::widget_executor::request_update;
+
+// A small D-Bus service, so scripts and other desktop components can control the bar:
+//
+// busctl --user call org.i3rustus /org/i3rustus org.i3rustus.Bar Refresh s "disk:home"
+// busctl --user call org.i3rustus /org/i3rustus org.i3rustus.Bar SetOverride sssu \
+//     "time" "Meeting in 5 minutes" "warning" 300
+//
+// Only the parts of the protocol that the service needs are implemented
+// See https://dbus.freedesktop.org/doc/dbus-specification.html
+const BUS_NAME: &str = "org.i3rustus";
+const OBJECT_PATH: &str = "/org/i3rustus";
+const INTERFACE: &str = "org.i3rustus.Bar";
+
+const INTROSPECTION: &str = r#"<!DOCTYPE node PUBLIC "-//freedesktop//DTD D-BUS Object Introspection 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd">
+<node>
+  <interface name="org.i3rustus.Bar">
+    <!-- Update a widget ("name" or "name:instance") right away, all widgets if it is empty -->
+    <method name="Refresh">
+      <arg name="widget" type="s" direction="in"/>
+    </method>
+    <!-- Stop / start updating the widgets -->
+    <method name="Pause"/>
+    <method name="Resume"/>
+    <!-- Show a text instead of the block of a widget, the color is a name like "warning",
+         a hex value or empty to keep the color of the widget. 0 seconds keeps the text
+         until ClearOverride is called -->
+    <method name="SetOverride">
+      <arg name="widget" type="s" direction="in"/>
+      <arg name="text" type="s" direction="in"/>
+      <arg name="color" type="s" direction="in"/>
+      <arg name="seconds" type="u" direction="in"/>
+    </method>
+    <method name="ClearOverride">
+      <arg name="widget" type="s" direction="in"/>
+    </method>
+  </interface>
+  <interface name="org.freedesktop.DBus.Introspectable">
+    <method name="Introspect">
+      <arg name="xml" type="s" direction="out"/>
+    </method>
+  </interface>
+  <interface name="org.freedesktop.DBus.Peer">
+    <method name="Ping"/>
+  </interface>
+</node>
+"#;
+
+// Message types
+const METHOD_CALL: u8 = 1;
+const METHOD_RETURN: u8 = 2;
+const ERROR: u8 = 3;
+
+// Header fields
+const FIELD_PATH: u8 = 1;
+const FIELD_INTERFACE: u8 = 2;
+const FIELD_MEMBER: u8 = 3;
+const FIELD_ERROR_NAME: u8 = 4;
+const FIELD_REPLY_SERIAL: u8 = 5;
+const FIELD_DESTINATION: u8 = 6;
+const FIELD_SENDER: u8 = 7;
+const FIELD_SIGNATURE: u8 = 8;
+
+const NO_REPLY_EXPECTED: u8 = 0x1;
+// Fail instead of waiting in the queue if another bar already owns the name
+const DO_NOT_QUEUE: u32 = 0x4;
+const PRIMARY_OWNER: u32 = 1;
+// Messages are much smaller, this only protects against a broken bus
+const MAX_MESSAGE_LENGTH: usize = 1 << 20;
+const AUTH_TIMEOUT: Duration = Duration::from_secs(5);
+
+const UNKNOWN_METHOD: &str = "org.freedesktop.DBus.Error.UnknownMethod";
+const INVALID_ARGS: &str = "org.freedesktop.DBus.Error.InvalidArgs";
+const UNKNOWN_WIDGET: &str = "org.i3rustus.Error.UnknownWidget";
+
+// Serializes values in the little endian wire format, every value is aligned to its size
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn align(&mut self, alignment: usize) {
+        while !self.bytes.len().is_multiple_of(alignment) {
+            self.bytes.push(0);
+        }
+    }
+
+    fn byte(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.align(4);
+        self.bytes.extend(value.to_le_bytes());
+    }
+
+    // Strings and object paths
+    fn string(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.bytes.extend(value.as_bytes());
+        self.bytes.push(0);
+    }
+
+    fn signature(&mut self, value: &str) {
+        self.byte(value.len() as u8);
+        self.bytes.extend(value.as_bytes());
+        self.bytes.push(0);
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    big_endian: bool,
+}
+
+impl Reader<'_> {
+    fn align(&mut self, alignment: usize) {
+        self.position = self.position.next_multiple_of(alignment);
+    }
+
+    fn take(&mut self, length: usize) -> Option<&[u8]> {
+        let bytes = self.bytes.get(self.position..self.position + length)?;
+        self.position += length;
+        Some(bytes)
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.align(4);
+        let bytes = self.take(4)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    // Strings are followed by a NUL byte that is not part of the length
+    fn string(&mut self) -> Option<String> {
+        let length = self.u32()? as usize;
+        let string = String::from_utf8(self.take(length)?.to_vec()).ok();
+        self.take(1)?;
+        string
+    }
+
+    fn signature(&mut self) -> Option<String> {
+        let length = self.byte()? as usize;
+        let signature = String::from_utf8(self.take(length)?.to_vec()).ok();
+        self.take(1)?;
+        signature
+    }
+}
+
+// Arguments of a message, the service only uses strings, integers and arrays of strings
+enum Argument {
+    String(String),
+    U32(u32),
+    Strings(Vec<String>),
+}
+
+#[derive(Default)]
+struct Message {
+    kind: u8,
+    flags: u8,
+    serial: u32,
+    path: Option<String>,
+    interface: Option<String>,
+    member: Option<String>,
+    error_name: Option<String>,
+    reply_serial: Option<u32>,
+    sender: Option<String>,
+    signature: String,
+    body: Vec<u8>,
+    big_endian: bool,
+}
+
+impl Message {
+    fn arguments(&self) -> Option<Vec<Argument>> {
+        let mut reader = Reader {
+            bytes: &self.body,
+            position: 0,
+            big_endian: self.big_endian,
+        };
+        let mut arguments = Vec::new();
+        let mut kinds = self.signature.chars();
+        while let Some(kind) = kinds.next() {
+            arguments.push(match kind {
+                's' | 'o' => Argument::String(reader.string()?),
+                'g' => Argument::String(reader.signature()?),
+                'u' => Argument::U32(reader.u32()?),
+                // Arrays start with their length in bytes, the strings are aligned to 4
+                'a' if kinds.next() == Some('s') => {
+                    let length = reader.u32()? as usize;
+                    let end = reader.position + length;
+                    let mut strings = Vec::new();
+                    while reader.position < end {
+                        strings.push(reader.string()?);
+                    }
+                    Argument::Strings(strings)
+                }
+                _ => return None,
+            });
+        }
+
+        Some(arguments)
+    }
+}
+
+struct Connection {
+    stream: UnixStream,
+    serial: u32,
+}
+
+// Addresses escape bytes as %XX
+fn unescape(value: &str) -> String {
+    let mut bytes = Vec::new();
+    let mut rest = value.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        match tail
+            .get(..2)
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok())
+        {
+            Some(escaped) if byte == b'%' => {
+                bytes.push(escaped);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+// e.g. "unix:path=/run/user/1000/bus" or "unix:abstract=/tmp/dbus-XYZ,guid=..."
+// Several addresses are separated by semicolons, the first one that works is used
+fn connect_to_session_bus() -> Result<UnixStream, IOError> {
+    let address = env::var("DBUS_SESSION_BUS_ADDRESS").unwrap_or_else(|_| {
+        // Not every session sets XDG_RUNTIME_DIR, e.g. without pam_systemd
+        let runtime_directory = env::var("XDG_RUNTIME_DIR")
+            .ok()
+            .filter(|directory| !directory.is_empty())
+            // SAFETY: getuid can not fail
+            .unwrap_or_else(|| format!("/run/user/{}", unsafe { libc::getuid() }));
+        format!("unix:path={}/bus", runtime_directory)
+    });
+    for address in address.split(';') {
+        let Some(options) = address.strip_prefix("unix:") else {
+            continue;
+        };
+        for option in options.split(',') {
+            let stream = match option.split_once('=') {
+                Some(("path", path)) => UnixStream::connect(unescape(path)),
+                // Abstract sockets only exist on Linux
+                #[cfg(target_os = "linux")]
+                Some(("abstract", name)) => {
+                    SocketAddr::from_abstract_name(unescape(name).as_bytes())
+                        .and_then(|address| UnixStream::connect_addr(&address))
+                }
+                _ => continue,
+            };
+            if let Ok(stream) = stream {
+                return Ok(stream);
+            }
+        }
+    }
+
+    Err(IOError::new(
+        ErrorKind::NotFound,
+        format!("No session bus at {}", address),
+    ))
+}
+
+// A message in the little endian wire format, the header fields are (code, type, value)
+fn encode_message(
+    kind: u8,
+    serial: u32,
+    fields: &[(u8, char, &str)],
+    reply_serial: Option<u32>,
+    signature: &str,
+    body: &[u8],
+) -> Vec<u8> {
+    let mut message = Writer::default();
+    message.byte(b'l');
+    message.byte(kind);
+    message.byte(0);
+    // Protocol version
+    message.byte(1);
+    message.u32(body.len() as u32);
+    message.u32(serial);
+
+    // Array of (field code, variant), the length is filled in afterwards
+    message.u32(0);
+    message.align(8);
+    let start = message.bytes.len();
+    let write_field = |message: &mut Writer, code: u8, kind: char, value: &str| {
+        message.align(8);
+        message.byte(code);
+        message.signature(&kind.to_string());
+        match kind {
+            'g' => message.signature(value),
+            _ => message.string(value),
+        }
+    };
+    for (code, kind, value) in fields {
+        write_field(&mut message, *code, *kind, value);
+    }
+    if !signature.is_empty() {
+        write_field(&mut message, FIELD_SIGNATURE, 'g', signature);
+    }
+    if let Some(reply_serial) = reply_serial {
+        message.align(8);
+        message.byte(FIELD_REPLY_SERIAL);
+        message.signature("u");
+        message.u32(reply_serial);
+    }
+    let length = (message.bytes.len() - start) as u32;
+    message.bytes[12..16].copy_from_slice(&length.to_le_bytes());
+    message.align(8);
+    message.bytes.extend(body);
+    message.bytes
+}
+
+// Read one message, messages of both byte orders are accepted
+fn read_message(stream: &mut impl Read) -> Result<Message, IOError> {
+    let invalid = || IOError::new(ErrorKind::InvalidData, "Invalid D-Bus message");
+    let mut fixed = [0; 16];
+    stream.read_exact(&mut fixed)?;
+    let big_endian = match fixed[0] {
+        b'l' => false,
+        b'B' => true,
+        _ => return Err(invalid()),
+    };
+    let number = |bytes: &[u8]| {
+        let bytes = bytes.try_into().unwrap();
+        if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    };
+    let body_length = number(&fixed[4..8]) as usize;
+    let fields_length = number(&fixed[12..16]) as usize;
+    let header_length = (16 + fields_length).next_multiple_of(8);
+    if header_length + body_length > MAX_MESSAGE_LENGTH {
+        return Err(invalid());
+    }
+    let mut bytes = fixed.to_vec();
+    bytes.resize(header_length + body_length, 0);
+    stream.read_exact(&mut bytes[16..])?;
+
+    let mut message = Message {
+        kind: fixed[1],
+        flags: fixed[2],
+        serial: number(&fixed[8..12]),
+        body: bytes[header_length..].to_vec(),
+        big_endian,
+        ..Message::default()
+    };
+    let mut reader = Reader {
+        bytes: &bytes[..16 + fields_length],
+        position: 16,
+        big_endian,
+    };
+    while reader.position < 16 + fields_length {
+        reader.align(8);
+        let code = reader.byte().ok_or_else(invalid)?;
+        let kind = reader.signature().ok_or_else(invalid)?;
+        let value = match kind.as_str() {
+            "s" | "o" => reader.string().map(Argument::String),
+            "g" => reader.signature().map(Argument::String),
+            "u" => reader.u32().map(Argument::U32),
+            _ => None,
+        }
+        .ok_or_else(invalid)?;
+        match (code, value) {
+            (FIELD_PATH, Argument::String(value)) => message.path = Some(value),
+            (FIELD_INTERFACE, Argument::String(value)) => message.interface = Some(value),
+            (FIELD_MEMBER, Argument::String(value)) => message.member = Some(value),
+            (FIELD_ERROR_NAME, Argument::String(value)) => message.error_name = Some(value),
+            (FIELD_REPLY_SERIAL, Argument::U32(value)) => message.reply_serial = Some(value),
+            (FIELD_SENDER, Argument::String(value)) => message.sender = Some(value),
+            (FIELD_SIGNATURE, Argument::String(value)) => message.signature = value,
+            _ => (),
+        }
+    }
+
+    Ok(message)
+}
+
+impl Connection {
+    fn open() -> Result<Self, IOError> {
+        let mut connection = Self {
+            stream: connect_to_session_bus()?,
+            serial: 0,
+        };
+        connection.authenticate()?;
+        connection.call_bus("Hello", "", Vec::new())?;
+
+        Ok(connection)
+    }
+
+    // The bus knows who we are from the socket, we only have to tell it our user ID
+    fn authenticate(&mut self) -> Result<(), IOError> {
+        self.stream.set_read_timeout(Some(AUTH_TIMEOUT))?;
+        // SAFETY: getuid can not fail
+        let uid = unsafe { libc::getuid() }.to_string();
+        let uid: String = uid.bytes().map(|byte| format!("{:02x}", byte)).collect();
+        self.stream
+            .write_all(format!("\0AUTH EXTERNAL {}\r\n", uid).as_bytes())?;
+
+        let mut response = Vec::new();
+        let mut byte = [0];
+        while !response.ends_with(b"\r\n") {
+            self.stream.read_exact(&mut byte)?;
+            response.push(byte[0]);
+        }
+        if !response.starts_with(b"OK ") {
+            return Err(IOError::new(
+                ErrorKind::PermissionDenied,
+                format!(
+                    "Authentication failed: {}",
+                    String::from_utf8_lossy(&response).trim()
+                ),
+            ));
+        }
+        self.stream.write_all(b"BEGIN\r\n")?;
+        self.stream.set_read_timeout(None)
+    }
+
+    fn send(
+        &mut self,
+        kind: u8,
+        fields: &[(u8, char, &str)],
+        reply_serial: Option<u32>,
+        signature: &str,
+        body: &[u8],
+    ) -> Result<u32, IOError> {
+        self.serial += 1;
+        let message = encode_message(kind, self.serial, fields, reply_serial, signature, body);
+        self.stream.write_all(&message)?;
+        Ok(self.serial)
+    }
+
+    fn receive(&mut self) -> Result<Message, IOError> {
+        read_message(&mut self.stream)
+    }
+
+    // Call a method of the bus itself and wait for the reply
+    fn call_bus(
+        &mut self,
+        member: &str,
+        signature: &str,
+        body: Vec<u8>,
+    ) -> Result<Message, IOError> {
+        let serial = self.send(
+            METHOD_CALL,
+            &[
+                (FIELD_PATH, 'o', "/org/freedesktop/DBus"),
+                (FIELD_INTERFACE, 's', "org.freedesktop.DBus"),
+                (FIELD_MEMBER, 's', member),
+                (FIELD_DESTINATION, 's', "org.freedesktop.DBus"),
+            ],
+            None,
+            signature,
+            &body,
+        )?;
+        // Signals (e.g. NameAcquired) can arrive before the reply
+        loop {
+            let message = self.receive()?;
+            if message.reply_serial != Some(serial) {
+                continue;
+            }
+            return match message.kind {
+                METHOD_RETURN => Ok(message),
+                _ => Err(IOError::other(format!(
+                    "{} failed: {}",
+                    member,
+                    message.error_name.unwrap_or_default()
+                ))),
+            };
+        }
+    }
+
+    fn reply(&mut self, call: &Message, result: Result<Option<String>, (&str, String)>) {
+        let Some(sender) = &call.sender else {
+            return;
+        };
+        let mut body = Writer::default();
+        let sent = match result {
+            Ok(Some(text)) => {
+                body.string(&text);
+                self.send(
+                    METHOD_RETURN,
+                    &[(FIELD_DESTINATION, 's', sender)],
+                    Some(call.serial),
+                    "s",
+                    &body.bytes,
+                )
+            }
+            Ok(None) => self.send(
+                METHOD_RETURN,
+                &[(FIELD_DESTINATION, 's', sender)],
+                Some(call.serial),
+                "",
+                &[],
+            ),
+            Err((name, text)) => {
+                body.string(&text);
+                self.send(
+                    ERROR,
+                    &[
+                        (FIELD_DESTINATION, 's', sender),
+                        (FIELD_ERROR_NAME, 's', name),
+                    ],
+                    Some(call.serial),
+                    "s",
+                    &body.bytes,
+                )
+            }
+        };
+        if let Err(error) = sent {
+            log::warn!("Could not answer a D-Bus call: {}", error);
+        }
+    }
+}
+
+fn find_widget(
+    executors: &WidgetExecutors,
+    widget: &str,
+) -> Result<WidgetId, (&'static str, String)> {
+    let id = WidgetId::parse(widget);
+    if executors.read().unwrap().contains_key(&id) {
+        Ok(id)
+    } else {
+        Err((
+            UNKNOWN_WIDGET,
+            format!("{} is not shown in the bar", widget),
+        ))
+    }
+}
+
+// Hex value of a color name (e.g. "warning") or of a hex value, None keeps the color of the widget
+fn parse_color(color: &str) -> Result<Option<String>, (&'static str, String)> {
+    if color.is_empty() {
+        return Ok(None);
+    }
+    if let Some(color) = Color::from_name(color) {
+        return Ok(Some(color.hex().to_string()));
+    }
+    if is_hex_color(color) {
+        return Ok(Some(color.to_string()));
+    }
+    Err((INVALID_ARGS, format!("Invalid color {}", color)))
+}
+
+// Returns the text of the reply, or the name and message of an error
+fn handle_call(
+    call: &Message,
+    executors: &WidgetExecutors,
+) -> Result<Option<String>, (&'static str, String)> {
+    let path = call.path.as_deref().unwrap_or_default();
+    let member = call.member.as_deref().unwrap_or_default();
+    // The interface is optional in method calls
+    let interface = call.interface.as_deref();
+    let arguments = call
+        .arguments()
+        .ok_or_else(|| (INVALID_ARGS, String::from("Invalid arguments")))?;
+    let expect = |signature: &str| {
+        if call.signature == signature {
+            Ok(())
+        } else {
+            Err((
+                INVALID_ARGS,
+                format!(
+                    "{} expects ({}), got ({})",
+                    member, signature, call.signature
+                ),
+            ))
+        }
+    };
+
+    match (interface, member) {
+        (None | Some("org.freedesktop.DBus.Peer"), "Ping") => Ok(None),
+        (None | Some("org.freedesktop.DBus.Introspectable"), "Introspect") => {
+            // Parents of the object only list their child, so tools can walk the tree
+            let introspection = match OBJECT_PATH.strip_prefix(path) {
+                Some("") => INTROSPECTION.to_string(),
+                Some(rest) => {
+                    let rest = rest.trim_start_matches('/');
+                    let child = rest.split('/').next().unwrap_or(rest);
+                    format!("<node>\n  <node name=\"{}\"/>\n</node>\n", child)
+                }
+                None => String::from("<node/>\n"),
+            };
+            Ok(Some(introspection))
+        }
+        _ if path != OBJECT_PATH || interface.is_some_and(|interface| interface != INTERFACE) => {
+            Err((UNKNOWN_METHOD, format!("No method {} at {}", member, path)))
+        }
+        (_, "Refresh") => {
+            expect("s")?;
+            match &arguments[..] {
+                [Argument::String(widget)] if widget.is_empty() => request_refresh(),
+                [Argument::String(widget)] => {
+                    let id = find_widget(executors, widget)?;
+                    if let Some(executor) = executors.read().unwrap().get(&id) {
+                        request_update(&id, executor);
+                    }
+                    request_render();
+                }
+                _ => unreachable!(),
+            }
+            Ok(None)
+        }
+        (_, "Pause") => {
+            expect("")?;
+            set_paused(true);
+            Ok(None)
+        }
+        (_, "Resume") => {
+            expect("")?;
+            set_paused(false);
+            request_refresh();
+            Ok(None)
+        }
+        (_, "SetOverride") => {
+            expect("sssu")?;
+            let [Argument::String(widget), Argument::String(text), Argument::String(color), Argument::U32(seconds)] =
+                &arguments[..]
+            else {
+                unreachable!()
+            };
+            let id = find_widget(executors, widget)?;
+            let duration = (*seconds > 0).then(|| Duration::from_secs(u64::from(*seconds)));
+            // The text replaces full_text, callers can't know that the block is pango markup
+            let text = if CONFIG.block_config(widget).markup == Some(Markup::Pango) {
+                escape_markup(text)
+            } else {
+                text.clone()
+            };
+            set_override(&id, text, parse_color(color)?, duration);
+            request_render();
+            Ok(None)
+        }
+        (_, "ClearOverride") => {
+            expect("s")?;
+            let [Argument::String(widget)] = &arguments[..] else {
+                unreachable!()
+            };
+            if !clear_override(&WidgetId::parse(widget)) {
+                return Err((UNKNOWN_WIDGET, format!("{} has no override", widget)));
+            }
+            request_render();
+            Ok(None)
+        }
+        _ => Err((
+            UNKNOWN_METHOD,
+            format!("No method {} in {}", member, INTERFACE),
+        )),
+    }
+}
+
+fn serve(executors: &WidgetExecutors) -> Result<(), IOError> {
+    let mut connection = Connection::open()?;
+    let mut name = Writer::default();
+    name.string(BUS_NAME);
+    name.u32(DO_NOT_QUEUE);
+    let reply = connection.call_bus("RequestName", "su", name.bytes)?;
+    match reply.arguments().as_deref() {
+        Some([Argument::U32(PRIMARY_OWNER)]) => (),
+        _ => {
+            return Err(IOError::new(
+                ErrorKind::AddrInUse,
+                format!("{} is owned by another process", BUS_NAME),
+            ))
+        }
+    }
+
+    loop {
+        let message = connection.receive()?;
+        if message.kind != METHOD_CALL {
+            continue;
+        }
+        let result = handle_call(&message, executors);
+        if message.flags & NO_REPLY_EXPECTED == 0 {
+            connection.reply(&message, result);
+        }
+    }
+}
+
+// Well-known names on the session bus (e.g. "org.freedesktop.Notifications")
+pub fn session_bus_names() -> Result<Vec<String>, IOError> {
+    let reply = Connection::open()?.call_bus("ListNames", "", Vec::new())?;
+    match reply.arguments() {
+        Some(arguments) => match arguments.into_iter().next() {
+            // Unique names like ":1.42" belong to single connections
+            Some(Argument::Strings(names)) => Ok(names
+                .into_iter()
+                .filter(|name| !name.starts_with(':'))
+                .collect()),
+            _ => Err(IOError::new(
+                ErrorKind::InvalidData,
+                "Invalid ListNames reply",
+            )),
+        },
+        None => Err(IOError::new(
+            ErrorKind::InvalidData,
+            "Invalid ListNames reply",
+        )),
+    }
+}
+
+// Serve the D-Bus interface on the session bus
+// Reading from the bus is blocking, so this runs in a dedicated thread
+pub fn listen_for_dbus_calls(executors: WidgetExecutors) {
+    if !CONFIG.dbus_interface() {
+        return;
+    }
+    thread::spawn(move || {
+        if let Err(error) = serve(&executors) {
+            log::warn!("The D-Bus interface is not available: {}", error);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(write: impl FnOnce(&mut Writer)) -> Vec<u8> {
+        let mut writer = Writer::default();
+        write(&mut writer);
+        writer.bytes
+    }
+
+    #[test]
+    fn method_call_round_trips() {
+        let body = body(|writer| {
+            writer.string("time");
+            writer.string("Meeting <now>");
+            writer.string("");
+            writer.u32(300);
+        });
+        let bytes = encode_message(
+            METHOD_CALL,
+            7,
+            &[
+                (FIELD_PATH, 'o', OBJECT_PATH),
+                (FIELD_INTERFACE, 's', INTERFACE),
+                (FIELD_MEMBER, 's', "SetOverride"),
+                (FIELD_SENDER, 's', ":1.42"),
+            ],
+            None,
+            "sssu",
+            &body,
+        );
+        // The body starts at a multiple of 8 after the header
+        assert!(bytes.ends_with(&body));
+        assert_eq!((bytes.len() - body.len()) % 8, 0);
+
+        let message = read_message(&mut &bytes[..]).unwrap();
+        assert_eq!(message.kind, METHOD_CALL);
+        assert_eq!(message.serial, 7);
+        assert_eq!(message.path.as_deref(), Some(OBJECT_PATH));
+        assert_eq!(message.interface.as_deref(), Some(INTERFACE));
+        assert_eq!(message.member.as_deref(), Some("SetOverride"));
+        assert_eq!(message.sender.as_deref(), Some(":1.42"));
+        assert_eq!(message.signature, "sssu");
+        assert_eq!(message.body, body);
+        match message.arguments().as_deref() {
+            Some(
+                [Argument::String(widget), Argument::String(text), Argument::String(color), Argument::U32(seconds)],
+            ) => {
+                assert_eq!(widget, "time");
+                assert_eq!(text, "Meeting <now>");
+                assert_eq!(color, "");
+                assert_eq!(*seconds, 300);
+            }
+            _ => panic!("Unexpected arguments"),
+        }
+    }
+
+    #[test]
+    fn error_reply_round_trips() {
+        let body = body(|writer| writer.string("disk:home is not shown in the bar"));
+        let bytes = encode_message(
+            ERROR,
+            3,
+            &[
+                (FIELD_DESTINATION, 's', ":1.42"),
+                (FIELD_ERROR_NAME, 's', UNKNOWN_WIDGET),
+            ],
+            Some(7),
+            "s",
+            &body,
+        );
+
+        let message = read_message(&mut &bytes[..]).unwrap();
+        assert_eq!(message.kind, ERROR);
+        assert_eq!(message.reply_serial, Some(7));
+        assert_eq!(message.error_name.as_deref(), Some(UNKNOWN_WIDGET));
+        assert!(matches!(
+            message.arguments().as_deref(),
+            Some([Argument::String(text)]) if text == "disk:home is not shown in the bar"
+        ));
+    }
+
+    #[test]
+    fn message_without_body_round_trips() {
+        let bytes = encode_message(
+            METHOD_CALL,
+            1,
+            &[(FIELD_MEMBER, 's', "Pause")],
+            None,
+            "",
+            &[],
+        );
+        assert_eq!(bytes.len() % 8, 0);
+
+        let message = read_message(&mut &bytes[..]).unwrap();
+        assert_eq!(message.member.as_deref(), Some("Pause"));
+        assert_eq!(message.signature, "");
+        assert!(matches!(message.arguments().as_deref(), Some([])));
+    }
+
+    #[test]
+    fn big_endian_messages_are_read() {
+        let mut bytes = vec![b'B', METHOD_CALL, 0, 1];
+        bytes.extend(4u32.to_be_bytes());
+        bytes.extend(9u32.to_be_bytes());
+        // The member field: code, signature "s", then the string
+        bytes.extend(13u32.to_be_bytes());
+        bytes.extend([FIELD_MEMBER, 1, b's', 0]);
+        bytes.extend(4u32.to_be_bytes());
+        bytes.extend(b"Ping\0");
+        bytes.resize(32, 0);
+        // Body "u" of 42
+        bytes.extend(42u32.to_be_bytes());
+
+        let mut message = read_message(&mut &bytes[..]).unwrap();
+        assert_eq!(message.serial, 9);
+        assert_eq!(message.member.as_deref(), Some("Ping"));
+        message.signature = String::from("u");
+        assert!(matches!(
+            message.arguments().as_deref(),
+            Some([Argument::U32(42)])
+        ));
+    }
+
+    #[test]
+    fn arrays_of_strings_are_read() {
+        let message = Message {
+            signature: String::from("as"),
+            body: body(|writer| {
+                // 4 + 1 + NUL, padding to 4, 4 + 2 + NUL
+                writer.u32(15);
+                writer.string("a");
+                writer.string("bc");
+            }),
+            ..Message::default()
+        };
+        match message.arguments().as_deref() {
+            Some([Argument::Strings(strings)]) => assert_eq!(strings, &["a", "bc"]),
+            _ => panic!("Unexpected arguments"),
+        }
+    }
+
+    #[test]
+    fn broken_messages_are_rejected() {
+        let bytes = encode_message(
+            METHOD_CALL,
+            1,
+            &[(FIELD_MEMBER, 's', "Ping")],
+            None,
+            "",
+            &[],
+        );
+        let truncated = read_message(&mut &bytes[..bytes.len() - 1]).err().unwrap();
+        assert_eq!(truncated.kind(), ErrorKind::UnexpectedEof);
+
+        let mut wrong_order = bytes.clone();
+        wrong_order[0] = b'x';
+        let error = read_message(&mut &wrong_order[..]).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+        let mut too_long = bytes.clone();
+        too_long[4..8].copy_from_slice(&(MAX_MESSAGE_LENGTH as u32).to_le_bytes());
+        let error = read_message(&mut &too_long[..]).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+        // A string that is longer than the body
+        let message = Message {
+            signature: String::from("s"),
+            body: body(|writer| writer.u32(100)),
+            ..Message::default()
+        };
+        assert!(message.arguments().is_none());
+        let message = Message {
+            signature: String::from("x"),
+            ..Message::default()
+        };
+        assert!(message.arguments().is_none());
+    }
+
+    #[test]
+    fn addresses_are_unescaped() {
+        assert_eq!(unescape("/run/user/1000/bus"), "/run/user/1000/bus");
+        assert_eq!(unescape("/tmp/dbus%2dXYZ"), "/tmp/dbus-XYZ");
+        // Incomplete escapes are kept as they are
+        assert_eq!(unescape("100%"), "100%");
+        assert_eq!(unescape("%zz"), "%zz");
+    }
+}
@@ -0,0 +1,136 @@
+use libc::{c_ulong, ioctl};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Error as IOError, Read, Write};
+use std::os::unix::io::AsRawFd;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// See https://www.kernel.org/doc/html/latest/i2c/dev-interface.html
+const I2C_SLAVE: c_ulong = 0x0703;
+// DDC/CI address of the monitor
+const DDC_CI_ADDRESS: c_ulong = 0x37;
+// Source address of the host and the address used for the reply checksum
+const HOST_ADDRESS: u8 = 0x51;
+const DESTINATION_ADDRESS: u8 = 0x6E;
+const REPLY_CHECKSUM_ADDRESS: u8 = 0x50;
+// DDC/CI opcodes
+const GET_VCP_FEATURE: u8 = 0x01;
+const GET_VCP_FEATURE_REPLY: u8 = 0x02;
+const SET_VCP_FEATURE: u8 = 0x03;
+// Monitors need some time to process a request before they can answer
+// See "DDC/CI Standard" version 1.1, section 4.4
+const REPLY_DELAY: Duration = Duration::from_millis(40);
+
+// VCP feature codes from the MCCS standard
+pub const VCP_BRIGHTNESS: u8 = 0x10;
+pub const VCP_CONTRAST: u8 = 0x12;
+
+#[derive(Debug, PartialEq)]
+pub struct VcpValue {
+    pub current: u16,
+    pub maximum: u16,
+}
+
+// One lock per i2c device, the brightness and external_monitor widgets can talk to the
+// same monitor and a reply must not be read by the widget that did not ask for it
+static BUSES: Mutex<Option<HashMap<String, Arc<Mutex<()>>>>> = Mutex::new(None);
+
+fn bus_lock(i2c_device: &str) -> Arc<Mutex<()>> {
+    let mut buses = BUSES.lock().unwrap();
+    Arc::clone(
+        buses
+            .get_or_insert_with(HashMap::new)
+            .entry(i2c_device.to_string())
+            .or_default(),
+    )
+}
+
+// An external monitor that is connected through an i2c bus (e.g. /dev/i2c-4)
+// The user needs read and write access to the device, which is usually
+// granted by the i2c group and the i2c-dev kernel module
+#[derive(Debug)]
+pub struct DdcMonitor {
+    device: File,
+    // Held for a whole request, from writing it to reading the reply
+    bus: Arc<Mutex<()>>,
+}
+
+impl DdcMonitor {
+    pub fn open(i2c_device: &str) -> Result<Self, IOError> {
+        let device = OpenOptions::new().read(true).write(true).open(i2c_device)?;
+
+        if unsafe { ioctl(device.as_raw_fd(), I2C_SLAVE as _, DDC_CI_ADDRESS) } < 0 {
+            return Err(IOError::last_os_error());
+        }
+
+        Ok(Self {
+            device,
+            bus: bus_lock(i2c_device),
+        })
+    }
+
+    // The checksum is a XOR over all bytes of the message, including the destination address
+    fn checksum(initial: u8, message: &[u8]) -> u8 {
+        message
+            .iter()
+            .fold(initial, |checksum, byte| checksum ^ byte)
+    }
+
+    fn write_message(&mut self, payload: &[u8]) -> Result<(), IOError> {
+        // Length byte has the highest bit set
+        let mut message = vec![HOST_ADDRESS, 0x80 | payload.len() as u8];
+        message.extend(payload);
+        message.push(Self::checksum(DESTINATION_ADDRESS, &message));
+        self.device.write_all(&message)?;
+        thread::sleep(REPLY_DELAY);
+
+        Ok(())
+    }
+
+    pub fn get_vcp_feature(&mut self, feature: u8) -> Result<VcpValue, IOError> {
+        let bus = Arc::clone(&self.bus);
+        let _bus = bus.lock().unwrap_or_else(|error| error.into_inner());
+        self.write_message(&[GET_VCP_FEATURE, feature])?;
+
+        // Source address, length, opcode, result code, feature, type,
+        // maximum (2 bytes), current value (2 bytes), checksum
+        let mut reply = [0; 11];
+        self.device.read_exact(&mut reply)?;
+
+        if Self::checksum(REPLY_CHECKSUM_ADDRESS, &reply[..10]) != reply[10] {
+            return Err(IOError::other("Invalid DDC/CI checksum"));
+        }
+        if reply[2] != GET_VCP_FEATURE_REPLY || reply[4] != feature {
+            return Err(IOError::other("Unexpected DDC/CI reply"));
+        }
+        if reply[3] != 0 {
+            return Err(IOError::other(format!(
+                "Monitor does not support VCP feature {:#04x}",
+                feature
+            )));
+        }
+
+        let maximum = u16::from_be_bytes([reply[6], reply[7]]);
+        // Percentages are relative to the maximum
+        if maximum == 0 {
+            return Err(IOError::other(format!(
+                "Monitor reports a maximum of 0 for VCP feature {:#04x}",
+                feature
+            )));
+        }
+
+        Ok(VcpValue {
+            maximum,
+            current: u16::from_be_bytes([reply[8], reply[9]]),
+        })
+    }
+
+    pub fn set_vcp_feature(&mut self, feature: u8, value: u16) -> Result<(), IOError> {
+        let bus = Arc::clone(&self.bus);
+        let _bus = bus.lock().unwrap_or_else(|error| error.into_inner());
+        let [value_high, value_low] = value.to_be_bytes();
+        self.write_message(&[SET_VCP_FEATURE, feature, value_high, value_low])
+    }
+}
@@ -0,0 +1,250 @@
+use libc::{access, R_OK, W_OK};
+use std::ffi::CString;
+use std::fs::{read_dir, read_to_string};
+use std::path::Path;
+
+use crate::config::WidgetId;
+use crate::config_check::check_config;
+use crate::dbus::session_bus_names;
+use crate::i3_status::{I3Status, CONFIG, GROUP};
+use crate::output::OutputFormat;
+use crate::sandbox::sandbox_description;
+use crate::utils::logger::LOG_FILE;
+use crate::utils::platform::build_description;
+
+// A report of the machine and the config for bug reports, printed by "i3rustus diagnose"
+// Nothing in here changes the system, widgets are only created and updated once
+
+const POWER_SUPPLY_PATH: &str = "/sys/class/power_supply";
+const HWMON_PATH: &str = "/sys/class/hwmon";
+const NET_PATH: &str = "/sys/class/net";
+const BACKLIGHT_PATH: &str = "/sys/class/backlight";
+
+// Session bus services that widgets or their tools talk to
+const KNOWN_SERVICES: [&str; 4] = [
+    "org.freedesktop.Notifications",
+    "org.freedesktop.ScreenSaver",
+    "org.mpris.MediaPlayer2",
+    "org.i3rustus",
+];
+
+// Names of the entries of a sysfs class, sorted
+fn class_entries(path: &str) -> Vec<String> {
+    let mut entries: Vec<String> = read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect()
+        })
+        .unwrap_or_default();
+    entries.sort();
+    entries
+}
+
+fn attribute(path: &str, entry: &str, name: &str) -> Option<String> {
+    read_to_string(Path::new(path).join(entry).join(name))
+        .ok()
+        .map(|value| value.trim().to_string())
+}
+
+fn print_list(label: &str, entries: &[String]) {
+    if entries.is_empty() {
+        println!("  {}: none", label);
+    } else {
+        println!("  {}: {}", label, entries.join(", "));
+    }
+}
+
+fn print_data_sources() {
+    println!("Data sources");
+
+    let power_supplies: Vec<String> = class_entries(POWER_SUPPLY_PATH)
+        .into_iter()
+        .map(
+            |supply| match attribute(POWER_SUPPLY_PATH, &supply, "type") {
+                Some(kind) => format!("{} ({})", supply, kind),
+                None => supply,
+            },
+        )
+        .collect();
+    print_list("Power supplies", &power_supplies);
+
+    let chips: Vec<String> = class_entries(HWMON_PATH)
+        .into_iter()
+        .map(|chip| match attribute(HWMON_PATH, &chip, "name") {
+            Some(name) => format!("{} ({})", name, chip),
+            None => chip,
+        })
+        .collect();
+    print_list("hwmon chips", &chips);
+
+    let interfaces: Vec<String> = class_entries(NET_PATH)
+        .into_iter()
+        .map(|interface| {
+            let kind = if Path::new(NET_PATH)
+                .join(&interface)
+                .join("wireless")
+                .exists()
+            {
+                "wireless"
+            } else if Path::new(NET_PATH).join(&interface).join("device").exists() {
+                "ethernet"
+            } else {
+                "virtual"
+            };
+            let state = attribute(NET_PATH, &interface, "operstate").unwrap_or_default();
+            format!("{} ({}, {})", interface, kind, state)
+        })
+        .collect();
+    print_list("Network interfaces", &interfaces);
+
+    print_list("Backlights", &class_entries(BACKLIGHT_PATH));
+
+    match session_bus_names() {
+        Ok(names) => {
+            let found: Vec<String> = KNOWN_SERVICES
+                .iter()
+                .filter(|service| {
+                    names
+                        .iter()
+                        .any(|name| name == *service || name.starts_with(&format!("{}.", service)))
+                })
+                .map(|service| service.to_string())
+                .collect();
+            println!("  Session bus: {} services", names.len());
+            print_list("Known services", &found);
+        }
+        Err(error) => println!("  Session bus: not available ({})", error),
+    }
+}
+
+fn has_access(path: &str, mode: i32) -> bool {
+    let Ok(path) = CString::new(path) else {
+        return false;
+    };
+    // SAFETY: the path is a valid C string that outlives the call
+    unsafe { access(path.as_ptr(), mode) == 0 }
+}
+
+fn print_permission_problems() {
+    println!("Permissions");
+    let mut problems = Vec::new();
+
+    let config_file = CONFIG.file_path();
+    if config_file.exists() && !has_access(&config_file.to_string_lossy(), R_OK) {
+        problems.push(format!("{} is not readable", config_file.display()));
+    }
+    // The log file is created if it does not exist
+    let log_writable = match Path::new(LOG_FILE).parent() {
+        Some(directory) if !Path::new(LOG_FILE).exists() => {
+            has_access(&directory.to_string_lossy(), W_OK)
+        }
+        _ => has_access(LOG_FILE, W_OK),
+    };
+    if !log_writable {
+        problems.push(format!("{} is not writable, nothing is logged", LOG_FILE));
+    }
+    for device in CONFIG.brightness_device_names() {
+        let brightness = format!("{}/{}/brightness", BACKLIGHT_PATH, device);
+        if Path::new(&brightness).exists() && !has_access(&brightness, W_OK) {
+            problems.push(format!(
+                "{} is not writable, scrolling does not change the brightness",
+                brightness
+            ));
+        }
+    }
+    for device in CONFIG.ddc_i2c_devices() {
+        if !has_access(&device, R_OK | W_OK) {
+            problems.push(format!(
+                "{} is not accessible, is the user in the i2c group?",
+                device
+            ));
+        }
+    }
+
+    if problems.is_empty() {
+        println!("  No problems found");
+    }
+    for problem in problems {
+        println!("  {}", problem);
+    }
+}
+
+// Widgets of the order, with the widgets of groups in place of the group
+fn configured_widgets() -> Vec<WidgetId> {
+    CONFIG
+        .widget_order()
+        .into_iter()
+        .flat_map(|id| {
+            match id
+                .instance
+                .as_deref()
+                .filter(|_| &*id.name == GROUP)
+                .and_then(|instance| CONFIG.group(instance))
+            {
+                Some(group) => group
+                    .widgets
+                    .iter()
+                    .map(|widget| WidgetId::parse(widget))
+                    .collect(),
+                None => vec![id],
+            }
+        })
+        .collect()
+}
+
+async fn print_widgets() {
+    println!("Widgets");
+    let mut i3status = I3Status::new(OutputFormat::Plain);
+    let timings = i3status.time_widget_updates().await;
+    for id in configured_widgets() {
+        let name = id.to_string();
+        match timings.iter().find(|(timed, _, _)| *timed == id) {
+            Some((_, elapsed, Ok(()))) => println!("  {:<24} ok ({:.1?})", name, elapsed),
+            Some((_, elapsed, Err(error))) => {
+                println!("  {:<24} error ({:.1?}): {}", name, elapsed, error)
+            }
+            // The reason is listed in the config problems
+            None => println!("  {:<24} not created", name),
+        }
+    }
+}
+
+pub async fn print_report() {
+    println!("i3rustus {}", env!("CARGO_PKG_VERSION"));
+    println!("Build: {}", build_description());
+    println!("Sandbox: {}", sandbox_description());
+    println!(
+        "Kernel: {}",
+        read_to_string("/proc/sys/kernel/osrelease")
+            .unwrap_or_default()
+            .trim()
+    );
+    println!();
+
+    let config_file = CONFIG.file_path();
+    println!("Config");
+    if config_file.exists() {
+        println!("  File: {}", config_file.display());
+    } else {
+        println!(
+            "  File: {} (missing, the defaults are used)",
+            config_file.display()
+        );
+    }
+    let errors = check_config();
+    if errors.is_empty() {
+        println!("  No problems found");
+    }
+    for error in errors {
+        println!("  {}", error);
+    }
+    println!();
+
+    print_data_sources();
+    println!();
+    print_permission_problems();
+    println!();
+    print_widgets().await;
+}
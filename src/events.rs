@@ -0,0 +1,252 @@
+use std::fs::File;
+use std::io::{Error as IOError, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use actix::dev::SendError;
+use actix::Recipient;
+use chrono::{Local, TimeDelta, TimeZone};
+
+use crate::config::WidgetId;
+use crate::i3_status::CONFIG;
+use crate::os;
+use crate::signals::request_render;
+use crate::utils::file_watcher::watch_file;
+use crate::utils::xkb::Xkb;
+use crate::widget_executor::KernelEvent;
+
+// Events are passed from monitors (e.g. a netlink socket) to the widgets that subscribed to them
+// Every monitor runs once, no matter how many widgets react to its events, and only
+// after the first widget subscribed to one of them
+
+// Kernel events after which a widget is updated right away instead of at the next tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WidgetEvent {
+    // A network interface went up or down or its address changed
+    Network,
+    // A power supply changed (e.g. AC plugged in or the battery is full)
+    PowerSupply,
+    // The brightness of a backlight changed (e.g. through the brightness keys)
+    Backlight,
+    // The local date changed, widgets that count something per day start over
+    NewDay,
+    // The system time zone changed (e.g. /etc/localtime points to another zone)
+    TimeZone,
+    // The keyboard layout was switched, or a compose sequence or latch started or ended
+    Keyboard,
+}
+
+// Sources of events, each runs in its own thread
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Monitor {
+    // Link and address changes of network interfaces
+    Route,
+    // Devices of the kernel (power supplies, backlights, ...)
+    Uevent,
+    // The local date
+    Clock,
+    // The file of the local time zone
+    LocalTime,
+    // The keyboard state of the X server
+    Xkb,
+}
+
+impl WidgetEvent {
+    fn monitor(&self) -> Monitor {
+        match self {
+            WidgetEvent::Network => Monitor::Route,
+            WidgetEvent::PowerSupply | WidgetEvent::Backlight => Monitor::Uevent,
+            WidgetEvent::NewDay => Monitor::Clock,
+            WidgetEvent::TimeZone => Monitor::LocalTime,
+            WidgetEvent::Keyboard => Monitor::Xkb,
+        }
+    }
+}
+
+struct Subscription {
+    id: WidgetId,
+    event: WidgetEvent,
+    executor: Recipient<KernelEvent>,
+}
+
+static SUBSCRIPTIONS: Mutex<Vec<Subscription>> = Mutex::new(Vec::new());
+// Monitors that were started, they run until the bar exits
+static MONITORS: Mutex<Vec<Monitor>> = Mutex::new(Vec::new());
+// Number of time zone changes so far, widgets compare it to the number they saw last
+static TIME_ZONE_CHANGES: AtomicU64 = AtomicU64::new(0);
+
+// The clock monitor checks the date at least this often, sleeping is not reliable across
+// suspend and the clock or the time zone can be changed while it sleeps
+const DATE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+// uevents are at most a few KiB, route messages are usually smaller
+const EVENT_BUFFER_LENGTH: usize = 8192;
+
+// A uevent is a list of NUL separated "KEY=value" pairs after an "action@devpath" line
+fn uevent_subsystem(message: &[u8]) -> Option<&[u8]> {
+    message
+        .split(|byte| *byte == 0)
+        .find_map(|field| field.strip_prefix(b"SUBSYSTEM="))
+}
+
+// Send an event to the executors that subscribed to it
+// Executors that stopped are dropped, a full mailbox only misses this event
+pub fn publish(event: WidgetEvent) {
+    SUBSCRIPTIONS.lock().unwrap().retain(|subscription| {
+        subscription.event != event
+            || !matches!(
+                subscription.executor.try_send(KernelEvent(event)),
+                Err(SendError::Closed(_))
+            )
+    });
+    request_render();
+}
+
+// Receive messages from a kernel socket in its own thread and map them to widget events
+fn listen<F>(mut socket: File, event: F)
+where
+    F: Fn(&[u8]) -> Option<WidgetEvent> + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut buffer = [0; EVENT_BUFFER_LENGTH];
+        loop {
+            match socket.read(&mut buffer) {
+                Ok(length) => {
+                    if let Some(event) = event(&buffer[..length]) {
+                        publish(event);
+                    }
+                }
+                Err(error) => {
+                    log::error!("Stopped listening for kernel events: {}", error);
+                    return;
+                }
+            }
+        }
+    });
+}
+
+// Time until the next local midnight
+// Some zones switch to daylight saving time at midnight, then the day starts at 01:00
+fn until_midnight() -> Duration {
+    let now = Local::now();
+    let Some(midnight) = now
+        .date_naive()
+        .succ_opt()
+        .and_then(|tomorrow| tomorrow.and_hms_opt(0, 0, 0))
+    else {
+        return DATE_CHECK_INTERVAL;
+    };
+    let next_day = Local.from_local_datetime(&midnight).earliest().or_else(|| {
+        Local
+            .from_local_datetime(&(midnight + TimeDelta::hours(1)))
+            .earliest()
+    });
+    match next_day {
+        Some(next_day) => (next_day - now).to_std().unwrap_or_default(),
+        None => DATE_CHECK_INTERVAL,
+    }
+}
+
+// Publish NewDay when the local date changes
+fn watch_date() {
+    thread::spawn(|| {
+        let mut date = Local::now().date_naive();
+        loop {
+            thread::sleep(until_midnight().min(DATE_CHECK_INTERVAL));
+            let today = Local::now().date_naive();
+            if today != date {
+                date = today;
+                publish(WidgetEvent::NewDay);
+            }
+        }
+    });
+}
+
+// Publish Keyboard when the X server reports a change of the keyboard state
+fn watch_keyboard() -> Result<(), IOError> {
+    let xkb = Xkb::new()?;
+    xkb.select_changes()?;
+    thread::spawn(move || loop {
+        xkb.wait_for_change();
+        publish(WidgetEvent::Keyboard);
+    });
+    Ok(())
+}
+
+pub fn time_zone_changes() -> u64 {
+    TIME_ZONE_CHANGES.load(Ordering::SeqCst)
+}
+
+// If subscribing fails, widgets are still updated every tick
+fn start_monitor(monitor: Monitor) {
+    match monitor {
+        Monitor::Route => match os::route_socket() {
+            Ok(socket) => listen(socket, |_| Some(WidgetEvent::Network)),
+            Err(error) => log::warn!("Could not subscribe to network events: {}", error),
+        },
+        Monitor::Uevent => match os::uevent_socket() {
+            Ok(socket) => listen(socket, |message| match uevent_subsystem(message)? {
+                b"power_supply" => Some(WidgetEvent::PowerSupply),
+                b"backlight" => Some(WidgetEvent::Backlight),
+                _ => None,
+            }),
+            Err(error) => log::warn!("Could not subscribe to uevents: {}", error),
+        },
+        Monitor::Clock => watch_date(),
+        Monitor::LocalTime => {
+            let path = CONFIG.localtime_path();
+            if let Err(error) = watch_file(Path::new(path), || {
+                TIME_ZONE_CHANGES.fetch_add(1, Ordering::SeqCst);
+                publish(WidgetEvent::TimeZone);
+            }) {
+                log::warn!("Could not watch {} for time zone changes: {}", path, error);
+            }
+        }
+        Monitor::Xkb => {
+            if let Err(error) = watch_keyboard() {
+                log::warn!("Could not subscribe to keyboard events: {}", error);
+            }
+        }
+    }
+}
+
+// Update the widget right away when one of the events happens, instead of at the next tick
+// Subscribing again (e.g. after the executor was restarted) replaces the previous subscriptions
+pub fn subscribe_to_events(
+    id: &WidgetId,
+    events: &[WidgetEvent],
+    executor: Recipient<KernelEvent>,
+) {
+    unsubscribe_from_events(id);
+    if events.is_empty() {
+        return;
+    }
+    let mut subscriptions = SUBSCRIPTIONS.lock().unwrap();
+    for event in events {
+        subscriptions.push(Subscription {
+            id: id.clone(),
+            event: *event,
+            executor: executor.clone(),
+        });
+    }
+    drop(subscriptions);
+
+    let mut monitors = MONITORS.lock().unwrap();
+    for event in events {
+        let monitor = event.monitor();
+        if !monitors.contains(&monitor) {
+            monitors.push(monitor);
+            start_monitor(monitor);
+        }
+    }
+}
+
+pub fn unsubscribe_from_events(id: &WidgetId) {
+    SUBSCRIPTIONS
+        .lock()
+        .unwrap()
+        .retain(|subscription| subscription.id != *id);
+}
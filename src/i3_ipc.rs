@@ -0,0 +1,108 @@
+use std::env;
+use std::io::{Error as IOError, Read, Write};
+use std::net::Shutdown;
+use std::os::unix::net::UnixStream;
+use std::process::Command;
+
+// See https://i3wm.org/docs/ipc.html
+const MAGIC_STRING: &[u8] = b"i3-ipc";
+// Magic string + payload length (u32) + message type (u32)
+const HEADER_SIZE: usize = 14;
+
+// Message types
+pub const GET_WORKSPACES: u32 = 1;
+pub const SUBSCRIBE: u32 = 2;
+pub const GET_OUTPUTS: u32 = 3;
+pub const GET_TREE: u32 = 4;
+// Events have the highest bit set in their message type
+pub const EVENT_MASK: u32 = 1 << 31;
+
+// Connection to the IPC socket of i3
+pub struct I3Ipc {
+    socket: UnixStream,
+}
+
+impl I3Ipc {
+    // The socket path is exported in I3SOCK by i3 itself,
+    // if that is not set (e.g. when started from a terminal), we ask i3
+    fn socket_path() -> Result<String, IOError> {
+        if let Ok(path) = env::var("I3SOCK") {
+            return Ok(path);
+        }
+
+        let output = Command::new("i3").arg("--get-socketpath").output()?;
+        let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if path.is_empty() {
+            Err(IOError::other("Could not find the i3 IPC socket"))
+        } else {
+            Ok(path)
+        }
+    }
+
+    pub fn connect() -> Result<Self, IOError> {
+        Ok(Self {
+            socket: UnixStream::connect(Self::socket_path()?)?,
+        })
+    }
+
+    pub fn send(&mut self, message_type: u32, payload: &str) -> Result<(), IOError> {
+        let mut message = MAGIC_STRING.to_vec();
+        message.extend((payload.len() as u32).to_ne_bytes());
+        message.extend(message_type.to_ne_bytes());
+        message.extend(payload.as_bytes());
+
+        self.socket.write_all(&message)
+    }
+
+    // Blocks until the next message (reply or event) arrives
+    // Returns the message type and the JSON payload
+    pub fn receive(&mut self) -> Result<(u32, String), IOError> {
+        let mut header = [0; HEADER_SIZE];
+        self.socket.read_exact(&mut header)?;
+        if &header[..MAGIC_STRING.len()] != MAGIC_STRING {
+            return Err(IOError::other("Invalid i3 IPC message"));
+        }
+
+        let payload_length = u32::from_ne_bytes(header[6..10].try_into().unwrap());
+        let message_type = u32::from_ne_bytes(header[10..14].try_into().unwrap());
+        let mut payload = vec![0; payload_length as usize];
+        self.socket.read_exact(&mut payload)?;
+
+        Ok((message_type, String::from_utf8_lossy(&payload).into_owned()))
+    }
+
+    // Send a request and wait for its reply
+    pub fn request(&mut self, message_type: u32, payload: &str) -> Result<String, IOError> {
+        self.send(message_type, payload)?;
+        loop {
+            let (reply_type, reply) = self.receive()?;
+            // Skip events that arrived before our reply
+            if reply_type == message_type {
+                return Ok(reply);
+            }
+        }
+    }
+
+    // Subscribe to the given events (e.g. "workspace", "window")
+    // The events can be read with `receive` afterwards
+    pub fn subscribe(&mut self, events: &[&str]) -> Result<(), IOError> {
+        let reply = self.request(SUBSCRIBE, &serde_json::to_string(events)?)?;
+        if reply.contains("\"success\":true") {
+            Ok(())
+        } else {
+            Err(IOError::other(format!(
+                "Could not subscribe to i3 events: {}",
+                reply
+            )))
+        }
+    }
+
+    // Returns a function that closes the connection from another thread,
+    // so a blocking `receive` returns with an error
+    pub fn shutdown_handle(&self) -> Result<impl FnOnce() + Send + 'static, IOError> {
+        let socket = self.socket.try_clone()?;
+        Ok(move || {
+            let _ = socket.shutdown(Shutdown::Both);
+        })
+    }
+}
@@ -1,104 +1,1152 @@
-use crate::config::Config;
-use crate::widget_executor::{UpdateWidgetValue, WidgetExecutor, WidgetValue};
+use crate::click_events::listen_for_click_events;
+use crate::config::{widget_name, Config, ScheduleAction, WidgetId};
+use crate::config_watcher::{take_config_change, watch_config_file};
+use crate::dbus::listen_for_dbus_calls;
+use crate::ipc::{listen_for_ipc_commands, remove_ipc_socket, take_order_request};
+use crate::output::{strip_markup, OutputFormat};
+use crate::overrides::apply_override;
+use crate::plugins::{check_plugin, load_plugin};
+use crate::protocol_check::check_status_line;
+use crate::recorder::Recorder;
+use crate::signals::{
+    install_signal_handlers, is_low_power, is_paused, is_shutdown_requested, set_low_power,
+    take_refresh_request, take_render_request,
+};
+use crate::snmp::check_oid;
+use crate::theme::{reset_palette, Color};
+use crate::utils::suggest::closest;
+use crate::utils::text_width::text_width;
+use crate::variables::{toggle_variable, variable};
+use crate::widget_executor::{
+    request_update, RecreateWidget, StopWidget, UpdateWidgetValue, WidgetExecutor, WidgetValue,
+};
+use crate::widgets::ad_blocker::AdBlocker;
+use crate::widgets::agent_status::AgentStatus;
+use crate::widgets::alarm::Alarm;
 use crate::widgets::battery_life::Battery;
+use crate::widgets::break_reminder::BreakReminder;
 use crate::widgets::brightness::Brightness;
 use crate::widgets::cpu_stats::CpuUsage;
 use crate::widgets::cpu_stats::CpuUsageType;
+use crate::widgets::derived::Derived;
 use crate::widgets::disk_stats::Disk;
+use crate::widgets::display_scale::DisplayScale;
+use crate::widgets::external_monitor::ExternalMonitor;
+use crate::widgets::focus_tracker::FocusTracker;
+use crate::widgets::home_assistant::HomeAssistant;
+use crate::widgets::keyboard::Keyboard;
 use crate::widgets::memory_stats::MemoryUsage;
+use crate::widgets::mqtt_subscriber::MqttSubscriber;
 use crate::widgets::network_information::NetworkInformation;
 use crate::widgets::network_information::NetworkType;
-use crate::widgets::time::Time;
+use crate::widgets::plugin::PluginWidget;
+use crate::widgets::pomodoro::Pomodoro;
+use crate::widgets::presentation_mode::PresentationMode;
+use crate::widgets::printer::PrinterWidget;
+use crate::widgets::remote::Remote;
+use crate::widgets::screen_time::ScreenTime;
+use crate::widgets::script::Script;
+use crate::widgets::security_key::SecurityKey;
+use crate::widgets::self_monitor::SelfMonitor;
+use crate::widgets::snmp_poller::SnmpPoller;
+use crate::widgets::syncthing::Syncthing;
+use crate::widgets::task_watcher::TaskWatcher;
+use crate::widgets::time::{
+    check_format as check_time_format, check_names as check_time_names, Time, Zone,
+};
+use crate::widgets::time_tracking::TimeTracking;
+use crate::widgets::timer::Timer;
+use crate::widgets::transfer::Transfer;
+use crate::widgets::webdav_quota::WebDavQuota;
 
-use actix::{Actor, Addr};
+use actix::clock::sleep;
+use actix::{Addr, Arbiter, MailboxError, Supervisor};
+use chrono::Local;
+use futures_util::future::join_all;
 use serde_json::{json, Value};
 use std::collections::HashMap;
-use std::{thread, time};
+use std::io::{stdout, BufWriter, StdoutLock, Write};
+use std::mem::take;
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 pub const CONFIG: Config = Config::new();
+// Time between two updates
+// Ticks happen right after every full second of the wall clock
+const TICK: Duration = Duration::from_secs(1);
+// Wake up a bit after the full second, so the time widget already sees the new second
+const TICK_DELAY: Duration = Duration::from_millis(5);
+// How often we check for refresh requests while waiting for the next tick
+const REFRESH_POLL_INTERVAL: Duration = Duration::from_millis(50);
+// Gap in pixels that i3bar leaves after a block without separator_block_width
+const DEFAULT_SEPARATOR_BLOCK_WIDTH: u32 = 9;
+// How long a widget may take to answer before its last block is shown again
+const WIDGET_TIMEOUT: Duration = Duration::from_millis(500);
+// How long we wait for a widget to stop when shutting down
+const STOP_TIMEOUT: Duration = Duration::from_secs(1);
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(10);
+// Name of the widget that combines several widgets into one block
+pub const GROUP: &str = "group";
+// Names of all widgets, to suggest one for a misspelled name
+// Keep this in sync with create_executor
+const WIDGET_NAMES: [&str; 38] = [
+    "ad_blocker",
+    "agent",
+    "alarm",
+    "battery",
+    "break_reminder",
+    "brightness",
+    "cpu_load",
+    "cpu_percentage",
+    "derived",
+    "disk",
+    "display_scale",
+    "ethernet",
+    "external_monitor",
+    "focus_tracker",
+    GROUP,
+    "home_assistant",
+    "keyboard",
+    "memory",
+    "mqtt",
+    "network",
+    "plugin",
+    "pomodoro",
+    "presentation_mode",
+    "printer",
+    "remote",
+    "screen_time",
+    "script",
+    "security_key",
+    "self_monitor",
+    "snmp",
+    "syncthing",
+    "task",
+    "time",
+    "timer",
+    "timewarrior",
+    "transfer",
+    "webdav",
+    "wireless",
+];
+
+// Widgets that are only shown with an instance, e.g. "remote:homelab"
+// [widgets.<name>] holds the options that all their instances share
+const INSTANCE_WIDGETS: [&str; 12] = [
+    "ad_blocker",
+    GROUP,
+    "home_assistant",
+    "mqtt",
+    "plugin",
+    "printer",
+    "remote",
+    "script",
+    "snmp",
+    "task",
+    "transfer",
+    "webdav",
+];
+
+// Executors of all shown widgets, shared with the click event listener
+// The map is replaced when the config file changes
+pub type WidgetExecutors = Arc<RwLock<HashMap<WidgetId, Addr<WidgetExecutor>>>>;
+
+// Why the main loop woke up
+enum Wakeup {
+    // All widgets are updated
+    Tick,
+    // Some widgets were updated by an event, only the bar is printed again
+    Render,
+    // SIGTERM or SIGINT was received
+    Shutdown,
+}
+
+// The next time the wall clock reaches a full second (plus TICK_DELAY)
+// Sleeping for a whole TICK would drift by the time the update takes,
+// which makes the time widget skip a second every now and then
+fn next_tick() -> Instant {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let subsecond = Duration::from_nanos(since_epoch.subsec_nanos() as u64);
+    Instant::now() + TICK.saturating_sub(subsecond) + TICK_DELAY
+}
 
 pub struct I3Status {
-    widget_executors: HashMap<String, Addr<WidgetExecutor>>,
+    widget_executors: WidgetExecutors,
+    // Widgets that have an executor, in the order they are shown
+    widget_order: Vec<WidgetId>,
+    // stdout is locked for the whole runtime, so nothing else can write between our lines
+    output: BufWriter<StdoutLock<'static>>,
+    output_format: OutputFormat,
+    // Last block of every widget, shown again when a widget does not answer in time
+    // Null for widgets that were hidden
+    last_blocks: Mutex<HashMap<WidgetId, Value>>,
+    // Widest text in pixels that a block with stable_width had so far, by "name:instance"
+    block_widths: HashMap<String, u32>,
+    // Number of updates so far, in low power mode most widgets only update every few ticks
+    ticks: u64,
 }
 
 impl I3Status {
-    pub fn new() -> Self {
-        let executors = vec![
-            WidgetExecutor::new(NetworkInformation::new(NetworkType::Wlan)),
-            WidgetExecutor::new(NetworkInformation::new(NetworkType::Ethernet)),
-            WidgetExecutor::new(Battery::new()),
-            WidgetExecutor::new(CpuUsage::new(CpuUsageType::CpuLoad)),
-            WidgetExecutor::new(CpuUsage::new(CpuUsageType::Percentage)),
-            WidgetExecutor::new(MemoryUsage::new()),
-            WidgetExecutor::new(Disk::new(String::from("root"), String::from("/"))),
-            WidgetExecutor::new(Time::new()),
-            WidgetExecutor::new(Brightness::new()),
-        ];
+    // Create the executor for the widget with the given name and instance
+    pub fn create_executor(id: &WidgetId) -> Option<WidgetExecutor> {
+        let widget_id = id.clone();
+        let executor = match (&*id.name, id.instance.as_deref()) {
+            ("wireless", None) => {
+                WidgetExecutor::new(widget_id, NetworkInformation::new(NetworkType::Wlan))
+            }
+            ("ethernet", None) => {
+                WidgetExecutor::new(widget_id, NetworkInformation::new(NetworkType::Ethernet))
+            }
+            ("network", None) => WidgetExecutor::new(
+                widget_id,
+                NetworkInformation::new(NetworkType::DefaultRoute),
+            ),
+            ("battery", None) => WidgetExecutor::new(widget_id, Battery::new()),
+            ("cpu_load", None) => {
+                WidgetExecutor::new(widget_id, CpuUsage::new(CpuUsageType::CpuLoad))
+            }
+            ("cpu_percentage", None) => {
+                WidgetExecutor::new(widget_id, CpuUsage::new(CpuUsageType::Percentage))
+            }
+            ("memory", None) => WidgetExecutor::new(widget_id, MemoryUsage::new()),
+            ("disk", None) => WidgetExecutor::new(
+                widget_id,
+                Disk::new(id.clone(), String::from("root"), String::from("/")),
+            ),
+            ("disk", Some(instance)) => WidgetExecutor::new(
+                widget_id,
+                Disk::new(
+                    id.clone(),
+                    instance.to_string(),
+                    CONFIG.disk_mount_point(instance)?.to_string(),
+                ),
+            ),
+            ("time", None) => WidgetExecutor::new(widget_id, Time::new(id.clone(), None)),
+            ("time", Some(instance)) => {
+                let zone = Zone::load(CONFIG.time_zone(instance)?).ok()?;
+                WidgetExecutor::new(
+                    widget_id,
+                    Time::new(id.clone(), Some((instance.to_uppercase(), zone))),
+                )
+            }
+            ("remote", Some(instance)) => WidgetExecutor::new(
+                widget_id,
+                Remote::new(instance.to_string(), CONFIG.remote_host(instance)?),
+            ),
+            ("snmp", Some(instance)) => WidgetExecutor::new(
+                widget_id,
+                SnmpPoller::new(id.clone(), CONFIG.snmp_target(instance)?),
+            ),
+            ("mqtt", Some(instance)) => WidgetExecutor::new(
+                widget_id,
+                MqttSubscriber::new(instance, CONFIG.mqtt_broker(instance)?),
+            ),
+            ("home_assistant", Some(instance)) => WidgetExecutor::new(
+                widget_id,
+                HomeAssistant::new(id.clone(), CONFIG.home_assistant_entity(instance)?),
+            ),
+            ("printer", Some(instance)) => WidgetExecutor::new(
+                widget_id,
+                PrinterWidget::new(instance.to_string(), CONFIG.printer(instance)?),
+            ),
+            ("ad_blocker", Some(instance)) => WidgetExecutor::new(
+                widget_id,
+                AdBlocker::new(
+                    id.clone(),
+                    instance.to_string(),
+                    CONFIG.ad_blocker(instance)?,
+                ),
+            ),
+            ("webdav", Some(instance)) => WidgetExecutor::new(
+                widget_id,
+                WebDavQuota::new(
+                    id.clone(),
+                    instance.to_string(),
+                    CONFIG.webdav_account(instance)?,
+                ),
+            ),
+            ("transfer", Some(instance)) => WidgetExecutor::new(
+                widget_id,
+                Transfer::new(instance.to_string(), CONFIG.transfer_source(instance)?),
+            ),
+            ("plugin", Some(instance)) => {
+                let plugin = match load_plugin(instance) {
+                    Ok(plugin) => plugin,
+                    Err(error) => {
+                        log::error!("{}", error);
+                        return None;
+                    }
+                };
+                WidgetExecutor::new(
+                    widget_id,
+                    PluginWidget::new(plugin, &CONFIG.plugin_options(instance))?,
+                )
+            }
+            ("script", Some(instance)) => WidgetExecutor::new(
+                widget_id,
+                Script::new(instance.to_string(), CONFIG.script(instance)?),
+            ),
+            ("task", Some(instance)) => WidgetExecutor::new(
+                widget_id,
+                TaskWatcher::new(instance.to_string(), CONFIG.task_marker(instance)?),
+            ),
+            ("syncthing", None) => WidgetExecutor::new(widget_id, Syncthing::new()),
+            ("brightness", None) => WidgetExecutor::new(widget_id, Brightness::new()),
+            ("security_key", None) => WidgetExecutor::new(widget_id, SecurityKey::new()),
+            ("agent", None) => WidgetExecutor::new(widget_id, AgentStatus::new()),
+            ("external_monitor", None) => WidgetExecutor::new(widget_id, ExternalMonitor::new()),
+            ("presentation_mode", None) => WidgetExecutor::new(widget_id, PresentationMode::new()),
+            ("focus_tracker", None) => WidgetExecutor::new(widget_id, FocusTracker::new()),
+            ("break_reminder", None) => WidgetExecutor::new(widget_id, BreakReminder::new()),
+            ("screen_time", None) => WidgetExecutor::new(widget_id, ScreenTime::new()),
+            ("alarm", None) => WidgetExecutor::new(widget_id, Alarm::new()),
+            ("timewarrior", None) => WidgetExecutor::new(widget_id, TimeTracking::new()),
+            ("display_scale", None) => WidgetExecutor::new(widget_id, DisplayScale::new()),
+            ("keyboard", None) => WidgetExecutor::new(widget_id, Keyboard::new()),
+            ("self_monitor", None) => WidgetExecutor::new(widget_id, SelfMonitor::new()),
+            ("pomodoro", None) => WidgetExecutor::new(widget_id, Pomodoro::new()),
+            ("timer", None) => WidgetExecutor::new(widget_id, Timer::new(id.clone(), None, None)),
+            ("timer", Some(instance)) => WidgetExecutor::new(
+                widget_id,
+                Timer::new(
+                    id.clone(),
+                    Some(instance.to_string()),
+                    Some(Duration::from_secs(CONFIG.timer_duration(instance)?)),
+                ),
+            ),
+            ("derived", _) => WidgetExecutor::new(
+                widget_id,
+                Derived::new(id.clone(), CONFIG.derived_expression(&id.to_string())?),
+            ),
+            _ => return None,
+        };
 
-        let mut widget_executors = HashMap::new();
+        Some(executor)
+    }
 
-        for executor in executors {
-            widget_executors.insert(executor.widget_name().to_owned(), executor.start());
+    // The formats and the name tables of all clocks, formats can contain strftime specifiers
+    // Every time instance with a section is checked, not only the ones in the widget order
+    fn check_time_formats() -> Result<(), String> {
+        let clocks = [WidgetId::new("time", None)]
+            .into_iter()
+            .chain(CONFIG.configured_instances("time"));
+        for id in clocks {
+            check_time_names(&id).map_err(|error| format!("{}: {}", id, error))?;
+            [
+                CONFIG.format(&id.to_string()),
+                CONFIG.short_format(&id.to_string()),
+            ]
+            .into_iter()
+            .flatten()
+            .try_for_each(|format| check_time_format(&format))
+            .map_err(|error| format!("{}: {}", id, error))?;
+        }
+        Ok(())
+    }
+
+    // Returns an error with a suggestion for a name that no widget has
+    pub fn check_widget_name(name: &str) -> Result<(), String> {
+        if WIDGET_NAMES.contains(&name) {
+            return Ok(());
         }
+        match closest(name, WIDGET_NAMES) {
+            Some(widget) => Err(format!(
+                "Unknown widget {}, did you mean `{}`?",
+                name, widget
+            )),
+            None => Err(format!("Unknown widget {}", name)),
+        }
+    }
+
+    pub fn needs_instance(name: &str) -> bool {
+        INSTANCE_WIDGETS.contains(&name)
+    }
 
-        Self { widget_executors }
+    // Check that create_executor knows a widget and can find its config, without creating it
+    // Many widgets start threads or open connections as soon as they are created
+    // Keep this in sync with create_executor
+    pub fn check_widget(id: &WidgetId) -> Result<(), String> {
+        let unknown_instance = || format!("No config for instance of {}", id);
+        match (&*id.name, id.instance.as_deref()) {
+            (
+                "wireless" | "ethernet" | "network" | "battery" | "cpu_load" | "cpu_percentage"
+                | "memory" | "disk" | "syncthing" | "brightness" | "security_key" | "agent"
+                | "external_monitor" | "presentation_mode" | "focus_tracker" | "break_reminder"
+                | "screen_time" | "alarm" | "timewarrior" | "display_scale" | "keyboard"
+                | "self_monitor" | "timer" | "pomodoro",
+                None,
+            ) => Ok(()),
+            ("disk", Some(instance)) => {
+                let mount_point = CONFIG
+                    .disk_mount_point(instance)
+                    .ok_or_else(unknown_instance)?;
+                if !Path::new(&mount_point).is_dir() {
+                    return Err(format!(
+                        "Mount point {} of {} does not exist",
+                        mount_point, id
+                    ));
+                }
+                Ok(())
+            }
+            ("time", None) => Self::check_time_formats(),
+            ("time", Some(instance)) => {
+                Self::check_time_formats()?;
+                let setting = CONFIG.time_zone(instance).ok_or_else(unknown_instance)?;
+                Zone::load(setting)
+                    .map(|_| ())
+                    .map_err(|error| format!("{}: {}", id, error))
+            }
+            ("derived", _) => CONFIG
+                .derived_expression(&id.to_string())
+                .map(|_| ())
+                .ok_or_else(|| format!("{} needs an expression in its section", id)),
+            ("timer", Some(instance)) => CONFIG
+                .timer_duration(instance)
+                .map(|_| ())
+                .ok_or_else(unknown_instance),
+            ("remote", Some(instance)) => CONFIG
+                .remote_host(instance)
+                .map(|_| ())
+                .ok_or_else(unknown_instance),
+            ("snmp", Some(instance)) => {
+                let target = CONFIG.snmp_target(instance).ok_or_else(unknown_instance)?;
+                target.oids.iter().try_for_each(|oid| {
+                    check_oid(&oid.oid).map_err(|error| format!("{}: {}: {}", id, oid.oid, error))
+                })
+            }
+            ("mqtt", Some(instance)) => CONFIG
+                .mqtt_broker(instance)
+                .map(|_| ())
+                .ok_or_else(unknown_instance),
+            ("home_assistant", Some(instance)) => CONFIG
+                .home_assistant_entity(instance)
+                .map(|_| ())
+                .ok_or_else(unknown_instance),
+            ("printer", Some(instance)) => CONFIG
+                .printer(instance)
+                .map(|_| ())
+                .ok_or_else(unknown_instance),
+            ("webdav", Some(instance)) => CONFIG
+                .webdav_account(instance)
+                .map(|_| ())
+                .ok_or_else(unknown_instance),
+            ("transfer", Some(instance)) => CONFIG
+                .transfer_source(instance)
+                .map(|_| ())
+                .ok_or_else(unknown_instance),
+            ("plugin", Some(instance)) => check_plugin(instance),
+            ("script", Some(instance)) => CONFIG
+                .script(instance)
+                .map(|_| ())
+                .ok_or_else(unknown_instance),
+            ("task", Some(instance)) => CONFIG
+                .task_marker(instance)
+                .map(|_| ())
+                .ok_or_else(unknown_instance),
+            ("ad_blocker", Some(instance)) => CONFIG
+                .ad_blocker(instance)
+                .map(|_| ())
+                .ok_or_else(unknown_instance),
+            (GROUP, Some(instance)) => {
+                let group = CONFIG.group(instance).ok_or_else(unknown_instance)?;
+                group
+                    .widgets
+                    .iter()
+                    .try_for_each(|widget| Self::check_widget(&WidgetId::parse(widget)))
+            }
+            (name, None) if Self::needs_instance(name) => Err(format!(
+                "{} needs an instance, e.g. {}:<instance>",
+                id, name
+            )),
+            _ => match closest(&id.name, WIDGET_NAMES) {
+                Some(name) => Err(format!("Unknown widget {}, did you mean `{}`?", id, name)),
+                None => Err(format!("Unknown widget {}", id)),
+            },
+        }
     }
 
-    async fn widget_values(&self) -> Value {
-        let mut values = json!([]);
-        // Make sure widgets are printed in the correct order
-        for widget_name in CONFIG.widget_order().iter() {
-            match self
-                .widget_executors
-                .get(widget_name)
-                .expect("ERROR: Unknown widget name")
-                .send(WidgetValue {})
-                .await
+    // Create and start the executor of a widget, or reuse the one it had before a config reload
+    // The widget of a reused executor is created again if its section in the config changed
+    // Returns false if the widget is unknown or already has an executor
+    fn add_executor(
+        widget_executors: &mut HashMap<WidgetId, Addr<WidgetExecutor>>,
+        previous_executors: &mut HashMap<WidgetId, Addr<WidgetExecutor>>,
+        changed_sections: &[String],
+        id: &WidgetId,
+    ) -> bool {
+        // A widget with the same name and instance would receive the clicks of the other one
+        if widget_executors.contains_key(id) {
+            log::error!("Widget {} is declared more than once, use an instance", id);
+            return false;
+        }
+        if let Some(executor) = previous_executors.remove(id) {
+            if changed_sections
+                .iter()
+                .any(|section| *section == id.to_string() || *section == *id.name)
             {
-                Ok(Ok(conf)) => values
-                    .as_array_mut()
-                    .expect("ERROR: Could not get a mutable Vec from serde JSON")
-                    .push(conf),
-                Ok(Err(error)) => {
-                    log::warn!("Invalid value for {}: \n\t{}", widget_name, error);
-                    continue;
+                executor.do_send(RecreateWidget);
+            }
+            widget_executors.insert(id.clone(), executor);
+            return true;
+        }
+        match Self::create_executor(id) {
+            Some(executor) => {
+                // Every executor runs in its own thread, so a widget that hangs
+                // does not block the other widgets or the main loop
+                // The supervisor restarts the executor when its widget panics
+                let arbiter = Arbiter::new();
+                widget_executors.insert(
+                    id.clone(),
+                    Supervisor::start_in_arbiter(&arbiter.handle(), move |_| executor),
+                );
+                true
+            }
+            None => {
+                log::error!("Unknown widget {}", id);
+                false
+            }
+        }
+    }
+
+    // Create the executors of all configured widgets and the order they are shown in
+    // Executors in previous_executors are reused, so their widgets keep their state unless their
+    // section in the config file changed
+    fn create_widgets(
+        order: Vec<WidgetId>,
+        mut previous_executors: HashMap<WidgetId, Addr<WidgetExecutor>>,
+        changed_sections: &[String],
+    ) -> (HashMap<WidgetId, Addr<WidgetExecutor>>, Vec<WidgetId>) {
+        let mut widget_executors = HashMap::new();
+        let mut widget_order = Vec::new();
+
+        // Only widgets that will be shown are created
+        // Some of them spawn threads, processes or open sockets
+        for id in order {
+            if &*id.name == GROUP {
+                match id
+                    .instance
+                    .as_deref()
+                    .and_then(|instance| CONFIG.group(instance))
+                {
+                    Some(group) => {
+                        for widget in group.widgets {
+                            Self::add_executor(
+                                &mut widget_executors,
+                                &mut previous_executors,
+                                changed_sections,
+                                &WidgetId::parse(&widget),
+                            );
+                        }
+                        widget_order.push(id);
+                    }
+                    None => log::error!("Unknown group {}", id),
                 }
-                _ => {
-                    log::error!(
-                        "Unexpected error when trying to get the value of {}!",
-                        widget_name
+            } else if Self::add_executor(
+                &mut widget_executors,
+                &mut previous_executors,
+                changed_sections,
+                &id,
+            ) {
+                widget_order.push(id);
+            }
+        }
+
+        // Executors that are left over stop as soon as their address is dropped
+        (widget_executors, widget_order)
+    }
+
+    pub fn new(output_format: OutputFormat) -> Self {
+        let (widget_executors, widget_order) =
+            Self::create_widgets(CONFIG.widget_order(), HashMap::new(), &[]);
+
+        Self {
+            widget_executors: Arc::new(RwLock::new(widget_executors)),
+            widget_order,
+            output: BufWriter::new(stdout().lock()),
+            output_format,
+            last_blocks: Mutex::new(HashMap::new()),
+            block_widths: HashMap::new(),
+            ticks: 0,
+        }
+    }
+
+    // Replace the text of a block with its short_text
+    // Blocks without short_text are left unchanged
+    fn minimize_block(block: &mut Value) {
+        if let Some(block) = block.as_object_mut() {
+            if let Some(short_text) = block.remove("short_text") {
+                block.insert(String::from("full_text"), short_text);
+            }
+        }
+    }
+
+    // Add the configured block options (e.g. markup) to the block of a widget
+    // The widget is declared as "name" or "name:instance"
+    fn apply_block_config(block: &mut Value, widget: &str) {
+        if let (Some(block), Ok(Value::Object(block_config))) = (
+            block.as_object_mut(),
+            serde_json::to_value(CONFIG.block_config(widget)),
+        ) {
+            block.extend(block_config);
+        }
+
+        for variable_color in CONFIG.variable_colors() {
+            if variable_color
+                .widgets
+                .iter()
+                .any(|colored| colored == widget || colored == widget_name(widget))
+                && variable(&variable_color.variable).as_deref() == Some(&variable_color.value)
+            {
+                if let Some(block) = block.as_object_mut() {
+                    block.insert(
+                        String::from("color"),
+                        Value::String(variable_color.color.hex().to_string()),
                     );
-                    continue;
                 }
+            }
+        }
+    }
+
+    // Replace the color of a block with the color of its value on the configured gradient
+    fn apply_gradient(block: &mut Value, id: &WidgetId) {
+        let Some(gradient) = CONFIG.color_gradient(&id.to_string()) else {
+            return;
+        };
+        let Some(value) =
+            variable(&id.variable_name("value")).and_then(|value| value.parse::<f32>().ok())
+        else {
+            return;
+        };
+        if let (Some(block), Some(color)) = (block.as_object_mut(), gradient.hex(value)) {
+            block.insert(String::from("color"), Value::String(color));
+        }
+    }
+
+    // Block that is shown instead of a widget that could not produce a value,
+    // so a misconfigured widget does not just vanish from the bar
+    fn error_block(id: &WidgetId) -> Value {
+        let mut block = json!({
+            "name": &*id.name,
+            "full_text": format!("{}: n/a", id),
+            "color": Color::Warning,
+        });
+        // Keep the instance, so clicks still reach the widget
+        if let (Some(instance), Some(block)) = (&id.instance, block.as_object_mut()) {
+            block.insert(
+                String::from("instance"),
+                Value::String(instance.to_string()),
+            );
+        }
+        apply_override(&mut block, id);
+        Self::apply_block_config(&mut block, &id.to_string());
+        block
+    }
+
+    // Get the block of a single widget
+    // Errors are logged and the widget is left out
+    async fn widget_block(&self, id: &WidgetId) -> Option<Value> {
+        // Unknown widgets are reported when the executors are created
+        let executor = self.widget_executors.read().unwrap().get(id)?.clone();
+        match executor.send(WidgetValue {}).timeout(WIDGET_TIMEOUT).await {
+            // The widget is empty and configured to be hidden
+            Ok(Ok(Value::Null)) => {
+                self.last_blocks
+                    .lock()
+                    .unwrap()
+                    .insert(id.clone(), Value::Null);
+                None
+            }
+            Ok(Ok(mut block)) => {
+                Self::apply_gradient(&mut block, id);
+                apply_override(&mut block, id);
+                Self::apply_block_config(&mut block, &id.to_string());
+                self.last_blocks
+                    .lock()
+                    .unwrap()
+                    .insert(id.clone(), block.clone());
+                Some(block)
+            }
+            Ok(Err(error)) => {
+                if CONFIG.block_config(&id.to_string()).hide_on_error {
+                    log::debug!("Hiding {} because of an error: {}", id, error);
+                    return None;
+                }
+                log::warn!("Invalid value for {}: \n\t{}", id, error);
+                Some(Self::error_block(id))
+            }
+            // The widget is still busy (e.g. reading from a hung network mount),
+            // so the last block it had is shown until it answers again
+            Err(MailboxError::Timeout) => {
+                log::warn!("{} did not answer in time", id);
+                match self.last_blocks.lock().unwrap().get(id) {
+                    Some(Value::Null) => None,
+                    Some(block) => Some(Self::mark_stale(block.clone())),
+                    None => Some(Self::error_block(id)),
+                }
+            }
+            Err(MailboxError::Closed) => {
+                log::error!("Unexpected error when trying to get the value of {}!", id);
+                Some(Self::error_block(id))
+            }
+        }
+    }
+
+    fn mark_stale(mut block: Value) -> Value {
+        if let (Some(marker), Some(text)) = (CONFIG.stale_marker(), block["full_text"].as_str()) {
+            block["full_text"] = Value::String(format!("{}{}", text, marker));
+        }
+        block
+    }
+
+    fn is_group_expanded(id: &WidgetId) -> bool {
+        variable(&id.variable_name("expanded")).as_deref() == Some("true")
+    }
+
+    // Expand or collapse a group, called when the label of a group is clicked
+    pub fn toggle_group(id: &WidgetId) {
+        let expandable = id
+            .instance
+            .as_deref()
+            .and_then(|instance| CONFIG.group(instance))
+            .is_some_and(|group| group.expandable);
+        if expandable {
+            toggle_variable(&id.variable_name("expanded"));
+        }
+    }
+
+    // Get the blocks of a group
+    // The widgets are merged into one block, unless the group was expanded by a click
+    async fn group_blocks(&self, id: &WidgetId) -> Vec<Value> {
+        let Some(group) = id
+            .instance
+            .as_deref()
+            .and_then(|instance| CONFIG.group(instance))
+        else {
+            return Vec::new();
+        };
+        let ids: Vec<WidgetId> = group
+            .widgets
+            .iter()
+            .map(|widget| WidgetId::parse(widget))
+            .collect();
+        let mut blocks: Vec<Value> = join_all(ids.iter().map(|id| self.widget_block(id)))
+            .await
+            .into_iter()
+            .flatten()
+            .collect();
+
+        let text = |block: &Value, key: &str| block[key].as_str().map(str::to_string);
+        let mut label = json!({
+            "name": GROUP,
+            "instance": id.instance.as_deref(),
+            "full_text": group.label,
+            "color": group.color.unwrap_or(Color::Neutral),
+        });
+        if group.expandable && Self::is_group_expanded(id) {
+            // The label stays in front of the widgets, so the group can be collapsed again
+            Self::apply_block_config(&mut label, &id.to_string());
+            blocks.insert(0, label);
+            return blocks;
+        }
+
+        let mut full_text = vec![group.label.to_string()];
+        let mut short_text = vec![group.label.to_string()];
+        let mut color = group.color.unwrap_or(Color::Neutral);
+        let mut urgent = false;
+        for block in &blocks {
+            full_text.extend(text(block, "full_text"));
+            short_text.extend(text(block, "short_text").or_else(|| text(block, "full_text")));
+            urgent |= block["urgent"].as_bool().unwrap_or(false);
+            // Colors are ordered from least to most severe
+            if let (None, Some(block_color)) = (
+                group.color,
+                block["color"].as_str().and_then(Color::from_hex),
+            ) {
+                color = color.max(block_color);
+            }
+        }
+        label["full_text"] = Value::String(full_text.join(" "));
+        label["short_text"] = Value::String(short_text.join(" "));
+        label["color"] = Value::String(color.hex().to_string());
+        if urgent {
+            label["urgent"] = Value::Bool(true);
+        }
+        Self::apply_block_config(&mut label, &id.to_string());
+
+        vec![label]
+    }
+
+    // Make the block at least as wide as its widest text so far
+    // The text is measured, because min_width as text only fits fonts with a fixed width
+    fn stabilize_width(&mut self, block: &mut Value) {
+        let Some(name) = block["name"].as_str() else {
+            return;
+        };
+        let key = match block["instance"].as_str() {
+            Some(instance) => format!("{}:{}", name, instance),
+            None => name.to_string(),
+        };
+        if !CONFIG.block_config(&key).stable_width {
+            return;
+        }
+        let text = block["full_text"].as_str().unwrap_or_default();
+        let text = if block["markup"] == "pango" {
+            strip_markup(text)
+        } else {
+            text.to_string()
+        };
+
+        let width = self.block_widths.entry(key).or_default();
+        *width = (*width).max(text_width(&text, CONFIG.bar_font_size()));
+        if let Some(block) = block.as_object_mut() {
+            block.insert(String::from("min_width"), json!(*width));
+        }
+    }
+
+    // Estimated width of a block in pixels, including the gap to the next block
+    fn block_width(block: &Value) -> u32 {
+        let text = block["full_text"].as_str().unwrap_or_default();
+        let text = if block["markup"] == "pango" {
+            strip_markup(text)
+        } else {
+            text.to_string()
+        };
+        let min_width = match &block["min_width"] {
+            Value::Number(width) => width.as_u64().unwrap_or_default() as u32,
+            Value::String(text) => text_width(text, CONFIG.bar_font_size()),
+            _ => 0,
+        };
+        let gap = block["separator_block_width"]
+            .as_u64()
+            .unwrap_or(DEFAULT_SEPARATOR_BLOCK_WIDTH as u64) as u32;
+        text_width(&text, CONFIG.bar_font_size()).max(min_width) + gap
+    }
+
+    fn block_priority(block: &Value) -> u8 {
+        let name = block["name"].as_str().unwrap_or_default();
+        match block["instance"].as_str() {
+            Some(instance) => CONFIG.widget_priority(&format!("{}:{}", name, instance)),
+            None => CONFIG.widget_priority(name),
+        }
+    }
+
+    // Make the blocks fit into max_bar_width: blocks of the lowest priority show their short text
+    // first, if that is not enough they are hidden. On the same priority, blocks on the left go
+    // first. This is done for every line, so blocks come back as soon as there is space again
+    fn fit_into_width(values: &mut Vec<Value>) {
+        let Some(max_width) = CONFIG.max_bar_width() else {
+            return;
+        };
+        let mut widths: Vec<u32> = values.iter().map(Self::block_width).collect();
+        if widths.iter().sum::<u32>() <= max_width {
+            return;
+        }
+        let mut by_priority: Vec<usize> = (0..values.len()).collect();
+        by_priority.sort_by_key(|index| Self::block_priority(&values[*index]));
+
+        for index in &by_priority {
+            let block = &mut values[*index];
+            if let Some(short_text) = block["short_text"].as_str().map(str::to_string) {
+                block["full_text"] = Value::String(short_text);
+                // The width that stable_width reserved is for the full text
+                if let Some(block) = block.as_object_mut() {
+                    block.remove("min_width");
+                }
+                widths[*index] = Self::block_width(block);
+                if widths.iter().sum::<u32>() <= max_width {
+                    return;
+                }
+            }
+        }
+
+        let mut hidden = vec![false; values.len()];
+        let mut width: u32 = widths.iter().sum();
+        for index in by_priority {
+            if width <= max_width {
+                break;
+            }
+            hidden[index] = true;
+            width -= widths[index];
+        }
+        let mut hidden = hidden.into_iter();
+        values.retain(|_| !hidden.next().unwrap_or_default());
+    }
+
+    async fn widget_values(&mut self) -> Vec<Value> {
+        let now = Local::now().time();
+        // All widgets are asked at the same time, so a slow widget only delays the line by its
+        // own timeout. join_all keeps the order of the widgets
+        let this = &*self;
+        let mut values: Vec<Value> = join_all(this.widget_order.iter().map(|id| async move {
+            let scheduled_action = CONFIG.scheduled_action(&id.name, now);
+            if scheduled_action == Some(ScheduleAction::Hide) {
+                return Vec::new();
+            }
+            let mut blocks = if &*id.name == GROUP {
+                this.group_blocks(id).await
+            } else {
+                this.widget_block(id).await.into_iter().collect()
             };
+            if scheduled_action == Some(ScheduleAction::Minimal) {
+                blocks.iter_mut().for_each(Self::minimize_block);
+            }
+            blocks
+        }))
+        .await
+        .into_iter()
+        .flatten()
+        .collect();
+        for block in values.iter_mut() {
+            self.stabilize_width(block);
         }
+        Self::fit_into_width(&mut values);
 
         values
     }
 
-    fn update_widgets(&self) {
+    // Low power mode is on while the battery widget reports a discharging battery below the limit
+    fn check_low_power() {
+        let low_power = CONFIG.low_power_below().is_some_and(|limit| {
+            variable("battery.discharging").as_deref() == Some("true")
+                && variable("battery.value")
+                    .and_then(|value| value.parse::<f32>().ok())
+                    .is_some_and(|value| value < limit)
+        });
+        if low_power != is_low_power() {
+            log::info!(
+                "Low power mode {}",
+                if low_power { "started" } else { "ended" }
+            );
+            set_low_power(low_power);
+        }
+    }
+
+    fn update_widgets(&mut self) {
+        Self::check_low_power();
+        let update_all = !is_low_power()
+            || self
+                .ticks
+                .is_multiple_of(CONFIG.low_power_tick_factor().max(1));
+        self.ticks = self.ticks.wrapping_add(1);
+
         // Send update message to all executors
         // This will start a "update" job
-        for executor in self.widget_executors.values() {
-            executor.do_send(UpdateWidgetValue {});
+        for (id, executor) in self.widget_executors.read().unwrap().iter() {
+            if update_all || CONFIG.low_power_exempt_widgets().contains(&&*id.name) {
+                request_update(id, executor);
+            }
+        }
+    }
+
+    // Write a line to i3bar and make sure it is sent right away
+    fn write_line(&mut self, line: &str) {
+        writeln!(self.output, "{}", line)
+            .and_then(|()| self.output.flush())
+            .expect("ERROR: Could not write to stdout");
+    }
+
+    // Wait until the next tick, until a refresh was requested through SIGUSR1
+    // or until a widget was updated by an event
+    // While the bar is hidden, we wait until it is shown again
+    // The timer yields to the actix system, so the widget executors keep running while we wait
+    async fn wait_for_next_update(&self, next_tick: Instant) -> Wakeup {
+        loop {
+            let now = Instant::now();
+            if is_shutdown_requested() {
+                return Wakeup::Shutdown;
+            } else if is_paused() {
+                sleep(REFRESH_POLL_INTERVAL).await;
+            } else if take_refresh_request() || now >= next_tick {
+                return Wakeup::Tick;
+            } else if take_render_request() {
+                return Wakeup::Render;
+            } else {
+                sleep(REFRESH_POLL_INTERVAL.min(next_tick - now)).await;
+            }
+        }
+    }
+
+    // Load the changed config file and rebuild the widgets
+    // Widgets that are still configured keep their executor, new widgets are created
+    // and removed widgets are dropped. The i3bar stream stays open the whole time.
+    fn reload_config(&mut self) {
+        let changed_sections = match CONFIG.load_file() {
+            Ok(changed_sections) => changed_sections,
+            Err(error) => {
+                log::error!("Could not reload the config file: {}", error);
+                return;
+            }
+        };
+        reset_palette();
+
+        let mut widget_executors = self.widget_executors.write().unwrap();
+        let (new_executors, widget_order) = Self::create_widgets(
+            CONFIG.widget_order(),
+            take(&mut *widget_executors),
+            &changed_sections,
+        );
+        *widget_executors = new_executors;
+        drop(widget_executors);
+        self.widget_order = widget_order;
+        log::info!("Reloaded the config file");
+    }
+
+    // Show the widgets in the order that was set over the IPC socket
+    // Widgets that are not running yet are started, the ones that are left out are stopped
+    fn apply_order(&mut self, order: Vec<WidgetId>) {
+        let mut widget_executors = self.widget_executors.write().unwrap();
+        let (new_executors, widget_order) =
+            Self::create_widgets(order, take(&mut *widget_executors), &[]);
+        *widget_executors = new_executors;
+        drop(widget_executors);
+        self.widget_order = widget_order;
+    }
+
+    // Stop all widgets, so they can release their sockets and files
+    async fn stop_widgets(&mut self) {
+        let executors: Vec<Addr<WidgetExecutor>> = self
+            .widget_executors
+            .write()
+            .unwrap()
+            .drain()
+            .map(|(_, executor)| executor)
+            .collect();
+        // Widgets that hang (e.g. on a dead NFS mount) are left behind
+        for executor in executors {
+            if let Err(error) = executor.send(StopWidget).timeout(STOP_TIMEOUT).await {
+                log::debug!("Could not stop a widget executor: {}", error);
+                continue;
+            }
+            // Wait until the widget was dropped in the thread of the executor
+            let deadline = Instant::now() + STOP_TIMEOUT;
+            while executor.connected() && Instant::now() < deadline {
+                sleep(STOP_POLL_INTERVAL).await;
+            }
+        }
+    }
+
+    // Stop the widgets and close the stream that was opened by the header
+    async fn shutdown(&mut self) {
+        self.stop_widgets().await;
+        for line in self.output_format.footer() {
+            self.write_line(&line);
+        }
+        log::info!("Shut down");
+    }
+
+    // Run the given number of ticks back to back and report how long they took
+    // Meant to be combined with --replay, so every run sees the same data
+    pub async fn benchmark(&mut self, ticks: u32) {
+        for line in self.output_format.header() {
+            self.write_line(&line);
+        }
+        let started = Instant::now();
+        for _ in 0..ticks {
+            self.update_widgets();
+            let values = self.widget_values().await;
+            self.write_line(&self.output_format.format(&values));
+        }
+        let elapsed = started.elapsed();
+        eprintln!(
+            "{} ticks in {:.1?} ({:.2?} per tick)",
+            ticks,
+            elapsed,
+            elapsed / ticks.max(1)
+        );
+        self.shutdown().await;
+    }
+
+    // Update every widget once and measure how long it took, for "i3rustus diagnose"
+    // Widgets that hang are given up on after the stop timeout
+    pub async fn time_widget_updates(&mut self) -> Vec<(WidgetId, Duration, Result<(), String>)> {
+        let executors: Vec<(WidgetId, Addr<WidgetExecutor>)> = self
+            .widget_executors
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(id, executor)| (id.clone(), executor.clone()))
+            .collect();
+        let mut timings = Vec::new();
+        for (id, executor) in executors {
+            let started = Instant::now();
+            let result = match executor.send(UpdateWidgetValue).timeout(STOP_TIMEOUT).await {
+                Ok(()) => match executor.send(WidgetValue).timeout(STOP_TIMEOUT).await {
+                    Ok(Ok(_)) => Ok(()),
+                    Ok(Err(error)) => Err(error.to_string()),
+                    Err(error) => Err(error.to_string()),
+                },
+                Err(error) => Err(error.to_string()),
+            };
+            timings.push((id, started.elapsed(), result));
         }
+        self.shutdown().await;
+
+        timings
     }
 
-    pub async fn init(&mut self) {
+    // Runs until the bar is stopped
+    // Fails if the first status would break the i3bar protocol, i3bar would only show an error
+    pub async fn init(&mut self) -> Result<(), Vec<String>> {
+        if let Err(error) = install_signal_handlers() {
+            log::error!("Could not install signal handlers: {}", error);
+        }
         // Make sure all widgets contain a valid value before starting the actual loop
         self.update_widgets();
-        // This is the output that is read by i3
-        println!("{{\"version\":1}}");
-        // Begin endless array
-        println!("[");
-        // Arrays have to be separated by comma in output
-        println!("[]");
+        if self.output_format == OutputFormat::I3bar {
+            let values = self.widget_values().await;
+            let errors = check_status_line(&self.output_format.format(&values));
+            if !errors.is_empty() {
+                // Nothing was written yet, so the array is not closed
+                self.stop_widgets().await;
+                return Err(errors);
+            }
+        }
+        // Forward click events from i3bar to the widgets
+        if self.output_format.supports_click_events() {
+            listen_for_click_events(self.widget_executors.clone());
+        }
+        listen_for_dbus_calls(self.widget_executors.clone());
+        listen_for_ipc_commands(self.widget_executors.clone());
+        if let Err(error) = watch_config_file(&CONFIG.file_path()) {
+            log::warn!("Changes of the config file are not picked up: {}", error);
+        }
+        for line in self.output_format.header() {
+            self.write_line(&line);
+        }
+        let mut previous_values = Vec::new();
+        let mut tick = next_tick();
+        let mut recorder = Recorder::new();
         loop {
-            // Print all values, these values will be seen in i3bar
-            println!(",{}", self.widget_values().await);
-            // Wait 1 secs before printing update
-            thread::sleep(time::Duration::from_secs(1));
-            self.update_widgets();
+            let values = self.widget_values().await;
+            // i3bar redraws the whole bar for every line it receives,
+            // so we only print the values if at least one block changed
+            if values != previous_values {
+                // Print all values, these values will be seen in the bar
+                self.write_line(&self.output_format.format(&values));
+                previous_values = values;
+            }
+            recorder.record_if_due();
+            match self.wait_for_next_update(tick).await {
+                Wakeup::Tick => {
+                    tick = next_tick();
+                    if take_config_change() {
+                        self.reload_config();
+                    }
+                    if let Some(order) = take_order_request() {
+                        self.apply_order(order);
+                    }
+                    self.update_widgets();
+                }
+                Wakeup::Render => (),
+                Wakeup::Shutdown => {
+                    remove_ipc_socket();
+                    self.shutdown().await;
+                    return Ok(());
+                }
+            }
         }
     }
 }
@@ -0,0 +1,196 @@
+use serde::Deserialize;
+
+use crate::i3_status::CONFIG;
+
+// Icons that widgets can show, the glyph depends on the configured icon set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Icon {
+    BatteryCharging,
+    BatteryDischarging,
+    BatteryFull,
+    BatteryUnknown,
+    Wireless,
+    Ethernet,
+    Vpn,
+    Tethered,
+    Cpu,
+    Memory,
+    Disk,
+    Time,
+}
+
+// Fonts without special glyphs can still show the ASCII set
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IconSet {
+    // Unicode symbols that are part of most fonts
+    Default,
+    // Glyphs of patched fonts, see https://www.nerdfonts.com/cheat-sheet
+    NerdFont,
+    Ascii,
+}
+
+impl Icon {
+    // Name that is used to override the icon in the config
+    pub fn name(self) -> &'static str {
+        match self {
+            Icon::BatteryCharging => "battery_charging",
+            Icon::BatteryDischarging => "battery_discharging",
+            Icon::BatteryFull => "battery_full",
+            Icon::BatteryUnknown => "battery_unknown",
+            Icon::Wireless => "wireless",
+            Icon::Ethernet => "ethernet",
+            Icon::Vpn => "vpn",
+            Icon::Tethered => "tethered",
+            Icon::Cpu => "cpu",
+            Icon::Memory => "memory",
+            Icon::Disk => "disk",
+            Icon::Time => "time",
+        }
+    }
+
+    fn default_glyph(self) -> &'static str {
+        match self {
+            Icon::BatteryCharging => "⚡",
+            Icon::BatteryDischarging => "🔋",
+            Icon::BatteryFull => "☻",
+            Icon::BatteryUnknown => "?",
+            Icon::Wireless => "W",
+            Icon::Ethernet => "E",
+            Icon::Vpn => "VPN",
+            Icon::Tethered => "T",
+            Icon::Cpu => "CPU",
+            Icon::Memory => "RAM",
+            Icon::Disk => "⛁",
+            Icon::Time => "◷",
+        }
+    }
+
+    fn nerd_font_glyph(self) -> &'static str {
+        match self {
+            Icon::BatteryCharging => "\u{F0084}",
+            Icon::BatteryDischarging => "\u{F0079}",
+            Icon::BatteryFull => "\u{F240}",
+            Icon::BatteryUnknown => "\u{F0091}",
+            Icon::Wireless => "\u{F05A9}",
+            Icon::Ethernet => "\u{F0200}",
+            Icon::Vpn => "\u{F0582}",
+            Icon::Tethered => "\u{F011C}",
+            Icon::Cpu => "\u{F0EE0}",
+            Icon::Memory => "\u{F035B}",
+            Icon::Disk => "\u{F02CA}",
+            Icon::Time => "\u{F0150}",
+        }
+    }
+
+    fn ascii_glyph(self) -> &'static str {
+        match self {
+            Icon::BatteryCharging => "CHR",
+            Icon::BatteryDischarging => "BAT",
+            Icon::BatteryFull => "FULL",
+            Icon::BatteryUnknown => "?",
+            Icon::Wireless => "W",
+            Icon::Ethernet => "E",
+            Icon::Vpn => "VPN",
+            Icon::Tethered => "T",
+            Icon::Cpu => "CPU",
+            Icon::Memory => "RAM",
+            Icon::Disk => "DISK",
+            Icon::Time => "TIME",
+        }
+    }
+
+    // Glyph of the icon, overrides in the config take precedence over the icon set
+    pub fn glyph(self) -> String {
+        if let Some(glyph) = CONFIG.icon_override(self.name()) {
+            return glyph;
+        }
+
+        match CONFIG.icon_set() {
+            IconSet::Default => self.default_glyph(),
+            IconSet::NerdFont => self.nerd_font_glyph(),
+            IconSet::Ascii => self.ascii_glyph(),
+        }
+        .to_string()
+    }
+}
+
+// Icons whose glyph depends on a percentage, e.g. the fill level of a battery
+// The glyphs are ordered from 0% to 100% and spread evenly over that range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ramp {
+    Battery,
+    Brightness,
+    // Quality of a wireless connection
+    Signal,
+}
+
+impl Ramp {
+    // Name that is used to override the ramp in the config
+    pub fn name(self) -> &'static str {
+        match self {
+            Ramp::Battery => "battery",
+            Ramp::Brightness => "brightness",
+            Ramp::Signal => "signal",
+        }
+    }
+
+    fn default_glyphs(self) -> &'static [&'static str] {
+        match self {
+            Ramp::Battery => &["▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"],
+            Ramp::Brightness => &["○", "◔", "◑", "◕", "●"],
+            Ramp::Signal => &["▂", "▂▄", "▂▄▆", "▂▄▆█"],
+        }
+    }
+
+    fn nerd_font_glyphs(self) -> &'static [&'static str] {
+        match self {
+            Ramp::Battery => &[
+                "\u{F008E}",
+                "\u{F007A}",
+                "\u{F007B}",
+                "\u{F007C}",
+                "\u{F007D}",
+                "\u{F007E}",
+                "\u{F007F}",
+                "\u{F0080}",
+                "\u{F0081}",
+                "\u{F0082}",
+                "\u{F0079}",
+            ],
+            Ramp::Brightness => &["\u{F00DE}", "\u{F00DF}", "\u{F00E0}"],
+            Ramp::Signal => &[
+                "\u{F092F}",
+                "\u{F091F}",
+                "\u{F0922}",
+                "\u{F0925}",
+                "\u{F0928}",
+            ],
+        }
+    }
+
+    fn ascii_glyphs(self) -> &'static [&'static str] {
+        match self {
+            Ramp::Battery | Ramp::Signal => &["[    ]", "[=   ]", "[==  ]", "[=== ]", "[====]"],
+            Ramp::Brightness => &["-", "o", "O"],
+        }
+    }
+
+    // Glyph for the percentage, ramps in the config take precedence over the icon set
+    pub fn glyph(self, percent: f32) -> String {
+        // 100% would be one past the last glyph
+        let index = |length: usize| {
+            ((percent.clamp(0.0, 100.0) / 100.0 * length as f32) as usize).min(length - 1)
+        };
+        if let Some(mut glyphs) = CONFIG.icon_ramp(self.name()) {
+            return glyphs.swap_remove(index(glyphs.len()));
+        }
+
+        let glyphs = match CONFIG.icon_set() {
+            IconSet::Default => self.default_glyphs(),
+            IconSet::NerdFont => self.nerd_font_glyphs(),
+            IconSet::Ascii => self.ascii_glyphs(),
+        };
+        glyphs[index(glyphs.len())].to_string()
+    }
+}
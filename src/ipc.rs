@@ -0,0 +1,238 @@
+use actix::MailboxError;
+use actix_rt::ArbiterHandle;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs::{metadata, remove_file};
+use std::io::{BufRead, BufReader, Error as IOError, ErrorKind, Write};
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::mpsc::channel;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use crate::config::WidgetId;
+use crate::i3_status::{I3Status, WidgetExecutors, CONFIG};
+use crate::sandbox::sandboxed_widgets;
+use crate::signals::{request_refresh, request_render};
+use crate::variables::set_variable;
+use crate::widget_executor::{request_update, WidgetValue};
+
+// Commands over a unix socket, for scripts and tools that do not want to use D-Bus
+// Every line is a JSON command and is answered with one line of JSON:
+//
+// {"command": "refresh"}                               -> {"ok": true}
+// {"command": "refresh", "widget": "disk:home"}        -> {"ok": true}
+// {"command": "set-order", "widgets": ["time", "cpu_load"]} -> {"ok": true}
+// {"command": "value", "widget": "time"}               -> {"ok": true, "block": {...}}
+// {"command": "set-variable", "name": "on_call", "value": "true"} -> {"ok": true}
+// Anything that fails                                  -> {"ok": false, "error": "..."}
+//
+// e.g. echo '{"command": "refresh"}' | socat - UNIX-CONNECT:$XDG_RUNTIME_DIR/i3rustus.sock
+
+// How long a query waits for the value of a widget
+const QUERY_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case", deny_unknown_fields)]
+enum Command {
+    Refresh { widget: Option<String> },
+    SetOrder { widgets: Vec<String> },
+    Value { widget: String },
+    SetVariable { name: String, value: String },
+}
+
+// Order that was set over the socket, the main loop shows it with the next tick
+// Reloading the config file goes back to the order of the config
+static REQUESTED_ORDER: Mutex<Option<Vec<WidgetId>>> = Mutex::new(None);
+
+pub fn take_order_request() -> Option<Vec<WidgetId>> {
+    REQUESTED_ORDER.lock().unwrap().take()
+}
+
+fn find_widget(executors: &WidgetExecutors, widget: &str) -> Result<WidgetId, String> {
+    let id = WidgetId::parse(widget);
+    if executors.read().unwrap().contains_key(&id) {
+        Ok(id)
+    } else {
+        Err(format!("{} is not shown in the bar", widget))
+    }
+}
+
+// Widgets that are not shown yet are started by the main loop, so they are checked like the
+// widgets of the config file. The sandbox only allows the files of the widgets that the bar
+// was started with, so it can't start others
+fn check_order_widget(executors: &WidgetExecutors, widget: &str) -> Result<WidgetId, String> {
+    let id = WidgetId::parse(widget);
+    if executors.read().unwrap().contains_key(&id) {
+        return Ok(id);
+    }
+    if sandboxed_widgets().is_some_and(|widgets| !widgets.contains(&id)) {
+        return Err(format!(
+            "{} can't be started in the sandbox, add it to the order in the config file",
+            widget
+        ));
+    }
+    I3Status::check_widget(&id)?;
+    Ok(id)
+}
+
+// Runs in the actix system, so the executors can be asked for their value
+async fn handle_command(command: Command, executors: WidgetExecutors) -> Result<Value, String> {
+    match command {
+        Command::Refresh { widget: None } => request_refresh(),
+        Command::Refresh {
+            widget: Some(widget),
+        } => {
+            let id = find_widget(&executors, &widget)?;
+            if let Some(executor) = executors.read().unwrap().get(&id) {
+                request_update(&id, executor);
+            }
+            request_render();
+        }
+        // Nothing changes if one of the widgets can't be shown
+        Command::SetOrder { widgets } => {
+            let mut order = Vec::new();
+            let mut errors = Vec::new();
+            for widget in &widgets {
+                match check_order_widget(&executors, widget) {
+                    Ok(id) => order.push(id),
+                    Err(error) => errors.push(error),
+                }
+            }
+            if !errors.is_empty() {
+                return Err(errors.join("; "));
+            }
+            *REQUESTED_ORDER.lock().unwrap() = Some(order);
+            request_refresh();
+        }
+        Command::Value { widget } => {
+            let id = find_widget(&executors, &widget)?;
+            // Clone the address, so the lock is not held while waiting for the widget
+            let Some(executor) = executors.read().unwrap().get(&id).cloned() else {
+                return Err(format!("{} is not shown in the bar", widget));
+            };
+            let block = match executor.send(WidgetValue).timeout(QUERY_TIMEOUT).await {
+                Ok(Ok(block)) => block,
+                Ok(Err(error)) => return Err(error.to_string()),
+                Err(MailboxError::Timeout) => {
+                    return Err(format!("{} did not answer in time", widget))
+                }
+                Err(error) => return Err(error.to_string()),
+            };
+            return Ok(json!({ "ok": true, "block": block }));
+        }
+        // Widgets that show the variable are updated with their next tick, only the colors
+        // change right away
+        Command::SetVariable { name, value } => {
+            set_variable(&name, value);
+            request_render();
+        }
+    }
+
+    Ok(json!({ "ok": true }))
+}
+
+fn answer(line: &str, executors: &WidgetExecutors, arbiter: &ArbiterHandle) -> Value {
+    let command = match serde_json::from_str::<Command>(line) {
+        Ok(command) => command,
+        Err(error) => return json!({ "ok": false, "error": error.to_string() }),
+    };
+    let (sender, receiver) = channel();
+    let executors = executors.clone();
+    let spawned = arbiter.spawn(async move {
+        let _ = sender.send(handle_command(command, executors).await);
+    });
+    match receiver.recv() {
+        Ok(Ok(reply)) => reply,
+        Ok(Err(error)) => json!({ "ok": false, "error": error }),
+        // The system is shutting down
+        Err(_) if !spawned => json!({ "ok": false, "error": "i3rustus is shutting down" }),
+        Err(_) => json!({ "ok": false, "error": "The command was dropped" }),
+    }
+}
+
+fn serve_client(
+    stream: UnixStream,
+    executors: &WidgetExecutors,
+    arbiter: &ArbiterHandle,
+) -> Result<(), IOError> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = answer(&line, executors, arbiter);
+        writeln!(writer, "{}", reply)?;
+    }
+
+    Ok(())
+}
+
+// Device and inode of the socket that this process bound, another run may have replaced it since
+static BOUND_SOCKET: Mutex<Option<(u64, u64)>> = Mutex::new(None);
+
+fn bind(path: &Path) -> Result<UnixListener, IOError> {
+    // A socket that nobody answers on is left over from a crashed run
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            return Err(IOError::new(
+                ErrorKind::AddrInUse,
+                format!("{} is used by another process", path.display()),
+            ));
+        }
+        remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    let socket = metadata(path)?;
+    *BOUND_SOCKET.lock().unwrap() = Some((socket.dev(), socket.ino()));
+    Ok(listener)
+}
+
+// Remove the socket on shutdown, so the next run does not have to find out that it is stale
+// Only the socket that this process bound is removed, not the one of a run that took over
+pub fn remove_ipc_socket() {
+    let Some(bound) = BOUND_SOCKET.lock().unwrap().take() else {
+        return;
+    };
+    if let Some(path) = CONFIG.ipc_socket_path() {
+        if metadata(&path).is_ok_and(|socket| (socket.dev(), socket.ino()) == bound) {
+            let _ = remove_file(path);
+        }
+    }
+}
+
+// Accept commands on the socket, they are handled by the arbiter of the caller
+// Accepting connections is blocking, so this runs in a dedicated thread
+// Every client gets its own thread, so a client that does not send anything does not block others
+pub fn listen_for_ipc_commands(executors: WidgetExecutors) {
+    let Some(path) = CONFIG.ipc_socket_path() else {
+        return;
+    };
+    let listener = match bind(&path) {
+        Ok(listener) => listener,
+        Err(error) => {
+            log::warn!("The IPC socket is not available: {}", error);
+            return;
+        }
+    };
+    let arbiter = actix_rt::Arbiter::current();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let executors = executors.clone();
+                    let arbiter = arbiter.clone();
+                    thread::spawn(move || {
+                        if let Err(error) = serve_client(stream, &executors, &arbiter) {
+                            log::debug!("IPC client failed: {}", error);
+                        }
+                    });
+                }
+                Err(error) => log::warn!("Could not accept an IPC client: {}", error),
+            }
+        }
+    });
+}
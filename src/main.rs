@@ -1,14 +1,52 @@
+mod alerts;
+mod click_events;
 mod config;
+mod config_check;
+mod config_watcher;
+mod cpu_accounting;
+mod dbus;
+mod ddc;
+mod diagnose;
+mod events;
+mod i3_ipc;
 mod i3_status;
+mod icons;
+mod ipc;
+mod mqtt;
+#[cfg(target_os = "linux")]
 mod netlink;
+mod os;
+mod output;
+mod overrides;
+mod plugins;
+mod protocol_check;
+mod recorder;
+mod replay;
+mod sandbox;
+mod signals;
+mod snmp;
+mod sound;
+mod theme;
 mod utils;
+mod variables;
 mod widget_executor;
 mod widgets;
 
-use i3_status::I3Status;
+use config_check::check_config;
+use diagnose::print_report;
+use i3_status::{I3Status, CONFIG};
 use log::LevelFilter;
+use output::OutputFormat;
+use replay::{set_mode, Mode};
+use sandbox::enter_sandbox;
+use std::env;
+use std::process::exit;
 use utils::logger::Logger;
 
+const USAGE: &str = "Usage: i3rustus [--output i3bar|plain|waybar] [--check-config] \
+                     [--record <directory> | --replay <directory>] [--benchmark <ticks>]\n       \
+                     i3rustus diagnose";
+
 #[actix_rt::main]
 async fn main() {
     // Set logger
@@ -19,7 +57,92 @@ async fn main() {
         println!("Enable to set logger: {}", error);
     }
 
-    let mut i3status = I3Status::new();
+    let mut output_format = OutputFormat::I3bar;
+    let mut check_only = false;
+    let mut diagnose = false;
+    let mut benchmark_ticks = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => match args.next().as_deref().and_then(OutputFormat::from_name) {
+                Some(format) => output_format = format,
+                None => {
+                    eprintln!("{}", USAGE);
+                    exit(2);
+                }
+            },
+            "--check-config" => check_only = true,
+            // --diagnose is kept for scripts that were written before the subcommand
+            "diagnose" | "--diagnose" => diagnose = true,
+            "--record" | "--replay" => match args.next() {
+                Some(directory) if arg == "--record" => set_mode(Mode::Record(directory.into())),
+                Some(directory) => set_mode(Mode::Replay(directory.into())),
+                None => {
+                    eprintln!("{}", USAGE);
+                    exit(2);
+                }
+            },
+            "--benchmark" => match args.next().and_then(|ticks| ticks.parse::<u32>().ok()) {
+                Some(ticks) => benchmark_ticks = Some(ticks),
+                None => {
+                    eprintln!("{}", USAGE);
+                    exit(2);
+                }
+            },
+            _ => {
+                eprintln!("Unknown argument {}\n{}", arg, USAGE);
+                exit(2);
+            }
+        }
+    }
+
+    if check_only {
+        let errors = check_config();
+        for error in &errors {
+            eprintln!("{}", error);
+        }
+        if !errors.is_empty() {
+            exit(1);
+        }
+        println!("{} is valid", CONFIG.file_path().display());
+        return;
+    }
+
+    // The report contains the problems of the config file, so it is loaded there
+    if diagnose {
+        print_report().await;
+        return;
+    }
+
+    // Without a valid config file, the compiled in defaults are used
+    // i3bar ignores stderr, but the error is visible when the bar is started from a terminal
+    if let Err(error) = CONFIG.load_file() {
+        eprintln!("Could not load the config file: {}", error);
+        log::error!("Could not load the config file: {}", error);
+    }
+
+    // The widgets are known now, their threads are started by I3Status::new
+    // The bar does not run without the sandbox that was asked for
+    if CONFIG.sandbox() {
+        if let Err(error) = enter_sandbox(&CONFIG.widget_order()) {
+            eprintln!("Could not enter the sandbox: {}", error);
+            log::error!("Could not enter the sandbox: {}", error);
+            exit(1);
+        }
+    }
+
+    let mut i3status = I3Status::new(output_format);
 
-    i3status.init().await;
+    match benchmark_ticks {
+        Some(ticks) => i3status.benchmark(ticks).await,
+        None => {
+            if let Err(errors) = i3status.init().await {
+                for error in &errors {
+                    eprintln!("{}", error);
+                    log::error!("{}", error);
+                }
+                exit(1);
+            }
+        }
+    }
 }
@@ -0,0 +1,203 @@
+use std::io::{Error as IOError, ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::time::Duration;
+
+// See the MQTT 3.1.1 specification, only QoS 0 is supported
+const PROTOCOL_LEVEL: u8 = 4;
+// The broker disconnects us if it doesn't hear from us for 1.5 times the keep alive
+const KEEP_ALIVE: Duration = Duration::from_secs(60);
+
+// Control packet types, shifted into the upper four bits of the first byte
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+// Subscribe packets have the reserved flags 0b0010
+const SUBSCRIBE: u8 = 0x82;
+const SUBACK: u8 = 0x90;
+const PINGREQ: u8 = 0xC0;
+
+// CONNECT flags
+const CLEAN_SESSION: u8 = 0x02;
+const PASSWORD_FLAG: u8 = 0x40;
+const USERNAME_FLAG: u8 = 0x80;
+
+pub struct MqttMessage {
+    pub topic: String,
+    pub payload: Vec<u8>,
+}
+
+fn encode_string(value: &str, buffer: &mut Vec<u8>) {
+    buffer.extend((value.len() as u16).to_be_bytes());
+    buffer.extend(value.as_bytes());
+}
+
+// Whether a topic matches a subscription filter with the wildcards "+" and "#"
+pub fn topic_matches(filter: &str, topic: &str) -> bool {
+    let mut topic_levels = topic.split('/');
+    for filter_level in filter.split('/') {
+        match (filter_level, topic_levels.next()) {
+            ("#", _) => return true,
+            ("+", Some(_)) => continue,
+            (filter_level, Some(topic_level)) if filter_level == topic_level => continue,
+            _ => return false,
+        }
+    }
+
+    topic_levels.next().is_none()
+}
+
+// A minimal MQTT client that subscribes to topics and receives their messages
+pub struct MqttClient {
+    stream: TcpStream,
+    packet_id: u16,
+}
+
+impl MqttClient {
+    // The address contains the port, e.g. "localhost:1883"
+    pub fn connect(
+        address: &str,
+        client_id: &str,
+        credentials: Option<(&str, &str)>,
+    ) -> Result<Self, IOError> {
+        let stream = TcpStream::connect(address)?;
+        // Time out before the keep alive runs out, so we can send a ping
+        stream.set_read_timeout(Some(KEEP_ALIVE / 2))?;
+        let mut client = Self {
+            stream,
+            packet_id: 0,
+        };
+
+        let mut packet = Vec::new();
+        encode_string("MQTT", &mut packet);
+        packet.push(PROTOCOL_LEVEL);
+        packet.push(match credentials {
+            Some(_) => CLEAN_SESSION | USERNAME_FLAG | PASSWORD_FLAG,
+            None => CLEAN_SESSION,
+        });
+        packet.extend((KEEP_ALIVE.as_secs() as u16).to_be_bytes());
+        encode_string(client_id, &mut packet);
+        if let Some((username, password)) = credentials {
+            encode_string(username, &mut packet);
+            encode_string(password, &mut packet);
+        }
+        client.write_packet(CONNECT, &packet)?;
+
+        let (packet_type, payload) = client.read_packet()?;
+        if packet_type != CONNACK || payload.len() != 2 {
+            return Err(IOError::new(ErrorKind::InvalidData, "Expected CONNACK"));
+        }
+        if payload[1] != 0 {
+            return Err(IOError::other(format!(
+                "Broker refused the connection with code {}",
+                payload[1]
+            )));
+        }
+
+        Ok(client)
+    }
+
+    fn write_packet(&mut self, packet_type: u8, payload: &[u8]) -> Result<(), IOError> {
+        let mut packet = vec![packet_type];
+        // The remaining length is encoded with 7 bits per byte,
+        // the highest bit signals that another byte follows
+        let mut length = payload.len();
+        loop {
+            let byte = (length % 128) as u8;
+            length /= 128;
+            if length > 0 {
+                packet.push(byte | 0x80);
+            } else {
+                packet.push(byte);
+                break;
+            }
+        }
+        packet.extend(payload);
+
+        self.stream.write_all(&packet)
+    }
+
+    fn read_packet(&mut self) -> Result<(u8, Vec<u8>), IOError> {
+        let mut header = [0; 1];
+        self.stream.read_exact(&mut header)?;
+
+        let mut length = 0;
+        for shift in (0..4).map(|index| index * 7) {
+            let mut byte = [0; 1];
+            self.stream.read_exact(&mut byte)?;
+            length |= ((byte[0] & 0x7F) as usize) << shift;
+            if byte[0] & 0x80 == 0 {
+                break;
+            }
+        }
+
+        let mut payload = vec![0; length];
+        self.stream.read_exact(&mut payload)?;
+        Ok((header[0], payload))
+    }
+
+    pub fn subscribe(&mut self, topics: &[&str]) -> Result<(), IOError> {
+        self.packet_id = self.packet_id.wrapping_add(1).max(1);
+        let mut packet = self.packet_id.to_be_bytes().to_vec();
+        for topic in topics {
+            encode_string(topic, &mut packet);
+            // Requested QoS
+            packet.push(0);
+        }
+        self.write_packet(SUBSCRIBE, &packet)?;
+
+        // Messages of retained topics may arrive before the acknowledgement,
+        // but we only subscribe once after connecting, so it is fine to wait for it here
+        loop {
+            let (packet_type, payload) = self.read_packet()?;
+            if packet_type == SUBACK {
+                if payload.iter().skip(2).any(|code| *code == 0x80) {
+                    return Err(IOError::other("Broker refused a subscription"));
+                }
+                return Ok(());
+            }
+        }
+    }
+
+    // Returns a function that closes the connection from another thread,
+    // so a blocking `receive` returns with an error
+    pub fn shutdown_handle(&self) -> Result<impl FnOnce() + Send + 'static, IOError> {
+        let stream = self.stream.try_clone()?;
+        Ok(move || {
+            let _ = stream.shutdown(Shutdown::Both);
+        })
+    }
+
+    // Wait for the next message of a subscribed topic
+    pub fn receive(&mut self) -> Result<MqttMessage, IOError> {
+        loop {
+            let (header, payload) = match self.read_packet() {
+                Ok(packet) => packet,
+                // Nothing happened for a while, let the broker know we are still there
+                Err(error)
+                    if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+                {
+                    self.write_packet(PINGREQ, &[])?;
+                    continue;
+                }
+                Err(error) => return Err(error),
+            };
+            // Ping responses and other packets are not interesting
+            if header & 0xF0 != PUBLISH || payload.len() < 2 {
+                continue;
+            }
+
+            let topic_length = u16::from_be_bytes([payload[0], payload[1]]) as usize;
+            let Some(topic) = payload.get(2..2 + topic_length) else {
+                continue;
+            };
+            // Messages with QoS 1 or 2 have a packet ID after the topic
+            let qos = (header >> 1) & 0x03;
+            let payload_start = 2 + topic_length + if qos > 0 { 2 } else { 0 };
+
+            return Ok(MqttMessage {
+                topic: String::from_utf8_lossy(topic).to_string(),
+                payload: payload.get(payload_start..).unwrap_or_default().to_vec(),
+            });
+        }
+    }
+}
@@ -21,7 +21,28 @@ pub const NL80211_BSS_STATUS_ASSOCIATED: u32 = 1;
 pub const NL80211_BSS_STATUS_IBSS_JOINED: u32 = 2;
 
 // nl80211_sta_info
+// Signal strength of the last received frame in dBm, a signed byte
+pub const NL80211_STA_INFO_SIGNAL: i32 = 7;
 pub const NL80211_STA_INFO_TX_BITRATE: i32 = 8;
 
 // nl80211_rate_info
 pub const NL80211_RATE_INFO_BITRATE: i32 = 1;
+
+// See https://git.kernel.org/pub/scm/linux/kernel/git/torvalds/linux.git/tree/include/uapi/linux/ethtool_netlink.h
+//
+// ethtool message types
+pub const ETHTOOL_MSG_LINKMODES_GET: i32 = 4;
+
+// ethtool_a_header
+pub const ETHTOOL_A_HEADER_DEV_INDEX: i32 = 1;
+
+// ethtool_a_linkmodes
+pub const ETHTOOL_A_LINKMODES_HEADER: i32 = 1;
+pub const ETHTOOL_A_LINKMODES_SPEED: i32 = 5;
+pub const ETHTOOL_A_LINKMODES_DUPLEX: i32 = 6;
+
+// Values of ETHTOOL_A_LINKMODES_SPEED and ETHTOOL_A_LINKMODES_DUPLEX for links without carrier
+// or drivers that don't know them (e.g. virtio)
+pub const SPEED_UNKNOWN: u32 = u32::MAX;
+pub const DUPLEX_HALF: u8 = 0;
+pub const DUPLEX_FULL: u8 = 1;
@@ -0,0 +1,78 @@
+use crate::netlink::netlink_header::parse_attributes;
+use crate::{
+    netlink::NetlinkAttribute,
+    utils::{macros::walk_to_number, walking_vec::WalkingVec},
+};
+
+// https://elixir.bootlin.com/linux/latest/source/include/uapi/linux/rtnetlink.h#L565
+#[derive(Debug, PartialEq)]
+pub struct InterfaceInfoMessage {
+    pub family: u8,
+    pub padding: u8,
+    // ARPHRD_* type of the device
+    pub device_type: u16,
+    pub index: i32,
+    // IFF_* flags of the device
+    pub flags: u32,
+    pub change: u32,
+    pub attributes: Vec<NetlinkAttribute>,
+}
+
+impl InterfaceInfoMessage {
+    pub fn build(family: u8, index: i32, attributes: Vec<NetlinkAttribute>) -> Self {
+        Self {
+            family,
+            padding: 0,
+            device_type: 0,
+            index,
+            flags: 0,
+            change: 0,
+            attributes,
+        }
+    }
+
+    // Calculate the actual size
+    pub fn size(&self) -> usize {
+        // u8 * 2 + u16 = family, padding and device_type, followed by index, flags and change
+        let mut size = (std::mem::size_of::<u8>() * 2)
+            + std::mem::size_of::<u16>()
+            + std::mem::size_of::<i32>()
+            + (std::mem::size_of::<u32>() * 2);
+        for attribute in self.attributes.iter() {
+            size += attribute.size();
+        }
+        size
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut buffer = vec![self.family, self.padding];
+        buffer.extend(self.device_type.to_le_bytes());
+        buffer.extend(self.index.to_le_bytes());
+        buffer.extend(self.flags.to_le_bytes());
+        buffer.extend(self.change.to_le_bytes());
+        for attribute in self.attributes.iter() {
+            buffer.extend(attribute.serialize());
+        }
+
+        buffer
+    }
+
+    pub fn deserialize(buffer: &mut WalkingVec) -> Self {
+        let family = walk_to_number!(buffer, u8);
+        let padding = walk_to_number!(buffer, u8);
+        let device_type = walk_to_number!(buffer, u16);
+        let index = walk_to_number!(buffer, i32);
+        let flags = walk_to_number!(buffer, u32);
+        let change = walk_to_number!(buffer, u32);
+
+        Self {
+            family,
+            padding,
+            device_type,
+            index,
+            flags,
+            change,
+            attributes: parse_attributes(buffer),
+        }
+    }
+}
@@ -1,245 +1,151 @@
 mod constants;
 mod generic_netlink_header;
 mod interface_address_message;
+mod interface_info_message;
 mod netlink_attribute;
 mod netlink_header;
+mod route_message;
+
+mod socket;
 
 use libc::{
-    bind, c_void, connect, recv, sa_family_t, send, sockaddr, socket, socklen_t, AF_NETLINK,
-    AF_UNSPEC, CTRL_ATTR_FAMILY_ID, CTRL_ATTR_FAMILY_NAME, CTRL_CMD_GETFAMILY, GENL_ID_CTRL,
-    IFA_LOCAL, NETLINK_GENERIC, NETLINK_ROUTE, NLMSG_DONE, NLMSG_ERROR, NLM_F_ACK, NLM_F_DUMP,
-    NLM_F_REQUEST, RTM_GETADDR, RT_SCOPE_UNIVERSE, SOCK_RAW,
+    AF_INET, AF_INET6, AF_UNSPEC, CTRL_ATTR_FAMILY_ID, CTRL_ATTR_FAMILY_NAME, CTRL_CMD_GETFAMILY,
+    EHOSTUNREACH, EINVAL, ENETUNREACH, GENL_ID_CTRL, IFA_ADDRESS, IFA_FLAGS, IFA_F_DEPRECATED,
+    IFA_F_TENTATIVE, IFA_LOCAL, IFF_LOWER_UP, IFLA_OPERSTATE, IF_OPER_UNKNOWN, IF_OPER_UP,
+    NETLINK_GENERIC, NETLINK_ROUTE, NLA_F_NESTED, NLM_F_ACK, NLM_F_DUMP, NLM_F_REQUEST, RTA_DST,
+    RTA_OIF, RTM_GETADDR, RTM_GETLINK, RTM_GETROUTE, RTN_UNICAST, RT_SCOPE_UNIVERSE,
 };
 use std::ffi::CString;
-use std::io::{Error as IOError, ErrorKind};
-use std::os::unix::io::RawFd;
+use std::io::Error as IOError;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::{Arc, Mutex, Weak};
 
 use crate::netlink::constants::*;
 use crate::netlink::generic_netlink_header::GenericNetlinkMessageHeader;
 use crate::netlink::interface_address_message::InterfaceAddressMessage;
+use crate::netlink::interface_info_message::InterfaceInfoMessage;
 use crate::netlink::netlink_attribute::NetlinkAttribute;
-use crate::netlink::netlink_header::{NetlinkMessageHeader, Payload};
+use crate::netlink::netlink_header::Payload;
+use crate::netlink::route_message::RouteMessage;
+use crate::netlink::socket::NetlinkSocket;
+use crate::os::{
+    BSSInformation, Duplex, InterfaceAddresses, Ipv6Address, LinkModes, StationInformation,
+};
+use crate::replay::capture;
 use crate::utils::walking_vec::WalkingVec;
 
-// This is the maximum length that a netlink message can have
-// https://git.kernel.org/pub/scm/linux/kernel/git/netdev/net-next.git/commit/?id=d35c99ff77ecb2eb239731b799386f3b3637a31e
-const MAX_NETLINK_MESSAGE_SIZE: usize = 32768;
 const WIRELESS_SUBSYSTEM_NAME: &str = "nl80211\0";
+const ETHTOOL_SUBSYSTEM_NAME: &str = "ethtool\0";
+// Public addresses (Cloudflare DNS) whose route is looked up to find the interface to the
+// internet, they are never contacted
+const PUBLIC_IPV4_ADDRESS: Ipv4Addr = Ipv4Addr::new(1, 1, 1, 1);
+const PUBLIC_IPV6_ADDRESS: Ipv6Addr = Ipv6Addr::new(0x2606, 0x4700, 0x4700, 0, 0, 0, 0, 0x1111);
 
-#[derive(Debug)]
-pub struct BSSInformation {
-    pub ssid: String,
-    pub frequency: f32,
-}
+// Sockets of the network widgets, they share them as long as at least one of them exists
+static SHARED: Mutex<Weak<Netlink>> = Mutex::new(Weak::new());
 
 #[derive(Debug)]
 pub struct Netlink {
-    generic_netlink_socket: RawFd,
-    netlink_route_socket: RawFd,
+    // This socket is used to retrieve: SSID, Frequency, bitrate
+    generic_netlink_socket: NetlinkSocket,
+    // This socket is used to retrieve: Local IP Address
+    netlink_route_socket: NetlinkSocket,
     nl_80211_family_id: Result<i32, IOError>,
+    // Kernels before 5.6 have no ethtool netlink interface
+    ethtool_family_id: Result<i32, IOError>,
 }
 
 impl Netlink {
-    // Create a generic netlink socket
+    // Open the netlink sockets
     pub fn new() -> Result<Self, IOError> {
-        // This socket is used to retrieve: SSID, Frequency, bitrate
-        let generic_netlink_socket = unsafe { socket(AF_NETLINK, SOCK_RAW, NETLINK_GENERIC) };
-        // This socket is used to retrieve: Local IP Address
-        let netlink_route_socket = unsafe { socket(AF_NETLINK, SOCK_RAW, NETLINK_ROUTE) };
-
-        if generic_netlink_socket < 0 || netlink_route_socket < 0 {
-            return Err(IOError::last_os_error());
-        }
-        let socket_address = sockaddr {
-            sa_family: AF_NETLINK as sa_family_t,
-            sa_data: [0; 14],
-        };
-
-        if unsafe {
-            bind(
-                generic_netlink_socket,
-                &socket_address as *const sockaddr,
-                std::mem::size_of::<sockaddr>() as socklen_t,
-            )
-        } < 0
-            || unsafe {
-                bind(
-                    netlink_route_socket,
-                    &socket_address as *const sockaddr,
-                    std::mem::size_of::<sockaddr>() as socklen_t,
-                )
-            } < 0
-        {
-            return Err(IOError::last_os_error());
-        }
-
-        if unsafe {
-            connect(
-                generic_netlink_socket,
-                &socket_address as *const sockaddr,
-                std::mem::size_of::<sockaddr>() as socklen_t,
-            )
-        } < 0
-            || unsafe {
-                connect(
-                    netlink_route_socket,
-                    &socket_address as *const sockaddr,
-                    std::mem::size_of::<sockaddr>() as socklen_t,
-                )
-            } < 0
-        {
-            return Err(IOError::last_os_error());
-        }
-        let nl_80211_family_id = Self::get_80211_family_id(generic_netlink_socket);
+        let generic_netlink_socket = NetlinkSocket::open(NETLINK_GENERIC)?;
+        let netlink_route_socket = NetlinkSocket::open(NETLINK_ROUTE)?;
+        let nl_80211_family_id =
+            Self::get_family_id(&generic_netlink_socket, WIRELESS_SUBSYSTEM_NAME);
+        let ethtool_family_id =
+            Self::get_family_id(&generic_netlink_socket, ETHTOOL_SUBSYSTEM_NAME);
 
         Ok(Self {
             generic_netlink_socket,
             netlink_route_socket,
             nl_80211_family_id,
+            ethtool_family_id,
         })
     }
 
-    // Retrive the subsystem family ID for nl80211
-    fn get_80211_family_id(socket: RawFd) -> Result<i32, IOError> {
-        let mut family_id = i32::MIN;
+    // The sockets that are already open, or new ones if no widget uses them right now
+    // Requests on the same socket are serialized, so widgets in different threads can share them
+    pub fn shared() -> Result<Arc<Self>, IOError> {
+        let mut shared = SHARED.lock().unwrap();
+        if let Some(netlink) = shared.upgrade() {
+            return Ok(netlink);
+        }
+        let netlink = Arc::new(Self::new()?);
+        *shared = Arc::downgrade(&netlink);
+        Ok(netlink)
+    }
 
+    // Retrive the family ID of a generic netlink subsystem (e.g. nl80211)
+    fn get_family_id(socket: &NetlinkSocket, subsystem_name: &str) -> Result<i32, IOError> {
         let genl_header = GenericNetlinkMessageHeader::build(
             CTRL_CMD_GETFAMILY,
             vec![NetlinkAttribute::build(
                 CTRL_ATTR_FAMILY_NAME,
-                WIRELESS_SUBSYSTEM_NAME.as_bytes().to_vec(),
+                subsystem_name.as_bytes().to_vec(),
             )],
         );
-        let response = Self::request(
-            socket,
+        let response = socket.request(
             GENL_ID_CTRL,
             NLM_F_REQUEST | NLM_F_ACK,
             Payload::GenericNetlink(genl_header),
         )?;
 
-        if let Payload::GenericNetlink(message) = &response[0].payload {
-            let family_id_attribute =
-                netlink_header::get_attribute(&message.attributes, CTRL_ATTR_FAMILY_ID);
-            if let Some(family_id_attribute) = family_id_attribute {
-                family_id =
-                    u16::from_le_bytes(family_id_attribute.data.clone().try_into().unwrap()) as i32;
-            }
-        }
-
-        if family_id == i32::MIN {
-            Err(IOError::new(
-                ErrorKind::Other,
-                "Could not retrieve nl80211 family ID",
-            ))
-        } else {
-            Ok(family_id)
-        }
-    }
-
-    fn request(
-        socket: RawFd,
-        netlink_message_type: i32,
-        flags: i32,
-        payload: Payload,
-    ) -> Result<Vec<NetlinkMessageHeader>, IOError> {
-        let mut result_buffer = Vec::new();
-        // Create netlink header
-        let input_buffer =
-            NetlinkMessageHeader::build(netlink_message_type, flags, payload).serialize();
-        // Send and receive answer from socket
-        unsafe {
-            send(
-                socket,
-                input_buffer.as_ptr() as *const c_void,
-                input_buffer.len(),
-                0,
-            );
-            loop {
-                // Temporary buffer that will hold the current response
-                let mut buffer = vec![0; MAX_NETLINK_MESSAGE_SIZE];
-                let mut bytes_read: u32 = 0;
-                let response_size = recv(
-                    socket,
-                    buffer.as_mut_ptr() as *mut c_void,
-                    MAX_NETLINK_MESSAGE_SIZE,
-                    0,
-                );
-
-                // Our input buffer is initialized with the maximum netlink message size
-                // So we truncate the result to only contain the actual response bytes
-                buffer.truncate(response_size as usize);
-                // Create a vector which we can walk through
-                // The idea here is that we don't want to manipulate the original response
-                // vector, since this would require to allocate vectors that are not needed
-                let mut walkable_buffer = WalkingVec {
-                    buffer,
-                    position: 0,
-                };
-                // In a single response, there could be multiple netlink message headers
-                loop {
-                    // Break out of loop if we have finished reading all bytebytes
-                    if bytes_read == response_size as u32 {
-                        break;
-                    }
-                    let header = NetlinkMessageHeader::deserialize(&mut walkable_buffer);
-                    // Set original walking vec position to the length of the header length
-                    // This is required because we create a new walking vec in
-                    // NetlinkMessageHeader::deserialize to make sure that deserialization
-                    // stops after we reached the end of the header
-                    // And the reason deserialization does not "just" stop from itself, is because
-                    // we look for netlink attributes recursively and only stop at the end of
-                    // the passed walking vec
-                    // We could simply pass the header length to all other deserialization methods
-                    // and let them do the checking but this is cleaner in my (humble) opinion
-                    walkable_buffer.position += (header.length as usize) - NETLINK_HEADER_SIZE;
-                    bytes_read += header.length;
-                    result_buffer.push(header);
+        response
+            .first()
+            .and_then(|message| match &message.payload {
+                Payload::GenericNetlink(message) => {
+                    netlink_header::get_attribute(&message.attributes, CTRL_ATTR_FAMILY_ID)
                 }
-                if let Some(last_header) = result_buffer.last() {
-                    // Error + error code 0 is the ACK messag
-                    if last_header.message_type as i32 == NLMSG_DONE
-                        || (last_header.message_type as i32 == NLMSG_ERROR)
-                            && last_header.payload == Payload::Error(0)
-                    {
-                        break;
-                    } else if last_header.message_type as i32 == NLMSG_ERROR
-                        && last_header.payload != Payload::Error(0)
-                    {
-                        log::error!(
-                            "Error occured with netlink request of type {}",
-                            netlink_message_type
-                        );
-                        result_buffer.clear();
-                        break;
-                    }
-                }
-            }
-        }
+                _ => None,
+            })
+            .and_then(|attribute| attribute.data.clone().try_into().ok())
+            .map(|family_id| u16::from_le_bytes(family_id) as i32)
+            .ok_or_else(|| {
+                IOError::other(format!(
+                    "Could not retrieve {} family ID",
+                    subsystem_name.trim_end_matches('\0')
+                ))
+            })
+    }
 
-        if result_buffer.is_empty() {
-            Err(IOError::new(
-                ErrorKind::Other,
-                "No netlink response could be found",
-            ))
-        } else {
-            Ok(result_buffer)
+    fn get_interface_index(&self, interface_name: &str) -> Result<u32, IOError> {
+        // Recorded with the responses, the interface may not exist where they are replayed
+        let interface_index = capture(&format!("ifindex/{}", interface_name), || {
+            let interface_index = unsafe {
+                let if_name = CString::new(interface_name).unwrap();
+                libc::if_nametoindex(if_name.as_ptr() as *const libc::c_char)
+            };
+            Ok(interface_index.to_string().into_bytes())
+        })?;
+
+        match String::from_utf8_lossy(&interface_index).parse::<u32>() {
+            Ok(interface_index) if interface_index != 0 => Ok(interface_index),
+            _ => Err(IOError::other("Could not retrieve interface index")),
         }
     }
 
-    fn get_interface_index(&self, interface_name: &str) -> Result<u32, IOError> {
-        let interface_index = unsafe {
-            let if_name = CString::new(interface_name).unwrap();
-            libc::if_nametoindex(if_name.as_ptr() as *const libc::c_char)
-        };
+    fn get_interface_name(&self, interface_index: u32) -> Result<String, IOError> {
+        let interface_name = capture(&format!("ifname/{}", interface_index), || {
+            let mut buffer = [0 as libc::c_char; libc::IF_NAMESIZE];
+            if unsafe { libc::if_indextoname(interface_index, buffer.as_mut_ptr()) }.is_null() {
+                return Err(IOError::last_os_error());
+            }
+            Ok(unsafe { std::ffi::CStr::from_ptr(buffer.as_ptr()) }
+                .to_bytes()
+                .to_vec())
+        })?;
 
-        if interface_index == 0 {
-            Err(IOError::new(
-                ErrorKind::Other,
-                "Could not retrieve interface index",
-            ))
-        } else {
-            Ok(interface_index)
-        }
+        Ok(String::from_utf8_lossy(&interface_name).into_owned())
     }
 
     pub fn interface_bss_information(
@@ -261,8 +167,7 @@ impl Netlink {
         );
 
         if let Ok(nl_80211_family_id) = self.nl_80211_family_id.as_ref() {
-            let response = Self::request(
-                self.generic_netlink_socket,
+            let response = self.generic_netlink_socket.request(
                 *nl_80211_family_id,
                 NLM_F_REQUEST | NLM_F_DUMP | NLM_F_ACK,
                 Payload::GenericNetlink(genl_header),
@@ -345,8 +250,150 @@ impl Netlink {
         Ok(bss)
     }
 
-    pub fn interface_ip(&self, interface_name: &str) -> Result<String, IOError> {
-        let mut ip = String::new();
+    // Whether the interface has a link, from its operational state (RFC 2863)
+    // Drivers that don't track it report "unknown", their lower layer flag is used then
+    pub fn interface_link_up(&self, interface_name: &str) -> Result<bool, IOError> {
+        let interface_index = self.get_interface_index(interface_name)?;
+        let message =
+            InterfaceInfoMessage::build(AF_UNSPEC as u8, interface_index as i32, Vec::new());
+
+        let response = self.netlink_route_socket.request(
+            RTM_GETLINK as i32,
+            NLM_F_REQUEST,
+            Payload::RtmGetLink(message),
+        )?;
+
+        for message in response.iter() {
+            if let Payload::RtmGetLink(message) = &message.payload {
+                if message.index as u32 != interface_index {
+                    continue;
+                }
+                let operstate =
+                    netlink_header::get_attribute(&message.attributes, IFLA_OPERSTATE as i32)
+                        .and_then(|operstate| operstate.data.first().copied());
+                return Ok(match operstate.map(i32::from) {
+                    Some(IF_OPER_UP) => true,
+                    Some(IF_OPER_UNKNOWN) | None => message.flags & IFF_LOWER_UP as u32 != 0,
+                    _ => false,
+                });
+            }
+        }
+
+        Err(IOError::other("Could not retrieve link state"))
+    }
+
+    // Interface that carries the traffic to the internet, None without a route to it
+    // The kernel is asked which route it would take to a public address, so its routing rules
+    // decide like for real traffic: wg-quick and Tailscale send all traffic to their own table
+    // with a rule, OpenVPN adds the more specific routes 0.0.0.0/1 and 128.0.0.0/1
+    // IPv4 is asked first, IPv6 only if there is no IPv4 route
+    pub fn default_route_interface(&self) -> Result<Option<String>, IOError> {
+        let destinations = [
+            (AF_INET, PUBLIC_IPV4_ADDRESS.octets().to_vec()),
+            (AF_INET6, PUBLIC_IPV6_ADDRESS.octets().to_vec()),
+        ];
+        for (family, address) in destinations {
+            if let Some(interface_index) = self.route_interface(family, address)? {
+                return self.get_interface_name(interface_index).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Outgoing interface of the route the kernel takes to the address, without sending anything
+    fn route_interface(&self, family: i32, address: Vec<u8>) -> Result<Option<u32>, IOError> {
+        let destination_length = (address.len() * 8) as u8;
+        let mut message = RouteMessage::build(
+            family as u8,
+            vec![NetlinkAttribute::build(RTA_DST as i32, address)],
+        );
+        message.destination_length = destination_length;
+
+        let response = match self.netlink_route_socket.request(
+            RTM_GETROUTE as i32,
+            NLM_F_REQUEST,
+            Payload::RtmGetRoute(message),
+        ) {
+            Ok(response) => response,
+            // There is no route (e.g. while offline or without IPv6)
+            Err(error)
+                if matches!(
+                    error.raw_os_error(),
+                    Some(ENETUNREACH | EHOSTUNREACH | EINVAL)
+                ) =>
+            {
+                return Ok(None)
+            }
+            Err(error) => return Err(error),
+        };
+
+        Ok(response.iter().find_map(|message| match &message.payload {
+            Payload::RtmGetRoute(message) if message.route_type == RTN_UNICAST => {
+                netlink_header::get_attribute(&message.attributes, RTA_OIF as i32)
+                    .and_then(|attribute| attribute.data.clone().try_into().ok())
+                    .map(u32::from_le_bytes)
+            }
+            _ => None,
+        }))
+    }
+
+    // Speed and duplex that the link negotiated, through the ethtool netlink interface
+    pub fn interface_link_modes(&self, interface_name: &str) -> Result<LinkModes, IOError> {
+        let interface_index = self.get_interface_index(interface_name)?;
+        let ethtool_family_id = self
+            .ethtool_family_id
+            .as_ref()
+            .map_err(|error| IOError::new(error.kind(), error.to_string()))?;
+        let mut link_modes = LinkModes {
+            speed: None,
+            duplex: Duplex::Unknown,
+        };
+
+        // The device is selected by a nested header attribute
+        let request_header = NetlinkAttribute::build(
+            ETHTOOL_A_LINKMODES_HEADER | NLA_F_NESTED,
+            NetlinkAttribute::build(
+                ETHTOOL_A_HEADER_DEV_INDEX,
+                interface_index.to_le_bytes().to_vec(),
+            )
+            .serialize(),
+        );
+        let genl_header =
+            GenericNetlinkMessageHeader::build(ETHTOOL_MSG_LINKMODES_GET, vec![request_header]);
+
+        let response = self.generic_netlink_socket.request(
+            *ethtool_family_id,
+            NLM_F_REQUEST | NLM_F_ACK,
+            Payload::GenericNetlink(genl_header),
+        )?;
+
+        if let Some(Payload::GenericNetlink(message)) =
+            response.first().map(|message| &message.payload)
+        {
+            link_modes.speed =
+                netlink_header::get_attribute(&message.attributes, ETHTOOL_A_LINKMODES_SPEED)
+                    .and_then(|speed| speed.data.clone().try_into().ok())
+                    .map(u32::from_le_bytes)
+                    .filter(|speed| *speed != SPEED_UNKNOWN);
+            link_modes.duplex = match netlink_header::get_attribute(
+                &message.attributes,
+                ETHTOOL_A_LINKMODES_DUPLEX,
+            )
+            .and_then(|duplex| duplex.data.first().copied())
+            {
+                Some(DUPLEX_HALF) => Duplex::Half,
+                Some(DUPLEX_FULL) => Duplex::Full,
+                _ => Duplex::Unknown,
+            };
+        }
+
+        Ok(link_modes)
+    }
+
+    // IPv4 and IPv6 addresses of the interface, dumped with RTM_GETADDR
+    pub fn interface_addresses(&self, interface_name: &str) -> Result<InterfaceAddresses, IOError> {
+        let mut addresses = InterfaceAddresses::default();
         let interface_index = self.get_interface_index(interface_name)?;
 
         let message = InterfaceAddressMessage::build(
@@ -358,8 +405,7 @@ impl Netlink {
             Vec::new(),
         );
 
-        let response = Self::request(
-            self.netlink_route_socket,
+        let response = self.netlink_route_socket.request(
             RTM_GETADDR as i32,
             NLM_F_REQUEST | NLM_F_DUMP,
             Payload::RtmGetAddr(message),
@@ -368,27 +414,52 @@ impl Netlink {
         for message in response.iter() {
             if let Payload::RtmGetAddr(message) = &message.payload {
                 // Only read messages that contain information about the specified interface
-                if message.index == interface_index {
-                    let interface_address_local =
-                        netlink_header::get_attribute(&message.attributes, IFA_LOCAL as i32);
-                    if let Some(interface_address_local) = interface_address_local {
-                        ip = format!(
-                            "{}.{}.{}.{}",
-                            interface_address_local.data[0],
-                            interface_address_local.data[1],
-                            interface_address_local.data[2],
-                            interface_address_local.data[3]
-                        );
+                if message.index != interface_index {
+                    continue;
+                }
+                // IFA_ADDRESS is the address of the other end on point to point links,
+                // IFA_LOCAL is only sent for IPv4
+                let address = netlink_header::get_attribute(&message.attributes, IFA_LOCAL as i32)
+                    .or_else(|| {
+                        netlink_header::get_attribute(&message.attributes, IFA_ADDRESS as i32)
+                    });
+                let Some(address) = address else {
+                    continue;
+                };
+                match message.family as i32 {
+                    AF_INET => {
+                        if let Ok(octets) = <[u8; 4]>::try_from(address.data.as_slice()) {
+                            addresses.ipv4.push(Ipv4Addr::from(octets));
+                        }
                     }
+                    AF_INET6 => {
+                        // The flags of the header only have room for the first 8 flags
+                        let flags =
+                            netlink_header::get_attribute(&message.attributes, IFA_FLAGS as i32)
+                                .and_then(|flags| flags.data.clone().try_into().ok())
+                                .map_or(message.flags as u32, u32::from_le_bytes);
+                        if let Ok(octets) = <[u8; 16]>::try_from(address.data.as_slice()) {
+                            addresses.ipv6.push(Ipv6Address {
+                                address: Ipv6Addr::from(octets),
+                                global: message.scope == RT_SCOPE_UNIVERSE,
+                                deprecated: flags & (IFA_F_DEPRECATED | IFA_F_TENTATIVE) != 0,
+                            });
+                        }
+                    }
+                    _ => {}
                 }
             }
         }
 
-        Ok(ip)
+        Ok(addresses)
     }
 
-    pub fn interface_bitrate(&self, interface_name: &str) -> Result<u16, IOError> {
-        let mut bitrate = 0;
+    // Bitrate and signal strength of the connection to the access point
+    pub fn interface_station_information(
+        &self,
+        interface_name: &str,
+    ) -> Result<StationInformation, IOError> {
+        let mut station = StationInformation::default();
         let interface_index = self.get_interface_index(interface_name)?;
 
         let genl_header = GenericNetlinkMessageHeader::build(
@@ -400,14 +471,16 @@ impl Netlink {
         );
 
         if let Ok(nl_80211_family_id) = self.nl_80211_family_id.as_ref() {
-            let response = Self::request(
-                self.generic_netlink_socket,
+            let response = self.generic_netlink_socket.request(
                 *nl_80211_family_id,
                 NLM_F_REQUEST | NLM_F_DUMP | NLM_F_ACK,
                 Payload::GenericNetlink(genl_header),
             )?;
 
-            if let Payload::GenericNetlink(message) = &response[0].payload {
+            // A dump is empty while the interface is not connected
+            if let Some(Payload::GenericNetlink(message)) =
+                response.first().map(|message| &message.payload)
+            {
                 let station_info =
                     netlink_header::get_attribute(&message.attributes, NL80211_ATTR_STA_INFO);
                 if let Some(station_info) = station_info {
@@ -430,17 +503,24 @@ impl Netlink {
                             &rate_info_attributes,
                             NL80211_RATE_INFO_BITRATE,
                         ) {
-                            bitrate =
-                                u16::from_le_bytes(raw_bitrate.data.clone().try_into().unwrap())
+                            station.bitrate =
+                                (u16::from_le_bytes(raw_bitrate.data.clone().try_into().unwrap())
                                     as u32
                                     * 100
-                                    / 1000;
+                                    / 1000) as u16;
                         }
                     }
+                    // Drivers that don't measure the signal (e.g. some USB adapters) leave it out
+                    station.signal = netlink_header::get_attribute(
+                        &station_info_attributes,
+                        NL80211_STA_INFO_SIGNAL,
+                    )
+                    .and_then(|signal| signal.data.first())
+                    .map(|&signal| signal as i8);
                 }
             }
         }
 
-        Ok(bitrate as u16)
+        Ok(station)
     }
 }
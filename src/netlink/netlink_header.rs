@@ -1,9 +1,11 @@
-use libc::{NLMSG_DONE, NLMSG_ERROR, RTM_NEWADDR};
+use libc::{NETLINK_ROUTE, NLMSG_DONE, NLMSG_ERROR, RTM_NEWADDR, RTM_NEWLINK, RTM_NEWROUTE};
 
 use crate::{
     netlink::generic_netlink_header::GenericNetlinkMessageHeader,
     netlink::interface_address_message::InterfaceAddressMessage,
+    netlink::interface_info_message::InterfaceInfoMessage,
     netlink::netlink_attribute::NetlinkAttribute,
+    netlink::route_message::RouteMessage,
     utils::{macros::walk_to_number, walking_vec::WalkingVec},
 };
 
@@ -54,6 +56,8 @@ pub struct NetlinkMessageHeader {
 pub enum Payload {
     GenericNetlink(GenericNetlinkMessageHeader),
     RtmGetAddr(InterfaceAddressMessage),
+    RtmGetLink(InterfaceInfoMessage),
+    RtmGetRoute(RouteMessage),
     Done(i32),
     Error(i32),
 }
@@ -64,6 +68,8 @@ impl Payload {
             Payload::Done(_) | Payload::Error(_) => std::mem::size_of::<i32>(),
             Payload::GenericNetlink(p) => p.size(),
             Payload::RtmGetAddr(p) => p.size(),
+            Payload::RtmGetLink(p) => p.size(),
+            Payload::RtmGetRoute(p) => p.size(),
         }
     }
 }
@@ -93,13 +99,17 @@ impl NetlinkMessageHeader {
         match &self.payload {
             Payload::GenericNetlink(message) => buffer.extend(message.serialize()),
             Payload::RtmGetAddr(message) => buffer.extend(message.serialize()),
+            Payload::RtmGetLink(message) => buffer.extend(message.serialize()),
+            Payload::RtmGetRoute(message) => buffer.extend(message.serialize()),
             _ => unimplemented!("This is not needed for now"),
         }
 
         buffer
     }
 
-    pub fn deserialize(walkable_buffer: &mut WalkingVec) -> Self {
+    // Message types of generic netlink are family IDs, which can have the same value as
+    // the message types of NETLINK_ROUTE (e.g. GENL_ID_CTRL and RTM_NEWLINK are both 16)
+    pub fn deserialize(walkable_buffer: &mut WalkingVec, protocol: i32) -> Self {
         let length = walk_to_number!(walkable_buffer, u32);
         let message_type = walk_to_number!(walkable_buffer, u16);
         let flags = walk_to_number!(walkable_buffer, u16);
@@ -121,10 +131,16 @@ impl NetlinkMessageHeader {
             Payload::Done(walk_to_number!(walkable_buffer, i32))
         } else if message_type as i32 == NLMSG_ERROR {
             Payload::Error(walk_to_number!(walkable_buffer, i32))
-        } else if message_type == RTM_NEWADDR {
+        } else if protocol == NETLINK_ROUTE && message_type == RTM_NEWADDR {
             Payload::RtmGetAddr(InterfaceAddressMessage::deserialize(
                 &mut limited_walking_buffer,
             ))
+        } else if protocol == NETLINK_ROUTE && message_type == RTM_NEWLINK {
+            Payload::RtmGetLink(InterfaceInfoMessage::deserialize(
+                &mut limited_walking_buffer,
+            ))
+        } else if protocol == NETLINK_ROUTE && message_type == RTM_NEWROUTE {
+            Payload::RtmGetRoute(RouteMessage::deserialize(&mut limited_walking_buffer))
         } else {
             Payload::GenericNetlink(GenericNetlinkMessageHeader::deserialize(
                 &mut limited_walking_buffer,
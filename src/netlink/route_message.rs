@@ -0,0 +1,97 @@
+use crate::netlink::netlink_header::parse_attributes;
+use crate::{
+    netlink::NetlinkAttribute,
+    utils::{macros::walk_to_number, walking_vec::WalkingVec},
+};
+
+// https://elixir.bootlin.com/linux/latest/source/include/uapi/linux/rtnetlink.h#L237
+#[derive(Debug, PartialEq)]
+pub struct RouteMessage {
+    pub family: u8,
+    // Prefix length of the destination, 0 for a default route
+    pub destination_length: u8,
+    pub source_length: u8,
+    pub tos: u8,
+    // RT_TABLE_* the route belongs to, tables above 255 are only in the RTA_TABLE attribute
+    pub table: u8,
+    // RTPROT_* that installed the route (e.g. dhcp)
+    pub protocol: u8,
+    pub scope: u8,
+    // RTN_* type of the route (e.g. unicast or unreachable)
+    pub route_type: u8,
+    // RTM_F_* flags of the route
+    pub flags: u32,
+    pub attributes: Vec<NetlinkAttribute>,
+}
+
+impl RouteMessage {
+    pub fn build(family: u8, attributes: Vec<NetlinkAttribute>) -> Self {
+        Self {
+            family,
+            destination_length: 0,
+            source_length: 0,
+            tos: 0,
+            table: 0,
+            protocol: 0,
+            scope: 0,
+            route_type: 0,
+            flags: 0,
+            attributes,
+        }
+    }
+
+    // Calculate the actual size
+    pub fn size(&self) -> usize {
+        // u8 * 8 = family, destination_length, source_length, tos, table, protocol, scope and
+        // route_type, followed by flags
+        let mut size = (std::mem::size_of::<u8>() * 8) + std::mem::size_of::<u32>();
+        for attribute in self.attributes.iter() {
+            size += attribute.size();
+        }
+        size
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut buffer = vec![
+            self.family,
+            self.destination_length,
+            self.source_length,
+            self.tos,
+            self.table,
+            self.protocol,
+            self.scope,
+            self.route_type,
+        ];
+        buffer.extend(self.flags.to_le_bytes());
+        for attribute in self.attributes.iter() {
+            buffer.extend(attribute.serialize());
+        }
+
+        buffer
+    }
+
+    pub fn deserialize(buffer: &mut WalkingVec) -> Self {
+        let family = walk_to_number!(buffer, u8);
+        let destination_length = walk_to_number!(buffer, u8);
+        let source_length = walk_to_number!(buffer, u8);
+        let tos = walk_to_number!(buffer, u8);
+        let table = walk_to_number!(buffer, u8);
+        let protocol = walk_to_number!(buffer, u8);
+        let scope = walk_to_number!(buffer, u8);
+        let route_type = walk_to_number!(buffer, u8);
+        let flags = walk_to_number!(buffer, u32);
+
+        Self {
+            family,
+            destination_length,
+            source_length,
+            tos,
+            table,
+            protocol,
+            scope,
+            route_type,
+            flags,
+            attributes: parse_attributes(buffer),
+        }
+    }
+}
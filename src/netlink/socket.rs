@@ -0,0 +1,210 @@
+use libc::{
+    bind, c_void, poll, pollfd, recv, sa_family_t, send, sockaddr, socket, socklen_t, AF_NETLINK,
+    NLM_F_ACK, NLM_F_MULTI, POLLIN, SOCK_CLOEXEC, SOCK_RAW,
+};
+use std::io::{Error as IOError, ErrorKind};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::netlink::constants::NETLINK_HEADER_SIZE;
+use crate::netlink::netlink_header::{NetlinkMessageHeader, Payload};
+use crate::replay::{capture, is_replaying};
+use crate::utils::walking_vec::WalkingVec;
+
+// This is the maximum length that a netlink message can have
+// https://git.kernel.org/pub/scm/linux/kernel/git/netdev/net-next.git/commit/?id=d35c99ff77ecb2eb239731b799386f3b3637a31e
+const MAX_NETLINK_MESSAGE_SIZE: usize = 32768;
+// The kernel answers right away, a request that takes longer than this is given up on,
+// so a widget can not hang on a socket forever
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(2);
+
+// A netlink socket that can be used by several widgets at the same time
+// Requests are serialized and every request gets its own sequence number, so answers
+// that arrive late (e.g. the ACK after a dump) are not mistaken for the answer of the next request
+#[derive(Debug)]
+pub struct NetlinkSocket {
+    fd: OwnedFd,
+    // NETLINK_ROUTE or NETLINK_GENERIC, their message types overlap
+    protocol: i32,
+    // Sequence number of the last request, also locked for the whole request
+    sequence_number: Mutex<u32>,
+}
+
+// Messages of a single datagram, checked so a malformed length can not make us read out of bounds
+fn parse_messages(buffer: Vec<u8>, protocol: i32) -> Result<Vec<NetlinkMessageHeader>, IOError> {
+    let size = buffer.len();
+    let mut walkable_buffer = WalkingVec {
+        buffer,
+        position: 0,
+    };
+    let mut messages = Vec::new();
+    while walkable_buffer.position + NETLINK_HEADER_SIZE <= size {
+        let start = walkable_buffer.position;
+        let length = u32::from_le_bytes(
+            walkable_buffer.buffer[start..start + 4]
+                .try_into()
+                .unwrap_or_default(),
+        ) as usize;
+        if length < NETLINK_HEADER_SIZE || start + length > size {
+            return Err(IOError::new(
+                ErrorKind::InvalidData,
+                format!("Invalid netlink message length {}", length),
+            ));
+        }
+        let message = NetlinkMessageHeader::deserialize(&mut walkable_buffer, protocol);
+        // Deserializing only walks the header, see NetlinkMessageHeader::deserialize
+        // Messages are padded to 4 bytes
+        walkable_buffer.position = (start + length).next_multiple_of(4).min(size);
+        messages.push(message);
+    }
+
+    Ok(messages)
+}
+
+impl NetlinkSocket {
+    pub fn open(protocol: i32) -> Result<Self, IOError> {
+        let fd = unsafe { socket(AF_NETLINK, SOCK_RAW | SOCK_CLOEXEC, protocol) };
+        if fd < 0 {
+            return Err(IOError::last_os_error());
+        }
+        // SAFETY: the socket was just created and is not owned by anything else
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        // The kernel assigns the port id when it is 0
+        let socket_address = sockaddr {
+            sa_family: AF_NETLINK as sa_family_t,
+            sa_data: [0; 14],
+        };
+        if unsafe {
+            bind(
+                fd.as_raw_fd(),
+                &socket_address as *const sockaddr,
+                std::mem::size_of::<sockaddr>() as socklen_t,
+            )
+        } < 0
+        {
+            return Err(IOError::last_os_error());
+        }
+
+        Ok(Self {
+            fd,
+            protocol,
+            sequence_number: Mutex::new(0),
+        })
+    }
+
+    // Wait until the socket can be read without blocking
+    fn wait_until_readable(&self, deadline: Instant) -> Result<(), IOError> {
+        let mut poll_fd = pollfd {
+            fd: self.fd.as_raw_fd(),
+            events: POLLIN,
+            revents: 0,
+        };
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            match unsafe { poll(&mut poll_fd, 1, timeout.as_millis() as i32) } {
+                0 => {
+                    return Err(IOError::new(
+                        ErrorKind::TimedOut,
+                        "The kernel did not answer the netlink request",
+                    ))
+                }
+                result if result > 0 => return Ok(()),
+                _ => {
+                    let error = IOError::last_os_error();
+                    if error.kind() != ErrorKind::Interrupted {
+                        return Err(error);
+                    }
+                }
+            }
+        }
+    }
+
+    fn receive(&self, deadline: Instant) -> Result<Vec<u8>, IOError> {
+        self.wait_until_readable(deadline)?;
+        let mut buffer = vec![0; MAX_NETLINK_MESSAGE_SIZE];
+        let response_size = unsafe {
+            recv(
+                self.fd.as_raw_fd(),
+                buffer.as_mut_ptr() as *mut c_void,
+                MAX_NETLINK_MESSAGE_SIZE,
+                0,
+            )
+        };
+        if response_size < 0 {
+            return Err(IOError::last_os_error());
+        }
+        // Our buffer is initialized with the maximum netlink message size
+        // So we truncate the result to only contain the actual response bytes
+        buffer.truncate(response_size as usize);
+        Ok(buffer)
+    }
+
+    // Send a request and collect the messages of its answer
+    // Answers of dumps can span several datagrams, they are put together until NLMSG_DONE
+    // An NLMSG_ERROR with an error code is returned as the matching OS error
+    pub fn request(
+        &self,
+        netlink_message_type: i32,
+        flags: i32,
+        payload: Payload,
+    ) -> Result<Vec<NetlinkMessageHeader>, IOError> {
+        let mut sequence_number = self.sequence_number.lock().unwrap();
+        *sequence_number = sequence_number.wrapping_add(1);
+        let mut header = NetlinkMessageHeader::build(netlink_message_type, flags, payload);
+        header.sequence_number = *sequence_number;
+        let input_buffer = header.serialize();
+
+        // Responses are recorded and replayed by the type of the request
+        // Recorded sequence numbers do not match ours, so they are not checked while replaying
+        let source = format!("netlink/{}", netlink_message_type);
+        let replaying = is_replaying();
+        // The kernel is not asked at all when recorded responses are replayed
+        if !replaying {
+            let sent = unsafe {
+                send(
+                    self.fd.as_raw_fd(),
+                    input_buffer.as_ptr() as *const c_void,
+                    input_buffer.len(),
+                    0,
+                )
+            };
+            if sent < 0 {
+                return Err(IOError::last_os_error());
+            }
+        }
+
+        let deadline = Instant::now() + RESPONSE_TIMEOUT;
+        let mut messages = Vec::new();
+        loop {
+            let buffer = capture(&source, || self.receive(deadline))?;
+            for message in parse_messages(buffer, self.protocol)? {
+                if !replaying && message.sequence_number != *sequence_number {
+                    log::debug!(
+                        "Ignoring netlink message {} of an earlier request",
+                        message.sequence_number
+                    );
+                    continue;
+                }
+                match message.payload {
+                    Payload::Done(_) => return Ok(messages),
+                    // Error code 0 is the ACK
+                    Payload::Error(0) => return Ok(messages),
+                    Payload::Error(error) => {
+                        return Err(IOError::from_raw_os_error(error.saturating_neg()))
+                    }
+                    _ => {
+                        // Single answers without an ACK are complete right away
+                        let complete =
+                            i32::from(message.flags) & NLM_F_MULTI == 0 && flags & NLM_F_ACK == 0;
+                        messages.push(message);
+                        if complete {
+                            return Ok(messages);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
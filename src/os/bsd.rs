@@ -0,0 +1,272 @@
+use libc::{
+    freeifaddrs, getifaddrs, getloadavg, ifaddrs, sockaddr_in, sockaddr_in6, socket, AF_INET,
+    AF_INET6, AF_UNSPEC, IFF_RUNNING, IFF_UP, PF_ROUTE, SOCK_CLOEXEC, SOCK_RAW,
+};
+use std::ffi::CStr;
+use std::fs::File;
+use std::io::{Error, ErrorKind};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::os::fd::FromRawFd;
+use std::process::Command;
+use std::ptr::null_mut;
+use std::sync::Arc;
+
+use crate::os::{
+    BSSInformation, InterfaceAddresses, InterfaceKind, Ipv6Address, LinkModes, StationInformation,
+};
+
+// The output of one of the tools of the base system, e.g. sysctl
+fn run(program: &str, args: &[&str]) -> Result<String, Error> {
+    let output = Command::new(program).args(args).output()?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(Error::other(format!(
+            "\"{} {}\" failed with {}: {}",
+            program,
+            args.join(" "),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+fn unsupported(what: &str) -> Error {
+    Error::new(
+        ErrorKind::Unsupported,
+        format!("{} is only available on Linux", what),
+    )
+}
+
+// The value of a "Key:\tvalue" line of acpiconf, e.g. "Remaining capacity"
+#[cfg(target_os = "freebsd")]
+fn acpiconf_value(device_name: &str, key: &str) -> Result<String, Error> {
+    // acpiconf counts the batteries like the kernel, BAT0 is unit 0
+    let unit = device_name.trim_start_matches(|character: char| !character.is_ascii_digit());
+    let unit = if unit.is_empty() { "0" } else { unit };
+    run("acpiconf", &["-i", unit])?
+        .lines()
+        .find_map(|line| {
+            line.strip_prefix(key)
+                .and_then(|value| value.strip_prefix(':'))
+                .map(|value| value.trim().to_string())
+        })
+        .ok_or_else(|| Error::other(format!("acpiconf did not report \"{}\"", key)))
+}
+
+// Status in the words of the Linux kernel, so the widgets don't have to tell the systems apart
+#[cfg(target_os = "freebsd")]
+pub fn battery_status(device_name: &str) -> Result<String, Error> {
+    // e.g. "discharging", "charging" or "high" when neither happens on AC
+    let state = acpiconf_value(device_name, "State")?;
+    Ok(String::from(if state.contains("discharging") {
+        "Discharging"
+    } else if state.contains("charging") {
+        "Charging"
+    } else if state.contains("high") {
+        "Full"
+    } else {
+        "Unknown"
+    }))
+}
+
+#[cfg(target_os = "freebsd")]
+pub fn battery_life(device_name: &str) -> Result<f32, Error> {
+    acpiconf_value(device_name, "Remaining capacity")?
+        .trim_end_matches('%')
+        .parse::<f32>()
+        .map_err(|error| Error::other(format!("Invalid battery capacity: {}", error)))
+}
+
+// apm only knows about all batteries together, the device name does not matter
+#[cfg(target_os = "openbsd")]
+pub fn battery_status(_device_name: &str) -> Result<String, Error> {
+    // Battery: 3 is charging, AC: 0 is disconnected and 1 connected
+    let battery = run("apm", &["-b"])?;
+    let ac = run("apm", &["-a"])?;
+    Ok(String::from(match (battery.trim(), ac.trim()) {
+        ("3", _) => "Charging",
+        (_, "0") => "Discharging",
+        (_, "1") => "Full",
+        _ => "Unknown",
+    }))
+}
+
+#[cfg(target_os = "openbsd")]
+pub fn battery_life(_device_name: &str) -> Result<f32, Error> {
+    run("apm", &["-l"])?
+        .trim()
+        .parse::<f32>()
+        .map_err(|error| Error::other(format!("Invalid battery capacity: {}", error)))
+}
+
+// Load of the last 1, 5 and 15 minutes
+pub fn load_average() -> Result<Vec<String>, Error> {
+    let mut load = [0.0; 3];
+    if unsafe { getloadavg(load.as_mut_ptr(), load.len() as i32) } < 0 {
+        return Err(Error::other("Could not retrieve the load average"));
+    }
+    Ok(load.iter().map(|load| format!("{:.2}", load)).collect())
+}
+
+// Time that all CPUs were idle and the time they were running at all, in ticks since boot
+// kern.cp_time is separated by spaces on FreeBSD and by commas on OpenBSD, idle comes last
+pub fn cpu_times() -> Result<(f32, f32), Error> {
+    let cp_time = run("sysctl", &["-n", "kern.cp_time"])?;
+    let ticks = cp_time
+        .split(|character: char| character == ',' || character.is_whitespace())
+        .filter(|ticks| !ticks.is_empty())
+        .map(|ticks| ticks.parse::<f32>())
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|error| Error::other(format!("Invalid kern.cp_time: {}", error)))?;
+
+    match ticks.last() {
+        Some(idle) => Ok((*idle, ticks.iter().sum())),
+        None => Err(Error::other("kern.cp_time is empty")),
+    }
+}
+
+// Receives a message whenever a network interface goes up or down or its address changes,
+// but also when a route changes
+pub fn route_socket() -> Result<File, Error> {
+    let fd = unsafe { socket(PF_ROUTE, SOCK_RAW | SOCK_CLOEXEC, AF_UNSPEC) };
+    if fd < 0 {
+        return Err(Error::last_os_error());
+    }
+    // The file takes care of closing the socket
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+// devd announces power supplies, but it is not a kernel socket
+pub fn uevent_socket() -> Result<File, Error> {
+    Err(unsupported("Listening for uevents"))
+}
+
+// Prefixes of the interfaces of tunnels and of the USB network drivers of phones
+const VPN_PREFIXES: [&str; 4] = ["tun", "tap", "wg", "ppp"];
+const TETHERING_PREFIXES: [&str; 2] = ["urndis", "ipheth"];
+
+// Wireless interfaces have many driver names, but all of them show an IEEE 802.11 media
+pub fn interface_kind(interface_name: &str) -> InterfaceKind {
+    let has_prefix = |prefixes: &[&str]| {
+        prefixes
+            .iter()
+            .any(|prefix| interface_name.starts_with(prefix))
+    };
+    if has_prefix(&VPN_PREFIXES) {
+        InterfaceKind::Vpn
+    } else if has_prefix(&TETHERING_PREFIXES) {
+        InterfaceKind::Tethered
+    } else if run("ifconfig", &[interface_name]).is_ok_and(|output| output.contains("IEEE802.11")) {
+        InterfaceKind::Wireless
+    } else {
+        InterfaceKind::Ethernet
+    }
+}
+
+// Network interfaces are looked up with getifaddrs, the wireless drivers of the BSDs
+// don't share an interface like nl80211 does
+#[derive(Debug)]
+pub struct Network;
+
+impl Network {
+    // Nothing is kept open, the interfaces are read again with every update
+    pub fn shared() -> Result<Arc<Self>, Error> {
+        Ok(Arc::new(Self))
+    }
+
+    // Call find for every address of the interface until it returns something
+    fn find_address<T, F>(interface_name: &str, mut find: F) -> Result<Option<T>, Error>
+    where
+        F: FnMut(&ifaddrs) -> Option<T>,
+    {
+        let mut addresses: *mut ifaddrs = null_mut();
+        if unsafe { getifaddrs(&mut addresses) } < 0 {
+            return Err(Error::last_os_error());
+        }
+
+        let mut found = None;
+        let mut address = addresses;
+        while let Some(current) = unsafe { address.as_ref() } {
+            let name = unsafe { CStr::from_ptr(current.ifa_name) };
+            if name.to_bytes() == interface_name.as_bytes() {
+                found = find(current);
+                if found.is_some() {
+                    break;
+                }
+            }
+            address = current.ifa_next;
+        }
+        unsafe { freeifaddrs(addresses) };
+
+        Ok(found)
+    }
+
+    pub fn interface_bss_information(
+        &self,
+        _interface_name: &str,
+    ) -> Result<BSSInformation, Error> {
+        Err(unsupported("Wireless information"))
+    }
+
+    // Every interface has at least its link level address, which carries the flags
+    pub fn interface_link_up(&self, interface_name: &str) -> Result<bool, Error> {
+        let running = (IFF_UP | IFF_RUNNING) as u32;
+        Self::find_address(interface_name, |address| {
+            Some(address.ifa_flags & running == running)
+        })?
+        .ok_or_else(|| Error::other("Could not retrieve link state"))
+    }
+
+    // Interface that carries the traffic to the internet, None without a default route
+    // route(8) fails if there is none, IPv4 is preferred
+    pub fn default_route_interface(&self) -> Result<Option<String>, Error> {
+        let interface = ["-inet", "-inet6"].iter().find_map(|family| {
+            run("route", &["-n", "get", family, "default"])
+                .ok()?
+                .lines()
+                .find_map(|line| Some(line.trim().strip_prefix("interface:")?.trim().to_string()))
+        });
+        Ok(interface)
+    }
+
+    pub fn interface_link_modes(&self, _interface_name: &str) -> Result<LinkModes, Error> {
+        Err(unsupported("Speed and duplex"))
+    }
+
+    pub fn interface_addresses(&self, interface_name: &str) -> Result<InterfaceAddresses, Error> {
+        let mut addresses = InterfaceAddresses::default();
+        Self::find_address(interface_name, |address| -> Option<()> {
+            let socket_address = unsafe { address.ifa_addr.as_ref() }?;
+            match i32::from(socket_address.sa_family) {
+                AF_INET => {
+                    let socket_address = unsafe { &*(address.ifa_addr as *const sockaddr_in) };
+                    addresses
+                        .ipv4
+                        .push(Ipv4Addr::from(u32::from_be(socket_address.sin_addr.s_addr)));
+                }
+                AF_INET6 => {
+                    let socket_address = unsafe { &*(address.ifa_addr as *const sockaddr_in6) };
+                    let ip = Ipv6Addr::from(socket_address.sin6_addr.s6_addr);
+                    addresses.ipv6.push(Ipv6Address {
+                        address: ip,
+                        // getifaddrs does not know the scope, link local addresses are fe80::/10
+                        global: !ip.is_loopback() && ip.segments()[0] & 0xffc0 != 0xfe80,
+                        deprecated: false,
+                    });
+                }
+                _ => {}
+            }
+            // Go on with the next address
+            None
+        })?;
+        Ok(addresses)
+    }
+
+    pub fn interface_station_information(
+        &self,
+        _interface_name: &str,
+    ) -> Result<StationInformation, Error> {
+        Err(unsupported("Station information"))
+    }
+}
@@ -0,0 +1,186 @@
+use libc::{
+    bind, sa_family_t, sockaddr, sockaddr_nl, socket, socklen_t, AF_NETLINK, ARPHRD_NONE,
+    ARPHRD_PPP, NETLINK_KOBJECT_UEVENT, NETLINK_ROUTE, RTMGRP_IPV4_IFADDR, RTMGRP_IPV4_ROUTE,
+    RTMGRP_IPV6_IFADDR, RTMGRP_IPV6_ROUTE, RTMGRP_LINK, SOCK_CLOEXEC, SOCK_DGRAM, SOCK_RAW,
+};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Error};
+use std::mem::{size_of, zeroed};
+use std::os::fd::FromRawFd;
+
+use crate::os::InterfaceKind;
+use crate::utils::file::{read_file, read_first_line_in_file};
+
+// The sockets of the network widgets
+pub use crate::netlink::Netlink as Network;
+
+const BATTERY_PATH: &str = "/sys/class/power_supply";
+const NET_PATH: &str = "/sys/class/net";
+// USB network drivers of phones (Android and iOS) and mobile broadband modems
+const TETHERING_DRIVERS: [&str; 6] = [
+    "rndis_host",
+    "cdc_ether",
+    "cdc_ncm",
+    "ipheth",
+    "cdc_mbim",
+    "qmi_wwan",
+];
+// uevents are only sent to multicast group 1 by the kernel
+const UEVENT_KERNEL_GROUP: u32 = 1;
+
+// Status of a power supply as reported by the kernel, e.g. "Discharging"
+pub fn battery_status(device_name: &str) -> Result<String, Error> {
+    Ok(
+        read_first_line_in_file(&format!("{}/{}/status", BATTERY_PATH, device_name))?
+            .trim()
+            .to_string(),
+    )
+}
+
+// Percentage of the energy that is left in the battery
+pub fn battery_life(device_name: &str) -> Result<f32, Error> {
+    let mut power_full: f32 = 0.0;
+    let mut power_now: f32 = 0.0;
+
+    if let Some(battery_file) = read_file(&format!("{}/{}/uevent", BATTERY_PATH, device_name)) {
+        let reader = BufReader::new(battery_file);
+        for line in reader.lines() {
+            // TODO: Find a better solution for this
+            // Apparently `?` consumes `line`,
+            // which causes a move after being used once
+            let unpacked_line = line?;
+
+            // TODO: I don't like the unwraps here,
+            // it should be impossble to panic here, because the file is managed
+            // by the kernel and it should always look the same.
+            if unpacked_line.starts_with("POWER_SUPPLY_ENERGY_FULL") {
+                power_full = unpacked_line
+                    .split('=')
+                    .next_back()
+                    .unwrap()
+                    .parse::<f32>()
+                    .unwrap();
+            } else if unpacked_line.starts_with("POWER_SUPPLY_ENERGY_NOW") {
+                power_now = unpacked_line
+                    .split('=')
+                    .next_back()
+                    .unwrap()
+                    .parse::<f32>()
+                    .unwrap();
+            }
+        }
+    }
+
+    Ok(power_now / power_full * 100.0)
+}
+
+// Value of a KEY=value line of a uevent file in sysfs
+fn uevent_value(path: &str, key: &str) -> Option<String> {
+    BufReader::new(read_file(path)?)
+        .lines()
+        .map_while(Result::ok)
+        .find_map(|line| Some(line.strip_prefix(key)?.strip_prefix('=')?.to_string()))
+}
+
+// Sorted by the device type, the driver and the ARP hardware type that sysfs shows
+// Tunnels without an own device type (tun and tap of OpenVPN) have tun_flags
+pub fn interface_kind(interface_name: &str) -> InterfaceKind {
+    let path = format!("{}/{}", NET_PATH, interface_name);
+    let device_type = uevent_value(&format!("{}/uevent", path), "DEVTYPE");
+    let driver = uevent_value(&format!("{}/device/uevent", path), "DRIVER");
+    let hardware_type = read_first_line_in_file(&format!("{}/type", path))
+        .ok()
+        .and_then(|hardware_type| hardware_type.trim().parse::<u16>().ok());
+
+    match device_type.as_deref() {
+        Some("wlan") => return InterfaceKind::Wireless,
+        Some("wireguard") => return InterfaceKind::Vpn,
+        Some("wwan") => return InterfaceKind::Tethered,
+        _ => {}
+    }
+    if driver.is_some_and(|driver| TETHERING_DRIVERS.contains(&driver.as_str())) {
+        InterfaceKind::Tethered
+    } else if matches!(hardware_type, Some(ARPHRD_NONE | ARPHRD_PPP))
+        || read_file(&format!("{}/tun_flags", path)).is_some()
+    {
+        InterfaceKind::Vpn
+    } else {
+        InterfaceKind::Ethernet
+    }
+}
+
+// Load of the last 1, 5 and 15 minutes
+pub fn load_average() -> Result<Vec<String>, Error> {
+    let load_avg = read_first_line_in_file("/proc/loadavg")?;
+    // The line goes on with the number of running processes and the last PID
+    Ok(load_avg
+        .split_whitespace()
+        .take(3)
+        .map(String::from)
+        .collect())
+}
+
+// Time that all CPUs were idle and the time they were running at all, in ticks since boot
+pub fn cpu_times() -> Result<(f32, f32), Error> {
+    let mut total: f32 = 0.0;
+    let mut idle: f32 = 0.0;
+
+    let cpu_line = read_first_line_in_file("/proc/stat")?;
+    let (_, cpu_stats) = cpu_line.split_once("  ").unwrap();
+
+    for (i, number) in cpu_stats.trim().split(' ').enumerate() {
+        let number_as_u32 = number.parse::<f32>().unwrap();
+
+        if i == 3 {
+            idle = number_as_u32;
+        }
+        total += number_as_u32;
+    }
+
+    Ok((idle, total))
+}
+
+// Open a netlink socket that receives the messages of the given multicast groups
+fn multicast_socket(socket_type: i32, protocol: i32, groups: u32) -> Result<File, Error> {
+    let fd = unsafe { socket(AF_NETLINK, socket_type | SOCK_CLOEXEC, protocol) };
+    if fd < 0 {
+        return Err(Error::last_os_error());
+    }
+    // The file takes care of closing the socket
+    let file = unsafe { File::from_raw_fd(fd) };
+
+    let mut address: sockaddr_nl = unsafe { zeroed() };
+    address.nl_family = AF_NETLINK as sa_family_t;
+    address.nl_groups = groups;
+    if unsafe {
+        bind(
+            fd,
+            &address as *const sockaddr_nl as *const sockaddr,
+            size_of::<sockaddr_nl>() as socklen_t,
+        )
+    } < 0
+    {
+        return Err(Error::last_os_error());
+    }
+
+    Ok(file)
+}
+
+// Receives a message whenever a network interface goes up or down, its address changes or
+// a route is added or removed
+pub fn route_socket() -> Result<File, Error> {
+    multicast_socket(
+        SOCK_RAW,
+        NETLINK_ROUTE,
+        (RTMGRP_LINK
+            | RTMGRP_IPV4_IFADDR
+            | RTMGRP_IPV6_IFADDR
+            | RTMGRP_IPV4_ROUTE
+            | RTMGRP_IPV6_ROUTE) as u32,
+    )
+}
+
+// Receives the uevents of all devices
+pub fn uevent_socket() -> Result<File, Error> {
+    multicast_socket(SOCK_DGRAM, NETLINK_KOBJECT_UEVENT, UEVENT_KERNEL_GROUP)
+}
@@ -0,0 +1,109 @@
+// Data sources that every operating system provides in its own way
+// Linux reads sysfs, procfs and netlink, on the BSDs sysctl, getifaddrs, route sockets and
+// the power management tools (acpiconf and apm) are used instead
+// Linux stays the only system with every widget, the BSDs run the bar with the basic ones
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "linux")]
+pub use linux::*;
+
+#[cfg(any(target_os = "freebsd", target_os = "openbsd"))]
+mod bsd;
+#[cfg(any(target_os = "freebsd", target_os = "openbsd"))]
+pub use bsd::*;
+
+#[derive(Debug)]
+pub struct BSSInformation {
+    pub ssid: String,
+    pub frequency: f32,
+}
+
+// Only Linux reports the duplex of a link
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+#[derive(Debug, PartialEq)]
+pub enum Duplex {
+    Half,
+    Full,
+    Unknown,
+}
+
+// What kind of connection a network interface is, it decides what the network widget shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterfaceKind {
+    Wireless,
+    Ethernet,
+    // Tunnels of VPNs (WireGuard, OpenVPN, PPTP, ...)
+    Vpn,
+    // A phone that shares its mobile connection over USB, or a mobile broadband modem
+    Tethered,
+}
+
+#[derive(Debug)]
+pub struct LinkModes {
+    // Megabits per second, None if the driver does not know it
+    pub speed: Option<u32>,
+    pub duplex: Duplex,
+}
+
+// What the access point reports about the connection of a wireless interface
+#[derive(Debug, Default)]
+pub struct StationInformation {
+    // Megabits per second
+    pub bitrate: u16,
+    // dBm of the last received frame, None while not connected
+    pub signal: Option<i8>,
+}
+
+impl StationInformation {
+    // Same scale as NetworkManager: -100 dBm and below is 0%, -50 dBm and above is 100%
+    pub fn signal_percent(&self) -> Option<u8> {
+        self.signal
+            .map(|dbm| ((i16::from(dbm) + 100) * 2).clamp(0, 100) as u8)
+    }
+}
+
+#[derive(Debug)]
+pub struct Ipv6Address {
+    pub address: Ipv6Addr,
+    // Reachable from other networks, not only on the link (fe80::/64) or the host
+    pub global: bool,
+    // Still being verified (duplicate address detection) or no longer used for new connections
+    pub deprecated: bool,
+}
+
+// Addresses of a network interface, in the order in which the system reports them
+#[derive(Debug, Default)]
+pub struct InterfaceAddresses {
+    pub ipv4: Vec<Ipv4Addr>,
+    pub ipv6: Vec<Ipv6Address>,
+}
+
+impl InterfaceAddresses {
+    pub fn ipv4(&self) -> Option<Ipv4Addr> {
+        self.ipv4.first().copied()
+    }
+
+    // Every interface that is up has a link local address, a global one is usually more
+    // interesting. Addresses that are in use are preferred over deprecated ones
+    pub fn ipv6(&self, prefer_global: bool) -> Option<Ipv6Addr> {
+        let preferred = if prefer_global {
+            self.ipv6
+                .iter()
+                .filter(|address| address.global)
+                .min_by_key(|address| address.deprecated)
+        } else {
+            None
+        };
+        preferred
+            .or_else(|| self.ipv6.first())
+            .map(|address| address.address)
+    }
+
+    // Link local addresses are there as soon as the link is up, they don't mean much
+    pub fn is_empty(&self) -> bool {
+        self.ipv4.is_empty() && !self.ipv6.iter().any(|address| address.global)
+    }
+}
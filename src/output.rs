@@ -0,0 +1,141 @@
+use serde_json::{json, Value};
+
+use crate::signals::{CONT_SIGNAL, STOP_SIGNAL};
+
+// How the values of the widgets are written to stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    // See https://i3wm.org/docs/i3bar-protocol.html, also understood by swaybar
+    I3bar,
+    // One line of text per update (e.g. for "xsetroot -name" in dwm or the tmux status line)
+    Plain,
+    // One JSON object per update as it is read by a Waybar custom module
+    // See https://github.com/Alexays/Waybar/wiki/Module:-Custom
+    Waybar,
+}
+
+// Remove pango markup from a text, so it can be shown where markup is not supported
+pub fn strip_markup(text: &str) -> String {
+    let mut stripped = String::new();
+    let mut in_tag = false;
+    for character in text.chars() {
+        match character {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => stripped.push(character),
+            _ => (),
+        }
+    }
+
+    stripped
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+// Escape a text, so it can be put into pango markup
+pub fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+impl OutputFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "i3bar" => Some(OutputFormat::I3bar),
+            "plain" => Some(OutputFormat::Plain),
+            "waybar" => Some(OutputFormat::Waybar),
+            _ => None,
+        }
+    }
+
+    // Only i3bar sends click events on stdin
+    pub fn supports_click_events(&self) -> bool {
+        *self == OutputFormat::I3bar
+    }
+
+    // Lines that have to be written before the first update
+    pub fn header(&self) -> Vec<String> {
+        match self {
+            OutputFormat::I3bar => vec![
+                json!({
+                    "version": 1,
+                    "click_events": true,
+                    "stop_signal": STOP_SIGNAL,
+                    "cont_signal": CONT_SIGNAL,
+                })
+                .to_string(),
+                // Begin endless array
+                String::from("["),
+                // Arrays have to be separated by comma in output
+                String::from("[]"),
+            ],
+            OutputFormat::Plain | OutputFormat::Waybar => Vec::new(),
+        }
+    }
+
+    // Lines that have to be written before exiting
+    pub fn footer(&self) -> Vec<String> {
+        match self {
+            // Close the endless array, so i3bar does not report a protocol error
+            OutputFormat::I3bar => vec![String::from("]")],
+            OutputFormat::Plain | OutputFormat::Waybar => Vec::new(),
+        }
+    }
+
+    // Text of a block without markup
+    fn plain_text(block: &Value) -> String {
+        let text = block["full_text"].as_str().unwrap_or_default();
+        if block["markup"] == "pango" {
+            strip_markup(text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    // Format the blocks of all widgets as one line
+    pub fn format(&self, blocks: &[Value]) -> String {
+        let texts = || {
+            blocks
+                .iter()
+                .map(Self::plain_text)
+                .filter(|text| !text.is_empty())
+        };
+
+        match self {
+            OutputFormat::I3bar => format!(",{}", Value::Array(blocks.to_vec())),
+            OutputFormat::Plain => texts().collect::<Vec<String>>().join(" | "),
+            OutputFormat::Waybar => {
+                // Waybar renders pango markup, so the colors of the blocks are kept
+                let text = blocks
+                    .iter()
+                    .filter_map(|block| {
+                        let text = block["full_text"]
+                            .as_str()
+                            .filter(|text| !text.is_empty())?;
+                        let text = if block["markup"] == "pango" {
+                            text.to_string()
+                        } else {
+                            escape_markup(text)
+                        };
+                        Some(match block["color"].as_str() {
+                            Some(color) => format!("<span color=\"{}\">{}</span>", color, text),
+                            None => text,
+                        })
+                    })
+                    .collect::<Vec<String>>()
+                    .join(" | ");
+                let urgent = blocks.iter().any(|block| block["urgent"] == true);
+                json!({
+                    "text": text,
+                    "tooltip": texts().collect::<Vec<String>>().join("\n"),
+                    "class": if urgent { "urgent" } else { "" },
+                })
+                .to_string()
+            }
+        }
+    }
+}
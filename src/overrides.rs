@@ -0,0 +1,65 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::config::WidgetId;
+
+// Text and color that replace the block of a widget for a while (e.g. set over D-Bus)
+struct BlockOverride {
+    full_text: String,
+    // Hex value, the color of the widget is kept if this is not set
+    color: Option<String>,
+    // The override stays until it is cleared if this is not set
+    until: Option<Instant>,
+}
+
+// Overrides by "name" or "name:instance"
+static OVERRIDES: Mutex<BTreeMap<String, BlockOverride>> = Mutex::new(BTreeMap::new());
+
+pub fn set_override(
+    id: &WidgetId,
+    full_text: String,
+    color: Option<String>,
+    duration: Option<Duration>,
+) {
+    OVERRIDES.lock().unwrap().insert(
+        id.to_string(),
+        BlockOverride {
+            full_text,
+            color,
+            until: duration.map(|duration| Instant::now() + duration),
+        },
+    );
+}
+
+// Returns false if the widget had no override
+pub fn clear_override(id: &WidgetId) -> bool {
+    OVERRIDES.lock().unwrap().remove(&id.to_string()).is_some()
+}
+
+// Replace the text and color of a block if its widget has an override that did not expire
+pub fn apply_override(block: &mut Value, id: &WidgetId) {
+    let mut overrides = OVERRIDES.lock().unwrap();
+    let key = id.to_string();
+    let Some(block_override) = overrides.get(&key) else {
+        return;
+    };
+    if block_override
+        .until
+        .is_some_and(|until| until <= Instant::now())
+    {
+        overrides.remove(&key);
+        return;
+    }
+
+    block["full_text"] = Value::String(block_override.full_text.clone());
+    // The short text of the widget would show the value that is overridden
+    if let Some(block) = block.as_object_mut() {
+        block.remove("short_text");
+    }
+    if let Some(color) = &block_override.color {
+        block["color"] = Value::String(color.clone());
+    }
+}
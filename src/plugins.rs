@@ -0,0 +1,155 @@
+use libc::{c_char, c_void, dlerror, dlopen, dlsym, RTLD_NOW};
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::i3_status::CONFIG;
+use crate::utils::platform::DYNAMIC_LOADING;
+
+// Widgets of other crates (or any language with a C ABI), loaded from shared libraries in the
+// plugin directory. "plugin:weather" loads <plugin directory>/weather.so
+//
+// A plugin exports these functions:
+//
+// uint32_t i3rustus_plugin_abi_version(void);
+//   Has to return PLUGIN_ABI_VERSION, plugins for another version are not loaded
+// void *i3rustus_widget_new(const char *options);
+//   Create a widget, options are the [widgets."plugin:<name>".options] table as JSON object
+//   Returns NULL if the widget can not be created
+// const char *i3rustus_widget_update(void *widget);
+//   Update the widget and return its block as JSON object, like the output of a JSON script
+//   (see widgets/script.rs). The text has to stay valid until the next call for this widget
+//   Returns NULL if the update failed
+// void i3rustus_widget_click(void *widget, uint8_t button);
+//   Optional, called for clicks on the block of the widget
+// void i3rustus_widget_free(void *widget);
+//   Called when the widget is removed from the bar
+//
+// Every widget runs in its own thread, so a widget has to be usable from another thread
+// than the one that created it. Functions of different widgets can be called at the same time
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+type AbiVersion = unsafe extern "C" fn() -> u32;
+type WidgetNew = unsafe extern "C" fn(*const c_char) -> *mut c_void;
+type WidgetUpdate = unsafe extern "C" fn(*mut c_void) -> *const c_char;
+type WidgetClick = unsafe extern "C" fn(*mut c_void, u8);
+type WidgetFree = unsafe extern "C" fn(*mut c_void);
+
+// Functions of a loaded plugin
+#[derive(Clone, Copy)]
+pub struct Plugin {
+    pub new: WidgetNew,
+    pub update: WidgetUpdate,
+    pub click: Option<WidgetClick>,
+    pub free: WidgetFree,
+}
+
+// Libraries are never unloaded, widgets of a plugin can outlive a config reload
+static PLUGINS: Mutex<Option<HashMap<String, Plugin>>> = Mutex::new(None);
+
+fn last_error() -> String {
+    // SAFETY: dlerror returns NULL or a valid string until the next dl* call of this thread
+    unsafe {
+        let error = dlerror();
+        if error.is_null() {
+            String::from("unknown error")
+        } else {
+            CStr::from_ptr(error).to_string_lossy().into_owned()
+        }
+    }
+}
+
+unsafe fn symbol(library: *mut c_void, name: &CStr) -> Option<*mut c_void> {
+    let symbol = dlsym(library, name.as_ptr());
+    (!symbol.is_null()).then_some(symbol)
+}
+
+unsafe fn required_symbol(library: *mut c_void, name: &CStr) -> Result<*mut c_void, String> {
+    symbol(library, name).ok_or_else(|| format!("{:?} is missing", name))
+}
+
+fn plugin_path(name: &str) -> Result<PathBuf, String> {
+    // Plugins are referenced by name, a path would allow loading libraries from anywhere
+    if name.is_empty() || name.contains('/') || name.starts_with('.') {
+        return Err(format!("Invalid plugin name {}", name));
+    }
+    if !DYNAMIC_LOADING {
+        return Err(format!(
+            "Could not load plugin {}: a static build cannot load libraries",
+            name
+        ));
+    }
+    Ok(CONFIG.plugin_directory().join(format!("{}.so", name)))
+}
+
+fn open(name: &str) -> Result<Plugin, String> {
+    let path = plugin_path(name)?;
+    let c_path = CString::new(path.to_string_lossy().as_bytes())
+        .map_err(|_| format!("Invalid plugin path {}", path.display()))?;
+
+    // SAFETY: the symbols are transmuted to the signatures that are documented above,
+    // loading a library runs its initializers, which is why only the plugin directory is used
+    unsafe {
+        let library = dlopen(c_path.as_ptr(), RTLD_NOW);
+        if library.is_null() {
+            // The error of dlopen already contains the path
+            return Err(format!("Could not load plugin {}: {}", name, last_error()));
+        }
+
+        let abi_version: AbiVersion =
+            std::mem::transmute(required_symbol(library, c"i3rustus_plugin_abi_version")?);
+        let version = abi_version();
+        if version != PLUGIN_ABI_VERSION {
+            return Err(format!(
+                "{} was built for plugin ABI {}, but {} is supported",
+                path.display(),
+                version,
+                PLUGIN_ABI_VERSION
+            ));
+        }
+
+        Ok(Plugin {
+            new: std::mem::transmute::<*mut c_void, WidgetNew>(required_symbol(
+                library,
+                c"i3rustus_widget_new",
+            )?),
+            update: std::mem::transmute::<*mut c_void, WidgetUpdate>(required_symbol(
+                library,
+                c"i3rustus_widget_update",
+            )?),
+            click: symbol(library, c"i3rustus_widget_click")
+                .map(|click| std::mem::transmute::<*mut c_void, WidgetClick>(click)),
+            free: std::mem::transmute::<*mut c_void, WidgetFree>(required_symbol(
+                library,
+                c"i3rustus_widget_free",
+            )?),
+        })
+    }
+}
+
+// Check that the library of a plugin can be read, without loading it
+// Loading runs the initializers of the library, which --check-config should not do
+pub fn check_plugin(name: &str) -> Result<(), String> {
+    let path = plugin_path(name)?;
+    let metadata = File::open(&path)
+        .and_then(|file| file.metadata())
+        .map_err(|error| format!("Could not read plugin {}: {}", path.display(), error))?;
+    if !metadata.is_file() {
+        return Err(format!("Plugin {} is not a file", path.display()));
+    }
+    Ok(())
+}
+
+// Load a plugin from the plugin directory, or return it if it was loaded before
+pub fn load_plugin(name: &str) -> Result<Plugin, String> {
+    let mut plugins = PLUGINS.lock().unwrap();
+    let plugins = plugins.get_or_insert_with(HashMap::new);
+    if let Some(plugin) = plugins.get(name) {
+        return Ok(*plugin);
+    }
+    let plugin = open(name)?;
+    plugins.insert(name.to_string(), plugin);
+    Ok(plugin)
+}
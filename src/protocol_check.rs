@@ -0,0 +1,125 @@
+use serde_json::{Map, Value};
+
+// Keys of a block that i3bar understands, see https://i3wm.org/docs/i3bar-protocol.html
+// Everything else has to start with an underscore, i3bar ignores those
+const STRING_KEYS: [&str; 6] = [
+    "full_text",
+    "short_text",
+    "name",
+    "instance",
+    "align",
+    "markup",
+];
+const COLOR_KEYS: [&str; 3] = ["color", "background", "border"];
+const BOOLEAN_KEYS: [&str; 2] = ["urgent", "separator"];
+const INTEGER_KEYS: [&str; 5] = [
+    "separator_block_width",
+    "border_top",
+    "border_right",
+    "border_bottom",
+    "border_left",
+];
+
+// "#RRGGBB" or "#RRGGBBAA"
+fn is_block_color(value: &str) -> bool {
+    (value.len() == 7 || value.len() == 9)
+        && value.starts_with('#')
+        && value[1..]
+            .chars()
+            .all(|character| character.is_ascii_hexdigit())
+}
+
+// serde_json escapes control characters, so they can't break the protocol, but i3bar draws
+// every block on one line and shows them as boxes or not at all. They usually come from
+// command output that was not trimmed (e.g. a trailing newline)
+fn check_characters(value: &Value, key: &str, errors: &mut Vec<String>) {
+    if let Some(control) = value
+        .as_str()
+        .and_then(|text| text.chars().find(|character| character.is_control()))
+    {
+        errors.push(format!(
+            "{} contains the control character U+{:04X}",
+            key, control as u32
+        ));
+    }
+}
+
+fn check_key(key: &str, value: &Value, errors: &mut Vec<String>) {
+    let valid = if STRING_KEYS.contains(&key) {
+        match (key, value.as_str()) {
+            ("align", Some(align)) => ["left", "center", "right"].contains(&align),
+            ("markup", Some(markup)) => ["pango", "none"].contains(&markup),
+            (_, text) => text.is_some(),
+        }
+    } else if COLOR_KEYS.contains(&key) {
+        value.as_str().is_some_and(is_block_color)
+    } else if BOOLEAN_KEYS.contains(&key) {
+        value.is_boolean()
+    } else if INTEGER_KEYS.contains(&key) {
+        value.is_i64()
+    } else if key == "min_width" {
+        // Pixels or a text that is as wide as the block should be
+        value.is_u64() || value.is_string()
+    } else if key.starts_with('_') {
+        true
+    } else {
+        errors.push(format!(
+            "unknown key {}, custom keys have to start with _",
+            key
+        ));
+        return;
+    };
+
+    if !valid {
+        errors.push(format!("invalid {}: {}", key, value));
+    }
+    check_characters(value, key, errors);
+}
+
+fn check_block(block: &Map<String, Value>) -> Vec<String> {
+    let mut errors = Vec::new();
+    if !block.contains_key("full_text") {
+        errors.push(String::from("full_text is missing"));
+    }
+    for (key, value) in block {
+        check_key(key, value, &mut errors);
+    }
+    errors
+}
+
+// e.g. "battery" or "disk:home", used in error messages
+fn block_label(block: &Value) -> String {
+    match (block["name"].as_str(), block["instance"].as_str()) {
+        (Some(name), Some(instance)) => format!("{}:{}", name, instance),
+        (Some(name), None) => name.to_string(),
+        _ => String::from("block without name"),
+    }
+}
+
+// Check one line of the status array against the i3bar protocol
+// A line that i3bar can't read empties the whole bar, so the bar does not start with one
+// Returns one message per problem, prefixed with the widget
+pub fn check_status_line(line: &str) -> Vec<String> {
+    if line.contains('\n') {
+        return vec![String::from("The status is not a single line")];
+    }
+    let status = line.strip_prefix(',').unwrap_or(line);
+    let blocks = match serde_json::from_str::<Value>(status) {
+        Ok(Value::Array(blocks)) => blocks,
+        Ok(_) => return vec![String::from("The status is not an array of blocks")],
+        Err(error) => return vec![format!("The status is not valid JSON: {}", error)],
+    };
+
+    let mut errors = Vec::new();
+    for block in &blocks {
+        match block.as_object() {
+            Some(object) => errors.extend(
+                check_block(object)
+                    .into_iter()
+                    .map(|error| format!("{}: {}", block_label(block), error)),
+            ),
+            None => errors.push(format!("{} is not a block", block)),
+        }
+    }
+    errors
+}
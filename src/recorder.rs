@@ -0,0 +1,63 @@
+use std::fs::{create_dir_all, metadata, rename, OpenOptions};
+use std::io::{Error as IOError, Write};
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+
+use crate::config::WidgetId;
+use crate::i3_status::CONFIG;
+use crate::utils::state::state_directory;
+use crate::variables::variable;
+
+// Appends the values of the configured widgets to a CSV file,
+// so they can be graphed later (e.g. battery degradation)
+//
+// Every line has the format: timestamp,widget,value
+// Once the file is larger than metrics_max_size, it is moved to metrics.csv.1 and a new
+// file is started
+pub struct Recorder {
+    last_record: Option<Instant>,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self { last_record: None }
+    }
+
+    fn record(&self) -> Result<(), IOError> {
+        let directory = state_directory();
+        create_dir_all(&directory)?;
+        let path = directory.join("metrics.csv");
+        if metadata(&path).is_ok_and(|metadata| metadata.len() >= CONFIG.metrics_max_size()) {
+            rename(&path, directory.join("metrics.csv.1"))?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+        let timestamp = Local::now().to_rfc3339();
+        let mut lines = String::new();
+        for widget in CONFIG.recorded_widgets() {
+            // Widgets without a value yet (e.g. errors) are skipped
+            if let Some(value) = variable(&WidgetId::parse(&widget).variable_name("value")) {
+                lines += &format!("{},{},{}\n", timestamp, widget, value);
+            }
+        }
+
+        file.write_all(lines.as_bytes())
+    }
+
+    // Record the values if the recording interval has passed since the last record
+    pub fn record_if_due(&mut self) {
+        if CONFIG.recorded_widgets().is_empty()
+            || self.last_record.is_some_and(|last_record| {
+                last_record.elapsed() < Duration::from_secs(CONFIG.recording_interval())
+            })
+        {
+            return;
+        }
+
+        self.last_record = Some(Instant::now());
+        if let Err(error) = self.record() {
+            log::error!("Could not record widget values: {}", error);
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+use std::fs::{create_dir_all, read, read_dir, write};
+use std::io::{Error as IOError, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+
+use ureq::{Middleware, MiddlewareNext, Request, Response};
+
+// Captures what widgets read from the system (files in /proc and /sys, netlink responses
+// and HTTP responses), so a run can be replayed later on another machine,
+// e.g. to reproduce a bug report or to benchmark the whole pipeline with realistic data
+//
+// Every read of a source is stored in its own file: <directory>/<source>/<index>
+// Replaying returns the captured reads of a source in the same order and starts over at the end
+#[derive(Clone)]
+pub enum Mode {
+    Live,
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+static MODE: RwLock<Mode> = RwLock::new(Mode::Live);
+
+// How often every source was read so far
+static READS: Mutex<BTreeMap<String, usize>> = Mutex::new(BTreeMap::new());
+
+pub fn set_mode(mode: Mode) {
+    *MODE.write().unwrap() = mode;
+}
+
+pub fn mode() -> Mode {
+    MODE.read().unwrap().clone()
+}
+
+// Only the netlink sockets replay whole conversations instead of single reads
+#[cfg(target_os = "linux")]
+pub fn is_replaying() -> bool {
+    matches!(*MODE.read().unwrap(), Mode::Replay(_))
+}
+
+// Sources are paths or URLs, so everything except a few safe characters is replaced
+fn source_directory(directory: &Path, source: &str) -> PathBuf {
+    let name: String = source
+        .chars()
+        .map(|character| {
+            if character.is_ascii_alphanumeric() || matches!(character, '-' | '_' | '.') {
+                character
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    directory.join(name)
+}
+
+fn next_read(source: &str) -> usize {
+    let mut reads = READS.lock().unwrap();
+    let count = reads.entry(source.to_string()).or_default();
+    *count += 1;
+    *count - 1
+}
+
+// Read a source live, record what was read or return what was recorded before
+// Failed reads are not recorded, replaying a source that was never read fails with NotFound
+pub fn capture<F>(source: &str, read_source: F) -> Result<Vec<u8>, IOError>
+where
+    F: FnOnce() -> Result<Vec<u8>, IOError>,
+{
+    match mode() {
+        Mode::Live => read_source(),
+        Mode::Record(directory) => {
+            let data = read_source()?;
+            let directory = source_directory(&directory, source);
+            create_dir_all(&directory)?;
+            write(directory.join(next_read(source).to_string()), &data)?;
+            Ok(data)
+        }
+        Mode::Replay(directory) => {
+            let directory = source_directory(&directory, source);
+            let recorded = read_dir(&directory)?.count();
+            if recorded == 0 {
+                return Err(IOError::new(
+                    ErrorKind::NotFound,
+                    format!("{} was not recorded", source),
+                ));
+            }
+            read(directory.join((next_read(source) % recorded).to_string()))
+        }
+    }
+}
+
+// Captures the responses of an HTTP agent by method and URL
+// The status is stored in the first line, followed by the body
+struct HttpCapture;
+
+impl Middleware for HttpCapture {
+    fn handle(&self, request: Request, next: MiddlewareNext) -> Result<Response, ureq::Error> {
+        // Keep the headers and streaming of live responses
+        if matches!(*MODE.read().unwrap(), Mode::Live) {
+            return next.handle(request);
+        }
+
+        let source = format!("{} {}", request.method(), request.url());
+        let captured = capture(&source, || {
+            let response = next.handle(request).map_err(IOError::other)?;
+            let status = format!("{} {}\n", response.status(), response.status_text());
+            Ok([status.into_bytes(), response.into_string()?.into_bytes()].concat())
+        })?;
+
+        let captured = String::from_utf8_lossy(&captured);
+        let (status, body) = captured.split_once('\n').unwrap_or((&captured, ""));
+        let (code, text) = status.split_once(' ').unwrap_or((status, ""));
+        let code = code
+            .parse()
+            .map_err(|_| IOError::new(ErrorKind::InvalidData, "Invalid recorded status"))?;
+        Response::new(code, text, body)
+    }
+}
+
+// HTTP agent whose responses are recorded and replayed with the rest of the widget data
+pub fn http_agent(timeout: Duration) -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout(timeout)
+        .middleware(HttpCapture)
+        .build()
+}
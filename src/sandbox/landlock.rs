@@ -0,0 +1,185 @@
+use libc::{
+    c_void, syscall, SYS_landlock_add_rule, SYS_landlock_create_ruleset,
+    SYS_landlock_restrict_self, O_CLOEXEC, O_PATH,
+};
+use std::fs::File;
+use std::io::{Error as IOError, ErrorKind};
+use std::mem::size_of;
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::os::unix::fs::OpenOptionsExt;
+use std::ptr::null;
+
+use crate::sandbox::rules::{Access, Rule};
+
+// See https://docs.kernel.org/userspace-api/landlock.html
+const LANDLOCK_CREATE_RULESET_VERSION: u32 = 1 << 0;
+const LANDLOCK_RULE_PATH_BENEATH: u32 = 1;
+
+const LANDLOCK_ACCESS_FS_EXECUTE: u64 = 1 << 0;
+const LANDLOCK_ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+const LANDLOCK_ACCESS_FS_READ_FILE: u64 = 1 << 2;
+const LANDLOCK_ACCESS_FS_READ_DIR: u64 = 1 << 3;
+const LANDLOCK_ACCESS_FS_REMOVE_DIR: u64 = 1 << 4;
+const LANDLOCK_ACCESS_FS_REMOVE_FILE: u64 = 1 << 5;
+const LANDLOCK_ACCESS_FS_MAKE_DIR: u64 = 1 << 7;
+const LANDLOCK_ACCESS_FS_MAKE_REG: u64 = 1 << 8;
+const LANDLOCK_ACCESS_FS_MAKE_SOCK: u64 = 1 << 9;
+const LANDLOCK_ACCESS_FS_MAKE_SYM: u64 = 1 << 12;
+// Since ABI 2
+const LANDLOCK_ACCESS_FS_REFER: u64 = 1 << 13;
+// Since ABI 3
+const LANDLOCK_ACCESS_FS_TRUNCATE: u64 = 1 << 14;
+// Since ABI 5
+const LANDLOCK_ACCESS_FS_IOCTL_DEV: u64 = 1 << 15;
+
+// Rights that can be granted on files, the others only make sense for directories
+const FILE_ACCESS: u64 = LANDLOCK_ACCESS_FS_EXECUTE
+    | LANDLOCK_ACCESS_FS_WRITE_FILE
+    | LANDLOCK_ACCESS_FS_READ_FILE
+    | LANDLOCK_ACCESS_FS_TRUNCATE
+    | LANDLOCK_ACCESS_FS_IOCTL_DEV;
+
+#[repr(C)]
+struct RulesetAttributes {
+    handled_access_fs: u64,
+}
+
+#[repr(C, packed)]
+struct PathBeneathAttributes {
+    allowed_access: u64,
+    parent_fd: i32,
+}
+
+// Version of the Landlock interface of the kernel, every version handles more rights
+// Fails if the kernel was built without Landlock or it is not enabled in the LSM list
+pub fn abi_version() -> Result<i64, IOError> {
+    let version = unsafe {
+        syscall(
+            SYS_landlock_create_ruleset,
+            null::<RulesetAttributes>(),
+            0,
+            LANDLOCK_CREATE_RULESET_VERSION,
+        )
+    };
+    if version < 0 {
+        Err(IOError::last_os_error())
+    } else {
+        Ok(version)
+    }
+}
+
+// Everything that the kernel can restrict, older kernels simply can't take all of it away
+fn handled_access(abi_version: i64) -> u64 {
+    // Rights of ABI 1 are all below REFER
+    let mut access = LANDLOCK_ACCESS_FS_REFER - 1;
+    if abi_version >= 2 {
+        access |= LANDLOCK_ACCESS_FS_REFER;
+    }
+    if abi_version >= 3 {
+        access |= LANDLOCK_ACCESS_FS_TRUNCATE;
+    }
+    if abi_version >= 5 {
+        access |= LANDLOCK_ACCESS_FS_IOCTL_DEV;
+    }
+    access
+}
+
+impl Access {
+    fn landlock_access(&self) -> u64 {
+        let read = LANDLOCK_ACCESS_FS_READ_FILE | LANDLOCK_ACCESS_FS_READ_DIR;
+        match self {
+            Access::Read => read,
+            Access::Execute => read | LANDLOCK_ACCESS_FS_EXECUTE,
+            Access::Write => {
+                read | LANDLOCK_ACCESS_FS_WRITE_FILE
+                    | LANDLOCK_ACCESS_FS_TRUNCATE
+                    | LANDLOCK_ACCESS_FS_MAKE_REG
+                    | LANDLOCK_ACCESS_FS_MAKE_DIR
+                    | LANDLOCK_ACCESS_FS_MAKE_SYM
+                    | LANDLOCK_ACCESS_FS_REMOVE_FILE
+                    | LANDLOCK_ACCESS_FS_REMOVE_DIR
+                    | LANDLOCK_ACCESS_FS_REFER
+            }
+            Access::Device => {
+                LANDLOCK_ACCESS_FS_READ_FILE
+                    | LANDLOCK_ACCESS_FS_WRITE_FILE
+                    | LANDLOCK_ACCESS_FS_IOCTL_DEV
+            }
+            // Stale sockets are removed before a new one is bound
+            Access::Socket => read | LANDLOCK_ACCESS_FS_MAKE_SOCK | LANDLOCK_ACCESS_FS_REMOVE_FILE,
+        }
+    }
+}
+
+fn add_rule(ruleset: &File, rule: &Rule, handled_access: u64) -> Result<(), IOError> {
+    let path = File::options()
+        .read(true)
+        .custom_flags(O_PATH | O_CLOEXEC)
+        .open(&rule.path)?;
+    let mut allowed_access = rule.access.landlock_access() & handled_access;
+    if !path.metadata()?.is_dir() {
+        allowed_access &= FILE_ACCESS;
+    }
+    let attributes = PathBeneathAttributes {
+        allowed_access,
+        parent_fd: path.as_raw_fd(),
+    };
+    if unsafe {
+        syscall(
+            SYS_landlock_add_rule,
+            ruleset.as_raw_fd(),
+            LANDLOCK_RULE_PATH_BENEATH,
+            &attributes as *const PathBeneathAttributes as *const c_void,
+            0,
+        )
+    } < 0
+    {
+        return Err(IOError::last_os_error());
+    }
+
+    Ok(())
+}
+
+// Only allow the given files for the calling thread and the threads and processes it starts
+// Threads that already run are not restricted, so this has to happen before they are started
+// Paths that don't exist (e.g. /lib64 on some systems) are skipped
+pub fn restrict_files(rules: &[Rule]) -> Result<(), IOError> {
+    let handled_access = handled_access(abi_version()?);
+    let attributes = RulesetAttributes {
+        handled_access_fs: handled_access,
+    };
+    let fd = unsafe {
+        syscall(
+            SYS_landlock_create_ruleset,
+            &attributes as *const RulesetAttributes,
+            size_of::<RulesetAttributes>(),
+            0,
+        )
+    };
+    if fd < 0 {
+        return Err(IOError::last_os_error());
+    }
+    // The file takes care of closing the ruleset
+    let ruleset = unsafe { File::from_raw_fd(fd as i32) };
+
+    for rule in rules {
+        match add_rule(&ruleset, rule, handled_access) {
+            Ok(()) => {}
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                log::debug!("Sandbox: {} does not exist", rule.path.display());
+            }
+            Err(error) => {
+                return Err(IOError::new(
+                    error.kind(),
+                    format!("Could not allow {}: {}", rule.path.display(), error),
+                ))
+            }
+        }
+    }
+
+    if unsafe { syscall(SYS_landlock_restrict_self, ruleset.as_raw_fd(), 0) } < 0 {
+        return Err(IOError::last_os_error());
+    }
+
+    Ok(())
+}
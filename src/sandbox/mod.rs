@@ -0,0 +1,91 @@
+// Optional hardening of the bar, enabled with `sandbox = true` in the config file
+// Some widgets parse what other hosts send (MQTT, SNMP, HTTP APIs, ...), a bug there should
+// not give access to the files of the user. Once the config file is loaded, Landlock limits
+// the files to the ones the configured widgets need and a seccomp filter denies system calls
+// and socket families that no widget uses. Both apply to the commands that widgets run too
+
+#[cfg(target_os = "linux")]
+mod landlock;
+#[cfg(target_os = "linux")]
+mod rules;
+// The filter checks the system call numbers of the architecture it was built for
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+mod seccomp;
+
+#[cfg(target_os = "linux")]
+use std::fs::create_dir_all;
+use std::io::Error as IOError;
+use std::sync::Mutex;
+
+use crate::config::WidgetId;
+#[cfg(target_os = "linux")]
+use crate::replay::{mode, Mode};
+#[cfg(target_os = "linux")]
+use crate::utils::state::state_directory;
+
+// Widgets that the sandbox allows the files of, None while the process is not sandboxed
+static SANDBOXED_WIDGETS: Mutex<Option<Vec<WidgetId>>> = Mutex::new(None);
+
+// The rules can't be changed once they are applied, so other widgets only work after a restart
+pub fn sandboxed_widgets() -> Option<Vec<WidgetId>> {
+    SANDBOXED_WIDGETS.lock().unwrap().clone()
+}
+
+// Confine the process to the files that the widgets need and deny unused system calls
+// Has to be called before any thread is started, Landlock only restricts the calling thread
+// and the threads it starts. Fails if either of them can't be set up (e.g. a kernel without
+// Landlock), so a sandbox that was asked for is never silently incomplete
+#[cfg(target_os = "linux")]
+pub fn enter_sandbox(widgets: &[WidgetId]) -> Result<(), IOError> {
+    // Files that are written by the bar itself have to exist before they can be allowed
+    create_dir_all(state_directory())?;
+    if let Mode::Record(directory) = mode() {
+        create_dir_all(directory)?;
+    }
+
+    // Neither the bar nor the commands it runs can gain privileges afterwards (e.g. through
+    // setuid programs like sudo), seccomp and Landlock need that from unprivileged processes
+    if unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) } != 0 {
+        return Err(IOError::last_os_error());
+    }
+    landlock::restrict_files(&rules::rules(widgets))
+        .map_err(|error| IOError::new(error.kind(), format!("Landlock: {}", error)))?;
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    return Err(IOError::new(
+        std::io::ErrorKind::Unsupported,
+        "System calls are only filtered on x86_64 and aarch64",
+    ));
+
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    {
+        seccomp::restrict_syscalls()
+            .map_err(|error| IOError::new(error.kind(), format!("seccomp: {}", error)))?;
+        *SANDBOXED_WIDGETS.lock().unwrap() = Some(widgets.to_vec());
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn enter_sandbox(_widgets: &[WidgetId]) -> Result<(), IOError> {
+    Err(IOError::new(
+        std::io::ErrorKind::Unsupported,
+        "The sandbox is only available on Linux",
+    ))
+}
+
+// e.g. "Landlock ABI 4" for the report of "i3rustus diagnose"
+#[cfg(target_os = "linux")]
+pub fn sandbox_description() -> String {
+    match landlock::abi_version() {
+        Ok(version) => format!("Landlock ABI {}, seccomp", version),
+        Err(error) => format!("seccomp, Landlock is not available ({})", error),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn sandbox_description() -> String {
+    String::from("not available")
+}
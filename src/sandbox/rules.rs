@@ -0,0 +1,177 @@
+use std::env;
+use std::fs::canonicalize;
+use std::path::{Path, PathBuf};
+
+use crate::config::WidgetId;
+use crate::i3_status::CONFIG;
+use crate::replay::{mode, Mode};
+use crate::utils::state::state_directory;
+use crate::widgets::brightness::BACKLIGHT_PATH;
+
+// What may be done with a file or everything below a directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    // Read and run programs
+    Execute,
+    // Read, create, change and remove files
+    Write,
+    // Read, write and ioctl, e.g. i2c devices
+    Device,
+    // Read and create unix sockets
+    Socket,
+}
+
+#[derive(Debug)]
+pub struct Rule {
+    pub path: PathBuf,
+    pub access: Access,
+}
+
+impl Rule {
+    fn new<P: Into<PathBuf>>(path: P, access: Access) -> Self {
+        Self {
+            path: path.into(),
+            access,
+        }
+    }
+}
+
+// Programs and libraries, widgets run commands and load plugins
+const SYSTEM_DIRECTORIES: [&str; 9] = [
+    "/usr",
+    "/bin",
+    "/sbin",
+    "/lib",
+    "/lib32",
+    "/lib64",
+    "/opt",
+    "/nix/store",
+    "/run/current-system",
+];
+
+fn home_directory() -> PathBuf {
+    PathBuf::from(env::var("HOME").unwrap_or_default())
+}
+
+fn config_home() -> PathBuf {
+    match env::var("XDG_CONFIG_HOME") {
+        Ok(config_home) if !config_home.is_empty() => PathBuf::from(config_home),
+        _ => home_directory().join(".config"),
+    }
+}
+
+fn runtime_directory() -> PathBuf {
+    PathBuf::from(env::var("XDG_RUNTIME_DIR").unwrap_or_default())
+}
+
+// An environment variable that contains a path, or the default path below the home directory
+fn path_from_env(variable: &str, default: &str) -> PathBuf {
+    match env::var(variable) {
+        Ok(path) if !path.is_empty() => PathBuf::from(path),
+        _ => home_directory().join(default),
+    }
+}
+
+// Files that every configuration needs
+fn common_rules() -> Vec<Rule> {
+    let mut rules: Vec<Rule> = SYSTEM_DIRECTORIES
+        .iter()
+        .map(|directory| Rule::new(*directory, Access::Execute))
+        .collect();
+    rules.extend([
+        // Users, DNS, the local time zone and the zoneinfo files
+        Rule::new("/etc", Access::Read),
+        Rule::new(CONFIG.zoneinfo_directory(), Access::Read),
+        // Most widgets read the kernel interfaces
+        Rule::new("/proc", Access::Read),
+        Rule::new("/sys", Access::Read),
+        // Commands are started with /dev/null as stdin
+        Rule::new("/dev/null", Access::Device),
+        Rule::new("/dev/urandom", Access::Read),
+        // Commands often need temporary files
+        Rule::new(env::temp_dir(), Access::Write),
+        // The config file is reloaded, plugins and scripts usually live next to it
+        Rule::new(CONFIG.file_path().with_file_name(""), Access::Execute),
+        // Sounds are played by pacat, which reads the cookie of PulseAudio
+        Rule::new(config_home().join("pulse"), Access::Read),
+        Rule::new(state_directory(), Access::Write),
+    ]);
+    if let Some(socket_path) = CONFIG.ipc_socket_path() {
+        if let Some(directory) = socket_path.parent() {
+            rules.push(Rule::new(directory, Access::Socket));
+        }
+    }
+    // Sound files may be anywhere, e.g. in the home directory
+    rules.extend(
+        CONFIG
+            .sound_alerts()
+            .into_iter()
+            .map(|alert| Rule::new(alert.file, Access::Read)),
+    );
+    match mode() {
+        Mode::Record(directory) => rules.push(Rule::new(directory, Access::Write)),
+        Mode::Replay(directory) => rules.push(Rule::new(directory, Access::Read)),
+        Mode::Live => {}
+    }
+    rules
+}
+
+// Files that only some widgets need, most of them for the commands they run
+fn widget_rules(widget_name: &str) -> Vec<Rule> {
+    match widget_name {
+        "agent" => vec![
+            Rule::new(path_from_env("GNUPGHOME", ".gnupg"), Access::Write),
+            // The agent is started on demand and creates its sockets there
+            Rule::new(runtime_directory().join("gnupg"), Access::Socket),
+        ],
+        // The sysfs links point to the device in /sys/devices
+        "brightness" => CONFIG
+            .brightness_device_names()
+            .iter()
+            .filter_map(|device| canonicalize(Path::new(BACKLIGHT_PATH).join(device)).ok())
+            .map(|device| Rule::new(device, Access::Write))
+            .chain(
+                CONFIG
+                    .ddc_i2c_devices()
+                    .into_iter()
+                    .map(|device| Rule::new(device, Access::Device)),
+            )
+            .collect(),
+        "display_scale" | "keyboard" => vec![Rule::new(
+            path_from_env("XAUTHORITY", ".Xauthority"),
+            Access::Read,
+        )],
+        "external_monitor" => CONFIG
+            .ddc_i2c_devices()
+            .into_iter()
+            .map(|device| Rule::new(device, Access::Device))
+            .collect(),
+        // ssh adds hosts to known_hosts
+        "remote" => vec![Rule::new(home_directory().join(".ssh"), Access::Write)],
+        "timewarrior" => vec![
+            Rule::new(
+                path_from_env("TIMEWARRIORDB", ".timewarrior"),
+                Access::Write,
+            ),
+            Rule::new(
+                home_directory().join(".local/share/timewarrior"),
+                Access::Write,
+            ),
+            Rule::new(config_home().join("timewarrior"), Access::Read),
+        ],
+        _ => Vec::new(),
+    }
+}
+
+// Everything that the widgets in the order may access
+pub fn rules(widgets: &[WidgetId]) -> Vec<Rule> {
+    let mut rules = common_rules();
+    let mut names: Vec<&str> = widgets.iter().map(|id| &*id.name).collect();
+    names.sort_unstable();
+    names.dedup();
+    for name in names {
+        rules.extend(widget_rules(name));
+    }
+    rules
+}
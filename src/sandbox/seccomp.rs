@@ -0,0 +1,176 @@
+use libc::{
+    sock_filter, sock_fprog, syscall, SYS_seccomp, BPF_ABS, BPF_JEQ, BPF_JGE, BPF_JMP, BPF_K,
+    BPF_LD, BPF_RET, BPF_W, EAFNOSUPPORT, ENOSYS, EPERM, SECCOMP_FILTER_FLAG_TSYNC,
+    SECCOMP_RET_ALLOW, SECCOMP_RET_ERRNO, SECCOMP_RET_KILL_PROCESS, SECCOMP_SET_MODE_FILTER,
+};
+use std::io::Error as IOError;
+
+// Offsets in struct seccomp_data, which the filter reads
+const SYSCALL_NUMBER_OFFSET: u32 = 0;
+const ARCHITECTURE_OFFSET: u32 = 4;
+// Lower half of the first argument, the byte order of the architecture is little endian
+const FIRST_ARGUMENT_OFFSET: u32 = 16;
+
+// See include/uapi/linux/audit.h
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: u32 = 0xC000_003E;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: u32 = 0xC000_00B7;
+
+// The x32 ABI uses the same architecture, but sets this bit in every system call number
+#[cfg(target_arch = "x86_64")]
+const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+// Nothing in i3rustus or the commands of widgets needs these, but they are what an attacker
+// would need to debug other processes, load kernel modules or leave the sandbox
+// io_uring is denied because its operations bypass seccomp
+const DENIED_SYSCALLS: &[i64] = &[
+    libc::SYS_ptrace,
+    libc::SYS_process_vm_readv,
+    libc::SYS_process_vm_writev,
+    libc::SYS_kexec_load,
+    libc::SYS_kexec_file_load,
+    libc::SYS_init_module,
+    libc::SYS_finit_module,
+    libc::SYS_delete_module,
+    libc::SYS_mount,
+    libc::SYS_umount2,
+    libc::SYS_pivot_root,
+    libc::SYS_chroot,
+    libc::SYS_fsopen,
+    libc::SYS_fsmount,
+    libc::SYS_move_mount,
+    libc::SYS_open_tree,
+    libc::SYS_unshare,
+    libc::SYS_setns,
+    libc::SYS_swapon,
+    libc::SYS_swapoff,
+    libc::SYS_reboot,
+    libc::SYS_acct,
+    libc::SYS_quotactl,
+    libc::SYS_bpf,
+    libc::SYS_perf_event_open,
+    libc::SYS_userfaultfd,
+    libc::SYS_keyctl,
+    libc::SYS_add_key,
+    libc::SYS_request_key,
+    libc::SYS_open_by_handle_at,
+    libc::SYS_name_to_handle_at,
+    libc::SYS_settimeofday,
+    libc::SYS_clock_settime,
+    libc::SYS_clock_adjtime,
+    libc::SYS_adjtimex,
+    libc::SYS_io_uring_setup,
+    libc::SYS_io_uring_enter,
+    libc::SYS_io_uring_register,
+];
+
+// Sockets of widgets are either local, IP or netlink sockets
+// Everything else (e.g. packet sockets to sniff the network) fails with EAFNOSUPPORT
+const ALLOWED_SOCKET_FAMILIES: [i32; 4] = [
+    libc::AF_UNIX,
+    libc::AF_INET,
+    libc::AF_INET6,
+    libc::AF_NETLINK,
+];
+
+fn statement(code: u32, k: u32) -> sock_filter {
+    sock_filter {
+        code: code as u16,
+        jt: 0,
+        jf: 0,
+        k,
+    }
+}
+
+// Skip the next instruction unless the accumulator equals k
+fn jump_unless_equal(k: u32) -> sock_filter {
+    sock_filter {
+        code: (BPF_JMP | BPF_JEQ | BPF_K) as u16,
+        jt: 0,
+        jf: 1,
+        k,
+    }
+}
+
+fn load(offset: u32) -> sock_filter {
+    statement(BPF_LD | BPF_W | BPF_ABS, offset)
+}
+
+fn fail_with(errno: i32) -> sock_filter {
+    statement(BPF_RET | BPF_K, SECCOMP_RET_ERRNO | errno as u32)
+}
+
+fn filter() -> Vec<sock_filter> {
+    let mut filter = vec![
+        // System calls of other architectures have other numbers, the process is killed
+        load(ARCHITECTURE_OFFSET),
+        sock_filter {
+            code: (BPF_JMP | BPF_JEQ | BPF_K) as u16,
+            jt: 1,
+            jf: 0,
+            k: AUDIT_ARCH,
+        },
+        statement(BPF_RET | BPF_K, SECCOMP_RET_KILL_PROCESS),
+        load(SYSCALL_NUMBER_OFFSET),
+    ];
+    #[cfg(target_arch = "x86_64")]
+    filter.extend([
+        sock_filter {
+            code: (BPF_JMP | BPF_JGE | BPF_K) as u16,
+            jt: 0,
+            jf: 1,
+            k: X32_SYSCALL_BIT,
+        },
+        fail_with(ENOSYS),
+    ]);
+    for number in DENIED_SYSCALLS {
+        filter.extend([jump_unless_equal(*number as u32), fail_with(EPERM)]);
+    }
+
+    // Skip the check of the family if this is not socket()
+    let family_check = 1 + ALLOWED_SOCKET_FAMILIES.len() * 2 + 1;
+    filter.push(sock_filter {
+        code: (BPF_JMP | BPF_JEQ | BPF_K) as u16,
+        jt: 0,
+        jf: family_check as u8,
+        k: libc::SYS_socket as u32,
+    });
+    filter.push(load(FIRST_ARGUMENT_OFFSET));
+    for family in ALLOWED_SOCKET_FAMILIES {
+        filter.extend([
+            jump_unless_equal(family as u32),
+            statement(BPF_RET | BPF_K, SECCOMP_RET_ALLOW),
+        ]);
+    }
+    filter.push(fail_with(EAFNOSUPPORT));
+
+    filter.push(statement(BPF_RET | BPF_K, SECCOMP_RET_ALLOW));
+    filter
+}
+
+// Deny the system calls for all threads of the process and the processes it starts
+// Requires no_new_privs, otherwise only root could install the filter
+pub fn restrict_syscalls() -> Result<(), IOError> {
+    let filter = filter();
+    let program = sock_fprog {
+        len: filter.len() as u16,
+        filter: filter.as_ptr() as *mut sock_filter,
+    };
+    match unsafe {
+        syscall(
+            SYS_seccomp,
+            SECCOMP_SET_MODE_FILTER,
+            SECCOMP_FILTER_FLAG_TSYNC,
+            &program as *const sock_fprog,
+        )
+    } {
+        0 => Ok(()),
+        // The ID of a thread that could not take the filter
+        thread if thread > 0 => Err(IOError::other(format!(
+            "Thread {} could not be restricted",
+            thread
+        ))),
+        _ => Err(IOError::last_os_error()),
+    }
+}
@@ -0,0 +1,104 @@
+use libc::{c_int, sighandler_t, signal, SIGCONT, SIGINT, SIGTERM, SIGUSR1, SIGUSR2, SIG_ERR};
+use std::io::Error as IOError;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Set by the SIGUSR1 handler, the main loop refreshes all widgets right away when it is set
+static REFRESH_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+// Set when a widget was updated outside of the tick, the main loop prints the bar right away
+static RENDER_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+// Set while i3bar has hidden the bar, no widgets are updated in that time
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+// Set while the battery runs low, widgets are updated less often and HTTP widgets stop polling
+static LOW_POWER: AtomicBool = AtomicBool::new(false);
+
+// Set by SIGTERM and SIGINT, the main loop stops all widgets and closes the i3bar stream
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+// i3bar sends this signal when the bar is hidden
+// We tell i3bar to use this instead of SIGSTOP in the protocol header
+pub const STOP_SIGNAL: c_int = SIGUSR2;
+// i3bar sends this signal when the bar is shown again
+pub const CONT_SIGNAL: c_int = SIGCONT;
+
+// Signal handlers may only do async-signal-safe things, so we only set a flag here
+extern "C" fn handle_refresh_signal(_signal: c_int) {
+    REFRESH_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+extern "C" fn handle_shutdown_signal(_signal: c_int) {
+    SHUTDOWN_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+extern "C" fn handle_stop_signal(_signal: c_int) {
+    PAUSED.store(true, Ordering::SeqCst);
+}
+
+extern "C" fn handle_cont_signal(_signal: c_int) {
+    PAUSED.store(false, Ordering::SeqCst);
+    // Show up to date values as soon as the bar is visible again
+    REFRESH_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+// Install the signal handlers:
+// - SIGUSR1, so `pkill -USR1 i3rustus` forces a refresh
+// - STOP_SIGNAL / CONT_SIGNAL to pause updates while the bar is hidden
+// - SIGTERM / SIGINT to shut down without leaving i3bar with a broken stream
+pub fn install_signal_handlers() -> Result<(), IOError> {
+    let handlers: [(c_int, extern "C" fn(c_int)); 5] = [
+        (SIGUSR1, handle_refresh_signal),
+        (SIGTERM, handle_shutdown_signal),
+        (SIGINT, handle_shutdown_signal),
+        (STOP_SIGNAL, handle_stop_signal),
+        (CONT_SIGNAL, handle_cont_signal),
+    ];
+    for (signal_number, handler) in handlers {
+        if unsafe { signal(signal_number, handler as *const () as sighandler_t) } == SIG_ERR {
+            return Err(IOError::last_os_error());
+        }
+    }
+
+    Ok(())
+}
+
+pub fn is_shutdown_requested() -> bool {
+    SHUTDOWN_REQUESTED.load(Ordering::SeqCst)
+}
+
+pub fn is_paused() -> bool {
+    PAUSED.load(Ordering::SeqCst)
+}
+
+// Pause updates from outside of a signal handler (e.g. over D-Bus)
+pub fn set_paused(paused: bool) {
+    PAUSED.store(paused, Ordering::SeqCst);
+}
+
+pub fn is_low_power() -> bool {
+    LOW_POWER.load(Ordering::SeqCst)
+}
+
+pub fn set_low_power(low_power: bool) {
+    LOW_POWER.store(low_power, Ordering::SeqCst);
+}
+
+// Request a refresh from outside of a signal handler (e.g. after a click)
+pub fn request_refresh() {
+    REFRESH_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+// Returns true if a refresh was requested since the last call
+pub fn take_refresh_request() -> bool {
+    REFRESH_REQUESTED.swap(false, Ordering::SeqCst)
+}
+
+// Print the bar again without updating all widgets (e.g. after a kernel event)
+pub fn request_render() {
+    RENDER_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+pub fn take_render_request() -> bool {
+    RENDER_REQUESTED.swap(false, Ordering::SeqCst)
+}
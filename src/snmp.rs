@@ -0,0 +1,458 @@
+use std::io::{Error as IOError, ErrorKind};
+use std::net::UdpSocket;
+use std::time::Duration;
+
+// See RFC 3416 for the PDUs and X.690 for the BER encoding
+const SNMP_VERSION_2C: i64 = 1;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+// Enough for the responses of a few OIDs, SNMP over UDP rarely exceeds this
+const MAX_RESPONSE_LENGTH: usize = 4096;
+
+// BER / SNMP tags
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_NULL: u8 = 0x05;
+const TAG_OBJECT_IDENTIFIER: u8 = 0x06;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_IP_ADDRESS: u8 = 0x40;
+const TAG_COUNTER32: u8 = 0x41;
+const TAG_GAUGE32: u8 = 0x42;
+const TAG_TIME_TICKS: u8 = 0x43;
+const TAG_COUNTER64: u8 = 0x46;
+const TAG_GET_REQUEST: u8 = 0xA0;
+const TAG_RESPONSE: u8 = 0xA2;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnmpValue {
+    Integer(i64),
+    // Counter32 or Counter64, only the difference between two values is meaningful
+    Counter(u64),
+    // Gauge32 or TimeTicks
+    Unsigned(u64),
+    Text(String),
+    // The device does not know the OID (noSuchObject, noSuchInstance, endOfMibView)
+    Missing,
+}
+
+fn invalid_data(message: &str) -> IOError {
+    IOError::new(ErrorKind::InvalidData, message.to_string())
+}
+
+fn encode_length(length: usize, buffer: &mut Vec<u8>) {
+    if length < 0x80 {
+        buffer.push(length as u8);
+    } else {
+        let bytes = length.to_be_bytes();
+        let bytes = &bytes[bytes.iter().position(|byte| *byte != 0).unwrap_or(0)..];
+        buffer.push(0x80 | bytes.len() as u8);
+        buffer.extend(bytes);
+    }
+}
+
+fn encode(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut buffer = vec![tag];
+    encode_length(content.len(), &mut buffer);
+    buffer.extend(content);
+    buffer
+}
+
+fn encode_integer(value: i64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    // Remove leading bytes that only repeat the sign
+    let mut start = 0;
+    while start < bytes.len() - 1
+        && ((bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xFF && bytes[start + 1] & 0x80 != 0))
+    {
+        start += 1;
+    }
+    encode(TAG_INTEGER, &bytes[start..])
+}
+
+// Returns an error for OIDs that can't be sent, e.g. "1.3.6.x"
+pub fn check_oid(oid: &str) -> Result<(), IOError> {
+    encode_oid(oid).map(|_| ())
+}
+
+fn encode_oid(oid: &str) -> Result<Vec<u8>, IOError> {
+    let arcs = oid
+        .trim_start_matches('.')
+        .split('.')
+        .map(|arc| arc.parse::<u64>())
+        .collect::<Result<Vec<u64>, _>>()
+        .map_err(|_| invalid_data("Invalid OID"))?;
+    if arcs.len() < 2 {
+        return Err(invalid_data("OIDs need at least two arcs"));
+    }
+    // OIDs come from the config file, the combined arc must not overflow
+    let first = match arcs[0] {
+        0..=2 => (arcs[0] * 40).checked_add(arcs[1]),
+        _ => None,
+    }
+    .ok_or_else(|| invalid_data("OIDs start with 0, 1 or 2"))?;
+
+    // The first two arcs are combined into one
+    let mut content = Vec::new();
+    for arc in [first].iter().chain(&arcs[2..]) {
+        // Base 128, the highest bit is set on all bytes except the last one
+        let mut bytes = vec![(arc & 0x7F) as u8];
+        let mut rest = arc >> 7;
+        while rest > 0 {
+            bytes.push(0x80 | (rest & 0x7F) as u8);
+            rest >>= 7;
+        }
+        content.extend(bytes.iter().rev());
+    }
+
+    Ok(encode(TAG_OBJECT_IDENTIFIER, &content))
+}
+
+// Reads tag-length-value triplets from a BER encoded message
+struct Decoder<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    fn next(&mut self) -> Result<(u8, &'a [u8]), IOError> {
+        let truncated = || invalid_data("Truncated SNMP message");
+        let (&tag, rest) = self.data.split_first().ok_or_else(truncated)?;
+        let (&first, mut rest) = rest.split_first().ok_or_else(truncated)?;
+        let length = if first & 0x80 == 0 {
+            first as usize
+        } else {
+            let count = (first & 0x7F) as usize;
+            if count > rest.len() || count > 4 {
+                return Err(truncated());
+            }
+            let length = rest[..count]
+                .iter()
+                .fold(0, |length, byte| (length << 8) | *byte as usize);
+            rest = &rest[count..];
+            length
+        };
+        if length > rest.len() {
+            return Err(truncated());
+        }
+
+        self.data = &rest[length..];
+        Ok((tag, &rest[..length]))
+    }
+
+    fn expect(&mut self, expected_tag: u8) -> Result<&'a [u8], IOError> {
+        let (tag, content) = self.next()?;
+        if tag != expected_tag {
+            return Err(invalid_data("Unexpected tag in SNMP message"));
+        }
+        Ok(content)
+    }
+}
+
+fn decode_integer(content: &[u8]) -> i64 {
+    // Negative numbers are sign extended
+    let initial = if content.first().is_some_and(|byte| byte & 0x80 != 0) {
+        -1
+    } else {
+        0
+    };
+    content
+        .iter()
+        .fold(initial, |value, byte| (value << 8) | *byte as i64)
+}
+
+fn decode_unsigned(content: &[u8]) -> u64 {
+    content
+        .iter()
+        .fold(0, |value, byte| (value << 8) | *byte as u64)
+}
+
+fn decode_value(tag: u8, content: &[u8]) -> SnmpValue {
+    match tag {
+        TAG_INTEGER => SnmpValue::Integer(decode_integer(content)),
+        TAG_COUNTER32 | TAG_COUNTER64 => SnmpValue::Counter(decode_unsigned(content)),
+        TAG_GAUGE32 | TAG_TIME_TICKS => SnmpValue::Unsigned(decode_unsigned(content)),
+        TAG_OCTET_STRING => SnmpValue::Text(String::from_utf8_lossy(content).to_string()),
+        TAG_IP_ADDRESS => SnmpValue::Text(
+            content
+                .iter()
+                .map(|byte| byte.to_string())
+                .collect::<Vec<String>>()
+                .join("."),
+        ),
+        _ => SnmpValue::Missing,
+    }
+}
+
+// A minimal SNMP v2c client that only supports GET requests
+pub struct SnmpClient {
+    socket: UdpSocket,
+    community: String,
+    request_id: i64,
+}
+
+impl SnmpClient {
+    // The address contains the port, e.g. "192.168.1.1:161"
+    pub fn connect(address: &str, community: &str) -> Result<Self, IOError> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.connect(address)?;
+        socket.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+
+        Ok(Self {
+            socket,
+            community: community.to_string(),
+            request_id: 0,
+        })
+    }
+
+    fn encode_request(&self, oids: &[&str]) -> Result<Vec<u8>, IOError> {
+        let mut variable_bindings = Vec::new();
+        for oid in oids {
+            let mut binding = encode_oid(oid)?;
+            binding.extend(encode(TAG_NULL, &[]));
+            variable_bindings.extend(encode(TAG_SEQUENCE, &binding));
+        }
+
+        let mut pdu = encode_integer(self.request_id);
+        // Error status and error index
+        pdu.extend(encode_integer(0));
+        pdu.extend(encode_integer(0));
+        pdu.extend(encode(TAG_SEQUENCE, &variable_bindings));
+
+        let mut message = encode_integer(SNMP_VERSION_2C);
+        message.extend(encode(TAG_OCTET_STRING, self.community.as_bytes()));
+        message.extend(encode(TAG_GET_REQUEST, &pdu));
+        Ok(encode(TAG_SEQUENCE, &message))
+    }
+
+    fn decode_response(&self, response: &[u8]) -> Result<Option<Vec<SnmpValue>>, IOError> {
+        let mut message = Decoder {
+            data: Decoder { data: response }.expect(TAG_SEQUENCE)?,
+        };
+        message.expect(TAG_INTEGER)?;
+        message.expect(TAG_OCTET_STRING)?;
+        let mut pdu = Decoder {
+            data: message.expect(TAG_RESPONSE)?,
+        };
+        // Late answers to older requests are ignored
+        if decode_integer(pdu.expect(TAG_INTEGER)?) != self.request_id {
+            return Ok(None);
+        }
+        let error_status = decode_integer(pdu.expect(TAG_INTEGER)?);
+        let error_index = decode_integer(pdu.expect(TAG_INTEGER)?);
+        if error_status != 0 {
+            return Err(IOError::other(format!(
+                "SNMP error {} for OID number {}",
+                error_status, error_index
+            )));
+        }
+
+        let mut variable_bindings = Decoder {
+            data: pdu.expect(TAG_SEQUENCE)?,
+        };
+        let mut values = Vec::new();
+        while !variable_bindings.data.is_empty() {
+            let mut binding = Decoder {
+                data: variable_bindings.expect(TAG_SEQUENCE)?,
+            };
+            binding.expect(TAG_OBJECT_IDENTIFIER)?;
+            let (tag, content) = binding.next()?;
+            values.push(decode_value(tag, content));
+        }
+
+        Ok(Some(values))
+    }
+
+    // Get the values of the OIDs in the same order
+    pub fn get(&mut self, oids: &[&str]) -> Result<Vec<SnmpValue>, IOError> {
+        self.request_id = (self.request_id + 1) % i32::MAX as i64;
+        self.socket.send(&self.encode_request(oids)?)?;
+
+        let mut response = [0; MAX_RESPONSE_LENGTH];
+        loop {
+            let length = self.socket.recv(&mut response)?;
+            if let Some(values) = self.decode_response(&response[..length])? {
+                return Ok(values);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client(request_id: i64) -> SnmpClient {
+        SnmpClient {
+            socket: UdpSocket::bind("127.0.0.1:0").unwrap(),
+            community: String::from("public"),
+            request_id,
+        }
+    }
+
+    // A response with the given request ID and variable bindings of (OID, tag, content)
+    fn response(request_id: i64, error_status: i64, bindings: &[(&str, u8, &[u8])]) -> Vec<u8> {
+        let mut variable_bindings = Vec::new();
+        for (oid, tag, content) in bindings {
+            let mut binding = encode_oid(oid).unwrap();
+            binding.extend(encode(*tag, content));
+            variable_bindings.extend(encode(TAG_SEQUENCE, &binding));
+        }
+        let mut pdu = encode_integer(request_id);
+        pdu.extend(encode_integer(error_status));
+        pdu.extend(encode_integer(0));
+        pdu.extend(encode(TAG_SEQUENCE, &variable_bindings));
+        let mut message = encode_integer(SNMP_VERSION_2C);
+        message.extend(encode(TAG_OCTET_STRING, b"public"));
+        message.extend(encode(TAG_RESPONSE, &pdu));
+        encode(TAG_SEQUENCE, &message)
+    }
+
+    #[test]
+    fn lengths_use_the_long_form_from_128_on() {
+        for (length, expected) in [
+            (0, vec![0x00]),
+            (127, vec![0x7F]),
+            (128, vec![0x81, 0x80]),
+            (300, vec![0x82, 0x01, 0x2C]),
+        ] {
+            let mut buffer = Vec::new();
+            encode_length(length, &mut buffer);
+            assert_eq!(buffer, expected, "{}", length);
+        }
+    }
+
+    #[test]
+    fn integers_use_the_fewest_bytes() {
+        for (value, content) in [
+            (0, vec![0x00]),
+            (127, vec![0x7F]),
+            (128, vec![0x00, 0x80]),
+            (256, vec![0x01, 0x00]),
+            (-1, vec![0xFF]),
+            (-128, vec![0x80]),
+            (-129, vec![0xFF, 0x7F]),
+        ] {
+            let encoded = encode_integer(value);
+            assert_eq!(encoded[0], TAG_INTEGER);
+            assert_eq!(&encoded[2..], content, "{}", value);
+            assert_eq!(decode_integer(&encoded[2..]), value);
+        }
+        assert_eq!(decode_integer(&encode_integer(i64::MIN)[2..]), i64::MIN);
+    }
+
+    #[test]
+    fn oids_are_encoded_in_base_128() {
+        assert_eq!(
+            encode_oid("1.3.6.1.2.1.1.3.0").unwrap(),
+            [0x06, 0x08, 0x2B, 0x06, 0x01, 0x02, 0x01, 0x01, 0x03, 0x00]
+        );
+        // A leading dot is allowed, as in the output of snmpwalk
+        assert_eq!(
+            encode_oid(".1.3.6.1.2.1.1.3.0").unwrap(),
+            encode_oid("1.3.6.1.2.1.1.3.0").unwrap()
+        );
+        assert_eq!(
+            encode_oid("1.3.16384").unwrap(),
+            [0x06, 0x04, 0x2B, 0x81, 0x80, 0x00]
+        );
+        // After a first arc of 2, the second one can be larger than 39
+        assert_eq!(encode_oid("2.100").unwrap(), [0x06, 0x02, 0x81, 0x34]);
+    }
+
+    #[test]
+    fn invalid_oids_are_rejected() {
+        for oid in ["", "1", "1.3.6.x", "1..3", "3.1", "2.18446744073709551615"] {
+            assert!(check_oid(oid).is_err(), "{} was accepted", oid);
+        }
+    }
+
+    #[test]
+    fn requests_contain_the_oids() {
+        let request = client(5).encode_request(&["1.3.6.1.2.1.1.3.0"]).unwrap();
+        let mut message = Decoder {
+            data: Decoder { data: &request }.expect(TAG_SEQUENCE).unwrap(),
+        };
+        assert_eq!(
+            decode_integer(message.expect(TAG_INTEGER).unwrap()),
+            SNMP_VERSION_2C
+        );
+        assert_eq!(message.expect(TAG_OCTET_STRING).unwrap(), b"public");
+        let mut pdu = Decoder {
+            data: message.expect(TAG_GET_REQUEST).unwrap(),
+        };
+        assert_eq!(decode_integer(pdu.expect(TAG_INTEGER).unwrap()), 5);
+        pdu.expect(TAG_INTEGER).unwrap();
+        pdu.expect(TAG_INTEGER).unwrap();
+        let mut bindings = Decoder {
+            data: pdu.expect(TAG_SEQUENCE).unwrap(),
+        };
+        let mut binding = Decoder {
+            data: bindings.expect(TAG_SEQUENCE).unwrap(),
+        };
+        assert_eq!(
+            binding.expect(TAG_OBJECT_IDENTIFIER).unwrap(),
+            &encode_oid("1.3.6.1.2.1.1.3.0").unwrap()[2..]
+        );
+        assert_eq!(binding.expect(TAG_NULL).unwrap(), b"");
+        assert!(bindings.data.is_empty());
+    }
+
+    #[test]
+    fn responses_are_decoded() {
+        let response = response(
+            5,
+            0,
+            &[
+                ("1.3.6.1.2.1.1.3.0", TAG_TIME_TICKS, &[0x01, 0x00]),
+                (
+                    "1.3.6.1.2.1.2.2.1.10.1",
+                    TAG_COUNTER32,
+                    &[0x00, 0xFF, 0xFF, 0xFF, 0xFF],
+                ),
+                ("1.3.6.1.2.1.1.5.0", TAG_OCTET_STRING, b"router"),
+                ("1.3.6.1.2.1.4.20.1.1.1", TAG_IP_ADDRESS, &[192, 168, 1, 1]),
+                ("1.3.6.1.2.1.25.1.6.0", TAG_INTEGER, &[0xFF]),
+                // noSuchObject
+                ("1.3.6.1.4.1.1", 0x80, &[]),
+            ],
+        );
+        assert_eq!(
+            client(5).decode_response(&response).unwrap(),
+            Some(vec![
+                SnmpValue::Unsigned(256),
+                SnmpValue::Counter(u64::from(u32::MAX)),
+                SnmpValue::Text(String::from("router")),
+                SnmpValue::Text(String::from("192.168.1.1")),
+                SnmpValue::Integer(-1),
+                SnmpValue::Missing,
+            ])
+        );
+    }
+
+    #[test]
+    fn late_and_failed_responses_are_not_values() {
+        let late = response(4, 0, &[("1.3.6.1.2.1.1.3.0", TAG_GAUGE32, &[1])]);
+        assert_eq!(client(5).decode_response(&late).unwrap(), None);
+
+        // tooBig
+        let failed = response(5, 1, &[]);
+        assert!(client(5).decode_response(&failed).is_err());
+    }
+
+    #[test]
+    fn truncated_messages_are_rejected() {
+        let response = response(5, 0, &[("1.3.6.1.2.1.1.3.0", TAG_GAUGE32, &[1])]);
+        for length in 0..response.len() {
+            assert!(
+                client(5).decode_response(&response[..length]).is_err(),
+                "{} of {} bytes were accepted",
+                length,
+                response.len()
+            );
+        }
+        // Lengths of more than 4 bytes are never needed for SNMP over UDP
+        let mut decoder = Decoder {
+            data: &[TAG_SEQUENCE, 0x85, 0, 0, 0, 0, 1, 0],
+        };
+        assert!(decoder.next().is_err());
+    }
+}
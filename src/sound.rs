@@ -0,0 +1,126 @@
+use std::fs::read;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use crate::i3_status::CONFIG;
+use crate::variables::variable;
+
+// WAVE_FORMAT_PCM, compressed WAV files are not supported
+const PCM_FORMAT: u16 = 1;
+
+// Samples of a WAV file and how they have to be played
+struct Pcm<'a> {
+    channels: u16,
+    sample_rate: u32,
+    // Sample format as understood by pacat, e.g. "s16le"
+    format: &'static str,
+    samples: &'a [u8],
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        bytes.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+// A WAV file is a RIFF container with a "fmt " chunk followed by a "data" chunk
+// See http://soundfile.sapp.org/doc/WaveFormat/
+fn parse_wav(bytes: &[u8]) -> Result<Pcm<'_>, String> {
+    if bytes.get(0..4) != Some(b"RIFF") || bytes.get(8..12) != Some(b"WAVE") {
+        return Err(String::from("not a WAV file"));
+    }
+
+    let mut pcm = None;
+    let mut offset = 12;
+    while let (Some(id), Some(length)) = (bytes.get(offset..offset + 4), u32_at(bytes, offset + 4))
+    {
+        let start = offset + 8;
+        let end = bytes.len().min(start + length as usize);
+        match id {
+            b"fmt " => {
+                let audio_format = u16_at(bytes, start).ok_or("truncated fmt chunk")?;
+                if audio_format != PCM_FORMAT {
+                    return Err(format!("unsupported audio format {}", audio_format));
+                }
+                let bits_per_sample = u16_at(bytes, start + 14).ok_or("truncated fmt chunk")?;
+                let format = match bits_per_sample {
+                    8 => "u8",
+                    16 => "s16le",
+                    24 => "s24le",
+                    32 => "s32le",
+                    bits => return Err(format!("unsupported sample size of {} bits", bits)),
+                };
+                pcm = Some((
+                    u16_at(bytes, start + 2).ok_or("truncated fmt chunk")?,
+                    u32_at(bytes, start + 4).ok_or("truncated fmt chunk")?,
+                    format,
+                ));
+            }
+            b"data" => {
+                let (channels, sample_rate, format) = pcm.ok_or("data chunk before fmt chunk")?;
+                return Ok(Pcm {
+                    channels,
+                    sample_rate,
+                    format,
+                    samples: &bytes[start..end],
+                });
+            }
+            _ => (),
+        }
+        // Chunks are padded to an even length
+        offset = start + length as usize + length as usize % 2;
+    }
+
+    Err(String::from("no data chunk"))
+}
+
+// Write the samples to pacat, which works with PulseAudio and with pipewire-pulse
+fn play(path: &str) -> Result<(), String> {
+    let bytes = read(path).map_err(|error| error.to_string())?;
+    let pcm = parse_wav(&bytes)?;
+
+    let mut player = Command::new("pacat")
+        .args([
+            "--playback",
+            "--raw",
+            "--client-name=i3rustus",
+            &format!("--format={}", pcm.format),
+            &format!("--rate={}", pcm.sample_rate),
+            &format!("--channels={}", pcm.channels),
+        ])
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|error| format!("Could not start pacat: {}", error))?;
+    // pacat plays until its input is closed, which happens when stdin is dropped
+    if let Some(mut stdin) = player.stdin.take() {
+        stdin
+            .write_all(pcm.samples)
+            .map_err(|error| error.to_string())?;
+    }
+    let status = player.wait().map_err(|error| error.to_string())?;
+    if !status.success() {
+        return Err(format!("pacat failed with {}", status));
+    }
+
+    Ok(())
+}
+
+// Play a sound file without blocking the widget, unless sounds are muted
+pub fn play_sound(path: String) {
+    if variable(CONFIG.sound_mute_variable()).as_deref() == Some("true") {
+        return;
+    }
+
+    thread::spawn(move || {
+        if let Err(error) = play(&path) {
+            log::error!("Could not play {}: {}", path, error);
+        }
+    });
+}
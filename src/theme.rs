@@ -0,0 +1,179 @@
+use std::borrow::Cow;
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::config::ColorGradient;
+use crate::i3_status::CONFIG;
+
+// Palette that is currently used, resolved from the config on first use
+// It is reset when the config file changes
+static PALETTE: RwLock<Option<Palette>> = RwLock::new(None);
+
+// Meaning of a color, widgets only use these and never hex values
+// The order goes from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Color {
+    Neutral,
+    Good,
+    // Highlights a widget without a good or bad meaning (e.g. brightness)
+    Accent,
+    Warning,
+    Critical,
+}
+
+// Hex values of the semantic colors, custom themes of the config file own theirs
+#[derive(Clone)]
+pub struct Palette {
+    pub neutral: Cow<'static, str>,
+    pub good: Cow<'static, str>,
+    pub accent: Cow<'static, str>,
+    pub warning: Cow<'static, str>,
+    pub critical: Cow<'static, str>,
+}
+
+#[derive(Clone)]
+pub enum Theme {
+    Default,
+    Gruvbox,
+    Nord,
+    Custom(Palette),
+}
+
+const DEFAULT: Palette = Palette {
+    neutral: Cow::Borrowed("#FFFFFF"),
+    good: Cow::Borrowed("#08FF00"),
+    accent: Cow::Borrowed("#E5DE00"),
+    warning: Cow::Borrowed("#FED037"),
+    critical: Cow::Borrowed("#FF0000"),
+};
+
+// See https://github.com/morhetz/gruvbox
+const GRUVBOX: Palette = Palette {
+    neutral: Cow::Borrowed("#EBDBB2"),
+    good: Cow::Borrowed("#B8BB26"),
+    accent: Cow::Borrowed("#FABD2F"),
+    warning: Cow::Borrowed("#FE8019"),
+    critical: Cow::Borrowed("#FB4934"),
+};
+
+// See https://www.nordtheme.com/docs/colors-and-palettes
+const NORD: Palette = Palette {
+    neutral: Cow::Borrowed("#ECEFF4"),
+    good: Cow::Borrowed("#A3BE8C"),
+    accent: Cow::Borrowed("#EBCB8B"),
+    warning: Cow::Borrowed("#D08770"),
+    critical: Cow::Borrowed("#BF616A"),
+};
+
+impl Theme {
+    pub const NAMES: [&'static str; 3] = ["default", "gruvbox", "nord"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Theme::Default),
+            "gruvbox" => Some(Theme::Gruvbox),
+            "nord" => Some(Theme::Nord),
+            _ => None,
+        }
+    }
+
+    fn palette(self) -> Palette {
+        match self {
+            Theme::Default => DEFAULT,
+            Theme::Gruvbox => GRUVBOX,
+            Theme::Nord => NORD,
+            Theme::Custom(palette) => palette,
+        }
+    }
+}
+
+fn with_current_palette<T>(read: impl FnOnce(&Palette) -> T) -> T {
+    if let Some(palette) = &*PALETTE.read().unwrap() {
+        return read(palette);
+    }
+    let palette = CONFIG.theme().palette();
+    let value = read(&palette);
+    *PALETTE.write().unwrap() = Some(palette);
+    value
+}
+
+// Resolve the palette again the next time a color is used
+pub fn reset_palette() {
+    *PALETTE.write().unwrap() = None;
+}
+
+impl Color {
+    const ALL: [Color; 5] = [
+        Color::Neutral,
+        Color::Good,
+        Color::Accent,
+        Color::Warning,
+        Color::Critical,
+    ];
+
+    pub fn hex(self) -> Cow<'static, str> {
+        with_current_palette(|palette| match self {
+            Color::Neutral => palette.neutral.clone(),
+            Color::Good => palette.good.clone(),
+            Color::Accent => palette.accent.clone(),
+            Color::Warning => palette.warning.clone(),
+            Color::Critical => palette.critical.clone(),
+        })
+    }
+
+    // Find the semantic color of a hex value in an already rendered block
+    pub fn from_hex(hex: &str) -> Option<Color> {
+        Self::ALL.into_iter().find(|color| color.hex() == hex)
+    }
+
+    // Semantic color by its name, e.g. in the output of a script
+    pub fn from_name(name: &str) -> Option<Color> {
+        match name {
+            "neutral" => Some(Color::Neutral),
+            "good" => Some(Color::Good),
+            "accent" => Some(Color::Accent),
+            "warning" => Some(Color::Warning),
+            "critical" => Some(Color::Critical),
+            _ => None,
+        }
+    }
+}
+
+// i3bar only understands hex values
+impl Serialize for Color {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.hex())
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<[u8; 3]> {
+    let channel = |index: usize| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok();
+    Some([channel(1)?, channel(3)?, channel(5)?])
+}
+
+impl ColorGradient {
+    // Hex value of the color at the position of the value between min and max
+    // Values outside of the range get the first or last color
+    pub fn hex(&self, value: f32) -> Option<String> {
+        let position = ((value - self.min) / (self.max - self.min)).clamp(0.0, 1.0);
+        let segments = self.colors.len().checked_sub(1)?;
+        let scaled = position * segments as f32;
+        // The last color is reached at max, which would otherwise start a new segment
+        let index = (scaled as usize).min(segments.saturating_sub(1));
+        let from = parse_hex(&self.colors[index])?;
+        let to = parse_hex(self.colors.get(index + 1).unwrap_or(&self.colors[index]))?;
+        let fraction = scaled - index as f32;
+
+        let [red, green, blue] = [0, 1, 2].map(|channel| {
+            let from = from[channel] as f32;
+            let to = to[channel] as f32;
+            (from + (to - from) * fraction).round() as u8
+        });
+        Some(format!("#{:02X}{:02X}{:02X}", red, green, blue))
+    }
+}
@@ -0,0 +1,31 @@
+// First strong isolate and pop directional isolate
+// The text in between gets its direction from its first strong character
+// and does not change the order of the text around it
+const FIRST_STRONG_ISOLATE: char = '\u{2068}';
+const POP_DIRECTIONAL_ISOLATE: char = '\u{2069}';
+
+// Hebrew, Arabic and the other scripts that are written from right to left
+fn is_right_to_left(character: char) -> bool {
+    matches!(
+        character,
+        '\u{0590}'..='\u{08FF}'
+            | '\u{FB1D}'..='\u{FDFF}'
+            | '\u{FE70}'..='\u{FEFF}'
+            | '\u{10800}'..='\u{10FFF}'
+            | '\u{1E800}'..='\u{1EFFF}'
+    )
+}
+
+// Isolate a text that contains right to left characters, so e.g. a Hebrew window title
+// does not pull the numbers or labels next to it into its own direction
+// Left to right texts are returned as they are
+pub fn isolate(text: &str) -> String {
+    if text.chars().any(is_right_to_left) {
+        format!(
+            "{}{}{}",
+            FIRST_STRONG_ISOLATE, text, POP_DIRECTIONAL_ISOLATE
+        )
+    } else {
+        text.to_string()
+    }
+}
@@ -0,0 +1,70 @@
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Values that are costly to compute or fetch (e.g. output of a command or a public API),
+// shared between all widgets. Widgets that need the same value use the same key,
+// so it is only computed once per time to live
+static CACHE: Mutex<BTreeMap<String, Entry>> = Mutex::new(BTreeMap::new());
+
+struct Entry {
+    value: Arc<dyn Any + Send + Sync>,
+    expires: Instant,
+}
+
+fn lookup<T>(key: &str) -> Option<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    let mut cache = CACHE.lock().unwrap();
+    let entry = cache.get(key)?;
+    if entry.expires <= Instant::now() {
+        cache.remove(key);
+        return None;
+    }
+    // A key that is used with different types is computed again
+    entry.value.downcast_ref::<T>().cloned()
+}
+
+fn store<T>(key: &str, value: T, time_to_live: Duration)
+where
+    T: Send + Sync + 'static,
+{
+    CACHE.lock().unwrap().insert(
+        key.to_string(),
+        Entry {
+            value: Arc::new(value),
+            expires: Instant::now() + time_to_live,
+        },
+    );
+}
+
+// Return the cached value of the key, or compute it and keep it for the time to live
+// The value is computed without holding the cache, so slow computations don't block other widgets
+pub fn cached<T, F>(key: &str, time_to_live: Duration, compute: F) -> T
+where
+    T: Clone + Send + Sync + 'static,
+    F: FnOnce() -> T,
+{
+    if let Some(value) = lookup(key) {
+        return value;
+    }
+    let value = compute();
+    store(key, value.clone(), time_to_live);
+    value
+}
+
+// Like cached, but errors are not cached, so the next call tries again
+pub fn cached_result<T, E, F>(key: &str, time_to_live: Duration, compute: F) -> Result<T, E>
+where
+    T: Clone + Send + Sync + 'static,
+    F: FnOnce() -> Result<T, E>,
+{
+    if let Some(value) = lookup(key) {
+        return Ok(value);
+    }
+    let value = compute()?;
+    store(key, value.clone(), time_to_live);
+    Ok(value)
+}
@@ -0,0 +1,82 @@
+use std::ops::Range;
+
+use crate::utils::tzif::TimeZoneInfo;
+
+// Offset of a time zone to UTC at a point in time
+pub struct Period {
+    // Seconds east of UTC
+    pub utc_offset: i32,
+    // e.g. "CEST"
+    pub abbreviation: String,
+    // Seconds since the epoch from which until before which the offset is in effect
+    pub valid: Range<i64>,
+}
+
+// Rules of a named time zone, so clocks don't care where they come from
+// The zoneinfo files are parsed by TimeZoneInfo, with the chrono-tz feature the database
+// that is built into chrono-tz is used instead
+pub trait Clock: Send {
+    fn period_at(&self, timestamp: i64) -> Period;
+}
+
+impl Clock for TimeZoneInfo {
+    fn period_at(&self, timestamp: i64) -> Period {
+        let (local_time_type, valid) = self.local_time_period(timestamp);
+        Period {
+            utc_offset: local_time_type.utc_offset,
+            abbreviation: local_time_type.abbreviation.clone(),
+            valid,
+        }
+    }
+}
+
+// Zones without abbreviation are shown with their offset like in the zoneinfo files,
+// e.g. "-03" or "+0530"
+#[cfg(feature = "chrono-tz")]
+fn numeric_abbreviation(utc_offset: i32) -> String {
+    let sign = if utc_offset < 0 { '-' } else { '+' };
+    let minutes = utc_offset.unsigned_abs() / 60;
+    if minutes.is_multiple_of(60) {
+        format!("{}{:02}", sign, minutes / 60)
+    } else {
+        format!("{}{:02}{:02}", sign, minutes / 60, minutes % 60)
+    }
+}
+
+// chrono-tz does not expose its transitions, so the offset is only valid for the given second
+// Looking it up is a binary search, like with TimeZoneInfo
+#[cfg(feature = "chrono-tz")]
+impl Clock for chrono_tz::Tz {
+    fn period_at(&self, timestamp: i64) -> Period {
+        use chrono::{DateTime, Offset, TimeZone};
+        use chrono_tz::OffsetName;
+
+        let utc = DateTime::from_timestamp(timestamp, 0)
+            .unwrap_or_default()
+            .naive_utc();
+        let offset = self.offset_from_utc_datetime(&utc);
+        let utc_offset = offset.fix().local_minus_utc();
+        Period {
+            utc_offset,
+            abbreviation: match offset.abbreviation() {
+                Some(abbreviation) => abbreviation.to_string(),
+                None => numeric_abbreviation(utc_offset),
+            },
+            valid: timestamp..timestamp + 1,
+        }
+    }
+}
+
+// Rules of the named zone from the database of chrono-tz, e.g. "Europe/Berlin"
+#[cfg(feature = "chrono-tz")]
+pub fn builtin_zone(name: &str) -> Option<Box<dyn Clock>> {
+    name.parse::<chrono_tz::Tz>()
+        .ok()
+        .map(|zone| Box::new(zone) as Box<dyn Clock>)
+}
+
+// Without the chrono-tz feature, all zones are read from zoneinfo files
+#[cfg(not(feature = "chrono-tz"))]
+pub fn builtin_zone(_name: &str) -> Option<Box<dyn Clock>> {
+    None
+}
@@ -0,0 +1,332 @@
+// A small expression language for derived widgets
+//
+// Supported are numbers with optional unit suffixes (e.g. 1.5G, 100KB/s), variables
+// (e.g. memory.value), arithmetic (+ - * /), comparisons (== != < <= > >=),
+// boolean operators (&& || !) and parentheses.
+// Booleans are represented as 1.0 (true) and 0.0 (false).
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Identifier(String),
+    Operator(&'static str),
+    OpenParenthesis,
+    CloseParenthesis,
+}
+
+// Operators sorted so that longer operators are matched first
+const OPERATORS: [&str; 13] = [
+    "&&", "||", "==", "!=", "<=", ">=", "<", ">", "+", "-", "*", "/", "!",
+];
+
+fn unit_multiplier(unit: &str) -> Result<f64, String> {
+    // Rates are treated like the amount itself (e.g. 1MB/s == 1MB)
+    let unit = unit.trim_end_matches("/s");
+    match unit {
+        "" | "B" => Ok(1.0),
+        "K" | "k" | "KB" | "kB" => Ok(1e3),
+        "M" | "MB" => Ok(1e6),
+        "G" | "GB" => Ok(1e9),
+        "KiB" => Ok(1024.0),
+        "MiB" => Ok(1024.0 * 1024.0),
+        "GiB" => Ok(1024.0 * 1024.0 * 1024.0),
+        "%" => Ok(1.0),
+        _ => Err(format!("Unknown unit \"{}\"", unit)),
+    }
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, String> {
+    let characters = expression.chars().collect::<Vec<char>>();
+    let mut tokens = Vec::new();
+    let mut position = 0;
+
+    while position < characters.len() {
+        let character = characters[position];
+        if character.is_whitespace() {
+            position += 1;
+        } else if character.is_ascii_digit() || character == '.' {
+            let start = position;
+            while position < characters.len()
+                && (characters[position].is_ascii_digit() || characters[position] == '.')
+            {
+                position += 1;
+            }
+            let number = characters[start..position]
+                .iter()
+                .collect::<String>()
+                .parse::<f64>()
+                .map_err(|error| error.to_string())?;
+            // Everything that directly follows a number is its unit
+            let unit_start = position;
+            while position < characters.len()
+                && (characters[position].is_alphabetic()
+                    || characters[position] == '%'
+                    || (characters[position] == '/' && characters.get(position + 1) == Some(&'s')))
+            {
+                position += if characters[position] == '/' { 2 } else { 1 };
+            }
+            let unit = characters[unit_start..position].iter().collect::<String>();
+            tokens.push(Token::Number(number * unit_multiplier(&unit)?));
+        } else if character.is_alphabetic() || character == '_' {
+            let start = position;
+            while position < characters.len()
+                && (characters[position].is_alphanumeric()
+                    || characters[position] == '_'
+                    || characters[position] == '.')
+            {
+                position += 1;
+            }
+            tokens.push(Token::Identifier(
+                characters[start..position].iter().collect(),
+            ));
+        } else if character == '(' {
+            tokens.push(Token::OpenParenthesis);
+            position += 1;
+        } else if character == ')' {
+            tokens.push(Token::CloseParenthesis);
+            position += 1;
+        } else {
+            let rest = characters[position..].iter().collect::<String>();
+            match OPERATORS
+                .iter()
+                .find(|operator| rest.starts_with(**operator))
+            {
+                Some(operator) => {
+                    tokens.push(Token::Operator(operator));
+                    position += operator.len();
+                }
+                None => return Err(format!("Unexpected character '{}'", character)),
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn as_bool(value: f64) -> bool {
+    value != 0.0
+}
+
+fn from_bool(value: bool) -> f64 {
+    if value {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+// Recursive descent parser that evaluates the expression while parsing it
+struct Evaluator<'a, F: Fn(&str) -> Option<String>> {
+    tokens: &'a [Token],
+    position: usize,
+    resolve_variable: F,
+}
+
+impl<F: Fn(&str) -> Option<String>> Evaluator<'_, F> {
+    fn peek_operator(&self, operators: &[&str]) -> Option<&'static str> {
+        match self.tokens.get(self.position) {
+            Some(Token::Operator(operator)) if operators.contains(operator) => Some(operator),
+            _ => None,
+        }
+    }
+
+    // Parse one precedence level of binary operators
+    fn binary(
+        &mut self,
+        operators: &[&str],
+        next: fn(&mut Self) -> Result<f64, String>,
+    ) -> Result<f64, String> {
+        let mut left = next(self)?;
+        while let Some(operator) = self.peek_operator(operators) {
+            self.position += 1;
+            let right = next(self)?;
+            left = match operator {
+                "||" => from_bool(as_bool(left) || as_bool(right)),
+                "&&" => from_bool(as_bool(left) && as_bool(right)),
+                "==" => from_bool(left == right),
+                "!=" => from_bool(left != right),
+                "<" => from_bool(left < right),
+                "<=" => from_bool(left <= right),
+                ">" => from_bool(left > right),
+                ">=" => from_bool(left >= right),
+                "+" => left + right,
+                "-" => left - right,
+                "*" => left * right,
+                // Infinity or NaN would be shown as a value
+                _ if right == 0.0 => return Err(String::from("Division by zero")),
+                _ => left / right,
+            };
+        }
+
+        Ok(left)
+    }
+
+    fn or(&mut self) -> Result<f64, String> {
+        self.binary(&["||"], Self::and)
+    }
+
+    fn and(&mut self) -> Result<f64, String> {
+        self.binary(&["&&"], Self::comparison)
+    }
+
+    fn comparison(&mut self) -> Result<f64, String> {
+        self.binary(&["==", "!=", "<", "<=", ">", ">="], Self::sum)
+    }
+
+    fn sum(&mut self) -> Result<f64, String> {
+        self.binary(&["+", "-"], Self::product)
+    }
+
+    fn product(&mut self) -> Result<f64, String> {
+        self.binary(&["*", "/"], Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<f64, String> {
+        match self.peek_operator(&["!", "-"]) {
+            Some("!") => {
+                self.position += 1;
+                Ok(from_bool(!as_bool(self.unary()?)))
+            }
+            Some(_) => {
+                self.position += 1;
+                Ok(-self.unary()?)
+            }
+            None => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<f64, String> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        match token {
+            Some(Token::Number(number)) => Ok(number),
+            Some(Token::Identifier(name)) => match name.as_str() {
+                "true" => Ok(1.0),
+                "false" => Ok(0.0),
+                _ => {
+                    let value = (self.resolve_variable)(&name)
+                        .ok_or_else(|| format!("Unknown variable \"{}\"", name))?;
+                    match value.as_str() {
+                        "true" => Ok(1.0),
+                        "false" => Ok(0.0),
+                        _ => value.parse::<f64>().map_err(|_| {
+                            format!("Variable \"{}\" is not a number: {}", name, value)
+                        }),
+                    }
+                }
+            },
+            Some(Token::OpenParenthesis) => {
+                let value = self.or()?;
+                if self.tokens.get(self.position) != Some(&Token::CloseParenthesis) {
+                    return Err(String::from("Missing closing parenthesis"));
+                }
+                self.position += 1;
+                Ok(value)
+            }
+            Some(token) => Err(format!("Unexpected token {:?}", token)),
+            None => Err(String::from("Unexpected end of expression")),
+        }
+    }
+}
+
+// Evaluate an expression, variables are looked up with the given function
+pub fn evaluate<F: Fn(&str) -> Option<String>>(
+    expression: &str,
+    resolve_variable: F,
+) -> Result<f64, String> {
+    let tokens = tokenize(expression)?;
+    let mut evaluator = Evaluator {
+        tokens: &tokens,
+        position: 0,
+        resolve_variable,
+    };
+    let value = evaluator.or()?;
+    if evaluator.position != tokens.len() {
+        return Err(format!("Unexpected token {:?}", tokens[evaluator.position]));
+    }
+
+    Ok(value)
+}
+
+// Whether the expression contains comparisons or boolean operators
+// The result of such an expression is a condition and is shown as an alert
+pub fn is_condition(expression: &str) -> bool {
+    tokenize(expression).is_ok_and(|tokens| {
+        tokens.iter().any(|token| {
+            matches!(
+                token,
+                Token::Operator("&&" | "||" | "==" | "!=" | "<" | "<=" | ">" | ">=" | "!")
+            )
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(expression: &str) -> Result<f64, String> {
+        evaluate(expression, |name| match name {
+            "memory.value" => Some(String::from("75")),
+            "vpn.connected" => Some(String::from("true")),
+            "script.text" => Some(String::from("up")),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn operators_have_the_usual_precedence() {
+        assert_eq!(value("1 + 2 * 3"), Ok(7.0));
+        assert_eq!(value("(1 + 2) * 3"), Ok(9.0));
+        assert_eq!(value("10 - 4 - 3"), Ok(3.0));
+        assert_eq!(value("12 / 3 / 2"), Ok(2.0));
+        assert_eq!(value("-2 * 3 + 1"), Ok(-5.0));
+        assert_eq!(value("1 + 1 == 2"), Ok(1.0));
+        assert_eq!(value("1 < 2 && 3 < 2 || 1"), Ok(1.0));
+        assert_eq!(value("1 || 0 && 0"), Ok(1.0));
+        assert_eq!(value("!0 && !(1 > 2)"), Ok(1.0));
+    }
+
+    #[test]
+    fn division_by_zero_is_an_error() {
+        assert_eq!(value("1 / 0"), Err(String::from("Division by zero")));
+        assert_eq!(
+            value("1 / (memory.value - 75)"),
+            Err(String::from("Division by zero"))
+        );
+        assert_eq!(value("0 / 1"), Ok(0.0));
+    }
+
+    #[test]
+    fn units_and_variables_are_numbers() {
+        assert_eq!(value("1.5G"), Ok(1.5e9));
+        assert_eq!(value("2MiB"), Ok(2.0 * 1024.0 * 1024.0));
+        assert_eq!(value("100KB/s > 99k"), Ok(1.0));
+        assert_eq!(value("memory.value >= 75%"), Ok(1.0));
+        assert_eq!(value("vpn.connected && true"), Ok(1.0));
+    }
+
+    #[test]
+    fn invalid_expressions_are_rejected() {
+        for expression in [
+            "",
+            "1 +",
+            "(1 + 2",
+            "1 2",
+            "1 ? 2",
+            "3XB",
+            "missing.value",
+            "script.text == 1",
+        ] {
+            assert!(value(expression).is_err(), "{} was accepted", expression);
+        }
+    }
+
+    #[test]
+    fn conditions_are_recognized() {
+        assert!(is_condition("cpu.value > 90"));
+        assert!(is_condition("!vpn.connected"));
+        assert!(!is_condition("memory.value * 2"));
+        assert!(!is_condition("1 ?"));
+    }
+}
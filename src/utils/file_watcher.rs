@@ -0,0 +1,105 @@
+#[cfg(target_os = "linux")]
+use libc::{
+    inotify_add_watch, inotify_event, inotify_init1, IN_CLOEXEC, IN_CLOSE_WRITE, IN_CREATE,
+    IN_DELETE, IN_MOVED_TO,
+};
+#[cfg(target_os = "linux")]
+use std::ffi::CString;
+#[cfg(target_os = "linux")]
+use std::fs::File;
+#[cfg(target_os = "linux")]
+use std::io::Read;
+use std::io::{Error as IOError, ErrorKind};
+#[cfg(target_os = "linux")]
+use std::mem::size_of;
+#[cfg(target_os = "linux")]
+use std::os::fd::FromRawFd;
+#[cfg(target_os = "linux")]
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+#[cfg(target_os = "linux")]
+use std::thread;
+
+// Enough for several events at once, names are at most NAME_MAX bytes long
+#[cfg(target_os = "linux")]
+const EVENT_BUFFER_LENGTH: usize = 4096;
+
+// Watch a file for changes with inotify and call on_change in a thread of its own
+// The directory is watched instead of the file, because most editors save a file
+// by writing a new one and moving it over the old one, and symlinks like /etc/localtime
+// are replaced instead of changed
+#[cfg(target_os = "linux")]
+pub fn watch_file<F>(path: &Path, on_change: F) -> Result<(), IOError>
+where
+    F: Fn() + Send + 'static,
+{
+    let (Some(directory), Some(file_name)) = (path.parent(), path.file_name()) else {
+        return Err(IOError::new(ErrorKind::InvalidInput, "Invalid path"));
+    };
+    let directory = CString::new(directory.as_os_str().as_bytes())?;
+    let file_name = file_name.as_bytes().to_vec();
+    let display_path = path.display().to_string();
+
+    let fd = unsafe { inotify_init1(IN_CLOEXEC) };
+    if fd < 0 {
+        return Err(IOError::last_os_error());
+    }
+    // The file takes care of closing the file descriptor
+    let mut inotify = unsafe { File::from_raw_fd(fd) };
+    if unsafe {
+        inotify_add_watch(
+            fd,
+            directory.as_ptr(),
+            IN_CLOSE_WRITE | IN_MOVED_TO | IN_DELETE | IN_CREATE,
+        )
+    } < 0
+    {
+        return Err(IOError::last_os_error());
+    }
+
+    thread::spawn(move || {
+        let mut buffer = [0; EVENT_BUFFER_LENGTH];
+        loop {
+            let length = match inotify.read(&mut buffer) {
+                Ok(length) => length,
+                Err(error) => {
+                    log::error!("Stopped watching {}: {}", display_path, error);
+                    return;
+                }
+            };
+
+            // Every event is followed by the NUL padded name of the file it belongs to
+            // Several events for the file in one read (e.g. delete and create) are one change
+            let mut changed = false;
+            let mut offset = 0;
+            while offset + size_of::<inotify_event>() <= length {
+                let name_start = offset + size_of::<inotify_event>();
+                let name_length =
+                    u32::from_ne_bytes(buffer[name_start - 4..name_start].try_into().unwrap())
+                        as usize;
+                let name = &buffer[name_start..(name_start + name_length).min(length)];
+                if name.split(|byte| *byte == 0).next() == Some(file_name.as_slice()) {
+                    changed = true;
+                }
+                offset = name_start + name_length;
+            }
+            if changed {
+                on_change();
+            }
+        }
+    });
+
+    Ok(())
+}
+
+// Other systems don't have inotify, callers fall back to checking the file every now and then
+#[cfg(not(target_os = "linux"))]
+pub fn watch_file<F>(_path: &Path, _on_change: F) -> Result<(), IOError>
+where
+    F: Fn() + Send + 'static,
+{
+    Err(IOError::new(
+        ErrorKind::Unsupported,
+        "Watching files is only supported on Linux",
+    ))
+}
@@ -0,0 +1,81 @@
+use crate::i3_status::CONFIG;
+
+// Characters that belong to the character before them, e.g. combining accents,
+// variation selectors, skin tone modifiers and the tags of subdivision flags
+fn is_extender(character: char) -> bool {
+    matches!(
+        character,
+        '\u{0300}'..='\u{036F}'
+            | '\u{1160}'..='\u{11FF}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{200C}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{FE00}'..='\u{FE0F}'
+            | '\u{FE20}'..='\u{FE2F}'
+            | '\u{1F3FB}'..='\u{1F3FF}'
+            | '\u{E0020}'..='\u{E007F}'
+            | '\u{E0100}'..='\u{E01EF}'
+    )
+}
+
+// Zero width joiner, joins emoji like "👩" + ZWJ + "💻" into one glyph
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+
+fn is_regional_indicator(character: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&character)
+}
+
+// Split a text into the characters a user sees (extended grapheme clusters)
+// This covers emoji sequences, flags and combining marks, but not every rule of UAX #29
+pub fn graphemes(text: &str) -> Vec<&str> {
+    let mut clusters = Vec::new();
+    let mut start = 0;
+    let mut previous: Option<char> = None;
+    // Flags are pairs of regional indicators, a third one starts a new flag
+    let mut regional_indicators = 0;
+    for (index, character) in text.char_indices() {
+        let joins_previous = match previous {
+            None => true,
+            Some('\r') => character == '\n',
+            Some(ZERO_WIDTH_JOINER) => true,
+            Some(previous) => {
+                is_extender(character)
+                    || character == ZERO_WIDTH_JOINER
+                    || (is_regional_indicator(previous)
+                        && is_regional_indicator(character)
+                        && regional_indicators % 2 == 1)
+            }
+        };
+        if !joins_previous {
+            clusters.push(&text[start..index]);
+            start = index;
+            regional_indicators = 0;
+        }
+        if is_regional_indicator(character) {
+            regional_indicators += 1;
+        }
+        previous = Some(character);
+    }
+    if start < text.len() {
+        clusters.push(&text[start..]);
+    }
+
+    clusters
+}
+
+// Shorten a text to at most max_length graphemes, including the ellipsis
+// Emoji and accented characters are never cut in half
+pub fn truncate(text: &str, max_length: usize) -> String {
+    let clusters = graphemes(text);
+    if clusters.len() <= max_length {
+        return text.to_string();
+    }
+
+    let ellipsis = CONFIG.truncation_ellipsis();
+    let kept = max_length.saturating_sub(graphemes(ellipsis).len());
+    let mut truncated: String = clusters[..kept].concat();
+    // The ellipsis should not be separated from the text by a space
+    truncated.truncate(truncated.trim_end().len());
+    truncated + ellipsis
+}
@@ -0,0 +1,113 @@
+use libc::{c_char, c_int, c_ulong, c_void, dlopen, RTLD_LAZY};
+use std::io::Error as IOError;
+use std::ptr;
+use std::time::Duration;
+
+use crate::utils::platform::{load_symbol, DYNAMIC_LOADING};
+
+// See XScreenSaverInfo in X11/extensions/scrnsaver.h
+#[repr(C)]
+struct XScreenSaverInfo {
+    window: c_ulong,
+    state: c_int,
+    kind: c_int,
+    til_or_since: c_ulong,
+    idle: c_ulong,
+    event_mask: c_ulong,
+}
+
+type XOpenDisplay = unsafe extern "C" fn(*const c_char) -> *mut c_void;
+type XCloseDisplay = unsafe extern "C" fn(*mut c_void) -> c_int;
+type XFree = unsafe extern "C" fn(*mut c_void) -> c_int;
+type XDefaultRootWindow = unsafe extern "C" fn(*mut c_void) -> c_ulong;
+type XScreenSaverAllocInfo = unsafe extern "C" fn() -> *mut XScreenSaverInfo;
+type XScreenSaverQueryInfo =
+    unsafe extern "C" fn(*mut c_void, c_ulong, *mut XScreenSaverInfo) -> c_int;
+
+// Asks the X server how long the user has been idle (no keyboard or mouse input)
+//
+// libX11 and libXss are loaded at runtime, so i3rustus does not need to be linked
+// against them and keeps working (without idle detection) where they are missing
+pub struct IdleMonitor {
+    display: *mut c_void,
+    root_window: c_ulong,
+    info: *mut XScreenSaverInfo,
+    query_info: XScreenSaverQueryInfo,
+    free: XFree,
+    close_display: XCloseDisplay,
+}
+
+// Every widget executor runs in its own thread, the monitor is moved there once
+// The display connection is only ever used by the thread that owns the monitor,
+// which is all that Xlib needs without XInitThreads
+unsafe impl Send for IdleMonitor {}
+
+impl IdleMonitor {
+    pub fn new() -> Result<Self, IOError> {
+        if !DYNAMIC_LOADING {
+            return Err(IOError::other(
+                "A static build cannot load libX11 and libXss",
+            ));
+        }
+        unsafe {
+            let x11 = dlopen(c"libX11.so.6".as_ptr(), RTLD_LAZY);
+            let xss = dlopen(c"libXss.so.1".as_ptr(), RTLD_LAZY);
+            if x11.is_null() || xss.is_null() {
+                return Err(IOError::other("Could not load libX11 or libXss"));
+            }
+
+            let open_display: XOpenDisplay =
+                std::mem::transmute(load_symbol(x11, c"XOpenDisplay")?);
+            let close_display: XCloseDisplay =
+                std::mem::transmute(load_symbol(x11, c"XCloseDisplay")?);
+            let free: XFree = std::mem::transmute(load_symbol(x11, c"XFree")?);
+            let default_root_window: XDefaultRootWindow =
+                std::mem::transmute(load_symbol(x11, c"XDefaultRootWindow")?);
+            let alloc_info: XScreenSaverAllocInfo =
+                std::mem::transmute(load_symbol(xss, c"XScreenSaverAllocInfo")?);
+            let query_info: XScreenSaverQueryInfo =
+                std::mem::transmute(load_symbol(xss, c"XScreenSaverQueryInfo")?);
+
+            // NULL means that the DISPLAY environment variable is used
+            let display = open_display(ptr::null());
+            if display.is_null() {
+                return Err(IOError::other("Could not open X display"));
+            }
+            let info = alloc_info();
+            if info.is_null() {
+                close_display(display);
+                return Err(IOError::other("Could not allocate XScreenSaverInfo"));
+            }
+
+            Ok(Self {
+                display,
+                root_window: default_root_window(display),
+                info,
+                query_info,
+                free,
+                close_display,
+            })
+        }
+    }
+
+    // Time since the last user input
+    pub fn idle_time(&self) -> Result<Duration, IOError> {
+        unsafe {
+            if (self.query_info)(self.display, self.root_window, self.info) == 0 {
+                return Err(IOError::other("XScreenSaverQueryInfo failed"));
+            }
+
+            Ok(Duration::from_millis((*self.info).idle as _))
+        }
+    }
+}
+
+// Executors are recreated on reloads, so the info and the connection must not be leaked
+impl Drop for IdleMonitor {
+    fn drop(&mut self) {
+        unsafe {
+            (self.free)(self.info.cast());
+            (self.close_display)(self.display);
+        }
+    }
+}
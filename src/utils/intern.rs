@@ -0,0 +1,18 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+// Strings that are used over and over again (e.g. names and instances of widgets) are stored
+// once, every user gets a reference to the same allocation. Parsing "disk:home" on every
+// update of a group or for every click event does not allocate after the first time
+static STRINGS: Mutex<Option<HashSet<Arc<str>>>> = Mutex::new(None);
+
+pub fn intern(text: &str) -> Arc<str> {
+    let mut strings = STRINGS.lock().unwrap();
+    let strings = strings.get_or_insert_with(HashSet::new);
+    if let Some(interned) = strings.get(text) {
+        return Arc::clone(interned);
+    }
+    let interned: Arc<str> = Arc::from(text);
+    strings.insert(Arc::clone(&interned));
+    interned
+}
@@ -0,0 +1,25 @@
+use serde_json::Value;
+
+// Select a value with a simple JSONPath, e.g. "$.sensors[0].temperature"
+// Only child names and array indices are supported, which is enough for sensor payloads
+pub fn select<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    let mut current = value;
+    let mut rest = path.strip_prefix('$').unwrap_or(path);
+    while !rest.is_empty() {
+        if let Some(after_bracket) = rest.strip_prefix('[') {
+            let (index, after_index) = after_bracket.split_once(']')?;
+            current = match index.trim_matches(|c| c == '\'' || c == '"') {
+                key if key.len() != index.len() => current.get(key)?,
+                index => current.get(index.parse::<usize>().ok()?)?,
+            };
+            rest = after_index;
+        } else {
+            let after_dot = rest.strip_prefix('.').unwrap_or(rest);
+            let end = after_dot.find(['.', '[']).unwrap_or(after_dot.len());
+            current = current.get(&after_dot[..end])?;
+            rest = &after_dot[end..];
+        }
+    }
+
+    Some(current)
+}
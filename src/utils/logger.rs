@@ -4,6 +4,8 @@ use std::{
     io::Write,
 };
 
+pub const LOG_FILE: &str = "/var/log/i3rustus.log";
+
 pub struct Logger {
     pub file: Option<File>,
 }
@@ -14,11 +16,11 @@ impl Log for Logger {
     }
 
     fn log(&self, record: &Record) {
-        if self.enabled(record.metadata()) && self.file.is_some() {
-            self.file
-                .as_ref()
-                .unwrap()
-                .write_all(format!("{} - {}\n", record.level(), record.args()).as_bytes())
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if let Some(mut file) = self.file.as_ref() {
+            file.write_all(format!("{} - {}\n", record.level(), record.args()).as_bytes())
                 .expect("Could not write to log file");
         }
     }
@@ -32,7 +34,7 @@ impl Logger {
             file: OpenOptions::new()
                 .create(true)
                 .append(true)
-                .open("/var/log/i3rustus.log")
+                .open(LOG_FILE)
                 .ok(),
         })
     }
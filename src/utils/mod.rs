@@ -1,4 +1,26 @@
+pub mod base64;
+pub mod bidi;
+pub mod cache;
+pub mod clock;
+pub mod expression;
 pub mod file;
+pub mod file_watcher;
+pub mod graphemes;
+pub mod idle;
+pub mod intern;
+pub mod json_path;
 pub mod logger;
+#[cfg(target_os = "linux")]
 pub mod macros;
+pub mod platform;
+pub mod posix_tz;
+pub mod secrets;
+pub mod state;
+pub mod stop;
+pub mod suggest;
+pub mod template;
+pub mod text_width;
+pub mod tzif;
+#[cfg(target_os = "linux")]
 pub mod walking_vec;
+pub mod xkb;
@@ -0,0 +1,37 @@
+use libc::{c_void, dlsym};
+use std::ffi::CStr;
+use std::io::Error as IOError;
+
+// Differences between the builds for the C libraries we run on
+// The musl target links statically by default, glibc builds usually link dynamically
+
+// Static binaries cannot load shared libraries, dlopen always fails there
+// Plugins, the idle detection and the keyboard state of X11 are not available then
+pub const DYNAMIC_LOADING: bool = !cfg!(target_feature = "crt-static");
+
+// A symbol of a library that was opened with dlopen
+pub unsafe fn load_symbol(library: *mut c_void, name: &CStr) -> Result<*mut c_void, IOError> {
+    let symbol = dlsym(library, name.as_ptr());
+    if symbol.is_null() {
+        Err(IOError::other(format!("Could not load {:?}", name)))
+    } else {
+        Ok(symbol)
+    }
+}
+
+// e.g. "x86_64 musl, static"
+pub fn build_description() -> String {
+    let c_library = if cfg!(target_env = "musl") {
+        "musl"
+    } else if cfg!(target_env = "gnu") {
+        "glibc"
+    } else {
+        "unknown C library"
+    };
+    format!(
+        "{} {}, {}",
+        std::env::consts::ARCH,
+        c_library,
+        if DYNAMIC_LOADING { "dynamic" } else { "static" }
+    )
+}
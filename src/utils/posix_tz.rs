@@ -0,0 +1,405 @@
+use chrono::{DateTime, Datelike, NaiveDate};
+
+use crate::utils::tzif::LocalTimeType;
+
+// Time zone rules in the format of the TZ environment variable, e.g. "CET-1CEST,M3.5.0,M10.5.0/3"
+// TZif files end with such a rule for the times after their last transition
+// See https://pubs.opengroup.org/onlinepubs/9799919799/basedefs/V1_chap08.html
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+// Transitions happen at 02:00:00 local time if the rule does not say otherwise
+const DEFAULT_TRANSITION_TIME: i64 = 2 * 60 * 60;
+
+// Day of the year on which daylight saving time starts or ends
+#[derive(Debug, Clone, Copy)]
+enum RuleDate {
+    // "Jn", 1 to 365, February 29 is never counted
+    Julian(u16),
+    // "n", 0 to 365, February 29 is counted in leap years
+    ZeroBased(u16),
+    // "Mm.w.d", day d (0 is Sunday) of week w (5 is the last one) of month m
+    MonthWeekDay { month: u32, week: u32, weekday: u32 },
+}
+
+#[derive(Debug, Clone)]
+struct DaylightSaving {
+    local_time_type: LocalTimeType,
+    // Local time of the day in seconds, can be negative or past midnight since version 3
+    start: (RuleDate, i64),
+    end: (RuleDate, i64),
+}
+
+#[derive(Debug, Clone)]
+pub struct PosixTimeZone {
+    standard: LocalTimeType,
+    daylight_saving: Option<DaylightSaving>,
+}
+
+struct Parser<'a> {
+    rest: &'a str,
+}
+
+impl Parser<'_> {
+    fn error(&self) -> String {
+        format!("Invalid time zone rule at \"{}\"", self.rest)
+    }
+
+    fn eat(&mut self, character: char) -> bool {
+        match self.rest.strip_prefix(character) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn number(&mut self) -> Result<i64, String> {
+        let length = self
+            .rest
+            .find(|character: char| !character.is_ascii_digit())
+            .unwrap_or(self.rest.len());
+        let number = self.rest[..length].parse().map_err(|_| self.error())?;
+        self.rest = &self.rest[length..];
+        Ok(number)
+    }
+
+    // "CET", or "<+03>" for names that are not only letters
+    fn name(&mut self) -> Result<String, String> {
+        let name = if self.eat('<') {
+            let end = self.rest.find('>').ok_or_else(|| self.error())?;
+            let name = &self.rest[..end];
+            self.rest = &self.rest[end + 1..];
+            name
+        } else {
+            let length = self
+                .rest
+                .find(|character: char| !character.is_ascii_alphabetic())
+                .unwrap_or(self.rest.len());
+            let name = &self.rest[..length];
+            self.rest = &self.rest[length..];
+            name
+        };
+        if name.len() < 3 {
+            return Err(self.error());
+        }
+        Ok(name.to_string())
+    }
+
+    // "[+-]hh[:mm[:ss]]" in seconds
+    fn time(&mut self) -> Result<i64, String> {
+        let sign = if self.eat('-') {
+            -1
+        } else {
+            self.eat('+');
+            1
+        };
+        // Hours can have any number of digits, so they might not fit into the seconds
+        let mut seconds = self.number()?.checked_mul(60 * 60);
+        if self.eat(':') {
+            let minutes = self.number()?;
+            seconds = seconds.and_then(|seconds| seconds.checked_add(minutes.checked_mul(60)?));
+            if self.eat(':') {
+                let rest = self.number()?;
+                seconds = seconds.and_then(|seconds| seconds.checked_add(rest));
+            }
+        }
+        let seconds = seconds.ok_or_else(|| self.error())?;
+        // Version 3 allows transition times of up to 167 hours
+        if seconds > 167 * 60 * 60 {
+            return Err(self.error());
+        }
+        Ok(sign * seconds)
+    }
+
+    // Offsets are west of UTC in TZ rules, the other way around than in TZif files
+    fn utc_offset(&mut self) -> Result<i32, String> {
+        let offset = -self.time()?;
+        if offset.abs() >= SECONDS_PER_DAY {
+            return Err(self.error());
+        }
+        Ok(offset as i32)
+    }
+
+    fn rule_date(&mut self) -> Result<RuleDate, String> {
+        let date = if self.eat('J') {
+            match self.number()? {
+                day @ 1..=365 => RuleDate::Julian(day as u16),
+                _ => return Err(self.error()),
+            }
+        } else if self.eat('M') {
+            let month = self.number()?;
+            if !self.eat('.') {
+                return Err(self.error());
+            }
+            let week = self.number()?;
+            if !self.eat('.') {
+                return Err(self.error());
+            }
+            let weekday = self.number()?;
+            if !(1..=12).contains(&month) || !(1..=5).contains(&week) || weekday > 6 {
+                return Err(self.error());
+            }
+            RuleDate::MonthWeekDay {
+                month: month as u32,
+                week: week as u32,
+                weekday: weekday as u32,
+            }
+        } else {
+            match self.number()? {
+                day @ 0..=365 => RuleDate::ZeroBased(day as u16),
+                _ => return Err(self.error()),
+            }
+        };
+        Ok(date)
+    }
+
+    fn transition(&mut self) -> Result<(RuleDate, i64), String> {
+        let date = self.rule_date()?;
+        let time = if self.eat('/') {
+            self.time()?
+        } else {
+            DEFAULT_TRANSITION_TIME
+        };
+        Ok((date, time))
+    }
+}
+
+impl RuleDate {
+    // Midnight of the date in the given year, in seconds since the epoch in local time
+    fn local_midnight(&self, year: i32) -> Option<i64> {
+        let date = match *self {
+            RuleDate::Julian(day) => {
+                let is_leap_year = NaiveDate::from_ymd_opt(year, 2, 29).is_some();
+                let ordinal = u32::from(day) + u32::from(is_leap_year && day >= 60);
+                NaiveDate::from_yo_opt(year, ordinal)?
+            }
+            RuleDate::ZeroBased(day) => NaiveDate::from_yo_opt(year, u32::from(day) + 1)?,
+            RuleDate::MonthWeekDay {
+                month,
+                week,
+                weekday,
+            } => {
+                let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+                let first_weekday = first.weekday().num_days_from_sunday();
+                let mut day = 1 + (weekday + 7 - first_weekday) % 7 + (week - 1) * 7;
+                // Week 5 is the last week, which can also be the fourth
+                while NaiveDate::from_ymd_opt(year, month, day).is_none() {
+                    day -= 7;
+                }
+                NaiveDate::from_ymd_opt(year, month, day)?
+            }
+        };
+        Some(date.and_hms_opt(0, 0, 0)?.and_utc().timestamp())
+    }
+}
+
+impl PosixTimeZone {
+    pub fn parse(rule: &str) -> Result<Self, String> {
+        let mut parser = Parser { rest: rule };
+        let standard = LocalTimeType {
+            abbreviation: parser.name()?,
+            utc_offset: parser.utc_offset()?,
+            is_dst: false,
+        };
+        if parser.rest.is_empty() {
+            return Ok(Self {
+                standard,
+                daylight_saving: None,
+            });
+        }
+
+        let abbreviation = parser.name()?;
+        let utc_offset = if parser.rest.is_empty() || parser.rest.starts_with(',') {
+            standard.utc_offset + 60 * 60
+        } else {
+            parser.utc_offset()?
+        };
+        // Without dates, the rules of the US from 2007 on apply
+        let (start, end) = if parser.eat(',') {
+            let start = parser.transition()?;
+            if !parser.eat(',') {
+                return Err(parser.error());
+            }
+            (start, parser.transition()?)
+        } else {
+            (
+                (
+                    RuleDate::MonthWeekDay {
+                        month: 3,
+                        week: 2,
+                        weekday: 0,
+                    },
+                    DEFAULT_TRANSITION_TIME,
+                ),
+                (
+                    RuleDate::MonthWeekDay {
+                        month: 11,
+                        week: 1,
+                        weekday: 0,
+                    },
+                    DEFAULT_TRANSITION_TIME,
+                ),
+            )
+        };
+        if !parser.rest.is_empty() {
+            return Err(parser.error());
+        }
+
+        Ok(Self {
+            standard,
+            daylight_saving: Some(DaylightSaving {
+                local_time_type: LocalTimeType {
+                    utc_offset,
+                    is_dst: true,
+                    abbreviation,
+                },
+                start,
+                end,
+            }),
+        })
+    }
+
+    // Start and end of daylight saving time in the given year, in seconds since the epoch
+    // The start is given in standard time and the end in daylight saving time
+    fn transitions(&self, daylight_saving: &DaylightSaving, year: i32) -> Option<(i64, i64)> {
+        let (start_date, start_time) = daylight_saving.start;
+        let (end_date, end_time) = daylight_saving.end;
+        let start =
+            start_date.local_midnight(year)? + start_time - i64::from(self.standard.utc_offset);
+        let end = end_date.local_midnight(year)? + end_time
+            - i64::from(daylight_saving.local_time_type.utc_offset);
+        Some((start, end))
+    }
+
+    // Local time type at the given seconds since the epoch and the period in which it is in effect
+    pub fn local_time_period(&self, timestamp: i64) -> (&LocalTimeType, i64, i64) {
+        let Some(daylight_saving) = &self.daylight_saving else {
+            return (&self.standard, i64::MIN, i64::MAX);
+        };
+        let Some(year) = DateTime::from_timestamp(timestamp, 0).map(|time| time.year()) else {
+            return (&self.standard, i64::MIN, i64::MAX);
+        };
+
+        // The neighbouring years are needed around new year and for the southern hemisphere,
+        // where daylight saving time starts in one year and ends in the next
+        let mut transitions: Vec<(i64, bool)> = (year - 1..=year + 1)
+            .filter_map(|year| self.transitions(daylight_saving, year))
+            .flat_map(|(start, end)| [(start, true), (end, false)])
+            .collect();
+        transitions.sort_unstable();
+        let passed = transitions.partition_point(|(transition, _)| *transition <= timestamp);
+        let (start, is_dst) = match passed {
+            0 => (i64::MIN, false),
+            passed => transitions[passed - 1],
+        };
+        let end = transitions
+            .get(passed)
+            .map(|(transition, _)| *transition)
+            .unwrap_or(i64::MAX);
+        let local_time_type = if is_dst {
+            &daylight_saving.local_time_type
+        } else {
+            &self.standard
+        };
+        (local_time_type, start, end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn timestamp(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> i64 {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+            .timestamp()
+    }
+
+    #[test]
+    fn southern_hemisphere_has_daylight_saving_time_around_new_year() {
+        let sydney = PosixTimeZone::parse("AEST-10AEDT,M10.1.0,M4.1.0/3").unwrap();
+
+        let (summer, _, _) = sydney.local_time_period(timestamp(2024, 1, 15, 0, 0));
+        assert_eq!(summer.abbreviation, "AEDT");
+        assert_eq!(summer.utc_offset, 11 * 60 * 60);
+        assert!(summer.is_dst);
+        let (winter, _, _) = sydney.local_time_period(timestamp(2024, 7, 15, 0, 0));
+        assert_eq!(winter.abbreviation, "AEST");
+        assert!(!winter.is_dst);
+
+        // 2024-04-07 03:00 AEDT and 2024-10-06 02:00 AEST
+        let (_, start, end) = sydney.local_time_period(timestamp(2024, 7, 15, 0, 0));
+        assert_eq!(start, timestamp(2024, 4, 6, 16, 0));
+        assert_eq!(end, timestamp(2024, 10, 5, 16, 0));
+        let (before_end, _, _) = sydney.local_time_period(timestamp(2024, 4, 6, 16, 0) - 1);
+        assert!(before_end.is_dst);
+    }
+
+    #[test]
+    fn week_five_is_the_last_week_of_the_month() {
+        let berlin = PosixTimeZone::parse("CET-1CEST,M3.5.0,M10.5.0/3").unwrap();
+
+        // March 2024 has five Sundays, the last one is the 31st
+        let (_, start, end) = berlin.local_time_period(timestamp(2024, 6, 1, 0, 0));
+        assert_eq!(start, timestamp(2024, 3, 31, 1, 0));
+        assert_eq!(end, timestamp(2024, 10, 27, 1, 0));
+        // March 2023 has only four, so the fourth one is the last one
+        let (_, start, _) = berlin.local_time_period(timestamp(2023, 6, 1, 0, 0));
+        assert_eq!(start, timestamp(2023, 3, 26, 1, 0));
+    }
+
+    #[test]
+    fn quoted_names_can_contain_signs_and_digits() {
+        let istanbul = PosixTimeZone::parse("<+03>-3").unwrap();
+        let (local_time_type, start, end) = istanbul.local_time_period(0);
+        assert_eq!(local_time_type.abbreviation, "+03");
+        assert_eq!(local_time_type.utc_offset, 3 * 60 * 60);
+        assert_eq!((start, end), (i64::MIN, i64::MAX));
+
+        let tehran = PosixTimeZone::parse("<+0330>-3:30").unwrap();
+        assert_eq!(tehran.standard.utc_offset, 3 * 60 * 60 + 30 * 60);
+
+        // Version 3 allows negative transition times, e.g. America/Nuuk
+        let nuuk = PosixTimeZone::parse("<-02>2<-01>,M3.5.0/-1,M10.5.0/0").unwrap();
+        let (summer, start, _) = nuuk.local_time_period(timestamp(2024, 6, 1, 0, 0));
+        assert_eq!(summer.abbreviation, "-01");
+        assert_eq!(start, timestamp(2024, 3, 31, 1, 0));
+    }
+
+    #[test]
+    fn daylight_saving_time_is_one_hour_ahead_by_default() {
+        let new_york = PosixTimeZone::parse("EST5EDT").unwrap();
+        let (summer, start, end) = new_york.local_time_period(timestamp(2024, 7, 1, 0, 0));
+        assert_eq!(summer.utc_offset, -4 * 60 * 60);
+        // The rules of the US from 2007 on
+        assert_eq!(start, timestamp(2024, 3, 10, 7, 0));
+        assert_eq!(end, timestamp(2024, 11, 3, 6, 0));
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        for rule in [
+            "",
+            "CE-1",
+            "CET",
+            "CET-1CEST,M3.5.0",
+            "CET-1CEST,M13.5.0,M10.5.0",
+            "CET-1CEST,M3.6.0,M10.5.0",
+            "CET-1CEST,M3.5.7,M10.5.0",
+            "CET-1CEST,J0,J100",
+            "CET-1CEST,0,366",
+            "CET-1CEST,M3.5.0,M10.5.0/168",
+            "CET-1 trailing",
+            "<+03-3",
+            "CET-24",
+            "CET-99999999999999999999",
+            "CET-9223372036854775807",
+            "CET-1:9223372036854775807",
+            "CET-1CEST,M3.5.0/9223372036854775807,M10.5.0",
+        ] {
+            assert!(PosixTimeZone::parse(rule).is_err(), "{} was accepted", rule);
+        }
+    }
+}
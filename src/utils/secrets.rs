@@ -0,0 +1,33 @@
+use std::env;
+use std::fs::read_to_string;
+use std::io::Error as IOError;
+use std::process::Command;
+
+// Resolve a secret (e.g. an API token) from the config, so it doesn't have to be written in plain text
+//
+// "env:NAME" reads an environment variable, "file:PATH" reads a file and
+// "command:COMMAND" runs a shell command (e.g. "command:pass show home-assistant")
+// Anything else is used as it is
+pub fn resolve_secret(reference: &str) -> Result<String, IOError> {
+    if let Some(name) = reference.strip_prefix("env:") {
+        env::var(name).map_err(|error| IOError::other(format!("{}: {}", name, error)))
+    } else if let Some(path) = reference.strip_prefix("file:") {
+        Ok(read_to_string(path)?.trim().to_string())
+    } else if let Some(command) = reference.strip_prefix("command:") {
+        let output = Command::new("sh").args(["-c", command]).output()?;
+        if !output.status.success() {
+            return Err(IOError::other(format!(
+                "\"{}\" failed with {}",
+                command, output.status
+            )));
+        }
+        // Password managers print the secret on the first line
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_string())
+    } else {
+        Ok(reference.to_string())
+    }
+}
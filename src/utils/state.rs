@@ -0,0 +1,32 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::env;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::io::Error as IOError;
+use std::path::PathBuf;
+
+// Directory in which widgets persist their state between restarts
+// Follows the XDG base directory specification
+pub fn state_directory() -> PathBuf {
+    let directory = match env::var("XDG_STATE_HOME") {
+        Ok(state_home) if !state_home.is_empty() => PathBuf::from(state_home),
+        _ => PathBuf::from(env::var("HOME").unwrap_or_default()).join(".local/state"),
+    };
+
+    directory.join("i3rustus")
+}
+
+// Load the state of a widget, returns None if there is no (valid) state yet
+pub fn load_state<T: DeserializeOwned>(widget_name: &str) -> Option<T> {
+    let content = read_to_string(state_directory().join(format!("{}.json", widget_name))).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+pub fn save_state<T: Serialize>(widget_name: &str, state: &T) -> Result<(), IOError> {
+    let directory = state_directory();
+    create_dir_all(&directory)?;
+    write(
+        directory.join(format!("{}.json", widget_name)),
+        serde_json::to_string(state)?,
+    )
+}
@@ -0,0 +1,69 @@
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+// Background threads of widgets (e.g. polling a server) have to stop together with their
+// widget, which is dropped when the config is reloaded or the widget is restarted after a panic
+//
+// The widget keeps the StopHandle and stops the thread when it is dropped,
+// the thread gets the StopSignal
+// Stopping wakes up a sleeping thread and shuts down the connection it registered,
+// so a thread that waits for a message stops as well
+#[derive(Default)]
+struct State {
+    stopped: bool,
+    // Interrupts a blocking read of the thread, e.g. by shutting down its socket
+    interrupt: Option<Box<dyn FnOnce() + Send>>,
+}
+
+#[derive(Clone)]
+pub struct StopSignal(Arc<(Mutex<State>, Condvar)>);
+
+pub struct StopHandle(StopSignal);
+
+pub fn stop_signal() -> (StopHandle, StopSignal) {
+    let signal = StopSignal(Arc::new((Mutex::new(State::default()), Condvar::new())));
+    (StopHandle(signal.clone()), signal)
+}
+
+impl StopSignal {
+    pub fn is_stopped(&self) -> bool {
+        self.0 .0.lock().unwrap().stopped
+    }
+
+    // Sleep until the duration passed or the thread should stop
+    // Returns whether the thread should keep running
+    pub fn sleep(&self, duration: Duration) -> bool {
+        let (state, condvar) = &*self.0;
+        let state = condvar
+            .wait_timeout_while(state.lock().unwrap(), duration, |state| !state.stopped)
+            .unwrap()
+            .0;
+        !state.stopped
+    }
+
+    // Register how a blocking read of the thread is interrupted, replacing the previous one
+    // (e.g. after a reconnect). Returns false if the thread should already stop
+    pub fn on_stop(&self, interrupt: impl FnOnce() + Send + 'static) -> bool {
+        let mut state = self.0 .0.lock().unwrap();
+        if state.stopped {
+            return false;
+        }
+        state.interrupt = Some(Box::new(interrupt));
+        true
+    }
+}
+
+impl StopHandle {
+    pub fn stop(&self) {
+        let (state, condvar) = &*self.0 .0;
+        let interrupt = {
+            let mut state = state.lock().unwrap();
+            state.stopped = true;
+            state.interrupt.take()
+        };
+        condvar.notify_all();
+        if let Some(interrupt) = interrupt {
+            interrupt();
+        }
+    }
+}
@@ -0,0 +1,57 @@
+// Number of single character insertions, deletions, substitutions and swaps of two
+// neighbouring characters between two words (optimal string alignment distance)
+// See https://en.wikipedia.org/wiki/Damerau%E2%80%93Levenshtein_distance
+fn edit_distance(left: &str, right: &str) -> usize {
+    let left: Vec<char> = left.chars().collect();
+    let right: Vec<char> = right.chars().collect();
+    // distances[i][j] is the distance between the first i characters of left and j of right
+    let mut distances = vec![vec![0; right.len() + 1]; left.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+    for i in 1..=left.len() {
+        for j in 1..=right.len() {
+            let cost = usize::from(left[i - 1] != right[j - 1]);
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && left[i - 1] == right[j - 2] && left[i - 2] == right[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+
+    distances[left.len()][right.len()]
+}
+
+// The candidate that is most likely meant by a misspelled word
+// Candidates that differ in more than a third of the characters are not suggested
+pub fn closest<'a, I>(word: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let max_distance = (word.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(word, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+// Add a suggestion to a serde error about an unknown key or value, e.g.
+// "unknown field `intervall`, expected one of `format`, `interval`"
+// Returns None for other errors
+pub fn suggest_for_serde_error(message: &str) -> Option<String> {
+    let rest = message
+        .strip_prefix("unknown field `")
+        .or_else(|| message.strip_prefix("unknown variant `"))?;
+    let (word, expected) = rest.split_once('`')?;
+    // Every other part is one of the expected names
+    let candidates = expected.split('`').skip(1).step_by(2);
+    closest(word, candidates).map(|candidate| format!("did you mean `{}`?", candidate))
+}
@@ -0,0 +1,195 @@
+use crate::i3_status::CONFIG;
+use crate::utils::bidi::isolate;
+use crate::utils::graphemes::truncate;
+use crate::variables::variable;
+use std::fmt::Display;
+
+// Value of a placeholder
+// Numbers can be rounded with a precision, e.g. "{percent:.0}"
+// Texts are truncated to the precision, e.g. "{title:.30}" keeps at most 30 characters,
+// even if they look like a number (e.g. a window title "1234")
+#[derive(Clone)]
+pub enum Placeholder {
+    Text(String),
+    // The number and how it is shown without a precision
+    Number(f64, String),
+}
+
+impl Placeholder {
+    // A text that is a number if it can be read as one, for values that are numbers but come
+    // as text (e.g. MQTT payloads or variables)
+    pub fn parsed(text: String) -> Self {
+        match text.trim().parse::<f64>() {
+            Ok(number) if number.is_finite() => Placeholder::Number(number, text),
+            _ => Placeholder::Text(text),
+        }
+    }
+}
+
+impl From<String> for Placeholder {
+    fn from(text: String) -> Self {
+        Placeholder::Text(text)
+    }
+}
+
+impl From<&str> for Placeholder {
+    fn from(text: &str) -> Self {
+        Placeholder::Text(text.to_string())
+    }
+}
+
+// A number that is shown as it is written by Display, unless a precision is given
+pub fn number<T>(value: T) -> Placeholder
+where
+    T: Into<f64> + Display,
+{
+    let text = value.to_string();
+    Placeholder::Number(value.into(), text)
+}
+
+// Replace placeholders like "{used}" in a template with their values
+//
+// "{{" and "}}" are written as literal braces
+// "{var:<name>}" is the value of a variable (see variables.rs), empty while it is not set
+// Variables that can be read as a number are rounded with a precision
+pub fn render(template: &str, placeholders: &[(&str, Placeholder)]) -> String {
+    let mut output = String::new();
+    let mut characters = template.chars().peekable();
+    while let Some(character) = characters.next() {
+        match character {
+            '{' if characters.peek() == Some(&'{') => {
+                characters.next();
+                output.push('{');
+            }
+            '}' if characters.peek() == Some(&'}') => {
+                characters.next();
+                output.push('}');
+            }
+            '{' => {
+                let placeholder: String = characters.by_ref().take_while(|c| *c != '}').collect();
+                output += &render_placeholder(&placeholder, placeholders);
+            }
+            _ => output.push(character),
+        }
+    }
+
+    output
+}
+
+// Values often come from outside (e.g. window titles), so they may be written right to left
+fn isolate_if_enabled(value: String) -> String {
+    if CONFIG.isolate_right_to_left_text() {
+        isolate(&value)
+    } else {
+        value
+    }
+}
+
+fn render_placeholder(placeholder: &str, placeholders: &[(&str, Placeholder)]) -> String {
+    let (name, precision) = match placeholder.split_once(":.") {
+        Some((name, precision)) => (name, precision.parse::<usize>().ok()),
+        None => (placeholder, None),
+    };
+
+    let variable_value;
+    let value = match name.strip_prefix("var:") {
+        Some(variable_name) => {
+            variable_value = Placeholder::parsed(variable(variable_name).unwrap_or_default());
+            &variable_value
+        }
+        None => match placeholders.iter().find(|(key, _)| *key == name) {
+            Some((_, value)) => value,
+            // Unknown placeholders are kept, so typos are visible in the bar
+            None => return format!("{{{}}}", placeholder),
+        },
+    };
+    match (value, precision) {
+        (Placeholder::Number(number, _), Some(precision)) => {
+            format!("{:.*}", precision, number)
+        }
+        (Placeholder::Number(_, text), None) => text.clone(),
+        (Placeholder::Text(text), Some(precision)) => isolate_if_enabled(truncate(text, precision)),
+        (Placeholder::Text(text), None) => isolate_if_enabled(text.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::variables::set_variable;
+
+    #[test]
+    fn placeholders_are_replaced() {
+        let placeholders = [("used", "3.2 GiB".into()), ("total", "16 GiB".into())];
+        assert_eq!(
+            render("{used} / {total}", &placeholders),
+            "3.2 GiB / 16 GiB"
+        );
+        assert_eq!(render("no placeholders", &placeholders), "no placeholders");
+        // The same placeholder can be used several times
+        assert_eq!(render("{used}{used}", &placeholders), "3.2 GiB3.2 GiB");
+    }
+
+    #[test]
+    fn unknown_placeholders_are_kept() {
+        assert_eq!(render("{usd} GiB", &[("used", number(3))]), "{usd} GiB");
+        assert_eq!(render("{used:.1} GiB", &[]), "{used:.1} GiB");
+        // An unclosed placeholder takes the rest of the template
+        assert_eq!(render("{used", &[("used", number(3))]), "3");
+    }
+
+    #[test]
+    fn double_braces_are_literal_braces() {
+        assert_eq!(
+            render("{{used}} {used}", &[("used", number(3))]),
+            "{used} 3"
+        );
+        assert_eq!(render("}} {{", &[]), "} {");
+    }
+
+    #[test]
+    fn numbers_are_rounded_to_the_precision() {
+        let placeholders = [("percent", number(41.666_f32)), ("count", number(7))];
+        assert_eq!(render("{percent:.0}%", &placeholders), "42%");
+        assert_eq!(render("{percent:.2}", &placeholders), "41.67");
+        assert_eq!(render("{count}", &placeholders), "7");
+        // An invalid precision shows the number as it is
+        assert_eq!(render("{count:.x}", &placeholders), "7");
+    }
+
+    #[test]
+    fn texts_are_truncated_to_the_precision() {
+        let placeholders = [("title", "1234567".into()), ("short", "abc".into())];
+        // Texts that look like numbers are still texts
+        assert_eq!(render("{title:.3}", &placeholders), "12…");
+        assert_eq!(render("{short:.3}", &placeholders), "abc");
+        assert_eq!(render("{title}", &placeholders), "1234567");
+    }
+
+    #[test]
+    fn parsed_texts_are_numbers_if_they_can_be() {
+        let placeholders = [
+            ("payload", Placeholder::parsed(String::from("21.54"))),
+            ("state", Placeholder::parsed(String::from("unavailable"))),
+            ("nan", Placeholder::parsed(String::from("NaN"))),
+        ];
+        assert_eq!(render("{payload:.1}", &placeholders), "21.5");
+        assert_eq!(render("{payload}", &placeholders), "21.54");
+        // The ellipsis counts to the precision
+        assert_eq!(render("{state:.3}", &placeholders), "un…");
+        assert_eq!(render("{nan:.2}", &placeholders), "N…");
+    }
+
+    #[test]
+    fn right_to_left_texts_are_isolated() {
+        let placeholders = [("title", "שלום".into())];
+        assert_eq!(render("{title} 1", &placeholders), "\u{2068}שלום\u{2069} 1");
+    }
+
+    #[test]
+    fn variables_are_read_by_name() {
+        set_variable("template_test.value", String::from("2.345"));
+        assert_eq!(render("{var:template_test.value:.1}", &[]), "2.3");
+        assert_eq!(render("[{var:template_test.missing}]", &[]), "[]");
+    }
+}
@@ -0,0 +1,39 @@
+// Approximate advance width of a character in em, modeled after DejaVu Sans
+// Good enough to keep a block from jumping around, not to lay out text exactly
+fn glyph_width(character: char) -> f32 {
+    match character {
+        // Combining marks, joiners, variation selectors and skin tone modifiers are drawn
+        // on top of the previous character, directional isolates are not drawn at all
+        '\u{0300}'..='\u{036F}'
+        | '\u{200B}'..='\u{200F}'
+        | '\u{2066}'..='\u{2069}'
+        | '\u{FE00}'..='\u{FE0F}'
+        | '\u{1F3FB}'..='\u{1F3FF}' => 0.0,
+        ' ' | '.' | ',' | ':' | ';' | '!' | '|' | '\'' | 'i' | 'j' | 'l' => 0.32,
+        'f' | 'r' | 't' | 'I' | '(' | ')' | '[' | ']' | '/' | '\\' | '-' => 0.4,
+        '0'..='9' => 0.64,
+        'm' | 'w' | 'M' | 'W' | '%' | '@' => 0.95,
+        'A'..='Z' => 0.7,
+        // CJK, Hangul and fullwidth forms
+        '\u{1100}'..='\u{115F}'
+        | '\u{2E80}'..='\u{A4CF}'
+        | '\u{AC00}'..='\u{D7A3}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{FE30}'..='\u{FE4F}'
+        | '\u{FF00}'..='\u{FF60}'
+        | '\u{FFE0}'..='\u{FFE6}'
+        // Symbols and emoji
+        | '\u{2600}'..='\u{27BF}'
+        | '\u{1F300}'..='\u{1FAFF}'
+        // Private use area, where icon fonts (e.g. Nerd Fonts) put their glyphs
+        | '\u{E000}'..='\u{F8FF}'
+        | '\u{F0000}'..='\u{10FFFF}' => 1.0,
+        _ => 0.6,
+    }
+}
+
+// Estimated width of a text without markup in pixels
+pub fn text_width(text: &str, font_size: f32) -> u32 {
+    let width: f32 = text.chars().map(glyph_width).sum();
+    (width * font_size).ceil() as u32
+}
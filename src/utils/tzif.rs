@@ -0,0 +1,422 @@
+use libc::{mmap, munmap, MAP_FAILED, MAP_PRIVATE, PROT_READ};
+use std::fs::File;
+use std::io::Error as IOError;
+use std::ops::Range;
+use std::os::fd::AsRawFd;
+use std::path::Path;
+use std::ptr::null_mut;
+use std::slice;
+
+use crate::utils::posix_tz::PosixTimeZone;
+
+// Time zone information files as found in /usr/share/zoneinfo
+// See https://www.rfc-editor.org/rfc/rfc8536 or tzfile(5)
+
+// A file mapped into memory, read only
+// Zone files are parsed once into their own structures, mapping them saves copying the file
+struct MappedFile {
+    address: *mut libc::c_void,
+    length: usize,
+}
+
+impl MappedFile {
+    fn open(path: &Path) -> Result<Self, IOError> {
+        let file = File::open(path)?;
+        let length = file.metadata()?.len() as usize;
+        // Empty files cannot be mapped
+        if length == 0 {
+            return Ok(Self {
+                address: null_mut(),
+                length,
+            });
+        }
+        // The mapping stays valid after the file is closed
+        let address = unsafe {
+            mmap(
+                null_mut(),
+                length,
+                PROT_READ,
+                MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if address == MAP_FAILED {
+            return Err(IOError::last_os_error());
+        }
+
+        Ok(Self { address, length })
+    }
+
+    fn data(&self) -> &[u8] {
+        if self.length == 0 {
+            return &[];
+        }
+        unsafe { slice::from_raw_parts(self.address as *const u8, self.length) }
+    }
+}
+
+impl Drop for MappedFile {
+    fn drop(&mut self) {
+        if self.length > 0 {
+            unsafe { munmap(self.address, self.length) };
+        }
+    }
+}
+
+const MAGIC: &[u8] = b"TZif";
+// Offsets of more than a day are invalid, FixedOffset would reject them too
+const MAX_UTC_OFFSET: i32 = 24 * 60 * 60 - 1;
+
+// Offset to UTC that is used from one transition to the next
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalTimeType {
+    // Seconds east of UTC
+    pub utc_offset: i32,
+    pub is_dst: bool,
+    // e.g. "CEST"
+    pub abbreviation: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct TimeZoneInfo {
+    // Seconds since the epoch at which the local time type changes, ascending
+    transitions: Vec<i64>,
+    // Index into types for every transition
+    transition_types: Vec<usize>,
+    types: Vec<LocalTimeType>,
+    // Rule for the times after the last transition, only in files of version 2 and newer
+    rule: Option<PosixTimeZone>,
+}
+
+struct Header {
+    version: u8,
+    isutcnt: usize,
+    isstdcnt: usize,
+    leapcnt: usize,
+    timecnt: usize,
+    typecnt: usize,
+    charcnt: usize,
+}
+
+// Reads big endian values from the file and fails instead of panicking on truncated files
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .position
+            .checked_add(length)
+            .and_then(|end| self.data.get(self.position..end))
+            .ok_or_else(|| String::from("The file is truncated"))?;
+        self.position += length;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(self.u32()? as i32)
+    }
+
+    fn i64(&mut self) -> Result<i64, String> {
+        let bytes = self.take(8)?;
+        let mut value = [0; 8];
+        value.copy_from_slice(bytes);
+        Ok(i64::from_be_bytes(value))
+    }
+
+    fn header(&mut self) -> Result<Header, String> {
+        if self.take(MAGIC.len())? != MAGIC {
+            return Err(String::from("Not a TZif file"));
+        }
+        let version = self.u8()?;
+        self.take(15)?;
+        let mut count = || self.u32().map(|count| count as usize);
+        Ok(Header {
+            version,
+            isutcnt: count()?,
+            isstdcnt: count()?,
+            leapcnt: count()?,
+            timecnt: count()?,
+            typecnt: count()?,
+            charcnt: count()?,
+        })
+    }
+}
+
+impl Header {
+    // Length of the data block that follows the header, times are 4 bytes in version 1
+    fn data_length(&self, time_size: usize) -> usize {
+        self.timecnt * time_size
+            + self.timecnt
+            + self.typecnt * 6
+            + self.charcnt
+            + self.leapcnt * (time_size + 4)
+            + self.isstdcnt
+            + self.isutcnt
+    }
+}
+
+impl TimeZoneInfo {
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader { data, position: 0 };
+        let mut header = reader.header()?;
+        if header.version >= b'2' {
+            // The version 1 block only has 32 bit times, the same data follows with 64 bit times
+            reader.take(header.data_length(4))?;
+            header = reader.header()?;
+            let mut info = Self::parse_data(&mut reader, &header, 8)?;
+            // Leap seconds and the standard / UT indicators are not needed
+            reader.take(header.leapcnt * 12 + header.isstdcnt + header.isutcnt)?;
+            info.rule = Self::parse_footer(&reader.data[reader.position..])?;
+            Ok(info)
+        } else {
+            Self::parse_data(&mut reader, &header, 4)
+        }
+    }
+
+    fn parse_data(reader: &mut Reader, header: &Header, time_size: usize) -> Result<Self, String> {
+        if header.typecnt == 0 {
+            return Err(String::from("The file has no local time types"));
+        }
+        // The counts are checked against the file before memory is reserved for them
+        if header.data_length(time_size) > reader.data.len() - reader.position {
+            return Err(String::from("The file is truncated"));
+        }
+        let mut transitions = Vec::with_capacity(header.timecnt);
+        for _ in 0..header.timecnt {
+            let transition = if time_size == 8 {
+                reader.i64()?
+            } else {
+                i64::from(reader.i32()?)
+            };
+            transitions.push(transition);
+        }
+        let mut transition_types = Vec::with_capacity(header.timecnt);
+        for _ in 0..header.timecnt {
+            let index = reader.u8()? as usize;
+            if index >= header.typecnt {
+                return Err(format!("Invalid local time type {}", index));
+            }
+            transition_types.push(index);
+        }
+        let mut records = Vec::with_capacity(header.typecnt);
+        for _ in 0..header.typecnt {
+            let utc_offset = reader.i32()?;
+            if !(-MAX_UTC_OFFSET..=MAX_UTC_OFFSET).contains(&utc_offset) {
+                return Err(format!("Invalid UTC offset {}", utc_offset));
+            }
+            records.push((utc_offset, reader.u8()? != 0, reader.u8()? as usize));
+        }
+        let abbreviations = reader.take(header.charcnt)?;
+        let types = records
+            .into_iter()
+            .map(|(utc_offset, is_dst, index)| {
+                // Abbreviations are NUL terminated strings in one block
+                let abbreviation = abbreviations
+                    .get(index..)
+                    .and_then(|rest| rest.split(|byte| *byte == 0).next())
+                    .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+                    .unwrap_or_default();
+                LocalTimeType {
+                    utc_offset,
+                    is_dst,
+                    abbreviation,
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            transitions,
+            transition_types,
+            types,
+            rule: None,
+        })
+    }
+
+    // The footer is a TZ rule between two newlines, it can be empty if there is no rule
+    fn parse_footer(footer: &[u8]) -> Result<Option<PosixTimeZone>, String> {
+        let footer = std::str::from_utf8(footer)
+            .ok()
+            .and_then(|footer| footer.strip_prefix('\n'))
+            .and_then(|footer| footer.split_once('\n'))
+            .map(|(rule, _)| rule)
+            .ok_or_else(|| String::from("Invalid footer"))?;
+        if footer.is_empty() {
+            return Ok(None);
+        }
+        PosixTimeZone::parse(footer).map(Some)
+    }
+
+    // Zone that only follows a TZ rule, e.g. TZ="CET-1CEST,M3.5.0,M10.5.0/3"
+    pub fn from_rule(rule: &str) -> Result<Self, String> {
+        Ok(Self {
+            transitions: Vec::new(),
+            transition_types: Vec::new(),
+            types: Vec::new(),
+            rule: Some(PosixTimeZone::parse(rule)?),
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file =
+            MappedFile::open(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+        Self::parse(file.data()).map_err(|error| format!("{}: {}", path.display(), error))
+    }
+
+    // Local time type at the given seconds since the epoch and the seconds since the epoch
+    // from which until before which it is in effect
+    // Before the first transition the first type is used, as RFC 8536 says,
+    // after the last transition the rule of the footer applies, or the last type stays in effect
+    pub fn local_time_period(&self, timestamp: i64) -> (&LocalTimeType, Range<i64>) {
+        // Number of transitions that already happened
+        let passed = self
+            .transitions
+            .partition_point(|transition| *transition <= timestamp);
+        if let Some(rule) = self
+            .rule
+            .as_ref()
+            .filter(|_| passed == self.transitions.len())
+        {
+            let (local_time_type, start, end) = rule.local_time_period(timestamp);
+            let last_transition = self.transitions.last().copied().unwrap_or(i64::MIN);
+            return (local_time_type, start.max(last_transition)..end);
+        }
+        let (index, start) = match passed {
+            0 => (0, i64::MIN),
+            passed => (
+                self.transition_types[passed - 1],
+                self.transitions[passed - 1],
+            ),
+        };
+        let end = self.transitions.get(passed).copied().unwrap_or(i64::MAX);
+        (&self.types[index], start..end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Header for the data below with timecnt transitions
+    fn header(version: u8, timecnt: u32) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(version);
+        bytes.extend([0; 15]);
+        // isutcnt, isstdcnt, leapcnt, timecnt, typecnt, charcnt
+        for count in [0, 0, 0, timecnt, 2, 9] {
+            bytes.extend(u32::to_be_bytes(count));
+        }
+        bytes
+    }
+
+    // CET until 1000000000 and CEST after it, with 32 or 64 bit times
+    fn data(time_size: usize) -> Vec<u8> {
+        let mut bytes = match time_size {
+            8 => 1_000_000_000i64.to_be_bytes().to_vec(),
+            _ => 1_000_000_000i32.to_be_bytes().to_vec(),
+        };
+        bytes.push(1);
+        bytes.extend(3600i32.to_be_bytes());
+        bytes.extend([0, 0]);
+        bytes.extend(7200i32.to_be_bytes());
+        bytes.extend([1, 4]);
+        bytes.extend(b"CET\0CEST\0");
+        bytes
+    }
+
+    fn version_1() -> Vec<u8> {
+        let mut bytes = header(0, 1);
+        bytes.extend(data(4));
+        bytes
+    }
+
+    fn version_2() -> Vec<u8> {
+        let mut bytes = header(b'2', 1);
+        bytes.extend(data(4));
+        bytes.extend(header(b'2', 1));
+        bytes.extend(data(8));
+        bytes.extend(b"\nCET-1CEST,M3.5.0,M10.5.0/3\n");
+        bytes
+    }
+
+    #[test]
+    fn version_1_files_are_read() {
+        let info = TimeZoneInfo::parse(&version_1()).unwrap();
+        let (before, range) = info.local_time_period(0);
+        assert_eq!(before.abbreviation, "CET");
+        assert_eq!(range, i64::MIN..1_000_000_000);
+        let (after, range) = info.local_time_period(1_000_000_000);
+        assert_eq!(after.abbreviation, "CEST");
+        assert_eq!(after.utc_offset, 7200);
+        assert!(after.is_dst);
+        assert_eq!(range, 1_000_000_000..i64::MAX);
+    }
+
+    #[test]
+    fn the_footer_applies_after_the_last_transition() {
+        let info = TimeZoneInfo::parse(&version_2()).unwrap();
+        // 2024-01-15, 2024-07-15
+        let (winter, _) = info.local_time_period(1_705_276_800);
+        assert_eq!(winter.abbreviation, "CET");
+        let (summer, range) = info.local_time_period(1_721_001_600);
+        assert_eq!(summer.abbreviation, "CEST");
+        // 2024-03-31 01:00 UTC until 2024-10-27 01:00 UTC
+        assert_eq!(range, 1_711_846_800..1_729_990_800);
+    }
+
+    #[test]
+    fn truncated_files_are_rejected() {
+        for file in [version_1(), version_2()] {
+            for length in 0..file.len() {
+                assert!(
+                    TimeZoneInfo::parse(&file[..length]).is_err(),
+                    "{} of {} bytes were accepted",
+                    length,
+                    file.len()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn counts_larger_than_the_file_are_rejected() {
+        let mut file = header(0, u32::MAX);
+        file.extend(data(4));
+        assert_eq!(
+            TimeZoneInfo::parse(&file).unwrap_err(),
+            "The file is truncated"
+        );
+    }
+
+    #[test]
+    fn invalid_files_are_rejected() {
+        let mut not_tzif = version_1();
+        not_tzif[0] = b'X';
+        assert_eq!(
+            TimeZoneInfo::parse(&not_tzif).unwrap_err(),
+            "Not a TZif file"
+        );
+
+        // The transition refers to a third local time type
+        let mut invalid_type = version_1();
+        invalid_type[header(0, 1).len() + 4] = 2;
+        assert!(TimeZoneInfo::parse(&invalid_type).is_err());
+
+        let mut invalid_footer = version_2();
+        invalid_footer.truncate(invalid_footer.len() - 1);
+        invalid_footer.extend(b"x\n");
+        assert!(TimeZoneInfo::parse(&invalid_footer).is_err());
+    }
+}
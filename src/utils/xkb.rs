@@ -0,0 +1,199 @@
+use libc::{c_char, c_int, c_long, c_uchar, c_uint, c_ulong, c_ushort, c_void, dlopen, RTLD_LAZY};
+use std::ffi::CStr;
+use std::io::Error as IOError;
+use std::ptr;
+
+use crate::utils::platform::{load_symbol, DYNAMIC_LOADING};
+
+// See X11/XKBlib.h and X11/extensions/XKB.h
+const XKB_USE_CORE_KEYBOARD: c_uint = 0x100;
+const XKB_STATE_NOTIFY: c_uint = 2;
+const XKB_INDICATOR_STATE_NOTIFY: c_uint = 4;
+const XKB_MODIFIER_LATCH_MASK: c_ulong = 1 << 2;
+const XKB_GROUP_STATE_MASK: c_ulong = 1 << 4;
+const XKB_GROUP_LATCH_MASK: c_ulong = 1 << 6;
+const XKB_ALL_INDICATORS_MASK: c_ulong = 0xFFFF_FFFF;
+// Name of the indicator (LED) that shows a started compose sequence
+const COMPOSE_INDICATOR: &CStr = c"Compose";
+
+// See XkbStateRec in X11/XKBlib.h
+#[repr(C)]
+#[derive(Default)]
+struct XkbStateRec {
+    group: c_uchar,
+    locked_group: c_uchar,
+    base_group: c_ushort,
+    latched_group: c_ushort,
+    mods: c_uchar,
+    base_mods: c_uchar,
+    latched_mods: c_uchar,
+    locked_mods: c_uchar,
+    compat_state: c_uchar,
+    grab_mods: c_uchar,
+    compat_grab_mods: c_uchar,
+    lookup_mods: c_uchar,
+    compat_lookup_mods: c_uchar,
+    ptr_buttons: c_ushort,
+}
+
+// XEvent is a union of all events, padded to 24 longs
+type XEvent = [c_long; 24];
+
+type XOpenDisplay = unsafe extern "C" fn(*const c_char) -> *mut c_void;
+type XCloseDisplay = unsafe extern "C" fn(*mut c_void) -> c_int;
+type XInternAtom = unsafe extern "C" fn(*mut c_void, *const c_char, c_int) -> c_ulong;
+type XNextEvent = unsafe extern "C" fn(*mut c_void, *mut XEvent) -> c_int;
+type XkbUseExtension = unsafe extern "C" fn(*mut c_void, *mut c_int, *mut c_int) -> c_int;
+type XkbGetState = unsafe extern "C" fn(*mut c_void, c_uint, *mut XkbStateRec) -> c_int;
+type XkbGetNamedIndicator = unsafe extern "C" fn(
+    *mut c_void,
+    c_ulong,
+    *mut c_int,
+    *mut c_int,
+    *mut c_void,
+    *mut c_int,
+) -> c_int;
+type XkbSelectEventDetails =
+    unsafe extern "C" fn(*mut c_void, c_uint, c_uint, c_ulong, c_ulong) -> c_int;
+
+// State of the core keyboard as the X server sees it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyboardState {
+    // Selected layout group, 0 is the first layout (e.g. "us" of "us,de")
+    pub group: u8,
+    // The Compose LED is on or a modifier or group is latched, so the next key is combined
+    // Dead keys that only the input method of a client knows about can't be seen here
+    pub composing: bool,
+}
+
+// Queries the keyboard state through the XKB extension of the X server
+//
+// libX11 is loaded at runtime like for the idle detection, see utils/idle.rs
+pub struct Xkb {
+    display: *mut c_void,
+    compose_indicator: c_ulong,
+    get_state: XkbGetState,
+    get_named_indicator: XkbGetNamedIndicator,
+    select_event_details: XkbSelectEventDetails,
+    next_event: XNextEvent,
+    close_display: XCloseDisplay,
+}
+
+// The display connection is only ever used by the thread that owns it, see IdleMonitor
+unsafe impl Send for Xkb {}
+
+impl Xkb {
+    pub fn new() -> Result<Self, IOError> {
+        if !DYNAMIC_LOADING {
+            return Err(IOError::other("A static build cannot load libX11"));
+        }
+        unsafe {
+            let x11 = dlopen(c"libX11.so.6".as_ptr(), RTLD_LAZY);
+            if x11.is_null() {
+                return Err(IOError::other("Could not load libX11"));
+            }
+
+            let open_display: XOpenDisplay =
+                std::mem::transmute(load_symbol(x11, c"XOpenDisplay")?);
+            let close_display: XCloseDisplay =
+                std::mem::transmute(load_symbol(x11, c"XCloseDisplay")?);
+            let intern_atom: XInternAtom = std::mem::transmute(load_symbol(x11, c"XInternAtom")?);
+            let next_event: XNextEvent = std::mem::transmute(load_symbol(x11, c"XNextEvent")?);
+            let use_extension: XkbUseExtension =
+                std::mem::transmute(load_symbol(x11, c"XkbUseExtension")?);
+            let get_state: XkbGetState = std::mem::transmute(load_symbol(x11, c"XkbGetState")?);
+            let get_named_indicator: XkbGetNamedIndicator =
+                std::mem::transmute(load_symbol(x11, c"XkbGetNamedIndicator")?);
+            let select_event_details: XkbSelectEventDetails =
+                std::mem::transmute(load_symbol(x11, c"XkbSelectEventDetails")?);
+
+            // NULL means that the DISPLAY environment variable is used
+            let display = open_display(ptr::null());
+            if display.is_null() {
+                return Err(IOError::other("Could not open X display"));
+            }
+            // Version 1.0 of XKB, which every X server since X11R6.1 has
+            let (mut major, mut minor) = (1, 0);
+            if use_extension(display, &mut major, &mut minor) == 0 {
+                close_display(display);
+                return Err(IOError::other("The X server does not support XKB"));
+            }
+
+            Ok(Self {
+                display,
+                compose_indicator: intern_atom(display, COMPOSE_INDICATOR.as_ptr(), 0),
+                get_state,
+                get_named_indicator,
+                select_event_details,
+                next_event,
+                close_display,
+            })
+        }
+    }
+
+    pub fn state(&self) -> Result<KeyboardState, IOError> {
+        let mut state = XkbStateRec::default();
+        let mut compose_lit = 0;
+        unsafe {
+            if (self.get_state)(self.display, XKB_USE_CORE_KEYBOARD, &mut state) != 0 {
+                return Err(IOError::other("XkbGetState failed"));
+            }
+            // Keymaps without a Compose indicator simply never light it
+            (self.get_named_indicator)(
+                self.display,
+                self.compose_indicator,
+                ptr::null_mut(),
+                &mut compose_lit,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            );
+        }
+
+        Ok(KeyboardState {
+            group: state.group,
+            composing: compose_lit != 0 || state.latched_mods != 0 || state.latched_group != 0,
+        })
+    }
+
+    // Send an event when the layout group, a latch or an indicator changes
+    // Pressing and releasing modifiers alone does not send one
+    // Events queue up in Xlib until they are read, so only select them if they are waited for
+    pub fn select_changes(&self) -> Result<(), IOError> {
+        let state_details = XKB_GROUP_STATE_MASK | XKB_MODIFIER_LATCH_MASK | XKB_GROUP_LATCH_MASK;
+        unsafe {
+            for (event_type, details) in [
+                (XKB_STATE_NOTIFY, state_details),
+                (XKB_INDICATOR_STATE_NOTIFY, XKB_ALL_INDICATORS_MASK),
+            ] {
+                if (self.select_event_details)(
+                    self.display,
+                    XKB_USE_CORE_KEYBOARD,
+                    event_type,
+                    details,
+                    details,
+                ) == 0
+                {
+                    return Err(IOError::other("Could not select XKB events"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Block until one of the selected changes happened
+    pub fn wait_for_change(&self) {
+        let mut event: XEvent = [0; 24];
+        unsafe {
+            (self.next_event)(self.display, &mut event);
+        }
+    }
+}
+
+// Executors are recreated on reloads, so the connection must not be leaked
+impl Drop for Xkb {
+    fn drop(&mut self) {
+        unsafe {
+            (self.close_display)(self.display);
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+// Named values that are shared between widgets
+// They are set by click actions or computed by widgets (e.g. "battery.value")
+static VARIABLES: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+pub fn set_variable(name: &str, value: String) {
+    VARIABLES.lock().unwrap().insert(name.to_string(), value);
+}
+
+pub fn variable(name: &str) -> Option<String> {
+    VARIABLES.lock().unwrap().get(name).cloned()
+}
+
+// Switch a flag between "true" and "false", unset flags count as "false"
+pub fn toggle_variable(name: &str) {
+    let mut variables = VARIABLES.lock().unwrap();
+    let enabled = variables.get(name).is_some_and(|value| value == "true");
+    variables.insert(name.to_string(), (!enabled).to_string());
+}
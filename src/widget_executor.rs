@@ -1,58 +1,360 @@
+use crate::alerts::send_alert;
+use crate::click_events::ClickEvent;
+use crate::config::{VariableAction, WidgetId};
+use crate::cpu_accounting::{forget_cpu_time, thread_cpu_time, CpuAccount};
+use crate::events::{subscribe_to_events, unsubscribe_from_events, WidgetEvent};
+use crate::i3_status::{I3Status, CONFIG};
+use crate::sound::play_sound;
+use crate::variables::{set_variable, toggle_variable};
 use crate::widgets::{Widget, WidgetError};
 use actix::prelude::*;
 use serde_json::Value;
+use std::any::Any;
+use std::collections::BTreeSet;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Mutex;
 
 // Actix message that is used to start a "update" job
 #[derive(Message)]
 #[rtype(result = "()")]
 pub struct UpdateWidgetValue;
 
+// Widgets that were asked to update but did not handle it yet, by "name:instance"
+// A widget that is still busy (e.g. reading from a hung network mount) is not sent more updates,
+// so they don't pile up in its mailbox
+static PENDING_UPDATES: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+// Start an "update" job, unless the widget has not handled the last one yet
+pub fn request_update(id: &WidgetId, executor: &Addr<WidgetExecutor>) {
+    if PENDING_UPDATES.lock().unwrap().insert(id.to_string()) {
+        executor.do_send(UpdateWidgetValue);
+    }
+}
+
 // Actix message that is used to get the value of a widget
 #[derive(Message)]
 #[rtype(result = "Result<Value, WidgetError>")]
 pub struct WidgetValue;
 
+// Actix message that is used to forward a click event to a widget
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct ClickWidget(pub ClickEvent);
+
+// Actix message that is sent to all widgets when a kernel event happens
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct KernelEvent(pub WidgetEvent);
+
+// Actix message that creates the widget again, after its section in the config file changed
+// The address stays the same, so clicks and updates still reach the executor
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct RecreateWidget;
+
+// Actix message that stops the executor, the widget is dropped afterwards
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct StopWidget;
+
 // A widget executor runs a widget without blocking the main thread
 // and calls the update method asynchronously
 pub struct WidgetExecutor {
+    // Name and instance of the widget in the bar
+    id: WidgetId,
     // Widget that should be executed in background
     widget: Box<dyn Widget>,
+    // Whether the widget is past its urgent threshold and an alert was already sent
+    alerted: bool,
+    // Sound alerts (by their index in the config) whose threshold the widget is past
+    sounded: Vec<usize>,
+    // Message of the panic that made the supervisor restart the executor,
+    // the block is shown as errored until the new widget was updated once
+    panic: Option<String>,
+    // Set when the executor is stopped on purpose, so it is not restarted with a new widget
+    stopping: bool,
+    // CPU time spent in the widget, checked against the budget every minute
+    cpu_account: CpuAccount,
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("unknown panic")
+    }
 }
 
 impl WidgetExecutor {
     // The widget has a static lifetime because we return a reference to it in `fn widget()`
     // The lifetime has to be static because this widget will live as long as the program (maybe
     // process is the better word?) is running
-    pub fn new<W>(widget: W) -> Self
+    pub fn new<W>(id: WidgetId, widget: W) -> Self
     where
         W: Widget + 'static,
     {
         Self {
+            id,
             widget: Box::new(widget),
+            alerted: false,
+            sounded: Vec::new(),
+            panic: None,
+            stopping: false,
+            cpu_account: CpuAccount::new(),
+        }
+    }
+
+    // Run a part of the widget and stop the executor if it panics,
+    // so a single broken widget does not take down the whole bar
+    // The supervisor restarts the executor with a new widget afterwards
+    fn isolate<T>(
+        &mut self,
+        ctx: &mut Context<Self>,
+        run: impl FnOnce(&mut Self) -> T,
+    ) -> Option<T> {
+        let started = thread_cpu_time();
+        let result = catch_unwind(AssertUnwindSafe(|| run(self)));
+        self.cpu_account
+            .add(&self.id, thread_cpu_time().saturating_sub(started));
+        match result {
+            Ok(result) => Some(result),
+            Err(panic) => {
+                let message = panic_message(&*panic);
+                log::error!("Widget {} panicked, restarting it: {}", self.id, message);
+                self.panic = Some(message);
+                ctx.stop();
+                None
+            }
+        }
+    }
+
+    fn update(&mut self) {
+        self.widget.update();
+        // Make the value of the widget available to other widgets
+        if let Some(value) = self.widget.value() {
+            set_variable(&self.id.variable_name("value"), value.to_string());
+        }
+        self.check_alert();
+        self.check_sounds();
+        self.panic = None;
+    }
+
+    // Replace the widget with a new one, which reads its options from the config again
+    // The old widget is dropped, which stops its background threads
+    fn recreate_widget(&mut self) -> bool {
+        match I3Status::create_executor(&self.id) {
+            Some(executor) => {
+                self.widget = executor.widget;
+                self.alerted = false;
+                self.sounded.clear();
+                true
+            }
+            None => {
+                log::error!("Could not create {} again", self.id);
+                false
+            }
+        }
+    }
+
+    // Send an alert when the widget crosses its urgent threshold
+    // The alert is sent again only after the value recovered in between
+    fn check_alert(&mut self) {
+        let (Some(threshold), Some(value)) = (
+            CONFIG.urgent_threshold(&self.id.to_string()),
+            self.widget.value(),
+        ) else {
+            return;
+        };
+
+        let crossed = threshold.is_crossed(value);
+        if crossed && !self.alerted {
+            let text = self
+                .widget
+                .display_text()
+                .ok()
+                .and_then(|block| block["full_text"].as_str().map(str::to_string))
+                .unwrap_or_else(|| value.to_string());
+            send_alert(&self.id, format!("{}: {}", self.id, text));
         }
+        self.alerted = crossed;
     }
 
-    pub fn widget_name(&self) -> &str {
-        self.widget.name()
+    // Play the sound of every sound alert whose threshold the widget just crossed
+    fn check_sounds(&mut self) {
+        let Some(value) = self.widget.value() else {
+            return;
+        };
+
+        for (index, alert) in CONFIG.sound_alerts().into_iter().enumerate() {
+            // Instances have their own sounds, e.g. "battery:BAT1"
+            if WidgetId::parse(&alert.widget) != self.id {
+                continue;
+            }
+            let crossed = alert.threshold.is_crossed(value);
+            let sounded = self.sounded.contains(&index);
+            if crossed && !sounded {
+                play_sound(alert.file);
+                self.sounded.push(index);
+            } else if !crossed && sounded {
+                self.sounded.retain(|sounded| *sounded != index);
+            }
+        }
     }
 }
 
 impl Actor for WidgetExecutor {
     type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        subscribe_to_events(&self.id, self.widget.events(), ctx.address().recipient());
+    }
+
+    // Every executor has its own arbiter, which is not needed anymore once the executor
+    // stopped for good. After a panic, the supervisor restarts it in the same arbiter
+    fn stopped(&mut self, ctx: &mut Context<Self>) {
+        if self.stopping || !ctx.connected() {
+            unsubscribe_from_events(&self.id);
+            forget_cpu_time(&self.id);
+            PENDING_UPDATES.lock().unwrap().remove(&self.id.to_string());
+            Arbiter::current().stop();
+        }
+    }
+}
+
+impl Supervised for WidgetExecutor {
+    // The widget may be in a broken state after a panic, so it is created again
+    // The address stays the same, so clicks and updates still reach the executor
+    fn restarting(&mut self, _ctx: &mut Context<Self>) {
+        if self.stopping {
+            return;
+        }
+        self.recreate_widget();
+    }
+}
+
+impl Handler<RecreateWidget> for WidgetExecutor {
+    type Result = ();
+
+    fn handle(&mut self, _msg: RecreateWidget, ctx: &mut Context<Self>) {
+        if self.recreate_widget() {
+            // The new options may subscribe to other events
+            subscribe_to_events(&self.id, self.widget.events(), ctx.address().recipient());
+            self.isolate(ctx, Self::update);
+        }
+    }
 }
 
 impl Handler<UpdateWidgetValue> for WidgetExecutor {
     type Result = ();
 
-    fn handle(&mut self, _msg: UpdateWidgetValue, _ctx: &mut Context<Self>) {
-        self.widget.update();
+    fn handle(&mut self, _msg: UpdateWidgetValue, ctx: &mut Context<Self>) {
+        PENDING_UPDATES.lock().unwrap().remove(&self.id.to_string());
+        self.isolate(ctx, Self::update);
+    }
+}
+
+impl Handler<StopWidget> for WidgetExecutor {
+    type Result = ();
+
+    fn handle(&mut self, _msg: StopWidget, ctx: &mut Context<Self>) {
+        self.stopping = true;
+        ctx.stop();
+    }
+}
+
+impl Handler<KernelEvent> for WidgetExecutor {
+    type Result = ();
+
+    // Only events the widget subscribed to are sent to it
+    fn handle(&mut self, msg: KernelEvent, ctx: &mut Context<Self>) {
+        log::debug!("Updating {} after a {:?} event", self.id, msg.0);
+        self.isolate(ctx, Self::update);
     }
 }
 
 impl Handler<WidgetValue> for WidgetExecutor {
     type Result = Result<Value, WidgetError>;
 
-    fn handle(&mut self, _msg: WidgetValue, _ctx: &mut Context<Self>) -> Self::Result {
-        self.widget.display_text()
+    fn handle(&mut self, _msg: WidgetValue, ctx: &mut Context<Self>) -> Self::Result {
+        if self.widget.updates_on_render() {
+            self.isolate(ctx, Self::update);
+        }
+        let value = self
+            .isolate(ctx, Self::widget_value)
+            .unwrap_or_else(|| Err(WidgetError::new(String::from("The widget panicked"))));
+        match &self.panic {
+            Some(panic) => Err(WidgetError::new(format!("The widget panicked: {}", panic))),
+            None => value,
+        }
+    }
+}
+
+impl WidgetExecutor {
+    fn widget_value(&mut self) -> Result<Value, WidgetError> {
+        let mut value = self.widget.display_text()?;
+        // Hidden widgets are returned as null and left out of the bar
+        let text_is_empty = value["full_text"].as_str().is_none_or(str::is_empty);
+        if CONFIG.block_config(&self.id.to_string()).hide_when_empty
+            && (text_is_empty || self.widget.is_empty())
+        {
+            return Ok(Value::Null);
+        }
+        // Widgets that could not be updated have no text, i3bar skips empty blocks
+        // but shows a protocol violation instead of missing ones
+        if value["full_text"].is_null() {
+            if let Some(block) = value.as_object_mut() {
+                block.insert(String::from("full_text"), Value::String(String::new()));
+            }
+        }
+        if let (Some(short_text), Some(block)) = (self.widget.short_text(), value.as_object_mut()) {
+            block.insert(String::from("short_text"), Value::String(short_text));
+        }
+        // i3bar sends the instance back in click events, so we know which widget was clicked
+        if let (Some(instance), Some(block)) = (&self.id.instance, value.as_object_mut()) {
+            block.insert(
+                String::from("instance"),
+                Value::String(instance.to_string()),
+            );
+        }
+        if let (Some(threshold), Some(widget_value), Some(block)) = (
+            CONFIG.urgent_threshold(&self.id.to_string()),
+            self.widget.value(),
+            value.as_object_mut(),
+        ) {
+            if threshold.is_crossed(widget_value) {
+                block.insert(String::from("urgent"), Value::Bool(true));
+            }
+        }
+
+        Ok(value)
+    }
+}
+
+impl Handler<ClickWidget> for WidgetExecutor {
+    type Result = ();
+
+    fn handle(&mut self, msg: ClickWidget, ctx: &mut Context<Self>) {
+        for click_action in CONFIG.click_actions() {
+            let widget = WidgetId::parse(&click_action.widget);
+            // A widget without an instance stands for all of its instances
+            let clicked =
+                widget == self.id || (widget.instance.is_none() && widget.name == self.id.name);
+            if clicked && click_action.button == msg.0.button {
+                match click_action.action {
+                    VariableAction::Toggle(variable) => toggle_variable(&variable),
+                    VariableAction::Set(variable, value) => set_variable(&variable, value),
+                }
+            }
+        }
+
+        self.isolate(ctx, |executor| {
+            if let Some(clickable) = executor.widget.clickable() {
+                clickable.click(&msg.0);
+                // Update the widget right away, so the click is reflected in the next output
+                executor.widget.update();
+            }
+        });
     }
 }
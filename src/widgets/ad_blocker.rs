@@ -0,0 +1,307 @@
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::click_events::{ClickEvent, BUTTON_LEFT};
+use crate::config::{AdBlockerApi, AdBlockerServer, WidgetId};
+use crate::i3_status::CONFIG;
+use crate::replay::http_agent;
+use crate::signals::is_low_power;
+use crate::theme::Color;
+use crate::utils::base64::basic_authorization;
+use crate::utils::secrets::resolve_secret;
+use crate::utils::template::{number, render, Placeholder};
+use crate::widgets::{Clickable, Widget, WidgetError};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// Placeholders: {label}, {status} (e.g. "12.3%", "off" while blocking is paused, "?" if the
+// server does not answer or "..." before the first answer) and {percent} (blocked queries,
+// empty while blocking is off)
+const FORMAT: &str = "{label}: {status}";
+
+struct BlockerStatus {
+    // Percentage of today's DNS queries that were blocked
+    blocked_percentage: f64,
+    blocking: bool,
+}
+
+// Talks to the API of Pi-hole or AdGuard Home
+struct BlockerClient {
+    agent: ureq::Agent,
+    url: String,
+    api: AdBlockerApi,
+    // Pi-hole session ID, requested again when it expires
+    session_id: Option<String>,
+}
+
+impl BlockerClient {
+    fn new(server: AdBlockerServer) -> Self {
+        Self {
+            agent: http_agent(REQUEST_TIMEOUT),
+            url: server.url.trim_end_matches('/').to_string(),
+            api: server.api,
+            session_id: None,
+        }
+    }
+
+    // See https://ftl.pi-hole.net/master/docs/#post-/auth
+    fn pihole_login(&mut self, password: &str) -> Result<(), WidgetError> {
+        let response: Value = serde_json::from_str(
+            &self
+                .agent
+                .post(&format!("{}/api/auth", self.url))
+                .set("Content-Type", "application/json")
+                .send_string(&json!({ "password": resolve_secret(password)? }).to_string())?
+                .into_string()?,
+        )?;
+        // Without a password there is no session
+        self.session_id = response["session"]["sid"].as_str().map(String::from);
+
+        Ok(())
+    }
+
+    // Send a request to Pi-hole, logs in again if the session expired
+    fn pihole_request(
+        &mut self,
+        password: &str,
+        method: &str,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<Value, WidgetError> {
+        for retry in [false, true] {
+            if retry || self.session_id.is_none() {
+                self.pihole_login(password)?;
+            }
+            let mut request = self.agent.request(method, &format!("{}{}", self.url, path));
+            if let Some(session_id) = &self.session_id {
+                request = request.set("X-FTL-SID", session_id);
+            }
+            let response = match &body {
+                Some(body) => request
+                    .set("Content-Type", "application/json")
+                    .send_string(&body.to_string()),
+                None => request.call(),
+            };
+            match response {
+                Err(ureq::Error::Status(401, _)) if !retry => continue,
+                response => return Ok(serde_json::from_str(&response?.into_string()?)?),
+            }
+        }
+
+        unreachable!("The second attempt always returns")
+    }
+
+    // See https://adguard-dns.io/kb/adguard-home/ (openapi/openapi.yaml)
+    fn adguard_request(
+        &self,
+        username: &str,
+        password: &str,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<Value, WidgetError> {
+        let url = format!("{}{}", self.url, path);
+        let authorization = basic_authorization(username, &resolve_secret(password)?);
+        let response = match body {
+            Some(body) => self
+                .agent
+                .post(&url)
+                .set("Authorization", &authorization)
+                .set("Content-Type", "application/json")
+                .send_string(&body.to_string())?,
+            None => self
+                .agent
+                .get(&url)
+                .set("Authorization", &authorization)
+                .call()?,
+        };
+        let response = response.into_string()?;
+
+        // Some endpoints answer with an empty body
+        Ok(serde_json::from_str(&response).unwrap_or(Value::Null))
+    }
+
+    fn status(&mut self) -> Result<BlockerStatus, WidgetError> {
+        // Logging in to Pi-hole changes the session, so the credentials can't be borrowed
+        match self.api.clone() {
+            AdBlockerApi::PiHole { password } => {
+                let summary = self.pihole_request(&password, "GET", "/api/stats/summary", None)?;
+                let blocking = self.pihole_request(&password, "GET", "/api/dns/blocking", None)?;
+                Ok(BlockerStatus {
+                    blocked_percentage: summary["queries"]["percent_blocked"]
+                        .as_f64()
+                        .unwrap_or(0.0),
+                    blocking: blocking["blocking"].as_str() == Some("enabled"),
+                })
+            }
+            AdBlockerApi::AdGuardHome { username, password } => {
+                let stats = self.adguard_request(&username, &password, "/control/stats", None)?;
+                let status = self.adguard_request(&username, &password, "/control/status", None)?;
+                let queries = stats["num_dns_queries"].as_f64().unwrap_or(0.0);
+                let blocked = stats["num_blocked_filtering"].as_f64().unwrap_or(0.0);
+                Ok(BlockerStatus {
+                    blocked_percentage: if queries > 0.0 {
+                        blocked / queries * 100.0
+                    } else {
+                        0.0
+                    },
+                    blocking: status["protection_enabled"].as_bool().unwrap_or(false),
+                })
+            }
+        }
+    }
+
+    // Disable blocking, it is enabled again by the server after the given duration
+    fn pause(&mut self, duration: Duration) -> Result<(), WidgetError> {
+        match self.api.clone() {
+            AdBlockerApi::PiHole { password } => {
+                self.pihole_request(
+                    &password,
+                    "POST",
+                    "/api/dns/blocking",
+                    Some(json!({ "blocking": false, "timer": duration.as_secs() })),
+                )?;
+            }
+            AdBlockerApi::AdGuardHome { username, password } => {
+                self.adguard_request(
+                    &username,
+                    &password,
+                    "/control/protection",
+                    Some(json!({ "enabled": false, "duration": duration.as_millis() as u64 })),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+pub struct AdBlocker {
+    // Name of the widget
+    name: &'static str,
+    #[serde(skip_serializing)]
+    // "ad_blocker:<instance>"
+    id: WidgetId,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    #[serde(skip_serializing)]
+    label: String,
+    #[serde(skip_serializing)]
+    // Latest status of the server, updated by the request thread
+    status: Arc<Mutex<Option<Result<BlockerStatus, String>>>>,
+    #[serde(skip_serializing)]
+    // Asks the request thread to disable blocking for a while
+    pause_requests: Sender<()>,
+}
+
+impl AdBlocker {
+    pub fn new(id: WidgetId, label: String, server: AdBlockerServer) -> Self {
+        let status = Arc::new(Mutex::new(None));
+        let (pause_requests, receiver) = channel();
+        let thread_status = Arc::clone(&status);
+        thread::spawn(move || {
+            let mut client = BlockerClient::new(server);
+            loop {
+                if !is_low_power() {
+                    let result = client.status();
+                    if let Err(error) = &result {
+                        log::warn!("Could not get the status of {}: {}", client.url, error);
+                    }
+                    *thread_status.lock().unwrap() =
+                        Some(result.map_err(|error| error.to_string()));
+                }
+
+                // Wait for the next poll, unless blocking should be disabled
+                match receiver.recv_timeout(Duration::from_secs(CONFIG.ad_blocker_interval())) {
+                    Ok(()) => {
+                        let duration = Duration::from_secs(CONFIG.ad_blocker_pause());
+                        if let Err(error) = client.pause(duration) {
+                            log::error!("Could not disable blocking of {}: {}", client.url, error);
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => (),
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+        });
+
+        Self {
+            name: "ad_blocker",
+            id,
+            full_text: None,
+            color: Color::Neutral,
+            label,
+            status,
+            pause_requests,
+        }
+    }
+}
+
+impl Widget for AdBlocker {
+    fn update(&mut self) {
+        let (status, percent) = match &*self.status.lock().unwrap() {
+            Some(Ok(status)) if status.blocking => {
+                self.color = Color::Good;
+                (
+                    format!("{:.1}%", status.blocked_percentage),
+                    number(status.blocked_percentage),
+                )
+            }
+            Some(Ok(_)) => {
+                self.color = Color::Warning;
+                (String::from("off"), Placeholder::from(""))
+            }
+            Some(Err(_)) => {
+                self.color = Color::Critical;
+                (String::from("?"), Placeholder::from(""))
+            }
+            // The first request is still running
+            None => (String::from("..."), Placeholder::from("")),
+        };
+        self.full_text = Some(render(
+            CONFIG
+                .format(&self.id.to_string())
+                .as_deref()
+                .unwrap_or(FORMAT),
+            &[
+                ("label", self.label.clone().into()),
+                ("status", status.into()),
+                ("percent", percent),
+            ],
+        ));
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn value(&self) -> Option<f32> {
+        match &*self.status.lock().unwrap() {
+            Some(Ok(status)) => Some(status.blocked_percentage as f32),
+            _ => None,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        matches!(&*self.status.lock().unwrap(), Some(Err(_)))
+    }
+
+    fn clickable(&mut self) -> Option<&mut dyn Clickable> {
+        Some(self)
+    }
+}
+
+impl Clickable for AdBlocker {
+    fn click(&mut self, event: &ClickEvent) {
+        if event.button == BUTTON_LEFT {
+            // The request thread only stops when the widget is dropped
+            let _ = self.pause_requests.send(());
+        }
+    }
+}
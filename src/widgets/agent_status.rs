@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::env;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::i3_status::CONFIG;
+use crate::theme::Color;
+use crate::utils::cache::cached_result;
+use crate::utils::template::{number, render};
+use crate::widgets::{Widget, WidgetError};
+
+// See https://datatracker.ietf.org/doc/html/draft-miller-ssh-agent#section-5.1
+const SSH_AGENTC_REQUEST_IDENTITIES: u8 = 11;
+const SSH_AGENT_IDENTITIES_ANSWER: u8 = 12;
+const SSH_AGENT_TIMEOUT: Duration = Duration::from_millis(200);
+// Placeholders: {ssh} (identities of the ssh-agent, "-" if it is not running),
+// {gpg} (cached GPG keys and the minutes until the first one expires, e.g. "2 (15m)"),
+// {gpg_keys} (cached GPG keys) and {gpg_minutes} (minutes until the first one expires, empty
+// without cached keys)
+const FORMAT: &str = "SSH: {ssh} GPG: {gpg}";
+
+#[derive(Serialize)]
+pub struct AgentStatus {
+    // Name of the widget
+    name: &'static str,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    #[serde(skip_serializing)]
+    // Holds the error message if an error occured during widget update
+    error: Option<String>,
+    #[serde(skip_serializing)]
+    // Keygrips of GPG keys with a cached passphrase and the moment we first saw them cached
+    // gpg-agent does not tell us when a passphrase was cached, so this is our best guess
+    cached_gpg_keys: HashMap<String, Instant>,
+}
+
+impl AgentStatus {
+    pub fn new() -> Self {
+        Self {
+            name: "agent",
+            full_text: None,
+            color: Color::Neutral,
+            error: None,
+            cached_gpg_keys: HashMap::new(),
+        }
+    }
+
+    // Ask the ssh-agent how many identities it currently holds
+    // Returns None if no agent is running
+    fn ssh_identities(&self) -> Result<Option<u32>, WidgetError> {
+        let socket_path = match env::var("SSH_AUTH_SOCK") {
+            Ok(socket_path) => socket_path,
+            Err(_) => return Ok(None),
+        };
+        let mut socket = match UnixStream::connect(socket_path) {
+            Ok(socket) => socket,
+            Err(_) => return Ok(None),
+        };
+        socket.set_read_timeout(Some(SSH_AGENT_TIMEOUT))?;
+        socket.set_write_timeout(Some(SSH_AGENT_TIMEOUT))?;
+
+        // Every message is prefixed with its length as big endian u32
+        let mut request = 1u32.to_be_bytes().to_vec();
+        request.push(SSH_AGENTC_REQUEST_IDENTITIES);
+        socket.write_all(&request)?;
+
+        // We only need the message type and the number of keys
+        // length (u32) + message type (u8) + number of keys (u32)
+        let mut response = [0; 9];
+        socket.read_exact(&mut response)?;
+        if response[4] != SSH_AGENT_IDENTITIES_ANSWER {
+            return Err(WidgetError::new(format!(
+                "Unexpected ssh-agent response type {}",
+                response[4]
+            )));
+        }
+
+        Ok(Some(u32::from_be_bytes(response[5..9].try_into().unwrap())))
+    }
+
+    // Returns the keygrips of all GPG keys whose passphrase is currently cached by gpg-agent
+    // gpg-connect-agent is only started once per poll interval
+    fn cached_gpg_keygrips(&self) -> Result<Vec<String>, WidgetError> {
+        cached_result(
+            "gpg-agent/keyinfo",
+            Duration::from_secs(CONFIG.gpg_agent_poll_interval()),
+            Self::list_gpg_keygrips,
+        )
+    }
+
+    fn list_gpg_keygrips() -> Result<Vec<String>, WidgetError> {
+        let output = Command::new("gpg-connect-agent")
+            .args(["--no-autostart", "keyinfo --list", "/bye"])
+            .output()?;
+        let mut keygrips = Vec::new();
+
+        // Every key is listed like this:
+        // S KEYINFO <keygrip> <type> <serialno> <idstr> <cached> <protection> ...
+        for line in String::from_utf8(output.stdout)?.lines() {
+            let fields = line.split_whitespace().collect::<Vec<&str>>();
+            if fields.len() > 6 && fields[1] == "KEYINFO" && fields[6] == "1" {
+                keygrips.push(fields[2].to_string());
+            }
+        }
+
+        Ok(keygrips)
+    }
+
+    // Returns the time until the first cached passphrase expires
+    fn next_gpg_expiry(&self) -> Option<Duration> {
+        let cache_ttl = Duration::from_secs(CONFIG.gpg_agent_cache_ttl());
+        self.cached_gpg_keys
+            .values()
+            .map(|cached_since| cache_ttl.saturating_sub(cached_since.elapsed()))
+            .min()
+    }
+}
+
+impl Widget for AgentStatus {
+    fn update(&mut self) {
+        self.error = None;
+        let ssh_identities = self.ssh_identities();
+        let gpg_keygrips = self.cached_gpg_keygrips();
+
+        match (ssh_identities, gpg_keygrips) {
+            (Ok(ssh_identities), Ok(gpg_keygrips)) => {
+                // Forget keys that are not cached anymore and remember the new ones
+                self.cached_gpg_keys
+                    .retain(|keygrip, _| gpg_keygrips.contains(keygrip));
+                for keygrip in gpg_keygrips {
+                    self.cached_gpg_keys
+                        .entry(keygrip)
+                        .or_insert_with(Instant::now);
+                }
+
+                let ssh_text = match ssh_identities {
+                    Some(identities) => identities.to_string(),
+                    None => String::from("-"),
+                };
+                let gpg_minutes = self
+                    .next_gpg_expiry()
+                    .map(|expiry| expiry.as_secs().div_ceil(60));
+                let gpg_text = match gpg_minutes {
+                    Some(minutes) => format!("{} ({}m)", self.cached_gpg_keys.len(), minutes),
+                    None => String::from("0"),
+                };
+
+                self.color = if ssh_identities.unwrap_or(0) > 0 || !self.cached_gpg_keys.is_empty()
+                {
+                    Color::Good
+                } else {
+                    Color::Neutral
+                };
+                self.full_text = Some(render(
+                    CONFIG.format(self.name).as_deref().unwrap_or(FORMAT),
+                    &[
+                        ("ssh", ssh_text.into()),
+                        ("gpg", gpg_text.into()),
+                        ("gpg_keys", number(self.cached_gpg_keys.len() as f64)),
+                        (
+                            "gpg_minutes",
+                            gpg_minutes.map_or("".into(), |minutes| number(minutes as f64)),
+                        ),
+                    ],
+                ));
+            }
+            (Err(error), _) | (_, Err(error)) => self.error = Some(error.to_string()),
+        }
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        if let Some(error_msg) = &self.error {
+            log::error!(
+                "Error occured when trying to get the agent status.\n{}",
+                error_msg
+            );
+        }
+
+        Ok(serde_json::to_value(self)?)
+    }
+}
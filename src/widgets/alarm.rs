@@ -0,0 +1,273 @@
+use std::process::Command;
+
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::click_events::{ClickEvent, BUTTON_LEFT, BUTTON_RIGHT};
+use crate::config::Alarm as AlarmConfig;
+use crate::i3_status::CONFIG;
+use crate::theme::Color;
+use crate::utils::state::{load_state, save_state};
+use crate::utils::template::render;
+use crate::widgets::{Clickable, Widget, WidgetError};
+
+const SECONDS_PER_MINUTE: i64 = 60;
+// Placeholders: {status} (e.g. "07:00" for the next alarm, "Mon 07:00" if it is not within a
+// day, "off", "snoozed until 07:09" or the label and time of the ringing alarm, e.g.
+// "Wake up 07:00"), {label} (of the next or ringing alarm, empty without alarm) and {time}
+// (of the next or ringing alarm, e.g. 07:00, the end of a snooze while snoozed)
+const FORMAT: &str = "⏰ {status}";
+
+// Persisted, so a dismissed alarm does not ring again and a snooze survives a restart
+#[derive(Default, Serialize, Deserialize)]
+struct AlarmState {
+    // Seconds since the epoch of the last alarm that was dismissed, older alarms are done too
+    dismissed: i64,
+    // Seconds since the epoch of the snoozed alarm and until when it is snoozed
+    snoozed: Option<(i64, i64)>,
+}
+
+// An alarm on a specific day
+struct Occurrence {
+    alarm: AlarmConfig,
+    // Seconds since the epoch
+    timestamp: i64,
+}
+
+#[derive(Serialize)]
+pub struct Alarm {
+    // Name of the widget
+    name: &'static str,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    // Whether an alarm is ringing
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    urgent: bool,
+    #[serde(skip_serializing)]
+    // Holds the error message if an error occured during widget update
+    error: Option<String>,
+    #[serde(skip_serializing)]
+    state: AlarmState,
+    #[serde(skip_serializing)]
+    // The alarm that is ringing or snoozed
+    current: Option<Occurrence>,
+    #[serde(skip_serializing)]
+    // Seconds since the epoch at which the alarm command was run the last time,
+    // so it is run once when an alarm starts ringing and once after every snooze
+    notified: Option<i64>,
+}
+
+// The alarm on the given day, None if it does not ring on that day
+// or if its time does not exist on that day (e.g. it is skipped by daylight saving time)
+fn occurrence(alarm: AlarmConfig, date: NaiveDate) -> Option<Occurrence> {
+    if !alarm.rings_on(date.weekday()) {
+        return None;
+    }
+    let (hour, minute) = alarm.time;
+    let time = Local
+        .from_local_datetime(&date.and_hms_opt(hour, minute, 0)?)
+        .earliest()?;
+    Some(Occurrence {
+        alarm,
+        timestamp: time.timestamp(),
+    })
+}
+
+fn hour_minute(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|time| time.with_timezone(&Local).format("%H:%M").to_string())
+        .unwrap_or_default()
+}
+
+impl Alarm {
+    fn render_text(status: String, label: &str, time: String) -> String {
+        render(
+            CONFIG.format("alarm").as_deref().unwrap_or(FORMAT),
+            &[
+                ("status", status.into()),
+                ("label", label.into()),
+                ("time", time.into()),
+            ],
+        )
+    }
+
+    pub fn new() -> Self {
+        Self {
+            name: "alarm",
+            full_text: None,
+            color: Color::Neutral,
+            urgent: false,
+            error: None,
+            state: load_state("alarm").unwrap_or_default(),
+            current: None,
+            notified: None,
+        }
+    }
+
+    // Alarms of today and yesterday, an alarm shortly before midnight can still ring after it
+    fn recent_occurrences(now: DateTime<Local>) -> impl Iterator<Item = Occurrence> {
+        let today = now.date_naive();
+        [today.checked_sub_days(Days::new(1)), Some(today)]
+            .into_iter()
+            .flatten()
+            .flat_map(|date| {
+                CONFIG
+                    .alarms()
+                    .into_iter()
+                    .filter_map(move |alarm| occurrence(alarm, date))
+            })
+    }
+
+    // The next alarm within a week
+    fn next_occurrence(now: DateTime<Local>) -> Option<Occurrence> {
+        (0..=7)
+            .filter_map(|days| now.date_naive().checked_add_days(Days::new(days)))
+            .flat_map(|date| {
+                CONFIG
+                    .alarms()
+                    .into_iter()
+                    .filter_map(move |alarm| occurrence(alarm, date))
+            })
+            .filter(|occurrence| occurrence.timestamp > now.timestamp())
+            .min_by_key(|occurrence| occurrence.timestamp)
+    }
+
+    // When the alarm started ringing the last time, which is after the snooze if it was snoozed
+    fn ringing_since(&self, occurrence: &Occurrence) -> i64 {
+        match self.state.snoozed {
+            Some((alarm, snoozed_until)) if alarm == occurrence.timestamp => snoozed_until,
+            _ => occurrence.timestamp,
+        }
+    }
+
+    fn notify(&self, alarm: &AlarmConfig) {
+        if let Some(command) = CONFIG.alarm_command() {
+            // Don't wait for the command, it may play a sound for a while
+            if let Err(error) = Command::new("sh")
+                .args(["-c", &command])
+                .env("ALARM_LABEL", &alarm.label)
+                .spawn()
+            {
+                log::error!("Could not run alarm command: {}", error);
+            }
+        }
+    }
+
+    fn save(&mut self) {
+        if let Err(error) = save_state(self.name, &self.state) {
+            self.error = Some(error.to_string());
+        }
+    }
+}
+
+impl Clickable for Alarm {
+    fn click(&mut self, event: &ClickEvent) {
+        let Some(current) = &self.current else {
+            return;
+        };
+        match event.button {
+            BUTTON_LEFT => {
+                let snoozed_until =
+                    Local::now().timestamp() + CONFIG.alarm_snooze_minutes() * SECONDS_PER_MINUTE;
+                self.state.snoozed = Some((current.timestamp, snoozed_until));
+            }
+            BUTTON_RIGHT => {
+                self.state.dismissed = current.timestamp;
+                self.state.snoozed = None;
+            }
+            _ => return,
+        }
+        self.save();
+    }
+}
+
+impl Widget for Alarm {
+    fn update(&mut self) {
+        self.error = None;
+        let now = Local::now();
+        let timestamp = now.timestamp();
+        let ring_time = CONFIG.alarm_ring_minutes() * SECONDS_PER_MINUTE;
+
+        // The latest alarm that was neither dismissed nor ignored for too long
+        self.current = Self::recent_occurrences(now)
+            .filter(|occurrence| {
+                occurrence.timestamp > self.state.dismissed
+                    && occurrence.timestamp <= timestamp
+                    && timestamp < self.ringing_since(occurrence) + ring_time
+            })
+            .max_by_key(|occurrence| occurrence.timestamp);
+        // A snooze ends with the alarm it belongs to
+        let snoozed_alarm = self.state.snoozed.map(|(alarm, _)| alarm);
+        if snoozed_alarm.is_some()
+            && snoozed_alarm != self.current.as_ref().map(|current| current.timestamp)
+        {
+            self.state.snoozed = None;
+            self.save();
+        }
+
+        let Some(current) = &self.current else {
+            self.urgent = false;
+            self.color = Color::Neutral;
+            let next = Self::next_occurrence(now);
+            let status = match &next {
+                Some(next) if next.timestamp - timestamp < 24 * 60 * SECONDS_PER_MINUTE => {
+                    hour_minute(next.timestamp)
+                }
+                Some(next) => DateTime::from_timestamp(next.timestamp, 0)
+                    .map(|time| time.with_timezone(&Local).format("%a %H:%M").to_string())
+                    .unwrap_or_default(),
+                None => String::from("off"),
+            };
+            self.full_text = Some(Self::render_text(
+                status,
+                next.as_ref().map_or("", |next| &next.alarm.label),
+                next.as_ref()
+                    .map(|next| hour_minute(next.timestamp))
+                    .unwrap_or_default(),
+            ));
+            return;
+        };
+
+        let ringing_since = self.ringing_since(current);
+        if ringing_since > timestamp {
+            self.urgent = false;
+            self.color = Color::Warning;
+            self.full_text = Some(Self::render_text(
+                format!("snoozed until {}", hour_minute(ringing_since)),
+                &current.alarm.label,
+                hour_minute(ringing_since),
+            ));
+            return;
+        }
+
+        self.urgent = true;
+        self.color = Color::Critical;
+        self.full_text = Some(Self::render_text(
+            format!("{} {}", current.alarm.label, hour_minute(current.timestamp)),
+            &current.alarm.label,
+            hour_minute(current.timestamp),
+        ));
+        if self.notified != Some(ringing_since) {
+            self.notify(&current.alarm);
+            self.notified = Some(ringing_since);
+        }
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        if let Some(error_msg) = &self.error {
+            log::error!(
+                "Error occured when trying to save the alarm state.\n{}",
+                error_msg
+            );
+        }
+
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn clickable(&mut self) -> Option<&mut dyn Clickable> {
+        Some(self)
+    }
+}
@@ -1,17 +1,21 @@
 use serde::Serialize;
 use serde_json::Value;
 
+use crate::events::WidgetEvent;
 use crate::i3_status::CONFIG;
+use crate::icons::{Icon, Ramp};
+use crate::os;
+use crate::theme::Color;
+use crate::utils::template::{number, render};
+use crate::variables::set_variable;
 use crate::widgets::{Widget, WidgetError};
-use crate::{
-    config::{GREEN, NEUTRAL, RED, YELLOW_WARNING},
-    utils::file::{read_file, read_first_line_in_file},
-};
-use std::io::{BufRead, BufReader, Error};
+use std::io::Error;
 
-const BATTERY_PATH: &str = "/sys/class/power_supply";
 const BATTERY_LOWER_THRESHOLD: f32 = 20.0;
 const BATTERY_UPPER_THRESHOLD: f32 = 80.0;
+// Placeholders: {state} (icon of e.g. charging), {icon} (fill level), {percent} of the battery life
+const FORMAT: &str = "{state} {icon} {percent:.2}%";
+const SHORT_FORMAT: &str = "{state} {percent:.0}%";
 
 #[derive(Serialize)]
 pub struct Battery {
@@ -20,13 +24,19 @@ pub struct Battery {
     // Text that will be shown in the status bar
     full_text: Option<String>,
     // Color of the text
-    color: &'static str,
+    color: Color,
     #[serde(skip_serializing)]
     // Holds the error message if an error occured during widget update
     error: Option<String>,
     #[serde(skip_serializing)]
+    // Shorter text that is shown when the bar runs out of space
+    short_text: Option<String>,
+    #[serde(skip_serializing)]
+    // Numeric value of the widget, used for thresholds
+    value: Option<f32>,
+    #[serde(skip_serializing)]
     // Device name of the power supply
-    device_name: &'static str,
+    device_name: String,
 }
 
 impl Battery {
@@ -34,88 +44,69 @@ impl Battery {
         Self {
             name: "battery",
             full_text: None,
-            color: NEUTRAL,
+            color: Color::Neutral,
             error: None,
+            short_text: None,
+            value: None,
             device_name: CONFIG.battery_device_name(),
         }
     }
 
-    // Returns a emoji String that should represent the current state
-    // Charging ⚡
-    // Battery is being used 🔋
-    // Battery full ☻
-    // State unknown ?
-    fn get_battery_state(&self) -> Result<String, Error> {
-        match read_first_line_in_file(&format!("{}/{}/status", BATTERY_PATH, self.device_name))?
-            .as_str()
-            .trim()
-        {
-            "Unknown" => Ok(String::from("?")),
-            "Charging" => Ok(String::from("⚡")),
-            "Discharging" => Ok(String::from("🔋")),
-            "Not charging" => Ok(String::from("🔋")),
-            "Full" => Ok(String::from("☻")),
-            &_ => panic!("Something horrible happened! Check battery state in /sys directory!"),
+    // Returns the icon that represents the current state (charging, discharging, full or unknown)
+    fn get_battery_state(status: &str) -> Result<String, Error> {
+        match status {
+            "Unknown" => Ok(Icon::BatteryUnknown.glyph()),
+            "Charging" => Ok(Icon::BatteryCharging.glyph()),
+            "Discharging" => Ok(Icon::BatteryDischarging.glyph()),
+            "Not charging" => Ok(Icon::BatteryDischarging.glyph()),
+            "Full" => Ok(Icon::BatteryFull.glyph()),
+            &_ => panic!(
+                "Something horrible happened! Unknown battery state {}!",
+                status
+            ),
         }
     }
-
-    fn get_battery_life(&self) -> Result<f32, WidgetError> {
-        let mut power_full: f32 = 0.0;
-        let mut power_now: f32 = 0.0;
-
-        if let Some(battery_file) =
-            read_file(&format!("{}/{}/uevent", BATTERY_PATH, self.device_name))
-        {
-            let reader = BufReader::new(battery_file);
-            for line in reader.lines() {
-                // TODO: Find a better solution for this
-                // Apparently `?` consumes `line`,
-                // which causes a move after being used once
-                let unpacked_line = line?;
-
-                // TODO: I don't like the unwraps here,
-                // it should be impossble to panic here, because the file is managed
-                // by the kernel and it should always look the same.
-                if unpacked_line.starts_with("POWER_SUPPLY_ENERGY_FULL") {
-                    power_full = unpacked_line
-                        .split('=')
-                        .last()
-                        .unwrap()
-                        .parse::<f32>()
-                        .unwrap();
-                } else if unpacked_line.starts_with("POWER_SUPPLY_ENERGY_NOW") {
-                    power_now = unpacked_line
-                        .split('=')
-                        .last()
-                        .unwrap()
-                        .parse::<f32>()
-                        .unwrap();
-                }
-            }
-        }
-
-        Ok(power_now / power_full * 100.0)
-    }
 }
 
 impl Widget for Battery {
-    fn name(&self) -> &str {
-        self.name
-    }
-
     fn update(&mut self) {
         self.error = None;
-        let battery_state = self.get_battery_state();
-        let battery_life = self.get_battery_life();
+        let status = os::battery_status(&self.device_name);
+        let battery_state = status
+            .as_deref()
+            .map_err(|error| Error::new(error.kind(), error.to_string()))
+            .and_then(Self::get_battery_state);
+        let battery_life = os::battery_life(&self.device_name);
         // Very ugly, but match would not make this more beautiful
         if let Ok(battery_state) = battery_state {
             if let Ok(battery_life) = battery_life {
-                self.full_text = Some(format!("{} BAT {:.2}%", battery_state, battery_life));
+                let placeholders = [
+                    ("state", battery_state.into()),
+                    ("icon", Ramp::Battery.glyph(battery_life).into()),
+                    ("percent", number(battery_life)),
+                ];
+                self.full_text = Some(render(
+                    CONFIG.format(self.name).as_deref().unwrap_or(FORMAT),
+                    &placeholders,
+                ));
+                self.short_text = Some(render(
+                    CONFIG
+                        .short_format(self.name)
+                        .as_deref()
+                        .unwrap_or(SHORT_FORMAT),
+                    &placeholders,
+                ));
+                // Low power mode only starts while the battery is discharging
+                set_variable(
+                    "battery.discharging",
+                    (status.as_deref().ok() == Some("Discharging")).to_string(),
+                );
+                self.value = Some(battery_life);
                 // See https://github.com/rust-lang/rust/issues/41620#issuecomment-314345874
                 self.color = match battery_life {
-                    x if x <= BATTERY_LOWER_THRESHOLD => RED,
-                    x if x >= BATTERY_UPPER_THRESHOLD => YELLOW_WARNING,
-                    _ => GREEN,
+                    x if x <= BATTERY_LOWER_THRESHOLD => Color::Critical,
+                    x if x >= BATTERY_UPPER_THRESHOLD => Color::Warning,
+                    _ => Color::Good,
                 };
             } else {
                 self.error = Some(battery_life.err().unwrap().to_string());
@@ -135,4 +126,17 @@ impl Widget for Battery {
 
         Ok(serde_json::to_value(self)?)
     }
+
+    fn short_text(&self) -> Option<String> {
+        self.short_text.clone()
+    }
+
+    fn value(&self) -> Option<f32> {
+        self.value
+    }
+
+    // Plugging in the charger should show up right away
+    fn events(&self) -> &[WidgetEvent] {
+        &[WidgetEvent::PowerSupply]
+    }
 }
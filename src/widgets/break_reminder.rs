@@ -0,0 +1,135 @@
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::i3_status::CONFIG;
+use crate::theme::Color;
+use crate::utils::idle::IdleMonitor;
+use crate::utils::template::{number, render};
+use crate::widgets::{Widget, WidgetError};
+
+// Placeholders: {status} ("break! (52m)" once it is time for a break, the active minutes
+// like "25m" otherwise) and {minutes} (active minutes without a break, e.g. 25)
+const FORMAT: &str = "☕ {status}";
+
+#[derive(Serialize)]
+pub struct BreakReminder {
+    // Name of the widget
+    name: &'static str,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    // Whether it is time for a break
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    urgent: bool,
+    #[serde(skip_serializing)]
+    // Holds the error message if an error occured during widget update
+    error: Option<String>,
+    #[serde(skip_serializing)]
+    idle_monitor: Option<IdleMonitor>,
+    #[serde(skip_serializing)]
+    // Start of the current period of continuous activity
+    active_since: Instant,
+    #[serde(skip_serializing)]
+    // Whether the notification command was already run for the current period
+    notified: bool,
+}
+
+impl BreakReminder {
+    pub fn new() -> Self {
+        let idle_monitor = match IdleMonitor::new() {
+            Ok(idle_monitor) => Some(idle_monitor),
+            Err(error) => {
+                log::error!("Idle detection is not available: {}", error);
+                None
+            }
+        };
+
+        Self {
+            name: "break_reminder",
+            full_text: None,
+            color: Color::Neutral,
+            urgent: false,
+            error: None,
+            idle_monitor,
+            active_since: Instant::now(),
+            notified: false,
+        }
+    }
+
+    fn notify(&self) {
+        if let Some(command) = CONFIG.break_reminder_command() {
+            // Don't wait for the command, notifications may block until they are dismissed
+            if let Err(error) = Command::new("sh").args(["-c", command]).spawn() {
+                log::error!("Could not run break reminder command: {}", error);
+            }
+        }
+    }
+}
+
+impl Widget for BreakReminder {
+    fn update(&mut self) {
+        self.error = None;
+        let idle_time = match &self.idle_monitor {
+            Some(idle_monitor) => match idle_monitor.idle_time() {
+                Ok(idle_time) => idle_time,
+                Err(error) => {
+                    self.error = Some(error.to_string());
+                    return;
+                }
+            },
+            None => {
+                self.error = Some(String::from("Idle detection is not available"));
+                return;
+            }
+        };
+
+        // Being idle long enough counts as a break
+        if idle_time >= Duration::from_secs(CONFIG.break_reminder_break_length()) {
+            self.active_since = Instant::now();
+            self.notified = false;
+        }
+
+        let active_time = self.active_since.elapsed();
+        self.urgent = active_time >= Duration::from_secs(CONFIG.break_reminder_interval());
+        if self.urgent && !self.notified {
+            self.notify();
+            self.notified = true;
+        }
+
+        self.color = if self.urgent {
+            Color::Critical
+        } else if idle_time.as_secs() > 0 {
+            Color::Good
+        } else {
+            Color::Neutral
+        };
+        let minutes = active_time.as_secs() / 60;
+        let status = if self.urgent {
+            format!("break! ({}m)", minutes)
+        } else {
+            format!("{}m", minutes)
+        };
+        self.full_text = Some(render(
+            CONFIG.format(self.name).as_deref().unwrap_or(FORMAT),
+            &[
+                ("status", status.into()),
+                ("minutes", number(minutes as f64)),
+            ],
+        ));
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        if let Some(error_msg) = &self.error {
+            log::error!(
+                "Error occured when trying to check for breaks.\n{}",
+                error_msg
+            );
+        }
+
+        Ok(serde_json::to_value(self)?)
+    }
+}
@@ -1,10 +1,102 @@
+use crate::click_events::{ClickEvent, SCROLL_DOWN, SCROLL_UP};
+use crate::ddc::{DdcMonitor, VcpValue, VCP_BRIGHTNESS};
+use crate::events::WidgetEvent;
 use crate::i3_status::CONFIG;
-use crate::widgets::{Widget, WidgetError};
-use crate::{config::YELLOW, utils::file::read_first_line_in_file};
+use crate::icons::Ramp;
+use crate::theme::Color;
+use crate::utils::file::read_first_line_in_file;
+use crate::utils::template::render;
+use crate::widgets::{Clickable, Widget, WidgetError};
 use serde::Serialize;
 use serde_json::Value;
+use std::fs::write;
+use std::time::{Duration, Instant};
 
-const BACKLIGHT_PATH: &str = "/sys/class/backlight";
+pub const BACKLIGHT_PATH: &str = "/sys/class/backlight";
+// Percentage that is added / subtracted when scrolling on the widget
+const BRIGHTNESS_STEP: f32 = 5.0;
+// DDC/CI requests are slow and some monitors don't like to be polled,
+// so the brightness of external monitors is only read every now and then
+const DDC_POLL_INTERVAL: Duration = Duration::from_secs(30);
+// Placeholders: {icon} (level of the first backlight), {brightness} of all backlights, e.g. "40% / 75%"
+const FORMAT: &str = "{icon}: {brightness}";
+
+// A device whose brightness can be read and changed
+pub trait Backlight: Send {
+    // Current brightness in percent
+    fn brightness(&mut self) -> Result<f32, WidgetError>;
+    // Set the brightness in percent
+    fn set_brightness(&mut self, percentage: f32) -> Result<(), WidgetError>;
+}
+
+// Backlight of an internal panel that is controlled through sysfs
+struct SysfsBacklight {
+    device_name: String,
+}
+
+impl SysfsBacklight {
+    fn read_value(&self, file_name: &str) -> Result<f32, WidgetError> {
+        Ok(read_first_line_in_file(&format!(
+            "{}/{}/{}",
+            BACKLIGHT_PATH, self.device_name, file_name
+        ))?
+        .parse::<f32>()?)
+    }
+}
+
+impl Backlight for SysfsBacklight {
+    fn brightness(&mut self) -> Result<f32, WidgetError> {
+        Ok(self.read_value("actual_brightness")? / self.read_value("max_brightness")? * 100.0)
+    }
+
+    // Writing to the brightness file requires the user to have write permissions on it
+    // (e.g. by being in the "video" group and using the udev rules shipped by systemd)
+    fn set_brightness(&mut self, percentage: f32) -> Result<(), WidgetError> {
+        let max_brightness = self.read_value("max_brightness")?;
+        let new_brightness = (max_brightness * percentage / 100.0).clamp(0.0, max_brightness);
+        write(
+            format!("{}/{}/brightness", BACKLIGHT_PATH, self.device_name),
+            (new_brightness.round() as u32).to_string(),
+        )?;
+
+        Ok(())
+    }
+}
+
+// External monitor that is controlled through DDC/CI
+struct DdcBacklight {
+    monitor: DdcMonitor,
+    // Last known brightness and when it was read
+    cached_value: Option<(VcpValue, Instant)>,
+}
+
+impl Backlight for DdcBacklight {
+    fn brightness(&mut self) -> Result<f32, WidgetError> {
+        let value = match self.cached_value.take() {
+            Some((value, read_at)) if read_at.elapsed() < DDC_POLL_INTERVAL => (value, read_at),
+            _ => (
+                self.monitor.get_vcp_feature(VCP_BRIGHTNESS)?,
+                Instant::now(),
+            ),
+        };
+        let percentage = value.0.current as f32 / value.0.maximum as f32 * 100.0;
+        self.cached_value = Some(value);
+
+        Ok(percentage)
+    }
+
+    fn set_brightness(&mut self, percentage: f32) -> Result<(), WidgetError> {
+        let maximum = match &self.cached_value {
+            Some((value, _)) => value.maximum,
+            None => self.monitor.get_vcp_feature(VCP_BRIGHTNESS)?.maximum,
+        };
+        let current = (maximum as f32 * percentage / 100.0).round() as u16;
+        self.monitor.set_vcp_feature(VCP_BRIGHTNESS, current)?;
+        self.cached_value = Some((VcpValue { current, maximum }, Instant::now()));
+
+        Ok(())
+    }
+}
 
 #[derive(Serialize)]
 pub struct Brightness {
@@ -13,54 +105,122 @@ pub struct Brightness {
     // Text that will be shown in the status bar
     full_text: Option<String>,
     // Color of the text
-    color: &'static str,
+    color: Color,
     #[serde(skip_serializing)]
-    // Device name of the digital display
-    device_name: &'static str,
+    // Holds the error message if an error occured during widget update
+    error: Option<String>,
+    #[serde(skip_serializing)]
+    // All backlights that are shown and controlled together
+    backlights: Vec<Box<dyn Backlight>>,
 }
 
 impl Brightness {
     pub fn new() -> Self {
+        let mut backlights: Vec<Box<dyn Backlight>> = Vec::new();
+        for device_name in CONFIG.brightness_device_names() {
+            backlights.push(Box::new(SysfsBacklight { device_name }));
+        }
+        for i2c_device in CONFIG.ddc_i2c_devices() {
+            match DdcMonitor::open(&i2c_device) {
+                Ok(monitor) => backlights.push(Box::new(DdcBacklight {
+                    monitor,
+                    cached_value: None,
+                })),
+                Err(error) => log::error!("Could not open {}: {}", i2c_device, error),
+            }
+        }
+
         Self {
             name: "brightness",
             full_text: None,
-            color: YELLOW,
-            device_name: CONFIG.brightness_device_name(),
+            color: Color::Accent,
+            error: None,
+            backlights,
         }
     }
+
+    // Change the brightness of all backlights by the given percentage
+    // The first backlight is used as reference, so all backlights are in sync afterwards
+    fn change_brightness(&mut self, percentage: f32) -> Result<(), WidgetError> {
+        let reference_brightness = match self.backlights.first_mut() {
+            Some(backlight) => backlight.brightness()?,
+            None => return Ok(()),
+        };
+        let new_brightness = (reference_brightness + percentage).clamp(0.0, 100.0);
+        for backlight in self.backlights.iter_mut() {
+            backlight.set_brightness(new_brightness)?;
+        }
+
+        Ok(())
+    }
 }
 
-impl Widget for Brightness {
-    fn name(&self) -> &str {
-        self.name
+impl Clickable for Brightness {
+    fn click(&mut self, event: &ClickEvent) {
+        let result = match event.button {
+            SCROLL_UP => self.change_brightness(BRIGHTNESS_STEP),
+            SCROLL_DOWN => self.change_brightness(-BRIGHTNESS_STEP),
+            _ => Ok(()),
+        };
+
+        if let Err(error) = result {
+            log::error!("Could not change brightness.\n{}", error);
+        }
     }
+}
 
+impl Widget for Brightness {
     fn update(&mut self) {
-        let actual_brightness = read_first_line_in_file(&format!(
-            "{}/{}/actual_brightness",
-            BACKLIGHT_PATH, self.device_name
-        ))
-        .unwrap()
-        .parse::<f32>()
-        .unwrap();
-        let max_brightness = read_first_line_in_file(&format!(
-            "{}/{}/max_brightness",
-            BACKLIGHT_PATH, self.device_name
-        ))
-        .unwrap()
-        .parse::<f32>()
-        .unwrap();
-
-        self.full_text = Some(
-            String::from("☼: ")
-                + &(actual_brightness / max_brightness * 100.0)
-                    .round()
-                    .to_string()
-                + "%",
-        );
+        self.error = None;
+        let mut percentages = Vec::new();
+        // The first backlight is the reference, like when the brightness is changed
+        let mut reference_brightness = None;
+        for backlight in self.backlights.iter_mut() {
+            match backlight.brightness() {
+                Ok(brightness) => {
+                    reference_brightness.get_or_insert(brightness);
+                    percentages.push(format!("{}%", brightness.round()));
+                }
+                Err(error) => {
+                    self.error = Some(error.to_string());
+                    return;
+                }
+            }
+        }
+
+        // Backlights that are in sync are only shown once
+        percentages.dedup();
+        self.full_text = Some(render(
+            CONFIG.format(self.name).as_deref().unwrap_or(FORMAT),
+            &[
+                (
+                    "icon",
+                    Ramp::Brightness
+                        .glyph(reference_brightness.unwrap_or(0.0))
+                        .into(),
+                ),
+                ("brightness", percentages.join(" / ").into()),
+            ],
+        ));
     }
 
     fn display_text(&self) -> Result<Value, WidgetError> {
+        if let Some(error_msg) = &self.error {
+            log::error!(
+                "Error occured when trying to get the brightness.\n{}",
+                error_msg
+            );
+        }
+
         Ok(serde_json::to_value(self)?)
     }
+
+    // Brightness keys change the backlight without going through i3rustus
+    fn events(&self) -> &[WidgetEvent] {
+        &[WidgetEvent::Backlight]
+    }
+
+    fn clickable(&mut self) -> Option<&mut dyn Clickable> {
+        Some(self)
+    }
 }
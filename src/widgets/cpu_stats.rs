@@ -1,14 +1,21 @@
 use serde::Serialize;
 use serde_json::Value;
 
-use crate::{
-    config::{NEUTRAL, RED},
-    utils::file::read_first_line_in_file,
-};
+use crate::i3_status::CONFIG;
+use crate::icons::Icon;
+use crate::os;
+use crate::utils::template::{number, render, Placeholder};
 
+use crate::theme::Color;
 use crate::widgets::{Widget, WidgetError};
 
 const CPU_USAGE_THRESHOLD: f32 = 30.0;
+// Placeholders: {load1}, {load5} and {load15} (load of the last 1, 5 and 15 minutes)
+const LOAD_FORMAT: &str = "Load: {load1}, {load5}, {load15}";
+// The load of the last minute is enough when we run out of space
+const LOAD_SHORT_FORMAT: &str = "Load: {load1}";
+// Placeholders: {icon}, {percent} of CPU usage
+const PERCENTAGE_FORMAT: &str = "{icon}:{percent:.0}%";
 
 #[derive(PartialEq, Eq)]
 pub enum CpuUsageType {
@@ -25,7 +32,7 @@ pub struct CpuUsage {
     // Text that will be shown in the status bar
     full_text: Option<String>,
     // Color of the text
-    color: &'static str,
+    color: Color,
     #[serde(skip_serializing)]
     pub usage_type: CpuUsageType,
     // Last idle time of CPU
@@ -35,6 +42,12 @@ pub struct CpuUsage {
     #[serde(skip_serializing)]
     last_total_usage: f32,
     #[serde(skip_serializing)]
+    // Shorter text that is shown when the bar runs out of space
+    short_text: Option<String>,
+    #[serde(skip_serializing)]
+    // Numeric value of the widget, used for thresholds
+    value: Option<f32>,
+    #[serde(skip_serializing)]
     // Holds the error message if an error occured during widget update
     error: Option<String>,
 }
@@ -53,33 +66,23 @@ impl CpuUsage {
             last_total_usage: 0.0,
             name,
             full_text: None,
-            color: RED,
+            color: Color::Critical,
             error: None,
+            short_text: None,
+            value: None,
         }
     }
 
-    fn get_cpu_load(&self) -> Result<String, WidgetError> {
-        let load_avg = read_first_line_in_file("/proc/loadavg")?;
-        // We only want the the load and not the
-        let load = &load_avg.split_whitespace().collect::<Vec<&str>>()[0..3];
-        Ok(format!("Load: {}", load.join(", ")))
+    fn get_cpu_load(&self) -> Result<Vec<(&'static str, Placeholder)>, WidgetError> {
+        let load = os::load_average()?;
+        Ok(["load1", "load5", "load15"]
+            .into_iter()
+            .zip(load.into_iter().map(Placeholder::parsed))
+            .collect())
     }
 
     fn get_cpu_usage(&mut self) -> Result<f32, WidgetError> {
-        let mut total: f32 = 0.0;
-        let mut idle: f32 = 0.0;
-
-        let cpu_line = read_first_line_in_file("/proc/stat")?;
-        let (_, cpu_stats) = cpu_line.split_once("  ").unwrap();
-
-        for (i, number) in cpu_stats.trim().split(' ').enumerate() {
-            let number_as_u32 = number.parse::<f32>().unwrap();
-
-            if i == 3 {
-                idle = number_as_u32;
-            }
-            total += number_as_u32;
-        }
+        let (idle, total) = os::cpu_times()?;
 
         let idle_delta = idle - self.last_idle_usage;
         let total_delta = total - self.last_total_usage;
@@ -92,28 +95,44 @@ impl CpuUsage {
 }
 
 impl Widget for CpuUsage {
-    fn name(&self) -> &str {
-        self.name
-    }
-
     fn update(&mut self) {
         self.error = None;
         if self.usage_type == CpuUsageType::CpuLoad {
             match self.get_cpu_load() {
                 Ok(load) => {
-                    self.full_text = Some(load);
-                    self.color = NEUTRAL;
+                    self.short_text = Some(render(
+                        CONFIG
+                            .short_format(self.name)
+                            .as_deref()
+                            .unwrap_or(LOAD_SHORT_FORMAT),
+                        &load,
+                    ));
+                    self.full_text = Some(render(
+                        CONFIG.format(self.name).as_deref().unwrap_or(LOAD_FORMAT),
+                        &load,
+                    ));
+                    self.color = Color::Neutral;
                 }
                 Err(error) => self.error = Some(error.to_string()),
             }
         } else {
             match self.get_cpu_usage() {
                 Ok(usage) => {
-                    self.full_text = Some(format!("CPU:{:.0}%", usage));
+                    self.full_text = Some(render(
+                        CONFIG
+                            .format(self.name)
+                            .as_deref()
+                            .unwrap_or(PERCENTAGE_FORMAT),
+                        &[
+                            ("icon", Icon::Cpu.glyph().into()),
+                            ("percent", number(usage)),
+                        ],
+                    ));
+                    self.value = Some(usage);
                     self.color = if usage > CPU_USAGE_THRESHOLD {
-                        RED
+                        Color::Critical
                     } else {
-                        NEUTRAL
+                        Color::Neutral
                     };
                 }
                 Err(error) => self.error = Some(error.to_string()),
@@ -128,4 +147,12 @@ impl Widget for CpuUsage {
 
         Ok(serde_json::to_value(self)?)
     }
+
+    fn short_text(&self) -> Option<String> {
+        self.short_text.clone()
+    }
+
+    fn value(&self) -> Option<f32> {
+        self.value
+    }
 }
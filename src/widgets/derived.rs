@@ -0,0 +1,102 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::{DerivedExpression, WidgetId};
+use crate::i3_status::CONFIG;
+use crate::theme::Color;
+use crate::utils::expression::{evaluate, is_condition};
+use crate::utils::template::{number, render};
+use crate::variables::variable;
+use crate::widgets::{Widget, WidgetError};
+
+// Placeholders: {label} and {value} (the result of the expression, 1 or 0 for conditions)
+const FORMAT: &str = "{label}: {value:.1}";
+// Conditions are only shown while they are true
+const CONDITION_FORMAT: &str = "⚠ {label}";
+
+// A widget whose value is computed from the values of other widgets
+// See utils::expression for the supported syntax
+#[derive(Serialize)]
+pub struct Derived {
+    // Name of the widget
+    name: &'static str,
+    #[serde(skip_serializing)]
+    // "derived" or e.g. "derived:busy", several derived values can be shown
+    id: WidgetId,
+    #[serde(skip_serializing)]
+    derived: DerivedExpression,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    // Conditions that are true are shown as urgent alerts
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    urgent: bool,
+    #[serde(skip_serializing)]
+    // Holds the error message if an error occured during widget update
+    error: Option<String>,
+    #[serde(skip_serializing)]
+    value: Option<f32>,
+}
+
+impl Derived {
+    pub fn new(id: WidgetId, derived: DerivedExpression) -> Self {
+        Self {
+            name: "derived",
+            id,
+            derived,
+            full_text: None,
+            color: Color::Neutral,
+            urgent: false,
+            error: None,
+            value: None,
+        }
+    }
+}
+
+impl Widget for Derived {
+    fn update(&mut self) {
+        self.error = None;
+        let DerivedExpression { label, expression } = &self.derived;
+        let format = CONFIG.format(&self.id.to_string());
+        match evaluate(expression, variable) {
+            Ok(value) => {
+                self.value = Some(value as f32);
+                let placeholders = [("label", label.as_str().into()), ("value", number(value))];
+                if is_condition(expression) {
+                    self.urgent = value != 0.0;
+                    self.color = Color::Critical;
+                    self.full_text = Some(if self.urgent {
+                        render(format.as_deref().unwrap_or(CONDITION_FORMAT), &placeholders)
+                    } else {
+                        String::new()
+                    });
+                } else {
+                    self.urgent = false;
+                    self.color = Color::Neutral;
+                    self.full_text =
+                        Some(render(format.as_deref().unwrap_or(FORMAT), &placeholders));
+                }
+            }
+            Err(error) => {
+                self.value = None;
+                self.error = Some(error);
+            }
+        }
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        if let Some(error_msg) = &self.error {
+            log::error!(
+                "Error occured when trying to evaluate derived expression.\n{}",
+                error_msg
+            );
+        }
+
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn value(&self) -> Option<f32> {
+        self.value
+    }
+}
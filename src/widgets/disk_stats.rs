@@ -6,34 +6,47 @@ use libc::statvfs;
 use serde::Serialize;
 use serde_json::Value;
 
-use crate::config::NEUTRAL;
-use crate::config::RED;
+use crate::config::WidgetId;
+use crate::i3_status::CONFIG;
+use crate::icons::Icon;
+use crate::theme::Color;
+use crate::utils::template::{number, render};
 use crate::widgets::Widget;
 use crate::widgets::WidgetError;
 
 const DISK_THRESHOLD: f64 = 20.0;
+// Placeholders: {icon}, {name}, {available} and {total} in GiB, {percent} of available space
+const FORMAT: &str = "{name}: {available} GiB";
 
 #[derive(Serialize)]
 // A struct that holds a Map of all paths that we want to watch over
 pub struct Disk {
     // Name of the widget
     name: &'static str,
+    // "disk" or "disk:<instance>", its section in the config file may set the format
+    #[serde(skip_serializing)]
+    id: WidgetId,
     // Text that will be shown in the status bar
     full_text: Option<String>,
     // Color of the text
-    color: &'static str,
+    color: Color,
     // Paths to watch
     #[serde(skip_serializing)]
     path_to_watch: (String, String),
+    #[serde(skip_serializing)]
+    // Numeric value of the widget, used for thresholds
+    value: Option<f32>,
 }
 
 impl Disk {
-    pub fn new(display_name: String, path: String) -> Self {
+    pub fn new(id: WidgetId, display_name: String, path: String) -> Self {
         Self {
             name: "disk",
+            id,
             full_text: None,
-            color: RED,
+            color: Color::Critical,
             path_to_watch: (display_name, path),
+            value: None,
         }
     }
 
@@ -73,10 +86,6 @@ impl Disk {
 }
 
 impl Widget for Disk {
-    fn name(&self) -> &str {
-        self.name
-    }
-
     fn update(&mut self) {
         // We need to borrow here because "String" does not implement the copy trait
         // and self is already borrowed. That means that we cannot move the "path_to_watch" variable
@@ -84,16 +93,34 @@ impl Widget for Disk {
         let (name, path) = &self.path_to_watch;
         let available_space = self.calulcate_available_disk_storage(Path::new(path));
         let total_space = self.get_total_disk_storage(Path::new(path));
-        self.color = if (available_space as f64 / total_space as f64 * 100.0) < DISK_THRESHOLD {
-            RED
+        let available_percentage = available_space as f64 / total_space as f64 * 100.0;
+        self.value = Some(available_percentage as f32);
+        self.color = if available_percentage < DISK_THRESHOLD {
+            Color::Critical
         } else {
-            NEUTRAL
+            Color::Neutral
         };
 
-        self.full_text = Some(format!("{name}: {available_space} GiB"));
+        self.full_text = Some(render(
+            CONFIG
+                .format(&self.id.to_string())
+                .as_deref()
+                .unwrap_or(FORMAT),
+            &[
+                ("icon", Icon::Disk.glyph().into()),
+                ("name", name.clone().into()),
+                ("available", number(available_space as f64)),
+                ("total", number(total_space as f64)),
+                ("percent", number(available_percentage)),
+            ],
+        ));
     }
 
     fn display_text(&self) -> Result<Value, WidgetError> {
         Ok(serde_json::to_value(self)?)
     }
+
+    fn value(&self) -> Option<f32> {
+        self.value
+    }
 }
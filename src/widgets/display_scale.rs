@@ -0,0 +1,153 @@
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::i3_ipc::{I3Ipc, GET_OUTPUTS, GET_WORKSPACES};
+use crate::i3_status::CONFIG;
+use crate::theme::Color;
+use crate::utils::template::{number, render};
+use crate::widgets::{Widget, WidgetError};
+
+// Outputs only change when a monitor is plugged in or reconfigured
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+// X11 draws at 96 DPI without scaling
+const DEFAULT_DPI: f32 = 96.0;
+// Placeholders: {output} (e.g. DP-1), {width} and {height} (in pixels), {scale} (e.g. 1.5),
+// {expected} (the configured scale) and {mismatch} (" (expected 1x)" if the scale is not the
+// expected one, empty otherwise)
+const FORMAT: &str = "🖵 {output} {width}x{height} {scale}x{mismatch}";
+
+// Scale of all X11 outputs, taken from the Xft.dpi resource
+// i3 has no scale per output, toolkits scale by Xft.dpi instead
+fn xft_scale() -> Option<f32> {
+    let output = Command::new("xrdb").arg("-query").output().ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("Xft.dpi:"))
+        .and_then(|dpi| dpi.trim().parse::<f32>().ok())
+        .map(|dpi| dpi / DEFAULT_DPI)
+}
+
+#[derive(Serialize)]
+pub struct DisplayScale {
+    // Name of the widget
+    name: &'static str,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    #[serde(skip_serializing)]
+    // Holds the error message if an error occured during widget update
+    error: Option<String>,
+    #[serde(skip_serializing)]
+    // When the outputs were read the last time
+    last_read: Option<Instant>,
+}
+
+impl DisplayScale {
+    pub fn new() -> Self {
+        Self {
+            name: "display_scale",
+            full_text: None,
+            color: Color::Neutral,
+            error: None,
+            last_read: None,
+        }
+    }
+
+    // Name, resolution and scale of the output with the focused workspace
+    fn focused_output() -> Result<(String, (u64, u64), f32), WidgetError> {
+        let mut ipc = I3Ipc::connect()?;
+        let workspaces: Value = serde_json::from_str(&ipc.request(GET_WORKSPACES, "")?)?;
+        let focused_output = workspaces
+            .as_array()
+            .and_then(|workspaces| {
+                workspaces
+                    .iter()
+                    .find(|workspace| workspace["focused"].as_bool() == Some(true))
+            })
+            .and_then(|workspace| workspace["output"].as_str())
+            .ok_or_else(|| WidgetError::new(String::from("No workspace is focused")))?
+            .to_string();
+
+        let outputs: Value = serde_json::from_str(&ipc.request(GET_OUTPUTS, "")?)?;
+        let output = outputs
+            .as_array()
+            .and_then(|outputs| {
+                outputs
+                    .iter()
+                    .find(|output| output["name"].as_str() == Some(&focused_output))
+            })
+            .ok_or_else(|| WidgetError::new(format!("Output {} does not exist", focused_output)))?;
+
+        // sway reports the mode in pixels and the rect in scaled pixels, i3 only has the rect
+        let mode = if output["current_mode"].is_object() {
+            &output["current_mode"]
+        } else {
+            &output["rect"]
+        };
+        let resolution = (
+            mode["width"].as_u64().unwrap_or_default(),
+            mode["height"].as_u64().unwrap_or_default(),
+        );
+        let scale = match output["scale"].as_f64() {
+            Some(scale) => scale as f32,
+            None => xft_scale().unwrap_or(1.0),
+        };
+
+        Ok((focused_output, resolution, scale))
+    }
+}
+
+impl Widget for DisplayScale {
+    fn update(&mut self) {
+        if self
+            .last_read
+            .is_some_and(|last_read| last_read.elapsed() < POLL_INTERVAL)
+        {
+            return;
+        }
+        self.last_read = Some(Instant::now());
+        self.error = None;
+
+        let (output, (width, height), scale) = match Self::focused_output() {
+            Ok(focused_output) => focused_output,
+            Err(error) => {
+                self.error = Some(error.to_string());
+                return;
+            }
+        };
+        let expected_scale = CONFIG.expected_output_scale(&output);
+        let mismatch = if (scale - expected_scale).abs() > 0.01 {
+            self.color = Color::Warning;
+            format!(" (expected {}x)", expected_scale)
+        } else {
+            self.color = Color::Neutral;
+            String::new()
+        };
+        self.full_text = Some(render(
+            CONFIG.format(self.name).as_deref().unwrap_or(FORMAT),
+            &[
+                ("output", output.into()),
+                ("width", number(width as f64)),
+                ("height", number(height as f64)),
+                ("scale", number(scale)),
+                ("expected", number(expected_scale)),
+                ("mismatch", mismatch.into()),
+            ],
+        ));
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        if let Some(error_msg) = &self.error {
+            return Err(WidgetError::new(format!(
+                "Error occured when trying to read the outputs from i3.\n{}",
+                error_msg
+            )));
+        }
+
+        Ok(serde_json::to_value(self)?)
+    }
+}
@@ -0,0 +1,196 @@
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::click_events::{ClickEvent, SCROLL_DOWN, SCROLL_UP};
+use crate::ddc::{DdcMonitor, VcpValue, VCP_BRIGHTNESS, VCP_CONTRAST};
+use crate::i3_status::CONFIG;
+use crate::theme::Color;
+use crate::utils::template::{number, render, Placeholder};
+use crate::widgets::{Clickable, Widget, WidgetError};
+
+// Percentage that is added / subtracted when scrolling on the widget
+const STEP: f32 = 5.0;
+// DDC/CI requests are slow, so the values are only read every now and then
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+// Placeholders: {monitors} (brightness and contrast of every monitor, e.g.
+// "B: 80% C: 50% | B: 60% C: 50%"), {brightness} and {contrast} (in percent, of the first
+// monitor, empty if unknown)
+const FORMAT: &str = "🖵 {monitors}";
+
+struct Monitor {
+    ddc: DdcMonitor,
+    brightness: Option<VcpValue>,
+    contrast: Option<VcpValue>,
+}
+
+#[derive(Serialize)]
+pub struct ExternalMonitor {
+    // Name of the widget
+    name: &'static str,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    #[serde(skip_serializing)]
+    // Holds the error message if an error occured during widget update
+    error: Option<String>,
+    #[serde(skip_serializing)]
+    monitors: Vec<Monitor>,
+    #[serde(skip_serializing)]
+    // When the values were read from the monitors the last time
+    last_read: Option<Instant>,
+}
+
+impl ExternalMonitor {
+    pub fn new() -> Self {
+        let mut monitors = Vec::new();
+        for i2c_device in CONFIG.ddc_i2c_devices() {
+            match DdcMonitor::open(&i2c_device) {
+                Ok(ddc) => monitors.push(Monitor {
+                    ddc,
+                    brightness: None,
+                    contrast: None,
+                }),
+                Err(error) => log::error!("Could not open {}: {}", i2c_device, error),
+            }
+        }
+
+        Self {
+            name: "external_monitor",
+            full_text: None,
+            color: Color::Accent,
+            error: None,
+            monitors,
+            last_read: None,
+        }
+    }
+
+    fn read_values(&mut self) -> Result<(), WidgetError> {
+        for monitor in self.monitors.iter_mut() {
+            monitor.brightness = Some(monitor.ddc.get_vcp_feature(VCP_BRIGHTNESS)?);
+            monitor.contrast = Some(monitor.ddc.get_vcp_feature(VCP_CONTRAST)?);
+        }
+        self.last_read = Some(Instant::now());
+
+        Ok(())
+    }
+
+    // Change a VCP feature of all monitors by the given percentage
+    fn change_feature(&mut self, feature: u8, percentage: f32) -> Result<(), WidgetError> {
+        for monitor in self.monitors.iter_mut() {
+            let value = if feature == VCP_BRIGHTNESS {
+                &mut monitor.brightness
+            } else {
+                &mut monitor.contrast
+            };
+            if let Some(value) = value {
+                let step = value.maximum as f32 * percentage / 100.0;
+                value.current =
+                    (value.current as f32 + step).clamp(0.0, value.maximum as f32) as u16;
+                monitor.ddc.set_vcp_feature(feature, value.current)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn percent(value: &Option<VcpValue>) -> Option<f32> {
+        value
+            .as_ref()
+            .map(|value| value.current as f32 / value.maximum as f32 * 100.0)
+    }
+
+    fn percentage(value: &Option<VcpValue>) -> String {
+        match Self::percent(value) {
+            Some(percent) => format!("{:.0}%", percent),
+            None => String::from("?"),
+        }
+    }
+
+    // Placeholder of a value of the first monitor
+    fn first_percent(&self, value: fn(&Monitor) -> &Option<VcpValue>) -> Placeholder {
+        match self
+            .monitors
+            .first()
+            .and_then(|monitor| Self::percent(value(monitor)))
+        {
+            Some(percent) => number(percent),
+            None => Placeholder::from(""),
+        }
+    }
+}
+
+impl Clickable for ExternalMonitor {
+    // Scrolling changes the brightness, scrolling while holding shift changes the contrast
+    fn click(&mut self, event: &ClickEvent) {
+        let feature = if event.modifiers.iter().any(|modifier| modifier == "Shift") {
+            VCP_CONTRAST
+        } else {
+            VCP_BRIGHTNESS
+        };
+        let result = match event.button {
+            SCROLL_UP => self.change_feature(feature, STEP),
+            SCROLL_DOWN => self.change_feature(feature, -STEP),
+            _ => Ok(()),
+        };
+
+        if let Err(error) = result {
+            log::error!("Could not change monitor settings.\n{}", error);
+        }
+    }
+}
+
+impl Widget for ExternalMonitor {
+    fn update(&mut self) {
+        self.error = None;
+        if self
+            .last_read
+            .is_none_or(|last_read| last_read.elapsed() >= POLL_INTERVAL)
+        {
+            if let Err(error) = self.read_values() {
+                self.error = Some(error.to_string());
+                return;
+            }
+        }
+
+        let monitors = self
+            .monitors
+            .iter()
+            .map(|monitor| {
+                format!(
+                    "B: {} C: {}",
+                    Self::percentage(&monitor.brightness),
+                    Self::percentage(&monitor.contrast)
+                )
+            })
+            .collect::<Vec<String>>();
+        self.full_text = Some(render(
+            CONFIG.format(self.name).as_deref().unwrap_or(FORMAT),
+            &[
+                ("monitors", monitors.join(" | ").into()),
+                (
+                    "brightness",
+                    self.first_percent(|monitor| &monitor.brightness),
+                ),
+                ("contrast", self.first_percent(|monitor| &monitor.contrast)),
+            ],
+        ));
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        if let Some(error_msg) = &self.error {
+            log::error!(
+                "Error occured when trying to read external monitor settings.\n{}",
+                error_msg
+            );
+        }
+
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn clickable(&mut self) -> Option<&mut dyn Clickable> {
+        Some(self)
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::BTreeMap;
+use std::fs::write;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::click_events::{ClickEvent, BUTTON_RIGHT};
+use crate::events::WidgetEvent;
+use crate::i3_ipc::{I3Ipc, EVENT_MASK, GET_WORKSPACES};
+use crate::i3_status::CONFIG;
+use crate::theme::Color;
+use crate::utils::idle::IdleMonitor;
+use crate::utils::state::{load_state, save_state, state_directory};
+use crate::utils::stop::{stop_signal, StopHandle, StopSignal};
+use crate::utils::template::{number, render};
+use crate::widgets::{Clickable, Widget, WidgetError};
+
+// How often the tracked time is written to the state file
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+// Placeholders: {hours} and {minutes} (with two digits) of the time worked today, and
+// {total_minutes}
+const FORMAT: &str = "worked {hours}h{minutes}m";
+
+#[derive(Default, Serialize, Deserialize)]
+struct FocusState {
+    // Day the tracked time belongs to (YYYY-MM-DD)
+    date: String,
+    // Active time of the day in milliseconds
+    active: u64,
+    // Active time per workspace in milliseconds
+    workspaces: BTreeMap<String, u64>,
+}
+
+#[derive(Serialize)]
+pub struct FocusTracker {
+    // Name of the widget
+    name: &'static str,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    #[serde(skip_serializing)]
+    // Holds the error message if an error occured during widget update
+    error: Option<String>,
+    #[serde(skip_serializing)]
+    state: FocusState,
+    #[serde(skip_serializing)]
+    idle_monitor: Option<IdleMonitor>,
+    #[serde(skip_serializing)]
+    // Name of the focused workspace, updated by the i3 IPC listener
+    focused_workspace: Arc<Mutex<Option<String>>>,
+    #[serde(skip_serializing)]
+    // Stops the i3 IPC listener when the widget is dropped
+    stop: StopHandle,
+    #[serde(skip_serializing)]
+    last_update: Instant,
+    #[serde(skip_serializing)]
+    last_save: Instant,
+}
+
+impl FocusTracker {
+    pub fn new() -> Self {
+        let idle_monitor = match IdleMonitor::new() {
+            Ok(idle_monitor) => Some(idle_monitor),
+            Err(error) => {
+                log::error!("Idle detection is not available: {}", error);
+                None
+            }
+        };
+        let focused_workspace = Arc::new(Mutex::new(None));
+        let (stop, signal) = stop_signal();
+        if CONFIG.focus_track_workspaces() {
+            Self::listen_for_workspace_focus(Arc::clone(&focused_workspace), signal);
+        }
+
+        Self {
+            name: "focus_tracker",
+            full_text: None,
+            color: Color::Neutral,
+            error: None,
+            state: load_state("focus_tracker").unwrap_or_default(),
+            idle_monitor,
+            focused_workspace,
+            stop,
+            last_update: Instant::now(),
+            last_save: Instant::now(),
+        }
+    }
+
+    // Keep track of the focused workspace through i3 IPC workspace events
+    fn listen_for_workspace_focus(
+        focused_workspace: Arc<Mutex<Option<String>>>,
+        signal: StopSignal,
+    ) {
+        thread::spawn(move || {
+            let result = (|| -> Result<(), WidgetError> {
+                let mut ipc = I3Ipc::connect()?;
+                if !signal.on_stop(ipc.shutdown_handle()?) {
+                    return Ok(());
+                }
+                let workspaces: Value = serde_json::from_str(&ipc.request(GET_WORKSPACES, "")?)?;
+                if let Some(workspaces) = workspaces.as_array() {
+                    for workspace in workspaces {
+                        if workspace["focused"] == true {
+                            *focused_workspace.lock().unwrap() =
+                                workspace["name"].as_str().map(String::from);
+                        }
+                    }
+                }
+
+                ipc.subscribe(&["workspace"])?;
+                loop {
+                    let (message_type, payload) = ipc.receive()?;
+                    if message_type & EVENT_MASK == 0 {
+                        continue;
+                    }
+                    let event: Value = serde_json::from_str(&payload)?;
+                    if event["change"] == "focus" {
+                        *focused_workspace.lock().unwrap() =
+                            event["current"]["name"].as_str().map(String::from);
+                    }
+                }
+            })();
+
+            // Stopping the widget closes the connection, which is not an error
+            if signal.is_stopped() {
+                return;
+            }
+            if let Err(error) = result {
+                log::error!("Workspace tracking stopped: {}", error);
+            }
+        });
+    }
+
+    fn is_active(&self) -> Result<bool, WidgetError> {
+        match &self.idle_monitor {
+            Some(idle_monitor) => {
+                Ok(idle_monitor.idle_time()? < Duration::from_secs(CONFIG.idle_timeout()))
+            }
+            None => Err(WidgetError::new(String::from(
+                "Idle detection is not available",
+            ))),
+        }
+    }
+
+    // Export the tracked time of today as CSV into the state directory
+    fn export_csv(&self) -> Result<(), WidgetError> {
+        let mut csv = String::from("date,workspace,seconds\n");
+        for (workspace, active) in self.state.workspaces.iter() {
+            csv += &format!("{},{},{}\n", self.state.date, workspace, active / 1000);
+        }
+        csv += &format!("{},total,{}\n", self.state.date, self.state.active / 1000);
+        write(
+            state_directory().join(format!("focus_tracker_{}.csv", self.state.date)),
+            csv,
+        )?;
+
+        Ok(())
+    }
+}
+
+impl Clickable for FocusTracker {
+    fn click(&mut self, event: &ClickEvent) {
+        if event.button == BUTTON_RIGHT {
+            if let Err(error) = self.export_csv() {
+                log::error!("Could not export focus time.\n{}", error);
+            }
+        }
+    }
+}
+
+impl Drop for FocusTracker {
+    fn drop(&mut self) {
+        self.stop.stop();
+    }
+}
+
+impl Widget for FocusTracker {
+    fn update(&mut self) {
+        self.error = None;
+        let elapsed = self.last_update.elapsed().as_millis() as u64;
+        self.last_update = Instant::now();
+
+        // Start from zero every day
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        if self.state.date != today {
+            self.state = FocusState {
+                date: today,
+                ..Default::default()
+            };
+        }
+
+        match self.is_active() {
+            Ok(true) => {
+                self.state.active += elapsed;
+                if let Some(workspace) = self.focused_workspace.lock().unwrap().as_ref() {
+                    *self.state.workspaces.entry(workspace.clone()).or_default() += elapsed;
+                }
+                self.color = Color::Good;
+            }
+            Ok(false) => self.color = Color::Neutral,
+            Err(error) => self.error = Some(error.to_string()),
+        }
+
+        if self.last_save.elapsed() >= SAVE_INTERVAL {
+            self.last_save = Instant::now();
+            if let Err(error) = save_state(self.name, &self.state) {
+                self.error = Some(error.to_string());
+            }
+        }
+
+        let minutes = self.state.active / 1000 / 60;
+        self.full_text = Some(render(
+            CONFIG.format(self.name).as_deref().unwrap_or(FORMAT),
+            &[
+                ("hours", number((minutes / 60) as f64)),
+                ("minutes", format!("{:02}", minutes % 60).into()),
+                ("total_minutes", number(minutes as f64)),
+            ],
+        ));
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        if let Some(error_msg) = &self.error {
+            log::error!(
+                "Error occured when trying to track focus time.\n{}",
+                error_msg
+            );
+        }
+
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn clickable(&mut self) -> Option<&mut dyn Clickable> {
+        Some(self)
+    }
+
+    // Start the new day right at midnight
+    fn events(&self) -> &[WidgetEvent] {
+        &[WidgetEvent::NewDay]
+    }
+}
@@ -0,0 +1,198 @@
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::click_events::{ClickEvent, BUTTON_LEFT};
+use crate::config::{HomeAssistantEntity, WidgetId};
+use crate::i3_status::CONFIG;
+use crate::replay::http_agent;
+use crate::signals::is_low_power;
+use crate::theme::Color;
+use crate::utils::secrets::resolve_secret;
+use crate::utils::template::{render, Placeholder};
+use crate::widgets::{Clickable, Widget, WidgetError};
+
+// Home Assistant may be slow, but it should not keep the request thread busy forever
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// Placeholders: {label}, {state} (e.g. "21.5" or "on", "?" if Home Assistant does not answer
+// or "..." before the first answer), {unit} (e.g. "°C", empty for entities without unit)
+// and {value} (the state with its unit, e.g. "21.5 °C")
+const FORMAT: &str = "{label} {value}";
+
+// State of an entity and its unit of measurement
+type EntityState = (String, Option<String>);
+
+#[derive(Serialize)]
+pub struct HomeAssistant {
+    // Name of the widget
+    name: &'static str,
+    #[serde(skip_serializing)]
+    // "home_assistant:<instance>", e.g. to look up the format of a single entity
+    id: WidgetId,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    #[serde(skip_serializing)]
+    label: String,
+    #[serde(skip_serializing)]
+    toggle: bool,
+    #[serde(skip_serializing)]
+    // Latest state of the entity, updated by the request thread
+    state: Arc<Mutex<Option<Result<EntityState, String>>>>,
+    #[serde(skip_serializing)]
+    // Asks the request thread to toggle the entity
+    toggle_requests: Sender<()>,
+}
+
+impl HomeAssistant {
+    pub fn new(id: WidgetId, entity: HomeAssistantEntity) -> Self {
+        let state = Arc::new(Mutex::new(None));
+        let (toggle_requests, receiver) = channel();
+        let entity_id = entity.entity_id;
+        let thread_state = Arc::clone(&state);
+        thread::spawn(move || {
+            let token = match resolve_secret(&CONFIG.home_assistant_token()) {
+                Ok(token) => token,
+                Err(error) => {
+                    *thread_state.lock().unwrap() = Some(Err(error.to_string()));
+                    log::error!("Could not get the Home Assistant token: {}", error);
+                    return;
+                }
+            };
+            let agent = http_agent(REQUEST_TIMEOUT);
+            loop {
+                if !is_low_power() {
+                    let result = Self::request_state(&agent, &token, &entity_id);
+                    if let Err(error) = &result {
+                        log::warn!("Could not get the state of {}: {}", entity_id, error);
+                    }
+                    *thread_state.lock().unwrap() = Some(result.map_err(|error| error.to_string()));
+                }
+
+                // Wait for the next poll, unless the entity should be toggled
+                match receiver.recv_timeout(Duration::from_secs(CONFIG.home_assistant_interval())) {
+                    Ok(()) => {
+                        if let Err(error) = Self::toggle_entity(&agent, &token, &entity_id) {
+                            log::error!("Could not toggle {}: {}", entity_id, error);
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => (),
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+        });
+
+        Self {
+            name: "home_assistant",
+            id,
+            full_text: None,
+            color: Color::Neutral,
+            label: entity.label,
+            toggle: entity.toggle,
+            state,
+            toggle_requests,
+        }
+    }
+
+    // See https://developers.home-assistant.io/docs/api/rest/
+    fn request_state(
+        agent: &ureq::Agent,
+        token: &str,
+        entity_id: &str,
+    ) -> Result<EntityState, WidgetError> {
+        let response: Value = serde_json::from_str(
+            &agent
+                .get(&format!(
+                    "{}/api/states/{}",
+                    CONFIG.home_assistant_url(),
+                    entity_id
+                ))
+                .set("Authorization", &format!("Bearer {}", token))
+                .call()?
+                .into_string()?,
+        )?;
+
+        Ok((
+            response["state"].as_str().unwrap_or("?").to_string(),
+            response["attributes"]["unit_of_measurement"]
+                .as_str()
+                .map(str::to_string),
+        ))
+    }
+
+    fn toggle_entity(agent: &ureq::Agent, token: &str, entity_id: &str) -> Result<(), WidgetError> {
+        agent
+            .post(&format!(
+                "{}/api/services/homeassistant/toggle",
+                CONFIG.home_assistant_url()
+            ))
+            .set("Authorization", &format!("Bearer {}", token))
+            .set("Content-Type", "application/json")
+            .send_string(&json!({ "entity_id": entity_id }).to_string())?;
+
+        Ok(())
+    }
+}
+
+impl Widget for HomeAssistant {
+    fn update(&mut self) {
+        let (state, unit) = match &*self.state.lock().unwrap() {
+            Some(Ok((state, unit))) => {
+                self.color = match state.as_str() {
+                    "unavailable" | "unknown" => Color::Critical,
+                    "on" | "open" | "unlocked" => Color::Accent,
+                    _ => Color::Neutral,
+                };
+                (state.clone(), unit.clone())
+            }
+            Some(Err(_)) => {
+                self.color = Color::Critical;
+                (String::from("?"), None)
+            }
+            // The first request is still running
+            None => (String::from("..."), None),
+        };
+        let value = match &unit {
+            Some(unit) => format!("{} {}", state, unit),
+            None => state.clone(),
+        };
+        self.full_text = Some(render(
+            CONFIG
+                .format(&self.id.to_string())
+                .as_deref()
+                .unwrap_or(FORMAT),
+            &[
+                ("label", self.label.clone().into()),
+                ("state", Placeholder::parsed(state)),
+                ("unit", unit.unwrap_or_default().into()),
+                ("value", value.into()),
+            ],
+        ));
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn clickable(&mut self) -> Option<&mut dyn Clickable> {
+        if self.toggle {
+            Some(self)
+        } else {
+            None
+        }
+    }
+}
+
+impl Clickable for HomeAssistant {
+    fn click(&mut self, event: &ClickEvent) {
+        if event.button == BUTTON_LEFT {
+            // The request thread only stops when the widget is dropped
+            let _ = self.toggle_requests.send(());
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::events::WidgetEvent;
+use crate::i3_status::CONFIG;
+use crate::theme::Color;
+use crate::utils::template::{number, render};
+use crate::utils::xkb::Xkb;
+use crate::widgets::{Widget, WidgetError};
+
+// Shown by {compose} while a compose sequence or a latched key waits for the next key
+const COMPOSE_TEXT: &str = " ⎄";
+// Placeholders: {group} (the selected layout group, 1 for the first layout of e.g. "us,de")
+// and {compose} (" ⎄" while the next key is combined with the previous ones, empty otherwise)
+const FORMAT: &str = "⌨ {group}{compose}";
+
+#[derive(Serialize)]
+pub struct Keyboard {
+    // Name of the widget
+    name: &'static str,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    #[serde(skip_serializing)]
+    // Holds the error message if an error occured during widget update
+    error: Option<String>,
+    #[serde(skip_serializing)]
+    // Connection to the X server, opened again on the next update if it failed
+    xkb: Option<Xkb>,
+}
+
+impl Keyboard {
+    pub fn new() -> Self {
+        Self {
+            name: "keyboard",
+            full_text: None,
+            color: Color::Neutral,
+            error: None,
+            xkb: None,
+        }
+    }
+}
+
+impl Widget for Keyboard {
+    fn update(&mut self) {
+        self.error = None;
+        let xkb = match &self.xkb {
+            Some(xkb) => xkb,
+            None => match Xkb::new() {
+                Ok(xkb) => self.xkb.insert(xkb),
+                Err(error) => {
+                    self.error = Some(error.to_string());
+                    return;
+                }
+            },
+        };
+        let state = match xkb.state() {
+            Ok(state) => state,
+            Err(error) => {
+                self.error = Some(error.to_string());
+                self.xkb = None;
+                return;
+            }
+        };
+
+        self.color = if state.composing {
+            Color::Accent
+        } else {
+            Color::Neutral
+        };
+        self.full_text = Some(render(
+            CONFIG.format(self.name).as_deref().unwrap_or(FORMAT),
+            &[
+                ("group", number(state.group + 1)),
+                (
+                    "compose",
+                    if state.composing { COMPOSE_TEXT } else { "" }.into(),
+                ),
+            ],
+        ));
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        if let Some(error_msg) = &self.error {
+            return Err(WidgetError::new(format!(
+                "Error occured when trying to read the keyboard state.\n{}",
+                error_msg
+            )));
+        }
+
+        Ok(serde_json::to_value(self)?)
+    }
+
+    // Switching the layout should show up right away and not at the next tick
+    fn events(&self) -> &[WidgetEvent] {
+        &[WidgetEvent::Keyboard]
+    }
+}
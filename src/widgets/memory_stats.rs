@@ -5,13 +5,18 @@ use std::num::ParseFloatError;
 use serde::Serialize;
 use serde_json::Value;
 
-use crate::config::NEUTRAL;
-use crate::config::RED;
+use crate::i3_status::CONFIG;
+use crate::icons::Icon;
+use crate::theme::Color;
 use crate::utils::file::read_file;
+use crate::utils::template::{number, render};
 use crate::widgets::Widget;
 use crate::widgets::WidgetError;
 
 const MEMORY_THRESHOLD: f32 = 25.0;
+// Placeholders: {icon}, {used}, {available} and {total} in GiB, {percent} of used memory
+const FORMAT: &str = "{icon} (GiB): U={used:.1} A={available:.1} / {total:.1}";
+const SHORT_FORMAT: &str = "{icon}: {percent:.0}%";
 
 #[derive(Debug)]
 struct MemoryInfromation {
@@ -39,7 +44,13 @@ pub struct MemoryUsage {
     // Text that will be shown in the status bar
     full_text: Option<String>,
     // Color of the text
-    color: &'static str,
+    color: Color,
+    #[serde(skip_serializing)]
+    // Shorter text that is shown when the bar runs out of space
+    short_text: Option<String>,
+    #[serde(skip_serializing)]
+    // Numeric value of the widget, used for thresholds
+    value: Option<f32>,
     #[serde(skip_serializing)]
     // Holds the error message if an error occured during widget update
     error: Option<String>,
@@ -50,8 +61,10 @@ impl MemoryUsage {
         Self {
             name: "memory",
             full_text: None,
-            color: NEUTRAL,
+            color: Color::Neutral,
             error: None,
+            short_text: None,
+            value: None,
         }
     }
 
@@ -112,24 +125,34 @@ impl MemoryUsage {
 }
 
 impl Widget for MemoryUsage {
-    fn name(&self) -> &str {
-        self.name
-    }
-
     fn update(&mut self) {
         self.error = None;
         match self.get_usage() {
             Ok(usage) => {
-                self.color = if (usage.used / usage.total_usable * 100.0) > MEMORY_THRESHOLD {
-                    RED
+                let used_percentage = usage.used / usage.total_usable * 100.0;
+                self.value = Some(used_percentage);
+                self.color = if used_percentage > MEMORY_THRESHOLD {
+                    Color::Critical
                 } else {
-                    NEUTRAL
+                    Color::Neutral
                 };
-                self.full_text = Some(format!(
-                    "RAM (GiB): U={used:.1} A={available:.1} / {total_usable:.1}",
-                    used = usage.used / 1024.0 / 1024.0,
-                    available = usage.available / 1024.0 / 1024.0,
-                    total_usable = usage.total_usable / 1024.0 / 1024.0
+                let placeholders = [
+                    ("icon", Icon::Memory.glyph().into()),
+                    ("used", number(usage.used / 1024.0 / 1024.0)),
+                    ("available", number(usage.available / 1024.0 / 1024.0)),
+                    ("total", number(usage.total_usable / 1024.0 / 1024.0)),
+                    ("percent", number(used_percentage)),
+                ];
+                self.short_text = Some(render(
+                    CONFIG
+                        .short_format(self.name)
+                        .as_deref()
+                        .unwrap_or(SHORT_FORMAT),
+                    &placeholders,
+                ));
+                self.full_text = Some(render(
+                    CONFIG.format(self.name).as_deref().unwrap_or(FORMAT),
+                    &placeholders,
                 ));
             }
             Err(error) => self.error = Some(error.to_string()),
@@ -146,4 +169,12 @@ impl Widget for MemoryUsage {
 
         Ok(serde_json::to_value(self)?)
     }
+
+    fn short_text(&self) -> Option<String> {
+        self.short_text.clone()
+    }
+
+    fn value(&self) -> Option<f32> {
+        self.value
+    }
 }
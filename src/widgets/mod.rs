@@ -1,27 +1,89 @@
 use serde_json::Value;
 
+use crate::click_events::ClickEvent;
+use crate::events::WidgetEvent;
+
 use std::error::Error;
 use std::fmt;
 use std::io;
+use std::num::{ParseFloatError, ParseIntError};
 use std::string::FromUtf8Error;
 
+pub mod ad_blocker;
+pub mod agent_status;
+pub mod alarm;
 pub mod battery_life;
+pub mod break_reminder;
 pub mod brightness;
 pub mod cpu_stats;
+pub mod derived;
 pub mod disk_stats;
+pub mod display_scale;
+pub mod external_monitor;
+pub mod focus_tracker;
+pub mod home_assistant;
+pub mod keyboard;
 pub mod memory_stats;
+pub mod mqtt_subscriber;
 pub mod network_information;
+pub mod plugin;
+pub mod pomodoro;
+pub mod presentation_mode;
+pub mod printer;
+pub mod remote;
+pub mod screen_time;
+pub mod script;
+pub mod security_key;
+pub mod self_monitor;
+pub mod snmp_poller;
+pub mod syncthing;
+pub mod task_watcher;
 pub mod time;
+pub mod time_tracking;
+pub mod timer;
+pub mod transfer;
+pub mod webdav_quota;
 
 // All widgets HAVE to implement this trait
-pub trait Widget {
-    // Get name of the widget
-    fn name(&self) -> &str;
+pub trait Widget: Send {
     // Update widget values
     fn update(&mut self);
     // The text that will be shown on the status bar
     // This method returns the full_text and the color the text should have
     fn display_text(&self) -> Result<Value, WidgetError>;
+    // Shorter version of the text, used by i3bar when the bar runs out of space
+    fn short_text(&self) -> Option<String> {
+        None
+    }
+    // The most important numeric value of the widget (e.g. battery percentage)
+    // This is used to check whether the configured thresholds are crossed
+    fn value(&self) -> Option<f32> {
+        None
+    }
+    // Whether the widget has nothing to show right now (e.g. a network device that is down)
+    // Such widgets are hidden if hide_when_empty is configured
+    fn is_empty(&self) -> bool {
+        false
+    }
+    // Kernel events after which the widget is updated right away
+    fn events(&self) -> &[WidgetEvent] {
+        &[]
+    }
+    // Widgets that show seconds are updated again right before their text is rendered
+    // Otherwise slow widgets before them can delay the bar until the shown second is over
+    fn updates_on_render(&self) -> bool {
+        false
+    }
+    // Widgets that react to mouse clicks return themselves here
+    fn clickable(&mut self) -> Option<&mut dyn Clickable> {
+        None
+    }
+}
+
+// Widgets that want to react to i3bar click events have to implement this trait
+pub trait Clickable {
+    // Handle a click on the block of the widget
+    fn click(&mut self, event: &ClickEvent);
 }
 
 // This should be used to signal that a widget is not working properly
@@ -58,8 +120,26 @@ impl From<FromUtf8Error> for WidgetError {
     }
 }
 
+impl From<ParseFloatError> for WidgetError {
+    fn from(item: ParseFloatError) -> Self {
+        WidgetError::new(item.to_string())
+    }
+}
+
+impl From<ParseIntError> for WidgetError {
+    fn from(item: ParseIntError) -> Self {
+        WidgetError::new(item.to_string())
+    }
+}
+
 impl From<serde_json::Error> for WidgetError {
     fn from(item: serde_json::Error) -> Self {
         WidgetError::new(item.to_string())
     }
 }
+
+impl From<ureq::Error> for WidgetError {
+    fn from(item: ureq::Error) -> Self {
+        WidgetError::new(item.to_string())
+    }
+}
@@ -0,0 +1,187 @@
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::{MqttBroker, MqttTopic};
+use crate::mqtt::{topic_matches, MqttClient};
+use crate::theme::Color;
+use crate::utils::json_path::select;
+use crate::utils::secrets::resolve_secret;
+use crate::utils::stop::{stop_signal, StopHandle, StopSignal};
+use crate::utils::template::{render, Placeholder};
+use crate::widgets::{Widget, WidgetError};
+
+// Time between two connection attempts when the broker is not reachable
+const RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
+struct Messages {
+    // Latest formatted message of every configured topic, in the order of the config
+    values: Vec<Option<String>>,
+    // Why the connection to the broker was lost
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct MqttSubscriber {
+    // Name of the widget
+    name: &'static str,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    #[serde(skip_serializing)]
+    // Updated by the thread that receives the messages
+    messages: Arc<Mutex<Messages>>,
+    #[serde(skip_serializing)]
+    // Stops the receiving thread when the widget is dropped, otherwise it would keep the
+    // connection with the same client ID as the new widget after a reload
+    stop: StopHandle,
+}
+
+impl MqttSubscriber {
+    pub fn new(instance: &str, broker: MqttBroker) -> Self {
+        let messages = Arc::new(Mutex::new(Messages {
+            values: vec![None; broker.topics.len()],
+            error: None,
+        }));
+        let client_id = format!("i3rustus-{}-{}", process::id(), instance);
+        let (stop, signal) = stop_signal();
+        Self::listen_for_messages(broker, client_id, Arc::clone(&messages), signal);
+
+        Self {
+            name: "mqtt",
+            full_text: None,
+            color: Color::Neutral,
+            messages,
+            stop,
+        }
+    }
+
+    fn format_payload(topic: &MqttTopic, payload: &[u8]) -> String {
+        let payload = String::from_utf8_lossy(payload);
+        let value = match topic.path.as_deref() {
+            Some(path) => match serde_json::from_str::<Value>(&payload) {
+                Ok(json) => match select(&json, path) {
+                    Some(Value::String(text)) => text.clone(),
+                    Some(value) => value.to_string(),
+                    None => String::from("?"),
+                },
+                Err(_) => String::from("?"),
+            },
+            None => payload.trim().to_string(),
+        };
+
+        render(&topic.format, &[("value", Placeholder::parsed(value))])
+    }
+
+    fn receive_messages(
+        broker: &MqttBroker,
+        client_id: &str,
+        messages: &Mutex<Messages>,
+        signal: &StopSignal,
+    ) -> Result<(), WidgetError> {
+        let password = broker
+            .credentials
+            .as_ref()
+            .map(|(_, password)| resolve_secret(password))
+            .transpose()?;
+        let credentials = broker
+            .credentials
+            .as_ref()
+            .zip(password.as_deref())
+            .map(|((username, _), password)| (username.as_str(), password));
+        let mut client = MqttClient::connect(&broker.address, client_id, credentials)?;
+        if !signal.on_stop(client.shutdown_handle()?) {
+            return Ok(());
+        }
+        let topics: Vec<&str> = broker
+            .topics
+            .iter()
+            .map(|topic| topic.topic.as_str())
+            .collect();
+        client.subscribe(&topics)?;
+        messages.lock().unwrap().error = None;
+
+        loop {
+            let message = client.receive()?;
+            let mut messages = messages.lock().unwrap();
+            for (index, topic) in broker.topics.iter().enumerate() {
+                if topic_matches(&topic.topic, &message.topic) {
+                    messages.values[index] = Some(Self::format_payload(topic, &message.payload));
+                }
+            }
+        }
+    }
+
+    // Messages arrive whenever the broker has them, so they are received in their own thread
+    fn listen_for_messages(
+        broker: MqttBroker,
+        client_id: String,
+        messages: Arc<Mutex<Messages>>,
+        signal: StopSignal,
+    ) {
+        thread::spawn(move || loop {
+            let result = Self::receive_messages(&broker, &client_id, &messages, &signal);
+            // Stopping the widget closes the connection, which is not an error
+            if signal.is_stopped() {
+                return;
+            }
+            if let Err(error) = result {
+                log::warn!(
+                    "Lost connection to MQTT broker {}: {}",
+                    broker.address,
+                    error
+                );
+                messages.lock().unwrap().error = Some(error.to_string());
+            }
+            if !signal.sleep(RECONNECT_DELAY) {
+                return;
+            }
+        });
+    }
+}
+
+impl Drop for MqttSubscriber {
+    fn drop(&mut self) {
+        self.stop.stop();
+    }
+}
+
+impl Widget for MqttSubscriber {
+    fn update(&mut self) {
+        let messages = self.messages.lock().unwrap();
+        if messages.error.is_some() {
+            self.full_text = Some(String::from("MQTT: disconnected"));
+            self.color = Color::Critical;
+            return;
+        }
+
+        // Topics without a message yet are left out
+        let values: Vec<&str> = messages
+            .values
+            .iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        self.full_text = Some(if values.is_empty() {
+            String::from("MQTT: ...")
+        } else {
+            values.join(" ")
+        });
+        self.color = Color::Neutral;
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    // The broker could not be reached
+    fn is_empty(&self) -> bool {
+        self.color == Color::Critical
+    }
+}
@@ -1,20 +1,39 @@
 use serde::Serialize;
 use serde_json::Value;
+use std::sync::Arc;
 
-use crate::config::GREEN;
-use crate::config::RED;
+use crate::events::WidgetEvent;
 use crate::i3_status::CONFIG;
-use crate::netlink::Netlink;
+use crate::icons::{Icon, Ramp};
+use crate::os::{interface_kind, Duplex, InterfaceAddresses, InterfaceKind, Network};
+use crate::theme::Color;
+use crate::utils::template::{number, render, Placeholder};
 use crate::widgets::Widget;
 use crate::widgets::WidgetError;
 
 static ETH_DEFAULT: &str = "E: down";
 static WIFI_DEFAULT: &str = "W: down";
+static NETWORK_DEFAULT: &str = "N: down";
+// Placeholders: {icon}, {speed} in Mb/s, {duplex} (full or half), {ipv4}, {ipv6}
+// {bitrate} is the same as {speed}, {ip} the same as {ipv4}
+const ETH_FORMAT: &str = "{icon}: S={speed} Mb/s {duplex} => {ip}";
+// Placeholders: {icon}, {ssid}, {signal} in percent, {signal_dbm}, {signal_icon}, {frequency} in
+// GHz, {bitrate} in Mb/s, {ipv4}, {ipv6}
+// {ip} is the same as {ipv4}
+const WIFI_FORMAT: &str =
+    "{icon}: SSID={ssid} Q={signal}% F={frequency} GHz S={bitrate} Mb/s => {ip}";
+// Used by the network widget for VPNs and tethered phones, wireless and ethernet connections
+// are shown with the formats of the widgets of the same name
+// Placeholders: {icon}, {device}, {ipv4}, {ipv6}
+// {ip} is the same as {ipv4}
+const NETWORK_FORMAT: &str = "{icon}: {device} => {ip}";
 
 #[derive(PartialEq, Eq)]
 pub enum NetworkType {
     Ethernet,
     Wlan,
+    // Whichever interface carries the default route
+    DefaultRoute,
 }
 
 #[derive(Serialize)]
@@ -24,132 +43,275 @@ pub struct NetworkInformation {
     // Text that will be shown in the status bar
     full_text: String,
     // Color of the text
-    color: &'static str,
-    // Device name
-    device_name: &'static str,
+    color: Color,
+    // Device name, i3bar ignores keys that start with an underscore
+    #[serde(rename = "_device_name")]
+    device_name: String,
     #[serde(skip_serializing)]
     network_type: NetworkType,
     #[serde(skip_serializing)]
     // Holds the error message if an error occured during widget update
     error: Option<String>,
     #[serde(skip_serializing)]
-    netlink: Result<Netlink, std::io::Error>,
+    network: Result<Arc<Network>, std::io::Error>,
     #[serde(skip_serializing)]
     default_full_text: String,
+    #[serde(skip_serializing)]
+    // Shorter text that is shown when the bar runs out of space
+    short_text: Option<String>,
+    #[serde(skip_serializing)]
+    // Show a global IPv6 address instead of the link local one
+    prefer_global_ipv6: bool,
+    #[serde(skip_serializing)]
+    // Up and configured, a weak signal can still make the block critical
+    connected: bool,
 }
 
 impl NetworkInformation {
     pub fn new(network_type: NetworkType) -> Self {
-        let name = if network_type == NetworkType::Wlan {
-            "wireless"
-        } else {
-            "ethernet"
-        };
-
-        let default_full_text = if network_type == NetworkType::Wlan {
-            WIFI_DEFAULT
-        } else {
-            ETH_DEFAULT
-        };
-
-        let device_name = if network_type == NetworkType::Wlan {
-            CONFIG.get_wifi_device_name()
-        } else {
-            CONFIG.get_ethernet_device_name()
+        // The network widget finds its device with every update
+        let (name, default_full_text, device_name) = match network_type {
+            NetworkType::Wlan => ("wireless", WIFI_DEFAULT, CONFIG.get_wifi_device_name()),
+            NetworkType::Ethernet => ("ethernet", ETH_DEFAULT, CONFIG.get_ethernet_device_name()),
+            NetworkType::DefaultRoute => ("network", NETWORK_DEFAULT, String::new()),
         };
 
         Self {
             name,
             full_text: default_full_text.to_string(),
-            color: RED,
-            device_name,
+            color: Color::Critical,
+            device_name: device_name.to_string(),
             network_type,
             error: None,
-            netlink: Netlink::new(),
+            network: Network::shared(),
             default_full_text: default_full_text.to_string(),
+            short_text: None,
+            prefer_global_ipv6: CONFIG.prefer_global_ipv6(name),
+            connected: false,
+        }
+    }
+
+    // {ip}, {ipv4} and {ipv6}, "????" if the interface has no such address
+    fn address_placeholders(
+        &self,
+        addresses: &InterfaceAddresses,
+    ) -> [(&'static str, Placeholder); 3] {
+        let ipv4 = addresses
+            .ipv4()
+            .map_or(String::from("????"), |address| address.to_string());
+        let ipv6 = addresses
+            .ipv6(self.prefer_global_ipv6)
+            .map_or(String::from("????"), |address| address.to_string());
+        [
+            ("ip", ipv4.clone().into()),
+            ("ipv4", ipv4.into()),
+            ("ipv6", ipv6.into()),
+        ]
+    }
+
+    // The link state comes from RTM_GETLINK, speed and duplex from ethtool
+    // A link without address or with half duplex (usually a bad cable or a
+    // misconfigured switch port) is shown as warning
+    fn get_ethernet_information(&self) -> Result<(String, Color, bool), WidgetError> {
+        if let Ok(network) = self.network.as_ref() {
+            if !network.interface_link_up(&self.device_name)? {
+                return Ok((self.default_full_text.to_string(), Color::Critical, false));
+            }
+            let addresses = network.interface_addresses(&self.device_name)?;
+            // Virtual devices and kernels before 5.6 don't report link modes
+            let (speed, duplex) = match network.interface_link_modes(&self.device_name) {
+                Ok(link_modes) => (link_modes.speed, link_modes.duplex),
+                Err(error) => {
+                    log::debug!(
+                        "Could not read the link modes of {}: {}",
+                        &self.device_name,
+                        error
+                    );
+                    (None, Duplex::Unknown)
+                }
+            };
+            let color = if addresses.is_empty() || duplex == Duplex::Half {
+                Color::Warning
+            } else {
+                Color::Good
+            };
+            let speed = || speed.map_or(Placeholder::from("?"), number);
+            let mut placeholders = vec![
+                ("icon", Icon::Ethernet.glyph().into()),
+                ("speed", speed()),
+                ("bitrate", speed()),
+                (
+                    "duplex",
+                    match duplex {
+                        Duplex::Full => "full",
+                        Duplex::Half => "half",
+                        Duplex::Unknown => "?",
+                    }
+                    .into(),
+                ),
+            ];
+            placeholders.extend(self.address_placeholders(&addresses));
+            let text = render(
+                CONFIG.format("ethernet").as_deref().unwrap_or(ETH_FORMAT),
+                &placeholders,
+            );
+            Ok((text, color, true))
+        } else {
+            Err(WidgetError::new(format!(
+                "Network socket error: {}",
+                &self.network.as_ref().unwrap_err()
+            )))
+        }
+    }
+
+    // A connection with a weak signal is shown as warning or critical, the thresholds are
+    // percentages of the signal strength
+    fn signal_color(percent: Option<u8>) -> Color {
+        let (warning, critical) = CONFIG.signal_thresholds();
+        match percent {
+            Some(percent) if percent < critical => Color::Critical,
+            Some(percent) if percent < warning => Color::Warning,
+            _ => Color::Good,
         }
     }
 
-    fn get_ethernet_information(&self) -> Result<String, WidgetError> {
-        if let Ok(netlink) = self.netlink.as_ref() {
-            let ip = netlink.interface_ip(self.device_name)?;
-            let bitrate = netlink.interface_bitrate(self.device_name)?;
-            if ip.is_empty() {
-                Ok(self.default_full_text.to_string())
+    fn get_wlan_information(&self) -> Result<(String, Color, bool), WidgetError> {
+        if let Ok(network) = self.network.as_ref() {
+            let bss = network.interface_bss_information(&self.device_name)?;
+            let addresses = network.interface_addresses(&self.device_name)?;
+            let station = network.interface_station_information(&self.device_name)?;
+            if bss.ssid.is_empty() && addresses.is_empty() {
+                Ok((self.default_full_text.to_string(), Color::Critical, false))
             } else {
-                Ok(format!("E: S={} Mb/s => {}", bitrate, ip))
+                // Connected, but the SSID or the address is still missing
+                let connected = !bss.ssid.is_empty() && !addresses.is_empty();
+                let signal = station.signal_percent();
+                let color = if connected {
+                    Self::signal_color(signal)
+                } else {
+                    Color::Critical
+                };
+                let mut placeholders = vec![
+                    ("icon", Icon::Wireless.glyph().into()),
+                    (
+                        "ssid",
+                        if bss.ssid.is_empty() {
+                            Placeholder::from("????")
+                        } else {
+                            bss.ssid.into()
+                        },
+                    ),
+                    ("signal", signal.map_or(Placeholder::from("?"), number)),
+                    (
+                        "signal_dbm",
+                        station.signal.map_or(Placeholder::from("?"), number),
+                    ),
+                    (
+                        "signal_icon",
+                        Ramp::Signal.glyph(f32::from(signal.unwrap_or(0))).into(),
+                    ),
+                    ("frequency", number(bss.frequency)),
+                    ("bitrate", number(station.bitrate)),
+                ];
+                placeholders.extend(self.address_placeholders(&addresses));
+                let text = render(
+                    CONFIG.format("wireless").as_deref().unwrap_or(WIFI_FORMAT),
+                    &placeholders,
+                );
+                Ok((text, color, connected))
             }
         } else {
             Err(WidgetError::new(format!(
-                "Netlink socket error: {}",
-                &self.netlink.as_ref().unwrap_err()
+                "Network socket error: {}",
+                &self.network.as_ref().unwrap_err()
             )))
         }
     }
 
-    fn get_wlan_information(&self) -> Result<String, WidgetError> {
-        if let Ok(netlink) = self.netlink.as_ref() {
-            let bss = netlink.interface_bss_information(self.device_name)?;
-            let ip = netlink.interface_ip(self.device_name)?;
-            let bitrate = netlink.interface_bitrate(self.device_name)?;
-            if bss.ssid.is_empty() && ip.is_empty() {
-                Ok(self.default_full_text.to_string())
+    // VPNs and tethered phones only show their addresses, speed and signal belong to the
+    // connection under them
+    fn get_tunnel_information(&self, icon: Icon) -> Result<(String, Color, bool), WidgetError> {
+        if let Ok(network) = self.network.as_ref() {
+            let addresses = network.interface_addresses(&self.device_name)?;
+            let color = if addresses.is_empty() {
+                Color::Warning
             } else {
-                Ok(format!(
-                    "W: SSID={} F={} GHz S={} Mb/s => {}",
-                    if bss.ssid.is_empty() {
-                        String::from("????")
-                    } else {
-                        bss.ssid
-                    },
-                    bss.frequency,
-                    bitrate,
-                    if ip.is_empty() {
-                        String::from("????")
-                    } else {
-                        ip
-                    },
-                ))
+                Color::Good
+            };
+            let mut placeholders = vec![
+                ("icon", icon.glyph().into()),
+                ("device", self.device_name.clone().into()),
+            ];
+            placeholders.extend(self.address_placeholders(&addresses));
+            let text = render(
+                CONFIG
+                    .format(self.name)
+                    .as_deref()
+                    .unwrap_or(NETWORK_FORMAT),
+                &placeholders,
+            );
+            Ok((text, color, true))
+        } else {
+            Err(WidgetError::new(format!(
+                "Network socket error: {}",
+                &self.network.as_ref().unwrap_err()
+            )))
+        }
+    }
+
+    // Follow the default route, the kind of its interface decides what is shown
+    fn get_default_route_information(&mut self) -> Result<(String, Color, bool), WidgetError> {
+        if let Ok(network) = self.network.as_ref() {
+            let Some(interface_name) = network.default_route_interface()? else {
+                self.device_name.clear();
+                return Ok((self.default_full_text.to_string(), Color::Critical, false));
+            };
+            let kind = interface_kind(&interface_name);
+            self.device_name = interface_name;
+            match kind {
+                InterfaceKind::Wireless => self.get_wlan_information(),
+                InterfaceKind::Ethernet => self.get_ethernet_information(),
+                InterfaceKind::Vpn => self.get_tunnel_information(Icon::Vpn),
+                InterfaceKind::Tethered => self.get_tunnel_information(Icon::Tethered),
             }
         } else {
             Err(WidgetError::new(format!(
-                "Netlink socket error: {}",
-                &self.netlink.as_ref().unwrap_err()
+                "Network socket error: {}",
+                &self.network.as_ref().unwrap_err()
             )))
         }
     }
 }
 
 impl Widget for NetworkInformation {
-    fn name(&self) -> &str {
-        self.name
-    }
-
     fn update(&mut self) {
         self.error = None;
         // Depending on the network type, we call a different method
-        let network_information = if self.network_type == NetworkType::Ethernet {
-            self.get_ethernet_information()
-        } else {
-            self.get_wlan_information()
+        let network_information = match self.network_type {
+            NetworkType::Ethernet => self.get_ethernet_information(),
+            NetworkType::Wlan => self.get_wlan_information(),
+            NetworkType::DefaultRoute => self.get_default_route_information(),
         };
 
         match network_information {
-            Ok(network_information) => {
+            Ok((network_information, color, connected)) => {
                 self.error = None;
-                self.color = if network_information[1..].eq(": down")
-                    || network_information.contains("????")
-                {
-                    RED
-                } else {
-                    GREEN
-                };
+                self.color = color;
+                self.connected = connected;
+                // Only show whether we are connected when we run out of space
+                self.short_text = Some(format!(
+                    "{}: {}",
+                    &self.default_full_text[..1],
+                    if self.connected { "up" } else { "down" }
+                ));
                 self.full_text = network_information;
             }
             Err(error) => {
                 self.error = Some(error.to_string());
-                self.color = RED;
+                self.color = Color::Critical;
+                self.connected = false;
                 self.full_text = self.default_full_text.to_string();
+                self.short_text = None;
             }
         }
     }
@@ -164,4 +326,17 @@ impl Widget for NetworkInformation {
 
         Ok(serde_json::to_value(self)?)
     }
+
+    fn short_text(&self) -> Option<String> {
+        self.short_text.clone()
+    }
+
+    // The device is down or its information could not be read
+    fn is_empty(&self) -> bool {
+        !self.connected
+    }
+
+    fn events(&self) -> &[WidgetEvent] {
+        &[WidgetEvent::Network]
+    }
 }
@@ -0,0 +1,136 @@
+use libc::c_void;
+use std::ffi::{CStr, CString};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::click_events::ClickEvent;
+use crate::plugins::Plugin;
+use crate::theme::Color;
+use crate::widgets::script::BlockOutput;
+use crate::widgets::{Clickable, Widget, WidgetError};
+
+// Widget of a shared library in the plugin directory, see plugins.rs for the interface
+#[derive(Serialize)]
+pub struct PluginWidget {
+    // Name of the widget
+    name: &'static str,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    // Whether the plugin asked for attention
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    urgent: bool,
+    #[serde(skip_serializing)]
+    short_text: Option<String>,
+    #[serde(skip_serializing)]
+    value: Option<f32>,
+    #[serde(skip_serializing)]
+    // Holds the error message if an error occured during widget update
+    error: Option<String>,
+    #[serde(skip_serializing)]
+    plugin: Plugin,
+    #[serde(skip_serializing)]
+    // Widget as created by the plugin, only the plugin knows what it points to
+    widget: *mut c_void,
+}
+
+// The plugin interface requires widgets to be usable from other threads
+unsafe impl Send for PluginWidget {}
+
+impl PluginWidget {
+    // Options are passed to the plugin as JSON object
+    pub fn new(plugin: Plugin, options: &str) -> Option<Self> {
+        let options = CString::new(options).ok()?;
+        // SAFETY: the options outlive the call, the plugin has to copy what it keeps
+        let widget = unsafe { (plugin.new)(options.as_ptr()) };
+        if widget.is_null() {
+            return None;
+        }
+
+        Some(Self {
+            name: "plugin",
+            full_text: None,
+            color: Color::Neutral,
+            urgent: false,
+            short_text: None,
+            value: None,
+            error: None,
+            plugin,
+            widget,
+        })
+    }
+
+    fn block(&mut self) -> Result<BlockOutput, String> {
+        // SAFETY: the widget was created by this plugin and is only used by this thread,
+        // the text stays valid until the next call, so it is copied right away
+        let block = unsafe {
+            let block = (self.plugin.update)(self.widget);
+            if block.is_null() {
+                return Err(String::from("update failed"));
+            }
+            CStr::from_ptr(block).to_string_lossy().into_owned()
+        };
+        BlockOutput::from_json(&block)
+    }
+}
+
+impl Widget for PluginWidget {
+    fn update(&mut self) {
+        match self.block() {
+            Ok(block) => {
+                self.color = block.color();
+                self.full_text = Some(block.full_text);
+                self.short_text = block.short_text;
+                self.urgent = block.urgent;
+                self.value = block.value;
+                self.error = None;
+            }
+            Err(error) => self.error = Some(error),
+        }
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        if let Some(error) = &self.error {
+            return Err(WidgetError::new(error.clone()));
+        }
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn short_text(&self) -> Option<String> {
+        self.short_text.clone()
+    }
+
+    fn value(&self) -> Option<f32> {
+        self.value
+    }
+
+    fn is_empty(&self) -> bool {
+        self.full_text.as_deref().is_some_and(str::is_empty)
+    }
+
+    fn clickable(&mut self) -> Option<&mut dyn Clickable> {
+        if self.plugin.click.is_some() {
+            Some(self)
+        } else {
+            None
+        }
+    }
+}
+
+impl Clickable for PluginWidget {
+    fn click(&mut self, event: &ClickEvent) {
+        if let Some(click) = self.plugin.click {
+            // SAFETY: see update
+            unsafe { click(self.widget, event.button) };
+        }
+    }
+}
+
+impl Drop for PluginWidget {
+    fn drop(&mut self) {
+        // SAFETY: the widget is not used after this
+        unsafe { (self.plugin.free)(self.widget) };
+    }
+}
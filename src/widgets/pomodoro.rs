@@ -0,0 +1,198 @@
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::click_events::{ClickEvent, BUTTON_LEFT, BUTTON_MIDDLE, BUTTON_RIGHT};
+use crate::i3_status::CONFIG;
+use crate::theme::Color;
+use crate::utils::template::{number, render};
+use crate::widgets::timer::format_duration;
+use crate::widgets::{Clickable, Widget, WidgetError};
+
+// Placeholders: {icon} (🍅 while running, ⏸ while paused), {phase} (e.g. work),
+// {remaining} (e.g. 24:59) and {cycles} (completed work phases)
+const FORMAT: &str = "{icon} {phase} {remaining} #{cycles}";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Phase {
+    Work,
+    Break,
+    LongBreak,
+}
+
+impl Phase {
+    fn duration(&self) -> Duration {
+        Duration::from_secs(match self {
+            Phase::Work => CONFIG.pomodoro_work_duration(),
+            Phase::Break => CONFIG.pomodoro_break_duration(),
+            Phase::LongBreak => CONFIG.pomodoro_long_break_duration(),
+        })
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Phase::Work => "work",
+            Phase::Break => "break",
+            Phase::LongBreak => "long break",
+        }
+    }
+}
+
+// Alternates between work phases and breaks, with a long break after every few work phases
+// A left click starts or pauses the phase, a middle click skips it and a right click starts over
+// When a phase is over, the next one waits for a click and the widget is urgent until then
+#[derive(Serialize)]
+pub struct Pomodoro {
+    // Name of the widget
+    name: &'static str,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    // Whether a phase is over and the next one was not started yet
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    urgent: bool,
+    #[serde(skip_serializing)]
+    // Phase that is running or waits to be started
+    phase: Phase,
+    #[serde(skip_serializing)]
+    // Number of work phases that were finished, skipped ones don't count
+    cycles: u32,
+    #[serde(skip_serializing)]
+    // Time of the phase that ran before it was paused the last time
+    elapsed_before: Duration,
+    #[serde(skip_serializing)]
+    // When the phase was started, None while it is paused
+    running_since: Option<Instant>,
+}
+
+impl Pomodoro {
+    pub fn new() -> Self {
+        Self {
+            name: "pomodoro",
+            full_text: None,
+            color: Color::Neutral,
+            urgent: false,
+            phase: Phase::Work,
+            cycles: 0,
+            elapsed_before: Duration::ZERO,
+            running_since: None,
+        }
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.elapsed_before
+            + self
+                .running_since
+                .map_or(Duration::ZERO, |running_since| running_since.elapsed())
+    }
+
+    // Switch to the phase after the current one, paused
+    fn next_phase(&mut self, finished: bool) {
+        self.phase = match self.phase {
+            Phase::Work => {
+                if finished {
+                    self.cycles += 1;
+                }
+                if finished
+                    && self
+                        .cycles
+                        .is_multiple_of(CONFIG.pomodoro_long_break_every().max(1))
+                {
+                    Phase::LongBreak
+                } else {
+                    Phase::Break
+                }
+            }
+            Phase::Break | Phase::LongBreak => Phase::Work,
+        };
+        self.elapsed_before = Duration::ZERO;
+        self.running_since = None;
+    }
+
+    fn notify(&self) {
+        if let Some(command) = CONFIG.pomodoro_command() {
+            // Don't wait for the command, notifications may block until they are dismissed
+            if let Err(error) = Command::new("sh")
+                .args(["-c", command])
+                .env("POMODORO_PHASE", self.phase.label())
+                .spawn()
+            {
+                log::error!("Could not run pomodoro command: {}", error);
+            }
+        }
+    }
+}
+
+impl Clickable for Pomodoro {
+    fn click(&mut self, event: &ClickEvent) {
+        match event.button {
+            BUTTON_LEFT => match self.running_since.take() {
+                Some(running_since) => self.elapsed_before += running_since.elapsed(),
+                None => self.running_since = Some(Instant::now()),
+            },
+            BUTTON_MIDDLE => self.next_phase(false),
+            BUTTON_RIGHT => {
+                self.phase = Phase::Work;
+                self.cycles = 0;
+                self.elapsed_before = Duration::ZERO;
+                self.running_since = None;
+            }
+            _ => return,
+        }
+        self.urgent = false;
+    }
+}
+
+impl Widget for Pomodoro {
+    fn update(&mut self) {
+        let duration = self.phase.duration();
+        if self.running_since.is_some() && self.elapsed() >= duration {
+            self.next_phase(true);
+            self.urgent = true;
+            self.notify();
+        }
+
+        self.color = match (self.phase, self.running_since) {
+            (_, None) => Color::Neutral,
+            (Phase::Work, Some(_)) => Color::Warning,
+            (Phase::Break | Phase::LongBreak, Some(_)) => Color::Good,
+        };
+        // Rounded up, so a phase shows 00:00 only when it is over
+        let remaining = Duration::from_secs(
+            self.phase
+                .duration()
+                .saturating_sub(self.elapsed())
+                .as_secs_f64()
+                .ceil() as u64,
+        );
+        let icon = if self.running_since.is_some() {
+            "🍅"
+        } else {
+            "⏸"
+        };
+        self.full_text = Some(render(
+            CONFIG.format(self.name).as_deref().unwrap_or(FORMAT),
+            &[
+                ("icon", icon.into()),
+                ("phase", self.phase.label().into()),
+                ("remaining", format_duration(remaining).into()),
+                ("cycles", number(self.cycles)),
+            ],
+        ));
+    }
+
+    fn updates_on_render(&self) -> bool {
+        true
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn clickable(&mut self) -> Option<&mut dyn Clickable> {
+        Some(self)
+    }
+}
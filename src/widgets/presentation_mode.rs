@@ -0,0 +1,129 @@
+use std::process::Command;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::click_events::{ClickEvent, BUTTON_LEFT};
+use crate::i3_status::CONFIG;
+use crate::theme::Color;
+use crate::utils::template::render;
+use crate::variables::set_variable;
+use crate::widgets::{Clickable, Widget, WidgetError};
+
+// Placeholders: {state} (ON or OFF)
+const FORMAT: &str = "📽 {state}";
+
+#[derive(Serialize)]
+pub struct PresentationMode {
+    // Name of the widget
+    name: &'static str,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    #[serde(skip_serializing)]
+    // Whether presentation mode is currently enabled
+    enabled: bool,
+}
+
+impl PresentationMode {
+    pub fn new() -> Self {
+        Self {
+            name: "presentation_mode",
+            full_text: None,
+            color: Color::Neutral,
+            enabled: false,
+        }
+    }
+
+    fn run_command(command: &str) -> Result<(), WidgetError> {
+        let status = Command::new("sh").args(["-c", command]).status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(WidgetError::new(format!(
+                "\"{}\" failed with {}",
+                command, status
+            )))
+        }
+    }
+
+    // Run all enable commands
+    // If one of them fails, the commands that already succeeded are reverted,
+    // so we never end up in a half enabled presentation mode
+    fn enable(&self) -> Result<(), WidgetError> {
+        let commands = CONFIG.presentation_mode_commands();
+        for (index, (enable_command, _)) in commands.iter().enumerate() {
+            if let Err(error) = Self::run_command(enable_command) {
+                for (_, disable_command) in commands[..index].iter().rev() {
+                    if let Err(error) = Self::run_command(disable_command) {
+                        log::error!("Could not revert presentation mode step: {}", error);
+                    }
+                }
+                return Err(error);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Revert all steps in reverse order
+    // We try to run every disable command, even if one of them fails
+    fn disable(&self) -> Result<(), WidgetError> {
+        let mut result = Ok(());
+        for (_, disable_command) in CONFIG.presentation_mode_commands().iter().rev() {
+            if let Err(error) = Self::run_command(disable_command) {
+                result = Err(error);
+            }
+        }
+
+        result
+    }
+}
+
+impl Clickable for PresentationMode {
+    fn click(&mut self, event: &ClickEvent) {
+        if event.button != BUTTON_LEFT {
+            return;
+        }
+
+        let result = if self.enabled {
+            self.disable()
+        } else {
+            self.enable()
+        };
+
+        match result {
+            Ok(()) => {
+                self.enabled = !self.enabled;
+                // Other parts of the bar (e.g. sound alerts) are muted while presenting
+                set_variable("presentation_mode.enabled", self.enabled.to_string());
+            }
+            Err(error) => log::error!("Could not toggle presentation mode.\n{}", error),
+        }
+    }
+}
+
+impl Widget for PresentationMode {
+    fn update(&mut self) {
+        let state = if self.enabled {
+            self.color = Color::Warning;
+            "ON"
+        } else {
+            self.color = Color::Neutral;
+            "OFF"
+        };
+        self.full_text = Some(render(
+            CONFIG.format(self.name).as_deref().unwrap_or(FORMAT),
+            &[("state", state.into())],
+        ));
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn clickable(&mut self) -> Option<&mut dyn Clickable> {
+        Some(self)
+    }
+}
@@ -0,0 +1,286 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::{Printer, PrinterApi};
+use crate::i3_status::CONFIG;
+use crate::replay::http_agent;
+use crate::signals::is_low_power;
+use crate::theme::Color;
+use crate::utils::secrets::resolve_secret;
+use crate::utils::stop::{stop_signal, StopHandle, StopSignal};
+use crate::utils::template::{number, render, Placeholder};
+use crate::widgets::{Widget, WidgetError};
+
+// Printers are usually controlled by a Raspberry Pi, which can be slow to answer
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// Placeholders: {label} (the instance), {status} (e.g. "42% 1h05m H210° B60°", "ready H25°",
+// "error: ...", "off" or "..." before the first answer), {state} (e.g. "printing", "ready",
+// "off"), {progress} in percent, {remaining} (e.g. 1h05m), {hotend} and {bed} in °C
+// Values that the printer does not report are empty
+const FORMAT: &str = "{label} {status}";
+
+#[derive(Default)]
+struct PrinterStatus {
+    // e.g. "printing", "ready" or "error"
+    state: String,
+    // Message of the printer, only used for errors
+    message: Option<String>,
+    // Progress of the current print in percent
+    progress: Option<f64>,
+    // Estimated time until the print is done
+    remaining: Option<Duration>,
+    hotend_temperature: Option<f64>,
+    bed_temperature: Option<f64>,
+    error: bool,
+}
+
+#[derive(Serialize)]
+pub struct PrinterWidget {
+    // Name of the widget
+    name: &'static str,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    // Whether the printer reports an error
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    urgent: bool,
+    #[serde(skip_serializing)]
+    label: String,
+    #[serde(skip_serializing)]
+    // Latest status of the printer, updated by the polling thread
+    status: Arc<Mutex<Option<Result<PrinterStatus, String>>>>,
+    #[serde(skip_serializing)]
+    // Stops the polling thread when the widget is dropped
+    stop: StopHandle,
+}
+
+impl PrinterWidget {
+    pub fn new(label: String, printer: Printer) -> Self {
+        let status = Arc::new(Mutex::new(None));
+        let (stop, signal) = stop_signal();
+        Self::poll_periodically(printer, Arc::clone(&status), signal);
+
+        Self {
+            name: "printer",
+            full_text: None,
+            color: Color::Neutral,
+            urgent: false,
+            label,
+            status,
+            stop,
+        }
+    }
+
+    // See https://docs.octoprint.org/en/master/api/job.html and
+    // https://docs.octoprint.org/en/master/api/printer.html
+    fn octoprint_status(
+        agent: &ureq::Agent,
+        url: &str,
+        api_key: &str,
+    ) -> Result<PrinterStatus, WidgetError> {
+        let request = |path: &str| -> Result<Value, WidgetError> {
+            let response = agent
+                .get(&format!("{}{}", url, path))
+                .set("X-Api-Key", api_key)
+                .call()?;
+            Ok(serde_json::from_str(&response.into_string()?)?)
+        };
+
+        let job = request("/api/job")?;
+        let state = job["state"].as_str().unwrap_or("unknown").to_lowercase();
+        let mut status = PrinterStatus {
+            error: state.starts_with("error") || state.starts_with("offline after error"),
+            message: job["error"].as_str().map(String::from),
+            progress: job["progress"]["completion"].as_f64(),
+            remaining: job["progress"]["printTimeLeft"]
+                .as_u64()
+                .map(Duration::from_secs),
+            state,
+            ..Default::default()
+        };
+
+        // Temperatures are only available while the printer is connected
+        if let Ok(printer) = request("/api/printer") {
+            status.hotend_temperature = printer["temperature"]["tool0"]["actual"].as_f64();
+            status.bed_temperature = printer["temperature"]["bed"]["actual"].as_f64();
+        }
+
+        Ok(status)
+    }
+
+    // See https://moonraker.readthedocs.io/en/latest/printer_objects/
+    fn moonraker_status(agent: &ureq::Agent, url: &str) -> Result<PrinterStatus, WidgetError> {
+        let response: Value = serde_json::from_str(
+            &agent
+                .get(&format!(
+                    "{}/printer/objects/query?print_stats&display_status&extruder&heater_bed",
+                    url
+                ))
+                .call()?
+                .into_string()?,
+        )?;
+
+        let objects = &response["result"]["status"];
+        let state = objects["print_stats"]["state"]
+            .as_str()
+            .unwrap_or("unknown")
+            .to_string();
+        let progress = objects["display_status"]["progress"]
+            .as_f64()
+            .map(|progress| progress * 100.0);
+        let print_duration = objects["print_stats"]["print_duration"].as_f64();
+        // Moonraker doesn't estimate the remaining time, so we extrapolate the elapsed time
+        let remaining = match (progress, print_duration) {
+            (Some(progress), Some(print_duration)) if progress > 0.0 && state == "printing" => {
+                Some(Duration::from_secs_f64(
+                    print_duration / progress * (100.0 - progress),
+                ))
+            }
+            _ => None,
+        };
+
+        Ok(PrinterStatus {
+            error: state == "error",
+            message: objects["print_stats"]["message"]
+                .as_str()
+                .filter(|message| !message.is_empty())
+                .map(String::from),
+            progress,
+            remaining,
+            hotend_temperature: objects["extruder"]["temperature"].as_f64(),
+            bed_temperature: objects["heater_bed"]["temperature"].as_f64(),
+            state,
+        })
+    }
+
+    // The printer may be slow or turned off, so it is polled in its own thread
+    fn poll_periodically(
+        printer: Printer,
+        status: Arc<Mutex<Option<Result<PrinterStatus, String>>>>,
+        signal: StopSignal,
+    ) {
+        thread::spawn(move || {
+            let agent = http_agent(REQUEST_TIMEOUT);
+            let url = printer.url.trim_end_matches('/');
+            loop {
+                if !is_low_power() {
+                    let result = match &printer.api {
+                        PrinterApi::OctoPrint { api_key } => resolve_secret(api_key)
+                            .map_err(WidgetError::from)
+                            .and_then(|api_key| Self::octoprint_status(&agent, url, &api_key)),
+                        PrinterApi::Moonraker => Self::moonraker_status(&agent, url),
+                    };
+                    if let Err(error) = &result {
+                        log::warn!("Could not get the status of {}: {}", url, error);
+                    }
+                    *status.lock().unwrap() = Some(result.map_err(|error| error.to_string()));
+                }
+                if !signal.sleep(Duration::from_secs(CONFIG.printer_interval())) {
+                    return;
+                }
+            }
+        });
+    }
+
+    // Remaining time of the print, e.g. 1h05m
+    fn format_remaining(remaining: Duration) -> String {
+        let minutes = remaining.as_secs() / 60;
+        format!("{}h{:02}m", minutes / 60, minutes % 60)
+    }
+
+    fn format_status(status: &PrinterStatus) -> String {
+        let mut parts = Vec::new();
+        if status.error {
+            parts.push(format!(
+                "error: {}",
+                status.message.as_deref().unwrap_or(&status.state)
+            ));
+        } else if let Some(progress) = status.progress.filter(|_| status.state == "printing") {
+            parts.push(format!("{:.0}%", progress));
+            parts.extend(status.remaining.map(Self::format_remaining));
+        } else {
+            parts.push(status.state.clone());
+        }
+        if let Some(temperature) = status.hotend_temperature {
+            parts.push(format!("H{:.0}°", temperature));
+        }
+        if let Some(temperature) = status.bed_temperature {
+            parts.push(format!("B{:.0}°", temperature));
+        }
+
+        parts.join(" ")
+    }
+}
+
+impl Drop for PrinterWidget {
+    fn drop(&mut self) {
+        self.stop.stop();
+    }
+}
+
+impl Widget for PrinterWidget {
+    fn update(&mut self) {
+        let status = self.status.lock().unwrap();
+        let (text, state, status) = match &*status {
+            Some(Ok(status)) => {
+                self.urgent = status.error;
+                self.color = if status.error {
+                    Color::Critical
+                } else if status.state == "printing" {
+                    Color::Accent
+                } else {
+                    Color::Neutral
+                };
+                (
+                    Self::format_status(status),
+                    status.state.clone(),
+                    Some(status),
+                )
+            }
+            // Printers are often turned off, that is not an error worth being urgent about
+            Some(Err(_)) => {
+                self.urgent = false;
+                self.color = Color::Neutral;
+                (String::from("off"), String::from("off"), None)
+            }
+            // The first request is still running
+            None => (String::from("..."), String::from("..."), None),
+        };
+        let value = |value: fn(&PrinterStatus) -> Option<f64>| {
+            status.and_then(value).map_or(Placeholder::from(""), number)
+        };
+        let remaining = status
+            .and_then(|status| status.remaining)
+            .map(Self::format_remaining)
+            .unwrap_or_default();
+        self.full_text = Some(render(
+            CONFIG
+                .format(&format!("{}:{}", self.name, self.label))
+                .as_deref()
+                .unwrap_or(FORMAT),
+            &[
+                ("label", self.label.clone().into()),
+                ("status", text.into()),
+                ("state", state.into()),
+                ("progress", value(|status| status.progress)),
+                ("remaining", remaining.into()),
+                ("hotend", value(|status| status.hotend_temperature)),
+                ("bed", value(|status| status.bed_temperature)),
+            ],
+        ));
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    // The printer is turned off or not reachable
+    fn is_empty(&self) -> bool {
+        matches!(*self.status.lock().unwrap(), Some(Err(_)))
+    }
+}
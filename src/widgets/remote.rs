@@ -0,0 +1,196 @@
+use std::env;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::{RemoteHost, RemoteMetric};
+use crate::i3_status::CONFIG;
+use crate::theme::Color;
+use crate::utils::stop::{stop_signal, StopHandle, StopSignal};
+use crate::utils::template::render;
+use crate::widgets::{Widget, WidgetError};
+
+// Keep the SSH connection open between two collections, so we don't have to log in every time
+const CONTROL_PERSIST: &str = "ControlPersist=600";
+// An unreachable host should not keep the collection thread busy for long
+const CONNECT_TIMEOUT: &str = "ConnectTimeout=5";
+// Placeholders: {label} (the instance), {values} (the metrics in their order, e.g.
+// "load 0.52 / 45% 48°C", "unreachable" or "..." while the first collection runs)
+const FORMAT: &str = "{label}: {values}";
+
+#[derive(Serialize)]
+pub struct Remote {
+    // Name of the widget
+    name: &'static str,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    #[serde(skip_serializing)]
+    // Label of the host, shown in front of the values
+    label: String,
+    #[serde(skip_serializing)]
+    // Latest output of the collection, updated by the collection thread
+    output: Arc<Mutex<Option<Result<String, String>>>>,
+    #[serde(skip_serializing)]
+    // Stops the collection thread when the widget is dropped
+    stop: StopHandle,
+}
+
+impl Remote {
+    pub fn new(label: String, host: RemoteHost) -> Self {
+        let output = Arc::new(Mutex::new(None));
+        let (stop, signal) = stop_signal();
+        Self::collect_periodically(host, Arc::clone(&output), signal);
+
+        Self {
+            name: "remote",
+            full_text: None,
+            color: Color::Neutral,
+            label,
+            output,
+            stop,
+        }
+    }
+
+    // Paths and zones come from the config file, so they are quoted for the remote shell
+    fn quote(argument: &str) -> String {
+        format!("'{}'", argument.replace('\'', "'\\''"))
+    }
+
+    // Shell command that prints exactly one line per metric, empty if it could not be read
+    fn collection_script(metrics: &[RemoteMetric]) -> String {
+        metrics
+            .iter()
+            .map(|metric| match metric {
+                RemoteMetric::Load => String::from("echo \"$(cut -d' ' -f1 /proc/loadavg)\""),
+                RemoteMetric::Disk(path) => format!(
+                    "echo \"$(df -P {} | awk 'NR == 2 {{ print $5 }}')\"",
+                    Self::quote(path)
+                ),
+                RemoteMetric::Temperature(zone) => format!(
+                    "echo \"$(cat /sys/class/thermal/{}/temp 2> /dev/null)\"",
+                    Self::quote(zone)
+                ),
+            })
+            .collect::<Vec<String>>()
+            .join("; ")
+    }
+
+    fn collect(host: &RemoteHost) -> Result<String, WidgetError> {
+        // %C is a hash of the connection, so every host gets its own master connection
+        let runtime_directory =
+            env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| String::from("/tmp"));
+        let control_path = format!("ControlPath={}/i3rustus-ssh-%C", runtime_directory);
+        let output = Command::new("ssh")
+            .args(["-o", "BatchMode=yes", "-o", CONNECT_TIMEOUT])
+            .args([
+                "-o",
+                "ControlMaster=auto",
+                "-o",
+                &control_path,
+                "-o",
+                CONTROL_PERSIST,
+            ])
+            .arg(&host.host)
+            .arg(Self::collection_script(&host.metrics))
+            .output()?;
+        if !output.status.success() {
+            return Err(WidgetError::new(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+
+        Ok(String::from_utf8(output.stdout)?)
+    }
+
+    // Format the collected lines in the order of the metrics
+    fn format_output(host: &RemoteHost, output: &str) -> String {
+        host.metrics
+            .iter()
+            .zip(output.lines())
+            .map(|(metric, value)| {
+                let value = value.trim();
+                if value.is_empty() {
+                    return String::from("?");
+                }
+                match metric {
+                    RemoteMetric::Load => format!("load {}", value),
+                    RemoteMetric::Disk(path) => format!("{} {}", path, value),
+                    RemoteMetric::Temperature(_) => match value.parse::<f32>() {
+                        // The kernel reports millidegrees
+                        Ok(temperature) => format!("{:.0}°C", temperature / 1000.0),
+                        Err(_) => String::from("?"),
+                    },
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    // Collecting over SSH can take seconds, so it runs in its own thread
+    fn collect_periodically(
+        host: RemoteHost,
+        output: Arc<Mutex<Option<Result<String, String>>>>,
+        signal: StopSignal,
+    ) {
+        thread::spawn(move || loop {
+            let result = Self::collect(&host)
+                .map(|lines| Self::format_output(&host, &lines))
+                .map_err(|error| error.to_string());
+            if let Err(error) = &result {
+                log::warn!("Could not collect values from {}: {}", host.host, error);
+            }
+            *output.lock().unwrap() = Some(result);
+            if !signal.sleep(Duration::from_secs(CONFIG.remote_interval())) {
+                return;
+            }
+        });
+    }
+}
+
+impl Drop for Remote {
+    fn drop(&mut self) {
+        self.stop.stop();
+    }
+}
+
+impl Widget for Remote {
+    fn update(&mut self) {
+        let values = match &*self.output.lock().unwrap() {
+            Some(Ok(values)) => {
+                self.color = Color::Neutral;
+                values.clone()
+            }
+            Some(Err(_)) => {
+                self.color = Color::Critical;
+                String::from("unreachable")
+            }
+            // The first collection is still running
+            None => String::from("..."),
+        };
+        self.full_text = Some(render(
+            CONFIG
+                .format(&format!("{}:{}", self.name, self.label))
+                .as_deref()
+                .unwrap_or(FORMAT),
+            &[
+                ("label", self.label.clone().into()),
+                ("values", values.into()),
+            ],
+        ));
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    // The host could not be reached
+    fn is_empty(&self) -> bool {
+        self.color == Color::Critical
+    }
+}
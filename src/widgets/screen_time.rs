@@ -0,0 +1,296 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::click_events::{ClickEvent, BUTTON_LEFT, BUTTON_RIGHT};
+use crate::events::WidgetEvent;
+use crate::i3_ipc::{I3Ipc, EVENT_MASK, GET_TREE};
+use crate::i3_status::CONFIG;
+use crate::theme::Color;
+use crate::utils::idle::IdleMonitor;
+use crate::utils::state::{load_state, save_state};
+use crate::utils::stop::{stop_signal, StopHandle, StopSignal};
+use crate::utils::template::render;
+use crate::widgets::{Clickable, Widget, WidgetError};
+
+// How often the usage is written to the state file
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+// Placeholders: {usage} (the screen time of today like "3h10m", the top application and its
+// time like "firefox: 1h02m" or "private"), {total} (screen time of today), {application}
+// and {application_time} (the application used the most today and its time, empty in the
+// privacy mode or without usage)
+const FORMAT: &str = "🖥 {usage}";
+
+#[derive(Default, Serialize, Deserialize)]
+struct UsageState {
+    // Day the usage belongs to (YYYY-MM-DD)
+    date: String,
+    // Focus time per application (window class) in milliseconds
+    applications: BTreeMap<String, u64>,
+}
+
+#[derive(Serialize)]
+pub struct ScreenTime {
+    // Name of the widget
+    name: &'static str,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    #[serde(skip_serializing)]
+    // Holds the error message if an error occured during widget update
+    error: Option<String>,
+    #[serde(skip_serializing)]
+    state: UsageState,
+    #[serde(skip_serializing)]
+    idle_monitor: Option<IdleMonitor>,
+    #[serde(skip_serializing)]
+    // Window class of the focused window, updated by the i3 IPC listener
+    focused_application: Arc<Mutex<Option<String>>>,
+    #[serde(skip_serializing)]
+    // Stops the i3 IPC listener when the widget is dropped
+    stop: StopHandle,
+    #[serde(skip_serializing)]
+    // Show the application that was used the most instead of the total time
+    show_top_application: bool,
+    #[serde(skip_serializing)]
+    // While private, nothing is tracked and no application names are shown
+    private: bool,
+    #[serde(skip_serializing)]
+    last_update: Instant,
+    #[serde(skip_serializing)]
+    last_save: Instant,
+}
+
+impl ScreenTime {
+    pub fn new() -> Self {
+        let idle_monitor = match IdleMonitor::new() {
+            Ok(idle_monitor) => Some(idle_monitor),
+            Err(error) => {
+                log::error!("Idle detection is not available: {}", error);
+                None
+            }
+        };
+        let focused_application = Arc::new(Mutex::new(None));
+        let (stop, signal) = stop_signal();
+        Self::listen_for_window_focus(Arc::clone(&focused_application), signal);
+
+        Self {
+            name: "screen_time",
+            full_text: None,
+            color: Color::Neutral,
+            error: None,
+            state: load_state("screen_time").unwrap_or_default(),
+            idle_monitor,
+            focused_application,
+            stop,
+            show_top_application: false,
+            private: false,
+            last_update: Instant::now(),
+            last_save: Instant::now(),
+        }
+    }
+
+    // Search the layout tree for the focused window and return its class
+    fn focused_window_class(node: &Value) -> Option<String> {
+        if node["focused"] == true {
+            return node["window_properties"]["class"]
+                .as_str()
+                .map(String::from);
+        }
+
+        node["nodes"]
+            .as_array()
+            .into_iter()
+            .chain(node["floating_nodes"].as_array())
+            .flatten()
+            .find_map(Self::focused_window_class)
+    }
+
+    // Keep track of the focused application through i3 IPC window events
+    fn listen_for_window_focus(
+        focused_application: Arc<Mutex<Option<String>>>,
+        signal: StopSignal,
+    ) {
+        thread::spawn(move || {
+            let result = (|| -> Result<(), WidgetError> {
+                let mut ipc = I3Ipc::connect()?;
+                if !signal.on_stop(ipc.shutdown_handle()?) {
+                    return Ok(());
+                }
+                let tree: Value = serde_json::from_str(&ipc.request(GET_TREE, "")?)?;
+                *focused_application.lock().unwrap() = Self::focused_window_class(&tree);
+
+                ipc.subscribe(&["window"])?;
+                loop {
+                    let (message_type, payload) = ipc.receive()?;
+                    if message_type & EVENT_MASK == 0 {
+                        continue;
+                    }
+                    let event: Value = serde_json::from_str(&payload)?;
+                    if event["change"] == "focus" {
+                        *focused_application.lock().unwrap() = event["container"]
+                            ["window_properties"]["class"]
+                            .as_str()
+                            .map(String::from);
+                    }
+                }
+            })();
+
+            // Stopping the widget closes the connection, which is not an error
+            if signal.is_stopped() {
+                return;
+            }
+            if let Err(error) = result {
+                log::error!("Window tracking stopped: {}", error);
+            }
+        });
+    }
+
+    fn is_active(&self) -> Result<bool, WidgetError> {
+        match &self.idle_monitor {
+            Some(idle_monitor) => {
+                Ok(idle_monitor.idle_time()? < Duration::from_secs(CONFIG.idle_timeout()))
+            }
+            None => Err(WidgetError::new(String::from(
+                "Idle detection is not available",
+            ))),
+        }
+    }
+
+    fn render_text(
+        usage: String,
+        total: &str,
+        application: &str,
+        application_time: &str,
+    ) -> String {
+        render(
+            CONFIG.format("screen_time").as_deref().unwrap_or(FORMAT),
+            &[
+                ("usage", usage.into()),
+                ("total", total.into()),
+                ("application", application.into()),
+                ("application_time", application_time.into()),
+            ],
+        )
+    }
+
+    fn format_duration(milliseconds: u64) -> String {
+        let minutes = milliseconds / 1000 / 60;
+        format!("{}h{:02}m", minutes / 60, minutes % 60)
+    }
+}
+
+impl Clickable for ScreenTime {
+    // Left click switches between total time and top application,
+    // right click toggles the privacy mode
+    fn click(&mut self, event: &ClickEvent) {
+        match event.button {
+            BUTTON_LEFT => self.show_top_application = !self.show_top_application,
+            BUTTON_RIGHT => self.private = !self.private,
+            _ => {}
+        }
+    }
+}
+
+impl Drop for ScreenTime {
+    fn drop(&mut self) {
+        self.stop.stop();
+    }
+}
+
+impl Widget for ScreenTime {
+    fn update(&mut self) {
+        self.error = None;
+        let elapsed = self.last_update.elapsed().as_millis() as u64;
+        self.last_update = Instant::now();
+
+        // Start from zero every day
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        if self.state.date != today {
+            self.state = UsageState {
+                date: today,
+                ..Default::default()
+            };
+        }
+
+        if self.private {
+            self.full_text = Some(Self::render_text(String::from("private"), "", "", ""));
+            self.color = Color::Accent;
+            return;
+        }
+        self.color = Color::Neutral;
+
+        match self.is_active() {
+            Ok(true) => {
+                if let Some(application) = self.focused_application.lock().unwrap().as_ref() {
+                    *self
+                        .state
+                        .applications
+                        .entry(application.clone())
+                        .or_default() += elapsed;
+                }
+            }
+            Ok(false) => {}
+            Err(error) => self.error = Some(error.to_string()),
+        }
+
+        if self.last_save.elapsed() >= SAVE_INTERVAL {
+            self.last_save = Instant::now();
+            if let Err(error) = save_state(self.name, &self.state) {
+                self.error = Some(error.to_string());
+            }
+        }
+
+        let total = Self::format_duration(self.state.applications.values().sum());
+        let (application, application_time) = match self
+            .state
+            .applications
+            .iter()
+            .max_by_key(|(_, milliseconds)| **milliseconds)
+        {
+            Some((application, milliseconds)) => {
+                (application.as_str(), Self::format_duration(*milliseconds))
+            }
+            None => ("", String::new()),
+        };
+        let usage = if !self.show_top_application {
+            total.clone()
+        } else if application.is_empty() {
+            String::from("-")
+        } else {
+            format!("{}: {}", application, application_time)
+        };
+        self.full_text = Some(Self::render_text(
+            usage,
+            &total,
+            application,
+            &application_time,
+        ));
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        if let Some(error_msg) = &self.error {
+            log::error!(
+                "Error occured when trying to track screen time.\n{}",
+                error_msg
+            );
+        }
+
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn clickable(&mut self) -> Option<&mut dyn Clickable> {
+        Some(self)
+    }
+
+    // Start the new day right at midnight
+    fn events(&self) -> &[WidgetEvent] {
+        &[WidgetEvent::NewDay]
+    }
+}
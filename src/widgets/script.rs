@@ -0,0 +1,211 @@
+use std::process::Command;
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::click_events::ClickEvent;
+use crate::config::ScriptCommand;
+use crate::theme::Color;
+use crate::widgets::{Clickable, Widget, WidgetError};
+
+// Scripts exit with this code to mark their block as urgent, like in i3blocks
+const URGENT_EXIT_CODE: i32 = 33;
+
+// What a script or plugin printed, either as lines or as JSON object:
+//
+// Lines: full text, short text (optional) and color (optional)
+// JSON: {"full_text": "...", "short_text": "...", "color": "warning", "urgent": true, "value": 42}
+//
+// Colors are the names of the semantic colors (e.g. "good" or "critical")
+#[derive(Deserialize, Default)]
+pub struct BlockOutput {
+    pub full_text: String,
+    pub short_text: Option<String>,
+    color: Option<String>,
+    #[serde(default)]
+    pub urgent: bool,
+    // Used for thresholds, gradients and sound alerts
+    pub value: Option<f32>,
+}
+
+impl BlockOutput {
+    pub fn from_json(output: &str) -> Result<Self, String> {
+        serde_json::from_str(output).map_err(|error| format!("invalid JSON: {}", error))
+    }
+
+    fn from_lines(output: &str, urgent: bool) -> Self {
+        let mut lines = output.lines().map(str::trim);
+        let full_text = lines.next().unwrap_or_default().to_string();
+        let short_text = lines.next().filter(|line| !line.is_empty());
+        let color = lines.next().filter(|line| !line.is_empty());
+        Self {
+            full_text,
+            short_text: short_text.map(String::from),
+            color: color.map(String::from),
+            urgent,
+            value: None,
+        }
+    }
+
+    // Unknown colors are shown as neutral
+    pub fn color(&self) -> Color {
+        self.color
+            .as_deref()
+            .and_then(Color::from_name)
+            .unwrap_or(Color::Neutral)
+    }
+}
+
+#[derive(Serialize)]
+pub struct Script {
+    // Name of the widget
+    name: &'static str,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    // Whether the script asked for attention
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    urgent: bool,
+    #[serde(skip_serializing)]
+    short_text: Option<String>,
+    #[serde(skip_serializing)]
+    value: Option<f32>,
+    #[serde(skip_serializing)]
+    // Holds the error message if the script failed
+    error: Option<String>,
+    #[serde(skip_serializing)]
+    // Latest output of the script, updated by the script thread
+    output: Arc<Mutex<Option<Result<BlockOutput, String>>>>,
+    #[serde(skip_serializing)]
+    // Buttons of clicks on the block, the script is run again with BLOCK_BUTTON set
+    clicks: Sender<u8>,
+}
+
+impl Script {
+    pub fn new(instance: String, script: ScriptCommand) -> Self {
+        let output = Arc::new(Mutex::new(None));
+        let (clicks, receiver) = channel();
+        let thread_output = Arc::clone(&output);
+        thread::spawn(move || {
+            let mut button = None;
+            loop {
+                let result = Self::run(&instance, &script, button);
+                if let Err(error) = &result {
+                    log::warn!("Script {} failed: {}", instance, error);
+                }
+                *thread_output.lock().unwrap() = Some(result);
+
+                // Scripts with an interval of 0 only run again after a click
+                let interval = match script.interval {
+                    0 => Duration::MAX,
+                    seconds => Duration::from_secs(seconds),
+                };
+                button = match receiver.recv_timeout(interval) {
+                    Ok(button) => Some(button),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                };
+            }
+        });
+
+        Self {
+            name: "script",
+            full_text: None,
+            color: Color::Neutral,
+            urgent: false,
+            short_text: None,
+            value: None,
+            error: None,
+            output,
+            clicks,
+        }
+    }
+
+    // The environment matches i3blocks, so existing blocklets can be used as they are
+    fn run(
+        instance: &str,
+        script: &ScriptCommand,
+        button: Option<u8>,
+    ) -> Result<BlockOutput, String> {
+        let mut command = Command::new("sh");
+        command
+            .args(["-c", &script.command])
+            .env("BLOCK_NAME", "script")
+            .env("BLOCK_INSTANCE", instance);
+        if let Some(button) = button {
+            command.env("BLOCK_BUTTON", button.to_string());
+        }
+        let output = command.output().map_err(|error| error.to_string())?;
+
+        let urgent = output.status.code() == Some(URGENT_EXIT_CODE);
+        if !output.status.success() && !urgent {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("{}: {}", output.status, stderr.trim()));
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if !script.json {
+            return Ok(BlockOutput::from_lines(&stdout, urgent));
+        }
+        // Empty output is an empty block, like an empty first line
+        if stdout.trim().is_empty() {
+            return Ok(BlockOutput::default());
+        }
+        let mut parsed = BlockOutput::from_json(&stdout)?;
+        parsed.urgent |= urgent;
+
+        Ok(parsed)
+    }
+}
+
+impl Widget for Script {
+    fn update(&mut self) {
+        match &*self.output.lock().unwrap() {
+            Some(Ok(output)) => {
+                self.full_text = Some(output.full_text.clone());
+                self.short_text = output.short_text.clone();
+                self.color = output.color();
+                self.urgent = output.urgent;
+                self.value = output.value;
+                self.error = None;
+            }
+            Some(Err(error)) => self.error = Some(error.clone()),
+            // The script is still running for the first time
+            None => self.full_text = Some(String::from("...")),
+        }
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        if let Some(error) = &self.error {
+            return Err(WidgetError::new(error.clone()));
+        }
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn short_text(&self) -> Option<String> {
+        self.short_text.clone()
+    }
+
+    fn value(&self) -> Option<f32> {
+        self.value
+    }
+
+    fn is_empty(&self) -> bool {
+        self.full_text.as_deref().is_some_and(str::is_empty)
+    }
+
+    fn clickable(&mut self) -> Option<&mut dyn Clickable> {
+        Some(self)
+    }
+}
+
+impl Clickable for Script {
+    fn click(&mut self, event: &ClickEvent) {
+        // The script thread only stops when the widget is dropped
+        let _ = self.clicks.send(event.button);
+    }
+}
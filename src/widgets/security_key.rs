@@ -0,0 +1,124 @@
+use std::fs::read_dir;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::i3_status::CONFIG;
+use crate::theme::Color;
+use crate::utils::file::read_first_line_in_file;
+use crate::utils::template::render;
+use crate::widgets::{Widget, WidgetError};
+
+const USB_DEVICES_PATH: &str = "/sys/bus/usb/devices";
+// Placeholders: {product} (e.g. YubiKey OTP+FIDO+CCID, "none" without a key) and
+// {touch} (": TOUCH" while the key waits for a touch, empty otherwise)
+const FORMAT: &str = "🔑 {product}{touch}";
+
+#[derive(Serialize)]
+pub struct SecurityKey {
+    // Name of the widget
+    name: &'static str,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    #[serde(skip_serializing)]
+    // Holds the error message if an error occured during widget update
+    error: Option<String>,
+    #[serde(skip_serializing)]
+    // Used to alternate the color while a touch is requested
+    flash_state: bool,
+}
+
+impl SecurityKey {
+    pub fn new() -> Self {
+        Self {
+            name: "security_key",
+            full_text: None,
+            color: Color::Neutral,
+            error: None,
+            flash_state: false,
+        }
+    }
+
+    // Returns the product name of the first inserted security key
+    // A key is detected by comparing the USB vendor ID of every connected device
+    // with the vendor IDs that are configured
+    fn find_inserted_key(&self) -> Result<Option<String>, WidgetError> {
+        for device in read_dir(USB_DEVICES_PATH)? {
+            let device_path = device?.path();
+            // Not every entry is a device (e.g. interfaces), those don't have a vendor ID
+            let vendor_id = match read_first_line_in_file(&format!(
+                "{}/idVendor",
+                device_path.to_string_lossy()
+            )) {
+                Ok(vendor_id) => vendor_id,
+                Err(_) => continue,
+            };
+
+            if CONFIG.security_key_vendor_ids().contains(&vendor_id.trim()) {
+                let product =
+                    read_first_line_in_file(&format!("{}/product", device_path.to_string_lossy()))
+                        .unwrap_or_else(|_| String::from("Security key"));
+                return Ok(Some(product));
+            }
+        }
+
+        Ok(None)
+    }
+
+    // The touch request is signaled by a notify hook (e.g. a gpg-agent or ssh wrapper)
+    // that creates the flag file when the key waits for a touch and removes it afterwards
+    fn touch_requested(&self) -> bool {
+        CONFIG
+            .security_key_touch_flag_path()
+            .is_some_and(|path| path.exists())
+    }
+}
+
+impl Widget for SecurityKey {
+    fn update(&mut self) {
+        self.error = None;
+        let (product, touch) = match self.find_inserted_key() {
+            Ok(Some(product)) => {
+                if self.touch_requested() {
+                    self.flash_state = !self.flash_state;
+                    self.color = if self.flash_state {
+                        Color::Critical
+                    } else {
+                        Color::Accent
+                    };
+                    (product, ": TOUCH")
+                } else {
+                    self.flash_state = false;
+                    self.color = Color::Good;
+                    (product, "")
+                }
+            }
+            Ok(None) => {
+                self.flash_state = false;
+                self.color = Color::Neutral;
+                (String::from("none"), "")
+            }
+            Err(error) => {
+                self.error = Some(error.to_string());
+                return;
+            }
+        };
+        self.full_text = Some(render(
+            CONFIG.format(self.name).as_deref().unwrap_or(FORMAT),
+            &[("product", product.into()), ("touch", touch.into())],
+        ));
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        if let Some(error_msg) = &self.error {
+            log::error!(
+                "Error occured when trying to detect security keys.\n{}",
+                error_msg
+            );
+        }
+
+        Ok(serde_json::to_value(self)?)
+    }
+}
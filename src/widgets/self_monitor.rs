@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::cpu_accounting::cpu_times;
+use crate::i3_status::CONFIG;
+use crate::theme::Color;
+use crate::utils::template::{number, render, Placeholder};
+use crate::widgets::{Widget, WidgetError};
+
+// Placeholders: {status} (e.g. "12ms/min (network_information 5ms)", "…" during the first
+// minute), {total} (CPU milliseconds per minute of all widgets), {widget} and {time} (the
+// most expensive widget and its CPU milliseconds per minute)
+// The placeholders except {status} are empty during the first minute
+const FORMAT: &str = "⚙ {status}";
+
+fn milliseconds(time: Duration) -> String {
+    format!("{}ms", time.as_millis())
+}
+
+#[derive(Serialize)]
+pub struct SelfMonitor {
+    // Name of the widget
+    name: &'static str,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+}
+
+impl SelfMonitor {
+    pub fn new() -> Self {
+        Self {
+            name: "self_monitor",
+            full_text: None,
+            color: Color::Neutral,
+        }
+    }
+}
+
+impl Widget for SelfMonitor {
+    // Shows the CPU time per minute of all widgets together and the most expensive widget
+    fn update(&mut self) {
+        let cpu_times = cpu_times();
+        let total: Duration = cpu_times.iter().map(|(_, time)| *time).sum();
+        let Some((most_expensive, time)) = cpu_times.into_iter().max_by_key(|(_, time)| *time)
+        else {
+            // The first numbers are there after a minute
+            self.full_text = Some(render(
+                CONFIG.format(self.name).as_deref().unwrap_or(FORMAT),
+                &[
+                    ("status", "…".into()),
+                    ("total", Placeholder::from("")),
+                    ("widget", Placeholder::from("")),
+                    ("time", Placeholder::from("")),
+                ],
+            ));
+            self.color = Color::Neutral;
+            return;
+        };
+
+        self.color = if time > Duration::from_millis(CONFIG.widget_cpu_budget_ms()) {
+            Color::Warning
+        } else {
+            Color::Neutral
+        };
+        let status = format!(
+            "{}/min ({} {})",
+            milliseconds(total),
+            most_expensive,
+            milliseconds(time)
+        );
+        self.full_text = Some(render(
+            CONFIG.format(self.name).as_deref().unwrap_or(FORMAT),
+            &[
+                ("status", status.into()),
+                ("total", number(total.as_millis() as f64)),
+                ("widget", most_expensive.to_string().into()),
+                ("time", number(time.as_millis() as f64)),
+            ],
+        ));
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        Ok(serde_json::to_value(self)?)
+    }
+}
@@ -0,0 +1,196 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::{SnmpTarget, WidgetId};
+use crate::i3_status::CONFIG;
+use crate::snmp::{SnmpClient, SnmpValue};
+use crate::theme::Color;
+use crate::utils::stop::{stop_signal, StopHandle, StopSignal};
+use crate::utils::template::{number, render, Placeholder};
+use crate::widgets::{Widget, WidgetError};
+
+// Placeholders: {values} (every value with its label and unit, e.g. "rx 1.2Mbit tx 0.3Mbit",
+// "SNMP: unreachable" or "SNMP: ..." before the first answer) and one placeholder per
+// value, named like its label (e.g. {rx:.1}), that is scaled but without unit
+const FORMAT: &str = "{values}";
+
+// A counter reading and when it was taken, used to calculate the rate
+type CounterReading = Option<(u64, Instant)>;
+// Every value with its label and unit, and the values by their labels
+type Values = (String, Vec<(String, Placeholder)>);
+
+#[derive(Serialize)]
+pub struct SnmpPoller {
+    // Name of the widget
+    name: &'static str,
+    #[serde(skip_serializing)]
+    // "snmp:<instance>", the format is looked up with it
+    id: WidgetId,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    #[serde(skip_serializing)]
+    // Latest formatted values, updated by the polling thread
+    output: Arc<Mutex<Option<Result<Values, String>>>>,
+    #[serde(skip_serializing)]
+    // Stops the polling thread when the widget is dropped
+    stop: StopHandle,
+}
+
+impl SnmpPoller {
+    pub fn new(id: WidgetId, target: SnmpTarget) -> Self {
+        let output = Arc::new(Mutex::new(None));
+        let (stop, signal) = stop_signal();
+        Self::poll_periodically(target, Arc::clone(&output), signal);
+
+        Self {
+            name: "snmp",
+            id,
+            full_text: None,
+            color: Color::Neutral,
+            output,
+            stop,
+        }
+    }
+
+    // Change per second since the last reading, None for the first reading
+    fn rate(current: u64, previous: &mut CounterReading) -> Option<f64> {
+        let now = Instant::now();
+        let rate = match previous {
+            // A smaller value means the counter wrapped around or the device restarted
+            Some((value, time)) if current >= *value => {
+                Some((current - *value) as f64 / now.duration_since(*time).as_secs_f64())
+            }
+            _ => None,
+        };
+        *previous = Some((current, now));
+
+        rate
+    }
+
+    fn format_values(
+        target: &SnmpTarget,
+        values: &[SnmpValue],
+        counters: &mut [CounterReading],
+    ) -> Values {
+        let (texts, placeholders) = target
+            .oids
+            .iter()
+            .zip(values)
+            .zip(counters.iter_mut())
+            .map(|((oid, value), previous)| {
+                let reading = match value {
+                    SnmpValue::Counter(counter) if oid.counter => Self::rate(*counter, previous),
+                    SnmpValue::Counter(value) | SnmpValue::Unsigned(value) => Some(*value as f64),
+                    SnmpValue::Integer(value) => Some(*value as f64),
+                    SnmpValue::Text(text) => {
+                        return (
+                            format!("{} {}", oid.label, text),
+                            (oid.label.clone(), text.clone().into()),
+                        )
+                    }
+                    SnmpValue::Missing => {
+                        return (format!("{} ?", oid.label), (oid.label.clone(), "?".into()))
+                    }
+                };
+                match reading.map(|reading| reading * oid.scale) {
+                    Some(scaled) => (
+                        format!("{} {:.1}{}", oid.label, scaled, oid.unit),
+                        (oid.label.clone(), number(scaled)),
+                    ),
+                    None => (
+                        format!("{} ...", oid.label),
+                        (oid.label.clone(), "...".into()),
+                    ),
+                }
+            })
+            .unzip::<_, _, Vec<String>, Vec<_>>();
+
+        (texts.join(" "), placeholders)
+    }
+
+    // Devices may be slow or unreachable, so they are polled in their own thread
+    fn poll_periodically(
+        target: SnmpTarget,
+        output: Arc<Mutex<Option<Result<Values, String>>>>,
+        signal: StopSignal,
+    ) {
+        thread::spawn(move || {
+            let oids: Vec<&str> = target.oids.iter().map(|oid| oid.oid.as_str()).collect();
+            let mut counters: Vec<CounterReading> = vec![None; oids.len()];
+            let mut client = None;
+            loop {
+                if client.is_none() {
+                    client = SnmpClient::connect(&target.address, &target.community)
+                        .map_err(|error| {
+                            log::error!("Could not connect to {}: {}", target.address, error)
+                        })
+                        .ok();
+                }
+                let result = match client.as_mut().map(|client| client.get(&oids)) {
+                    Some(Ok(values)) => Ok(Self::format_values(&target, &values, &mut counters)),
+                    Some(Err(error)) => {
+                        log::warn!("Could not poll {}: {}", target.address, error);
+                        Err(error.to_string())
+                    }
+                    None => Err(String::from("Not connected")),
+                };
+                *output.lock().unwrap() = Some(result);
+                if !signal.sleep(Duration::from_secs(CONFIG.snmp_interval())) {
+                    return;
+                }
+            }
+        });
+    }
+}
+
+impl Drop for SnmpPoller {
+    fn drop(&mut self) {
+        self.stop.stop();
+    }
+}
+
+impl Widget for SnmpPoller {
+    fn update(&mut self) {
+        let output = self.output.lock().unwrap();
+        let placeholders = match &*output {
+            Some(Ok((text, values))) => {
+                self.color = Color::Neutral;
+                let mut placeholders = vec![("values", text.clone().into())];
+                placeholders.extend(
+                    values
+                        .iter()
+                        .map(|(label, value)| (label.as_str(), value.clone())),
+                );
+                placeholders
+            }
+            Some(Err(_)) => {
+                self.color = Color::Critical;
+                vec![("values", "SNMP: unreachable".into())]
+            }
+            // The first request is still running
+            None => vec![("values", "SNMP: ...".into())],
+        };
+        self.full_text = Some(render(
+            CONFIG
+                .format(&self.id.to_string())
+                .as_deref()
+                .unwrap_or(FORMAT),
+            &placeholders,
+        ));
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    // The device could not be reached
+    fn is_empty(&self) -> bool {
+        self.color == Color::Critical
+    }
+}
@@ -0,0 +1,235 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::i3_status::CONFIG;
+use crate::replay::http_agent;
+use crate::signals::is_low_power;
+use crate::theme::Color;
+use crate::utils::secrets::resolve_secret;
+use crate::utils::stop::{stop_signal, StopHandle};
+use crate::utils::template::{number, render, Placeholder};
+use crate::widgets::{Widget, WidgetError};
+
+// Syncthing usually runs on the same machine, so it should answer quickly
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+// Placeholders: {status} (e.g. "97% 2 dev 14 items", "error in Photos 2 dev", "?" if Syncthing
+// does not answer or "..." before the first answer), {completion} in percent, {devices}
+// (connected devices), {items} (items that still have to be synced) and {failed_folder}
+// Values that are not known yet are empty
+const FORMAT: &str = "Sync: {status}";
+
+struct SyncStatus {
+    // Completion of all folders in percent
+    completion: f64,
+    connected_devices: usize,
+    // Files and directories that still have to be synced
+    out_of_sync_items: u64,
+    // Label of the first folder that reports errors
+    failed_folder: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct Syncthing {
+    // Name of the widget
+    name: &'static str,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    // Whether a folder reports errors
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    urgent: bool,
+    #[serde(skip_serializing)]
+    // Latest status of Syncthing, updated by the polling thread
+    status: Arc<Mutex<Option<Result<SyncStatus, String>>>>,
+    #[serde(skip_serializing)]
+    // Stops the polling thread when the widget is dropped
+    stop: StopHandle,
+}
+
+impl Syncthing {
+    pub fn new() -> Self {
+        let status = Arc::new(Mutex::new(None));
+        let thread_status = Arc::clone(&status);
+        let (stop, signal) = stop_signal();
+        thread::spawn(move || {
+            let api_key = match resolve_secret(CONFIG.syncthing_api_key()) {
+                Ok(api_key) => api_key,
+                Err(error) => {
+                    *thread_status.lock().unwrap() = Some(Err(error.to_string()));
+                    log::error!("Could not get the Syncthing API key: {}", error);
+                    return;
+                }
+            };
+            let agent = http_agent(REQUEST_TIMEOUT);
+            loop {
+                if !is_low_power() {
+                    let result = Self::request_status(&agent, &api_key);
+                    if let Err(error) = &result {
+                        log::warn!("Could not get the status of Syncthing: {}", error);
+                    }
+                    *thread_status.lock().unwrap() =
+                        Some(result.map_err(|error| error.to_string()));
+                }
+                if !signal.sleep(Duration::from_secs(CONFIG.syncthing_interval())) {
+                    return;
+                }
+            }
+        });
+
+        Self {
+            name: "syncthing",
+            full_text: None,
+            color: Color::Neutral,
+            urgent: false,
+            status,
+            stop,
+        }
+    }
+
+    // See https://docs.syncthing.net/dev/rest.html
+    fn request_status(agent: &ureq::Agent, api_key: &str) -> Result<SyncStatus, WidgetError> {
+        let request = |path: &str| -> Result<Value, WidgetError> {
+            let response = agent
+                .get(&format!(
+                    "{}{}",
+                    CONFIG.syncthing_url().trim_end_matches('/'),
+                    path
+                ))
+                .set("X-API-Key", api_key)
+                .call()?;
+            Ok(serde_json::from_str(&response.into_string()?)?)
+        };
+
+        // Without parameters, this is the completion of all folders on this device
+        let completion = request("/rest/db/completion")?;
+        let connections = request("/rest/system/connections")?;
+        let mut status = SyncStatus {
+            completion: completion["completion"].as_f64().unwrap_or(0.0),
+            connected_devices: connections["connections"]
+                .as_object()
+                .map(|devices| {
+                    devices
+                        .values()
+                        .filter(|device| device["connected"].as_bool() == Some(true))
+                        .count()
+                })
+                .unwrap_or(0),
+            out_of_sync_items: 0,
+            failed_folder: None,
+        };
+
+        let folders = request("/rest/config/folders")?;
+        for folder in folders.as_array().into_iter().flatten() {
+            let Some(id) = folder["id"].as_str() else {
+                continue;
+            };
+            if folder["paused"].as_bool() == Some(true) {
+                continue;
+            }
+            let folder_status = request(&format!("/rest/db/status?folder={}", id))?;
+            status.out_of_sync_items += folder_status["needTotalItems"].as_u64().unwrap_or(0);
+            let has_errors = folder_status["state"].as_str() == Some("error")
+                || folder_status["errors"].as_u64().unwrap_or(0) > 0
+                || folder_status["pullErrors"].as_u64().unwrap_or(0) > 0;
+            if has_errors && status.failed_folder.is_none() {
+                status.failed_folder = Some(
+                    folder["label"]
+                        .as_str()
+                        .filter(|label| !label.is_empty())
+                        .unwrap_or(id)
+                        .to_string(),
+                );
+            }
+        }
+
+        Ok(status)
+    }
+
+    fn format_status(status: &SyncStatus) -> String {
+        let mut parts = Vec::new();
+        if let Some(folder) = &status.failed_folder {
+            parts.push(format!("error in {}", folder));
+        } else {
+            parts.push(format!("{:.0}%", status.completion.floor()));
+        }
+        parts.push(format!("{} dev", status.connected_devices));
+        if status.out_of_sync_items > 0 {
+            parts.push(format!("{} items", status.out_of_sync_items));
+        }
+
+        parts.join(" ")
+    }
+}
+
+impl Drop for Syncthing {
+    fn drop(&mut self) {
+        self.stop.stop();
+    }
+}
+
+impl Widget for Syncthing {
+    fn update(&mut self) {
+        let status = self.status.lock().unwrap();
+        let (text, status) = match &*status {
+            Some(Ok(status)) => {
+                self.urgent = status.failed_folder.is_some();
+                self.color = if self.urgent {
+                    Color::Critical
+                } else if status.connected_devices == 0 {
+                    Color::Warning
+                } else if status.out_of_sync_items > 0 {
+                    Color::Accent
+                } else {
+                    Color::Good
+                };
+                (Self::format_status(status), Some(status))
+            }
+            Some(Err(_)) => {
+                self.urgent = false;
+                self.color = Color::Critical;
+                (String::from("?"), None)
+            }
+            // The first request is still running
+            None => (String::from("..."), None),
+        };
+        let value =
+            |value: fn(&SyncStatus) -> f64| status.map(value).map_or(Placeholder::from(""), number);
+        self.full_text = Some(render(
+            CONFIG.format(self.name).as_deref().unwrap_or(FORMAT),
+            &[
+                ("status", text.into()),
+                ("completion", value(|status| status.completion)),
+                ("devices", value(|status| status.connected_devices as f64)),
+                ("items", value(|status| status.out_of_sync_items as f64)),
+                (
+                    "failed_folder",
+                    status
+                        .and_then(|status| status.failed_folder.clone())
+                        .unwrap_or_default()
+                        .into(),
+                ),
+            ],
+        ));
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn value(&self) -> Option<f32> {
+        match &*self.status.lock().unwrap() {
+            Some(Ok(status)) => Some(status.completion as f32),
+            _ => None,
+        }
+    }
+
+    // Syncthing is not running
+    fn is_empty(&self) -> bool {
+        matches!(*self.status.lock().unwrap(), Some(Err(_)))
+    }
+}
@@ -0,0 +1,178 @@
+use std::fs::{read, read_dir};
+use std::path::Path;
+use std::process;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::TaskMarker;
+use crate::i3_status::CONFIG;
+use crate::theme::Color;
+use crate::utils::cache::cached;
+use crate::utils::file::read_first_line_in_file;
+use crate::utils::template::render;
+use crate::variables::variable;
+use crate::widgets::{Widget, WidgetError};
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+// Placeholders: {label}, {status} (the running time like "1:23" while the task runs,
+// "done in 1:23" after it finished) and {duration} (running time, e.g. 1:23)
+const FORMAT: &str = "{label}: {status}";
+
+// The process list is shared by all task watchers, so /proc is scanned once per update
+const PROCESS_LIST_TTL: Duration = Duration::from_millis(900);
+
+// Command lines of all processes except our own
+fn command_lines() -> Arc<Vec<String>> {
+    let own_pid = process::id().to_string();
+    let Ok(entries) = read_dir("/proc") else {
+        return Arc::default();
+    };
+    let command_lines = entries
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            let pid = entry.file_name();
+            let pid = pid.to_string_lossy();
+            pid.bytes().all(|byte| byte.is_ascii_digit()) && pid != own_pid
+        })
+        .filter_map(|entry| read(entry.path().join("cmdline")).ok())
+        .map(|cmdline| {
+            // Arguments are separated by NUL bytes
+            let cmdline: Vec<u8> = cmdline
+                .iter()
+                .map(|byte| if *byte == 0 { b' ' } else { *byte })
+                .collect();
+            String::from_utf8_lossy(&cmdline).into_owned()
+        })
+        .collect();
+
+    Arc::new(command_lines)
+}
+
+// Whether a process with the pattern in its command line is running
+fn is_process_running(pattern: &str) -> bool {
+    cached("proc/cmdlines", PROCESS_LIST_TTL, command_lines)
+        .iter()
+        .any(|cmdline| cmdline.contains(pattern))
+}
+
+fn is_task_running(marker: &TaskMarker) -> bool {
+    match marker {
+        TaskMarker::PidFile(path) => read_first_line_in_file(path)
+            .ok()
+            .and_then(|pid| pid.trim().parse::<u32>().ok())
+            .is_some_and(|pid| Path::new(&format!("/proc/{}", pid)).exists()),
+        TaskMarker::Process(pattern) => is_process_running(pattern),
+        TaskMarker::Variable(name) => variable(name).as_deref() == Some("true"),
+    }
+}
+
+#[derive(Serialize)]
+pub struct TaskWatcher {
+    // Name of the widget
+    name: &'static str,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    // Set for a short time after the task finished
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    urgent: bool,
+    #[serde(skip_serializing)]
+    label: String,
+    #[serde(skip_serializing)]
+    marker: TaskMarker,
+    #[serde(skip_serializing)]
+    // When the running task was first seen
+    started: Option<Instant>,
+    #[serde(skip_serializing)]
+    // When the last task finished and how long it took
+    finished: Option<(Instant, Duration)>,
+}
+
+impl TaskWatcher {
+    pub fn new(label: String, marker: TaskMarker) -> Self {
+        Self {
+            name: "task",
+            full_text: None,
+            color: Color::Accent,
+            urgent: false,
+            label,
+            marker,
+            started: None,
+            finished: None,
+        }
+    }
+}
+
+impl Widget for TaskWatcher {
+    fn update(&mut self) {
+        let running = is_task_running(&self.marker);
+        match (running, self.started) {
+            (true, None) => self.started = Some(Instant::now()),
+            (false, Some(started)) => {
+                self.finished = Some((Instant::now(), started.elapsed()));
+                self.started = None;
+            }
+            _ => (),
+        }
+
+        let flash_duration = Duration::from_secs(CONFIG.task_flash_duration());
+        self.urgent = false;
+        let status = if let Some(started) = self.started {
+            self.color = Color::Accent;
+            let duration = format_duration(started.elapsed());
+            Some((duration.clone(), duration))
+        } else if let Some((finished, duration)) = self
+            .finished
+            .filter(|(finished, _)| finished.elapsed() < flash_duration)
+        {
+            self.color = Color::Good;
+            // Only flash once, i3bar keeps urgent blocks highlighted
+            self.urgent = finished.elapsed() < Duration::from_secs(1);
+            let duration = format_duration(duration);
+            Some((format!("done in {}", duration), duration))
+        } else {
+            None
+        };
+        let format = CONFIG.format(&format!("{}:{}", self.name, self.label));
+        self.full_text = status.map(|(status, duration)| {
+            render(
+                format.as_deref().unwrap_or(FORMAT),
+                &[
+                    ("label", self.label.clone().into()),
+                    ("status", status.into()),
+                    ("duration", duration.into()),
+                ],
+            )
+        });
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn value(&self) -> Option<f32> {
+        self.started.map(|started| started.elapsed().as_secs_f32())
+    }
+
+    // No task is running and the last one finished a while ago
+    fn is_empty(&self) -> bool {
+        self.full_text.is_none()
+    }
+}
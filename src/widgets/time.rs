@@ -1,10 +1,321 @@
-use chrono::Local;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Datelike, FixedOffset, Local, TimeZone, Utc};
 use serde::Serialize;
 use serde_json::Value;
+use std::env;
+use std::fmt::Display;
+use std::fs::{metadata, read_link};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 
-use crate::config::NEUTRAL;
-use crate::widgets::Widget;
-use crate::widgets::WidgetError;
+use crate::click_events::{ClickEvent, BUTTON_LEFT};
+use crate::config::{Markup, TimeZoneSetting, WidgetId};
+use crate::events::{time_zone_changes, WidgetEvent};
+use crate::i3_status::CONFIG;
+use crate::icons::Icon;
+use crate::output::escape_markup;
+use crate::theme::Color;
+use crate::utils::clock::{builtin_zone, Clock};
+use crate::utils::template::{render, Placeholder};
+use crate::utils::tzif::TimeZoneInfo;
+use crate::widgets::{Clickable, Widget, WidgetError};
+
+// Placeholders: {icon}, {date} (e.g. 16.10.2026), {time} (e.g. 13:37:00), {hour_minute} (e.g. 13:37),
+// {weekday} (e.g. Friday), {month} (e.g. October), {week} (ISO 8601 week, e.g. 42),
+// {week_year} (year the ISO week belongs to, e.g. 2026), {day_of_year} (e.g. 289),
+// {zone} (e.g. Europe/Berlin), {zone_abbreviation} (e.g. CEST), {utc_time} (e.g. 11:37:00Z)
+// and {utc_hour_minute} (e.g. 11:37Z)
+// With twelve_hour, {time} and {hour_minute} are shown as e.g. 1:37:00 PM and 1:37 PM,
+// the UTC placeholders always have 24 hours
+// With show_utc, the UTC time is shown after the format, e.g. "16.10.2026 13:37:00 / 11:37:00Z"
+// Formats can also contain strftime specifiers, e.g. "%a %d %b %H:%M" for "Fri 16 Oct 13:37"
+// See https://docs.rs/chrono/latest/chrono/format/strftime/index.html, "%%" is a single "%"
+const FORMAT: &str = "{date} {time}";
+const SHORT_FORMAT: &str = "{hour_minute}";
+const UTC_FORMAT: &str = " / {utc_time}";
+const SHORT_UTC_FORMAT: &str = " / {utc_hour_minute}";
+// Shown for a while after a left click
+const DATE_FORMAT: &str = "{weekday} %-d {month} %Y, week {week}, day {day_of_year}";
+const DATE_DURATION: Duration = Duration::from_secs(10);
+// The local zone source is also checked this often, in case /etc/localtime cannot be watched
+const LOCAL_ZONE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+// Returns an error for formats with unknown strftime specifiers
+pub fn check_format(format: &str) -> Result<(), String> {
+    if StrftimeItems::new(format).any(|item| item == Item::Error) {
+        Err(format!("Invalid strftime specifier in \"{}\"", format))
+    } else {
+        Ok(())
+    }
+}
+
+// Returns an error for name tables that do not have a name for every day or month
+pub fn check_names(clock: &WidgetId) -> Result<(), String> {
+    match (CONFIG.day_names(clock), CONFIG.month_names(clock)) {
+        (Some(names), _) if names.len() != 7 => Err(format!(
+            "day_names needs 7 names from Monday to Sunday, not {}",
+            names.len()
+        )),
+        (_, Some(names)) if names.len() != 12 => Err(format!(
+            "month_names needs 12 names from January to December, not {}",
+            names.len()
+        )),
+        _ => Ok(()),
+    }
+}
+
+// Configured names of the day and month, None if the names of chrono are used
+fn day_name(clock: &WidgetId, now: &impl Datelike) -> Option<String> {
+    CONFIG
+        .day_names(clock)
+        .filter(|names| names.len() == 7)
+        .map(|mut names| names.swap_remove(now.weekday().num_days_from_monday() as usize))
+}
+
+fn month_name(clock: &WidgetId, now: &impl Datelike) -> Option<String> {
+    CONFIG
+        .month_names(clock)
+        .filter(|names| names.len() == 12)
+        .map(|mut names| names.swap_remove(now.month0() as usize))
+}
+
+// First three characters of a name, for %a and %b
+fn abbreviate(name: &str) -> &str {
+    name.char_indices()
+        .nth(3)
+        .map(|(index, _)| &name[..index])
+        .unwrap_or(name)
+}
+
+// Replace %A, %a, %B and %b with the configured names, before chrono sees the format
+// A "%" in a name is escaped, so chrono shows it as it is, with markup the name is also
+// escaped for pango, as it becomes part of the format
+fn localize(clock: &WidgetId, now: &impl Datelike, format: &str, markup: bool) -> String {
+    let (day, month) = (day_name(clock, now), month_name(clock, now));
+    if day.is_none() && month.is_none() {
+        return format.to_string();
+    }
+
+    let mut localized = String::with_capacity(format.len());
+    let mut characters = format.chars();
+    while let Some(character) = characters.next() {
+        if character != '%' {
+            localized.push(character);
+            continue;
+        }
+        let Some(specifier) = characters.next() else {
+            localized.push(character);
+            break;
+        };
+        let name = match (specifier, day.as_deref(), month.as_deref()) {
+            ('A', Some(day), _) => day,
+            ('a', Some(day), _) => abbreviate(day),
+            ('B', _, Some(month)) => month,
+            ('b', _, Some(month)) => abbreviate(month),
+            _ => {
+                localized.push(character);
+                localized.push(specifier);
+                continue;
+            }
+        };
+        let name = if markup {
+            escape_markup(name)
+        } else {
+            name.to_string()
+        };
+        localized.push_str(&name.replace('%', "%%"));
+    }
+    localized
+}
+
+// Replace the strftime specifiers of a format, before its placeholders are replaced,
+// so the values of the placeholders are shown as they are
+// Formats with unknown specifiers are shown as they are, chrono would panic on them
+// With markup, the text of the format is pango markup and only what chrono fills in is escaped
+fn strftime<Tz>(clock: &WidgetId, now: &DateTime<Tz>, format: &str, markup: bool) -> String
+where
+    Tz: TimeZone,
+    Tz::Offset: Display,
+{
+    if check_format(format).is_err() {
+        return format.to_string();
+    }
+    let localized = localize(clock, now, format, markup);
+    if !markup {
+        return now.format(&localized).to_string();
+    }
+    StrftimeItems::new(&localized)
+        .map(|item| match item {
+            Item::Literal(text) | Item::Space(text) => text.to_string(),
+            item => escape_markup(&now.format_with_items([item].into_iter()).to_string()),
+        })
+        .collect()
+}
+
+// Zone of a clock, the local zone is loaded the same way as the zones of other clocks
+pub enum Zone {
+    Fixed(FixedOffset),
+    // The offset is kept with the seconds since the epoch in which it is valid, every update
+    // checks if a transition (e.g. to daylight saving time) was crossed since
+    Named {
+        // e.g. "Europe/Berlin", None for zones that are only a TZ rule
+        name: Option<String>,
+        rules: Box<dyn Clock>,
+        offset: FixedOffset,
+        // e.g. "CEST"
+        abbreviation: String,
+        valid: Range<i64>,
+    },
+}
+
+// Name of a zoneinfo file, e.g. "Europe/Berlin" for /usr/share/zoneinfo/Europe/Berlin
+fn zone_name(path: &Path) -> Option<String> {
+    let path = path.to_str()?;
+    path.rsplit_once("zoneinfo/")
+        .map(|(_, name)| name.to_string())
+}
+
+impl Zone {
+    fn named(name: Option<String>, rules: Box<dyn Clock>) -> Self {
+        Zone::Named {
+            name,
+            rules,
+            offset: FixedOffset::east_opt(0).unwrap(),
+            abbreviation: String::new(),
+            // Empty, so the offset is looked up with the first update
+            valid: 0..0,
+        }
+    }
+
+    // Zone files are read from the zoneinfo directory, unless the zone is built in
+    // Only files below the zoneinfo directory are read
+    fn load_name(name: &str) -> Result<Self, String> {
+        if name.is_empty() || name.starts_with('/') || name.split('/').any(|part| part == "..") {
+            return Err(format!("Invalid time zone {}", name));
+        }
+        if let Some(rules) = builtin_zone(name) {
+            return Ok(Self::named(Some(name.to_string()), rules));
+        }
+        Self::load_file(
+            &Path::new(CONFIG.zoneinfo_directory()).join(name),
+            Some(name.to_string()),
+        )
+    }
+
+    fn load_file(path: &Path, name: Option<String>) -> Result<Self, String> {
+        TimeZoneInfo::load(path).map(|info| Self::named(name, Box::new(info)))
+    }
+
+    pub fn load(setting: TimeZoneSetting) -> Result<Self, String> {
+        match setting {
+            TimeZoneSetting::Offset(offset) => FixedOffset::east_opt(offset)
+                .map(Zone::Fixed)
+                .ok_or_else(|| format!("UTC offset {} is out of range", offset)),
+            TimeZoneSetting::Name(name) => Self::load_name(&name),
+        }
+    }
+
+    // The local zone like the C library finds it: TZ is a zone name, a zoneinfo file
+    // after a ":" or a TZ rule, and /etc/localtime is used if TZ is not set
+    fn load_local(source: &LocalZoneSource) -> Result<Self, String> {
+        let Some(tz) = &source.tz else {
+            let path = Path::new(CONFIG.localtime_path());
+            let name = source.localtime_target.as_deref().and_then(zone_name);
+            // A copy of a zone file has no name, so it is always read
+            if let Some(rules) = name.as_deref().and_then(builtin_zone) {
+                return Ok(Self::named(name, rules));
+            }
+            return Self::load_file(path, name);
+        };
+
+        let tz = tz.strip_prefix(':').unwrap_or(tz);
+        if tz.is_empty() {
+            // An empty TZ is UTC
+            Self::load_name("UTC").or(Ok(Zone::Fixed(FixedOffset::east_opt(0).unwrap())))
+        } else if tz.starts_with('/') {
+            let path = Path::new(tz);
+            Self::load_file(path, zone_name(path))
+        } else if builtin_zone(tz).is_some()
+            || Path::new(CONFIG.zoneinfo_directory()).join(tz).is_file()
+        {
+            Self::load_name(tz)
+        } else {
+            TimeZoneInfo::from_rule(tz).map(|info| Self::named(None, Box::new(info)))
+        }
+    }
+
+    fn name(&self) -> Option<&str> {
+        match self {
+            Zone::Fixed(_) => None,
+            Zone::Named { name, .. } => name.as_deref(),
+        }
+    }
+
+    fn abbreviation(&self) -> String {
+        match self {
+            Zone::Fixed(offset) => offset.to_string(),
+            Zone::Named { abbreviation, .. } => abbreviation.clone(),
+        }
+    }
+
+    fn offset_at(&mut self, now: DateTime<Utc>) -> FixedOffset {
+        match self {
+            Zone::Fixed(offset) => *offset,
+            Zone::Named {
+                rules,
+                offset,
+                abbreviation,
+                valid,
+                ..
+            } => {
+                // The clock can also be set back, e.g. by NTP
+                let timestamp = now.timestamp();
+                if !valid.contains(&timestamp) {
+                    let period = rules.period_at(timestamp);
+                    // Offsets are checked when the file is parsed
+                    let new_offset = FixedOffset::east_opt(period.utc_offset)
+                        .unwrap_or(FixedOffset::east_opt(0).unwrap());
+                    if *offset != new_offset && !valid.is_empty() {
+                        log::info!(
+                            "Switched from {} to {} ({})",
+                            offset,
+                            new_offset,
+                            period.abbreviation
+                        );
+                    }
+                    *offset = new_offset;
+                    *abbreviation = period.abbreviation;
+                    *valid = period.valid;
+                }
+                *offset
+            }
+        }
+    }
+}
+
+// Where the local zone comes from, it is loaded again when this changes
+// (e.g. timedatectl set-timezone points /etc/localtime to another zone)
+#[derive(PartialEq)]
+struct LocalZoneSource {
+    tz: Option<String>,
+    localtime_target: Option<PathBuf>,
+    // Also catches a /etc/localtime that is replaced by a copy of another zone
+    localtime_modified: Option<SystemTime>,
+}
+
+impl LocalZoneSource {
+    fn current() -> Self {
+        let localtime_path = CONFIG.localtime_path();
+        Self {
+            tz: env::var("TZ").ok(),
+            localtime_target: read_link(localtime_path).ok(),
+            localtime_modified: metadata(localtime_path)
+                .and_then(|metadata| metadata.modified())
+                .ok(),
+        }
+    }
+}
 
 #[derive(Serialize)]
 pub struct Time {
@@ -13,29 +324,229 @@ pub struct Time {
     // Text that will be shown in the status bar
     full_text: Option<String>,
     // Color of the text
-    color: &'static str,
+    color: Color,
+    #[serde(skip_serializing)]
+    // Shorter text that is shown when the bar runs out of space
+    short_text: Option<String>,
+    #[serde(skip_serializing)]
+    // "time" or "time:<instance>", the format and block options are looked up with it
+    id: WidgetId,
+    #[serde(skip_serializing)]
+    // Label and zone of another time zone, the local time is shown if not set
+    zone: Option<(String, Zone)>,
+    #[serde(skip_serializing)]
+    // Where the local zone was loaded from and the zone, None if it could not be loaded
+    local: Option<(LocalZoneSource, Option<Zone>)>,
+    #[serde(skip_serializing)]
+    // Number of time zone changes that were seen and when the local zone source was checked
+    local_checked: Option<(u64, Instant)>,
+    #[serde(skip_serializing)]
+    // Until when the full date is shown instead of the format, set by a click
+    date_shown_until: Option<Instant>,
 }
 
 impl Time {
-    pub fn new() -> Self {
+    pub fn new(id: WidgetId, zone: Option<(String, Zone)>) -> Self {
         Self {
             name: "time",
+            id,
             full_text: None,
-            color: NEUTRAL,
+            color: Color::Neutral,
+            short_text: None,
+            zone,
+            local: None,
+            local_checked: None,
+            date_shown_until: None,
         }
     }
-}
 
-impl Widget for Time {
-    fn name(&self) -> &str {
-        self.name
+    // The local zone, loaded again when /etc/localtime changed
+    // The source is only checked after a TimeZone event and every LOCAL_ZONE_CHECK_INTERVAL,
+    // in case the file could not be watched
+    // None if it could not be loaded, the local time of chrono is shown then
+    fn local_zone(&mut self) -> Option<&mut Zone> {
+        let changes = time_zone_changes();
+        if self.local_checked.is_none_or(|(seen_changes, checked)| {
+            seen_changes != changes || checked.elapsed() >= LOCAL_ZONE_CHECK_INTERVAL
+        }) {
+            self.local_checked = Some((changes, Instant::now()));
+            self.reload_local_zone();
+        }
+
+        self.local.as_mut().and_then(|(_, zone)| zone.as_mut())
+    }
+
+    fn reload_local_zone(&mut self) {
+        let source = LocalZoneSource::current();
+        if self
+            .local
+            .as_ref()
+            .is_none_or(|(loaded_source, _)| *loaded_source != source)
+        {
+            let zone = match Zone::load_local(&source) {
+                Ok(zone) => {
+                    if self.local.is_some() {
+                        log::info!(
+                            "The local time zone changed to {}",
+                            zone.name().unwrap_or("a zone without name")
+                        );
+                    }
+                    Some(zone)
+                }
+                Err(error) => {
+                    log::error!("Could not load the local time zone: {}", error);
+                    None
+                }
+            };
+            self.local = Some((source, zone));
+        }
     }
 
+    // zone is the name of the zone (e.g. Europe/Berlin) or its abbreviation if it has no name
+    fn format<Tz>(&mut self, now: DateTime<Tz>, zone: String, abbreviation: String)
+    where
+        Tz: TimeZone,
+        Tz::Offset: Display,
+    {
+        if self
+            .date_shown_until
+            .is_some_and(|shown_until| Instant::now() >= shown_until)
+        {
+            self.date_shown_until = None;
+        }
+        let mut format = match self.date_shown_until {
+            Some(_) => DATE_FORMAT.to_string(),
+            None => CONFIG
+                .format(&self.id.to_string())
+                .unwrap_or_else(|| FORMAT.to_string()),
+        };
+        let mut short_format = CONFIG
+            .short_format(&self.id.to_string())
+            .unwrap_or_else(|| SHORT_FORMAT.to_string());
+        if CONFIG.time_show_utc(&self.id) {
+            format.push_str(UTC_FORMAT);
+            short_format.push_str(SHORT_UTC_FORMAT);
+        }
+        let (time, hour_minute) = if CONFIG.twelve_hour_clock(&self.id) {
+            ("%-I:%M:%S %p", "%-I:%M %p")
+        } else {
+            ("%H:%M:%S", "%H:%M")
+        };
+        let utc = now.with_timezone(&Utc);
+        let markup = CONFIG.block_config(&self.id.to_string()).markup == Some(Markup::Pango);
+        let placeholders = [
+            ("icon", Icon::Time.glyph()),
+            ("date", now.format("%d.%m.%Y").to_string()),
+            ("time", now.format(time).to_string()),
+            ("hour_minute", now.format(hour_minute).to_string()),
+            (
+                "weekday",
+                day_name(&self.id, &now).unwrap_or_else(|| now.format("%A").to_string()),
+            ),
+            (
+                "month",
+                month_name(&self.id, &now).unwrap_or_else(|| now.format("%B").to_string()),
+            ),
+            // The first days of January can still be in the last week of the year before
+            ("week", now.iso_week().week().to_string()),
+            ("week_year", now.iso_week().year().to_string()),
+            ("day_of_year", now.ordinal().to_string()),
+            ("zone", zone),
+            ("zone_abbreviation", abbreviation),
+            ("utc_time", utc.format("%H:%M:%SZ").to_string()),
+            ("utc_hour_minute", utc.format("%H:%MZ").to_string()),
+        ];
+        // Values like configured names must not break the markup of the format
+        let placeholders = placeholders.map(|(name, value)| {
+            if markup {
+                (name, Placeholder::from(escape_markup(&value)))
+            } else {
+                (name, Placeholder::from(value))
+            }
+        });
+        let full_text = render(&strftime(&self.id, &now, &format, markup), &placeholders);
+        let short_text = render(
+            &strftime(&self.id, &now, &short_format, markup),
+            &placeholders,
+        );
+
+        match &self.zone {
+            Some((label, _)) => {
+                let label = if markup {
+                    escape_markup(label)
+                } else {
+                    label.to_string()
+                };
+                self.full_text = Some(format!("{} {}", label, full_text));
+                self.short_text = Some(format!("{} {}", label, short_text));
+            }
+            None => {
+                self.full_text = Some(full_text);
+                self.short_text = Some(short_text);
+            }
+        }
+    }
+}
+
+impl Widget for Time {
     fn update(&mut self) {
-        self.full_text = Some(Local::now().format("%d.%m.%Y %H:%M:%S").to_string());
+        let now = Utc::now();
+        let zone = match &mut self.zone {
+            Some((_, zone)) => Some(zone),
+            None => self.local_zone(),
+        };
+        match zone {
+            Some(zone) => {
+                let offset = zone.offset_at(now);
+                let abbreviation = zone.abbreviation();
+                let name = zone.name().map(str::to_string);
+                self.format(
+                    now.with_timezone(&offset),
+                    name.unwrap_or_else(|| abbreviation.clone()),
+                    abbreviation,
+                )
+            }
+            None => {
+                let now = Local::now();
+                let offset = now.format("%:z").to_string();
+                self.format(now, offset.clone(), offset)
+            }
+        }
     }
 
     fn display_text(&self) -> Result<Value, WidgetError> {
         Ok(serde_json::to_value(self)?)
     }
+
+    fn short_text(&self) -> Option<String> {
+        self.short_text.clone()
+    }
+
+    fn updates_on_render(&self) -> bool {
+        true
+    }
+
+    // The local time follows the system time zone right away
+    fn events(&self) -> &[WidgetEvent] {
+        match self.zone {
+            Some(_) => &[],
+            None => &[WidgetEvent::TimeZone],
+        }
+    }
+
+    fn clickable(&mut self) -> Option<&mut dyn Clickable> {
+        Some(self)
+    }
+}
+
+impl Clickable for Time {
+    // A left click shows the full date for a while, another one goes back to the time
+    fn click(&mut self, event: &ClickEvent) {
+        if event.button == BUTTON_LEFT {
+            self.date_shown_until = match self.date_shown_until {
+                Some(_) => None,
+                None => Some(Instant::now() + DATE_DURATION),
+            };
+        }
+    }
 }
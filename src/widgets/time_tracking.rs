@@ -0,0 +1,217 @@
+use std::env;
+use std::fs::{read_dir, read_to_string};
+use std::path::PathBuf;
+use std::process::Command;
+
+use chrono::{NaiveDateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::click_events::{ClickEvent, BUTTON_LEFT};
+use crate::i3_status::CONFIG;
+use crate::theme::Color;
+use crate::utils::template::render;
+use crate::widgets::{Clickable, Widget, WidgetError};
+
+// Intervals are stored in UTC, e.g. "inc 20261016T080000Z - 20261016T093000Z # work meeting"
+// Running intervals have no end
+const TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+// Placeholders: {status} (the tags and the tracked time like "work 1:23", "off" while nothing
+// is tracked), {tags} (e.g. "work meeting") and {duration} (tracked time, e.g. 1:23)
+// {tags} and {duration} are empty while nothing is tracked
+const FORMAT: &str = "⏱ {status}";
+
+// Database directory of timewarrior, the same way timewarrior looks for it:
+// $TIMEWARRIORDB, ~/.timewarrior if it exists, and $XDG_DATA_HOME/timewarrior otherwise
+fn database_directory() -> PathBuf {
+    if let Ok(directory) = env::var("TIMEWARRIORDB") {
+        if !directory.is_empty() {
+            return PathBuf::from(directory);
+        }
+    }
+    let home = PathBuf::from(env::var("HOME").unwrap_or_default());
+    let legacy_directory = home.join(".timewarrior");
+    if legacy_directory.is_dir() {
+        return legacy_directory;
+    }
+    match env::var("XDG_DATA_HOME") {
+        Ok(data_home) if !data_home.is_empty() => PathBuf::from(data_home).join("timewarrior"),
+        _ => home.join(".local/share/timewarrior"),
+    }
+}
+
+// Tags of an interval, tags with spaces are quoted
+fn parse_tags(tags: &str) -> Vec<String> {
+    let mut parsed = Vec::new();
+    let mut rest = tags.trim();
+    while !rest.is_empty() {
+        let (tag, remaining) = match rest.strip_prefix('"') {
+            Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+            None => rest.split_once(' ').unwrap_or((rest, "")),
+        };
+        parsed.push(tag.replace("\\\"", "\""));
+        rest = remaining.trim_start();
+    }
+
+    parsed
+}
+
+// Start (seconds since the epoch) and tags of the running interval, None if nothing is tracked
+// Only the last interval can be running, it is the last line of the newest data file
+fn running_interval() -> Result<Option<(i64, Vec<String>)>, WidgetError> {
+    let data_directory = database_directory().join("data");
+    let newest_file = read_dir(&data_directory)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        // Files are named after their month, e.g. 2026-10.data
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "data")
+        })
+        .max();
+    let Some(newest_file) = newest_file else {
+        return Ok(None);
+    };
+
+    let content = read_to_string(newest_file)?;
+    let Some(line) = content.lines().rev().find(|line| !line.trim().is_empty()) else {
+        return Ok(None);
+    };
+    let line = line
+        .strip_prefix("inc ")
+        .ok_or_else(|| WidgetError::new(format!("Invalid interval \"{}\"", line)))?;
+    // The tags can be followed by an annotation
+    let mut parts = line.splitn(3, " # ");
+    let range = parts.next().unwrap_or_default().trim();
+    if range.contains(" - ") {
+        return Ok(None);
+    }
+    let start = NaiveDateTime::parse_from_str(range, TIMESTAMP_FORMAT)
+        .map_err(|error| WidgetError::new(format!("Invalid start \"{}\": {}", range, error)))?;
+
+    Ok(Some((
+        start.and_utc().timestamp(),
+        parse_tags(parts.next().unwrap_or_default()),
+    )))
+}
+
+#[derive(Serialize)]
+pub struct TimeTracking {
+    // Name of the widget
+    name: &'static str,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    #[serde(skip_serializing)]
+    // Holds the error message if an error occured during widget update
+    error: Option<String>,
+    #[serde(skip_serializing)]
+    // Whether an interval is running, a click stops it or starts a new one
+    tracking: bool,
+}
+
+impl TimeTracking {
+    pub fn new() -> Self {
+        Self {
+            name: "timewarrior",
+            full_text: None,
+            color: Color::Neutral,
+            error: None,
+            tracking: false,
+        }
+    }
+
+    fn run_timew(args: &[&str]) -> Result<(), WidgetError> {
+        let output = Command::new("timew").args(args).output()?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(WidgetError::new(format!(
+                "\"timew {}\" failed with {}: {}",
+                args.join(" "),
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )))
+        }
+    }
+}
+
+impl Clickable for TimeTracking {
+    fn click(&mut self, event: &ClickEvent) {
+        if event.button != BUTTON_LEFT {
+            return;
+        }
+
+        let result = if self.tracking {
+            Self::run_timew(&["stop"])
+        } else {
+            let mut args = vec!["start"];
+            args.extend(CONFIG.timewarrior_tags());
+            Self::run_timew(&args)
+        };
+        if let Err(error) = result {
+            log::error!("Could not toggle time tracking.\n{}", error);
+        }
+    }
+}
+
+impl TimeTracking {
+    fn render_text(&self, status: String, tags: String, duration: String) -> String {
+        render(
+            CONFIG.format(self.name).as_deref().unwrap_or(FORMAT),
+            &[
+                ("status", status.into()),
+                ("tags", tags.into()),
+                ("duration", duration.into()),
+            ],
+        )
+    }
+}
+
+impl Widget for TimeTracking {
+    fn update(&mut self) {
+        self.error = None;
+        match running_interval() {
+            Ok(Some((start, tags))) => {
+                let elapsed = (Utc::now().timestamp() - start).max(0) / 60;
+                self.tracking = true;
+                self.color = Color::Good;
+                let duration = format!("{}:{:02}", elapsed / 60, elapsed % 60);
+                let tags = tags.join(" ");
+                let status = if tags.is_empty() {
+                    duration.clone()
+                } else {
+                    format!("{} {}", tags, duration)
+                };
+                self.full_text = Some(self.render_text(status, tags, duration));
+            }
+            Ok(None) => {
+                self.tracking = false;
+                self.color = Color::Neutral;
+                self.full_text =
+                    Some(self.render_text(String::from("off"), String::new(), String::new()));
+            }
+            Err(error) => {
+                self.tracking = false;
+                self.error = Some(error.to_string());
+                self.full_text = None;
+            }
+        }
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        if let Some(error_msg) = &self.error {
+            return Err(WidgetError::new(format!(
+                "Error occured when trying to read the timewarrior database.\n{}",
+                error_msg
+            )));
+        }
+
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn clickable(&mut self) -> Option<&mut dyn Clickable> {
+        Some(self)
+    }
+}
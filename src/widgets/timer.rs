@@ -0,0 +1,152 @@
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::click_events::{ClickEvent, BUTTON_LEFT, BUTTON_RIGHT};
+use crate::config::WidgetId;
+use crate::i3_status::CONFIG;
+use crate::theme::Color;
+use crate::utils::template::render;
+use crate::widgets::{Clickable, Widget, WidgetError};
+
+// Placeholders: {icon} (⏵ while running, ⏸ while paused), {label} (the name of the timer,
+// empty for the stopwatch) and {time} (remaining time, or the elapsed time of the stopwatch,
+// e.g. 04:59)
+const FORMAT: &str = "{icon} {label} {time}";
+// Format of the stopwatch, which has no label
+const STOPWATCH_FORMAT: &str = "{icon} {time}";
+
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds >= 60 * 60 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    } else {
+        format!("{:02}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+// Counts down from a duration, or up like a stopwatch if there is none
+// A left click starts or pauses the timer, a right click resets it
+#[derive(Serialize)]
+pub struct Timer {
+    // Name of the widget
+    name: &'static str,
+    #[serde(skip_serializing)]
+    // "timer" for the stopwatch or "timer:<label>"
+    id: WidgetId,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    // Whether the countdown is over
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    urgent: bool,
+    #[serde(skip_serializing)]
+    // Shown in front of the time, e.g. "tea"
+    label: Option<String>,
+    #[serde(skip_serializing)]
+    // Duration of a countdown, None for a stopwatch
+    duration: Option<Duration>,
+    #[serde(skip_serializing)]
+    // Time that ran before the timer was paused the last time
+    elapsed_before: Duration,
+    #[serde(skip_serializing)]
+    // When the timer was started, None while it is paused
+    running_since: Option<Instant>,
+}
+
+impl Timer {
+    pub fn new(id: WidgetId, label: Option<String>, duration: Option<Duration>) -> Self {
+        Self {
+            name: "timer",
+            id,
+            full_text: None,
+            color: Color::Neutral,
+            urgent: false,
+            label,
+            duration,
+            elapsed_before: Duration::ZERO,
+            running_since: None,
+        }
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.elapsed_before
+            + self
+                .running_since
+                .map_or(Duration::ZERO, |running_since| running_since.elapsed())
+    }
+}
+
+impl Clickable for Timer {
+    fn click(&mut self, event: &ClickEvent) {
+        match event.button {
+            BUTTON_LEFT => match self.running_since.take() {
+                Some(running_since) => self.elapsed_before += running_since.elapsed(),
+                None => self.running_since = Some(Instant::now()),
+            },
+            BUTTON_RIGHT => {
+                self.elapsed_before = Duration::ZERO;
+                self.running_since = None;
+            }
+            _ => (),
+        }
+    }
+}
+
+impl Widget for Timer {
+    fn update(&mut self) {
+        let elapsed = self.elapsed();
+        let shown = match self.duration {
+            // Rounded up, so a countdown shows 00:00 only when it is over
+            Some(duration) => {
+                Duration::from_secs(duration.saturating_sub(elapsed).as_secs_f64().ceil() as u64)
+            }
+            None => elapsed,
+        };
+        self.urgent = self.duration.is_some_and(|duration| elapsed >= duration);
+        self.color = if self.urgent {
+            Color::Critical
+        } else if self.running_since.is_some() {
+            Color::Good
+        } else {
+            Color::Neutral
+        };
+
+        let icon = if self.running_since.is_some() {
+            "⏵"
+        } else {
+            "⏸"
+        };
+        let format = CONFIG.format(&self.id.to_string());
+        self.full_text = Some(render(
+            format.as_deref().unwrap_or(match self.label {
+                Some(_) => FORMAT,
+                None => STOPWATCH_FORMAT,
+            }),
+            &[
+                ("icon", icon.into()),
+                ("label", self.label.clone().unwrap_or_default().into()),
+                ("time", format_duration(shown).into()),
+            ],
+        ));
+    }
+
+    fn updates_on_render(&self) -> bool {
+        true
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn clickable(&mut self) -> Option<&mut dyn Clickable> {
+        Some(self)
+    }
+}
@@ -0,0 +1,252 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::TransferSource;
+use crate::i3_status::CONFIG;
+use crate::replay::http_agent;
+use crate::signals::is_low_power;
+use crate::theme::Color;
+use crate::utils::base64::basic_authorization;
+use crate::utils::secrets::resolve_secret;
+use crate::utils::stop::{stop_signal, StopHandle};
+use crate::utils::template::{number, render};
+use crate::widgets::{Widget, WidgetError};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+// rsync rewrites its progress line every second, a file that was not written for
+// longer than this belongs to a job that was killed
+const STALE_AFTER: Duration = Duration::from_secs(60);
+// Only the end of the progress file is read, it can get large for long jobs
+const TAIL_LENGTH: u64 = 4096;
+// Placeholders: {label} (the instance), {status} (e.g. "45% 12.34MB/s ETA 0:01:23"),
+// {percent}, {speed} (e.g. 12.34MB/s) and {eta} (e.g. 0:01:23)
+// Speed and ETA are empty until the transfer reports them
+const FORMAT: &str = "{label}: {status}";
+
+struct TransferProgress {
+    percent: f64,
+    // e.g. "12.34MB/s"
+    speed: Option<String>,
+    // Estimated remaining time, e.g. "0:01:23"
+    eta: Option<String>,
+}
+
+fn format_duration(seconds: u64) -> String {
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+// Parse the output of "rsync --info=progress2", returns None if the job is done
+// The progress line is rewritten with carriage returns, e.g.
+// "    123,456,789  45%   12.34MB/s    0:01:23 (xfr#12, to-chk=3/100)"
+fn parse_rsync_progress(output: &str) -> Option<TransferProgress> {
+    // rsync prints a summary at the end
+    if output.contains("total size is") {
+        return None;
+    }
+    let line = output
+        .split(['\r', '\n'])
+        .rev()
+        .find(|line| line.contains('%'))?;
+    if line.contains("to-chk=0/") {
+        return None;
+    }
+
+    let mut progress = TransferProgress {
+        percent: 0.0,
+        speed: None,
+        eta: None,
+    };
+    for field in line.split_whitespace() {
+        if let Some(percent) = field.strip_suffix('%') {
+            progress.percent = percent.parse().ok()?;
+        } else if field.ends_with("/s") {
+            progress.speed = Some(field.to_string());
+        } else if field.matches(':').count() == 2 {
+            progress.eta = Some(field.to_string());
+        }
+    }
+
+    Some(progress)
+}
+
+fn read_progress_file(path: &str) -> Result<Option<TransferProgress>, WidgetError> {
+    let mut file = File::open(path)?;
+    let metadata = file.metadata()?;
+    let age = SystemTime::now()
+        .duration_since(metadata.modified()?)
+        .unwrap_or_default();
+    if age > STALE_AFTER {
+        return Ok(None);
+    }
+
+    file.seek(SeekFrom::Start(metadata.len().saturating_sub(TAIL_LENGTH)))?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail)?;
+
+    Ok(parse_rsync_progress(&String::from_utf8_lossy(&tail)))
+}
+
+// See https://rclone.org/rc/#core-stats
+fn request_rclone_progress(
+    agent: &ureq::Agent,
+    url: &str,
+    credentials: Option<(&str, &str)>,
+) -> Result<Option<TransferProgress>, WidgetError> {
+    let mut request = agent.post(&format!("{}/core/stats", url.trim_end_matches('/')));
+    if let Some((username, password)) = credentials {
+        request = request.set(
+            "Authorization",
+            &basic_authorization(username, &resolve_secret(password)?),
+        );
+    }
+    let stats: Value = serde_json::from_str(
+        &request
+            .set("Content-Type", "application/json")
+            .send_string("{}")?
+            .into_string()?,
+    )?;
+
+    // rclone only lists "transferring" while files are transferred
+    let transferring = stats["transferring"]
+        .as_array()
+        .is_some_and(|files| !files.is_empty());
+    let total_bytes = stats["totalBytes"].as_f64().unwrap_or(0.0);
+    if !transferring || total_bytes <= 0.0 {
+        return Ok(None);
+    }
+
+    Ok(Some(TransferProgress {
+        percent: stats["bytes"].as_f64().unwrap_or(0.0) / total_bytes * 100.0,
+        speed: stats["speed"]
+            .as_f64()
+            .map(|speed| format!("{:.2}MB/s", speed / 1000.0 / 1000.0)),
+        eta: stats["eta"].as_u64().map(format_duration),
+    }))
+}
+
+#[derive(Serialize)]
+pub struct Transfer {
+    // Name of the widget
+    name: &'static str,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    #[serde(skip_serializing)]
+    label: String,
+    #[serde(skip_serializing)]
+    // Only set for progress files, rclone is polled by its own thread
+    progress_file: Option<String>,
+    #[serde(skip_serializing)]
+    // Progress of the running job, None if no job is running
+    progress: Arc<Mutex<Option<TransferProgress>>>,
+    #[serde(skip_serializing)]
+    // Stops the rclone polling thread when the widget is dropped
+    stop: Option<StopHandle>,
+}
+
+impl Transfer {
+    pub fn new(label: String, source: TransferSource) -> Self {
+        let progress = Arc::new(Mutex::new(None));
+        let (progress_file, stop) = match source {
+            TransferSource::ProgressFile(path) => (Some(path), None),
+            TransferSource::Rclone { url, credentials } => {
+                let thread_progress = Arc::clone(&progress);
+                let (stop, signal) = stop_signal();
+                thread::spawn(move || {
+                    let agent = http_agent(REQUEST_TIMEOUT);
+                    loop {
+                        if !is_low_power() {
+                            // rclone only runs while it is transferring something
+                            let credentials = credentials
+                                .as_ref()
+                                .map(|(username, password)| (username.as_str(), password.as_str()));
+                            let result = request_rclone_progress(&agent, &url, credentials)
+                                .unwrap_or_else(|error| {
+                                    log::debug!("Could not get the progress of rclone: {}", error);
+                                    None
+                                });
+                            *thread_progress.lock().unwrap() = result;
+                        }
+                        if !signal.sleep(Duration::from_secs(CONFIG.transfer_interval())) {
+                            return;
+                        }
+                    }
+                });
+                (None, Some(stop))
+            }
+        };
+
+        Self {
+            name: "transfer",
+            full_text: None,
+            color: Color::Accent,
+            label,
+            progress_file,
+            progress,
+            stop,
+        }
+    }
+}
+
+impl Drop for Transfer {
+    fn drop(&mut self) {
+        if let Some(stop) = &self.stop {
+            stop.stop();
+        }
+    }
+}
+
+impl Widget for Transfer {
+    fn update(&mut self) {
+        if let Some(path) = &self.progress_file {
+            // A missing file means that no job was started yet
+            *self.progress.lock().unwrap() = read_progress_file(path).unwrap_or(None);
+        }
+
+        let format = CONFIG.format(&format!("{}:{}", self.name, self.label));
+        self.full_text = self.progress.lock().unwrap().as_ref().map(|progress| {
+            let mut parts = vec![format!("{:.0}%", progress.percent)];
+            parts.extend(progress.speed.clone());
+            parts.extend(progress.eta.as_ref().map(|eta| format!("ETA {}", eta)));
+            render(
+                format.as_deref().unwrap_or(FORMAT),
+                &[
+                    ("label", self.label.clone().into()),
+                    ("status", parts.join(" ").into()),
+                    ("percent", number(progress.percent)),
+                    ("speed", progress.speed.clone().unwrap_or_default().into()),
+                    ("eta", progress.eta.clone().unwrap_or_default().into()),
+                ],
+            )
+        });
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn value(&self) -> Option<f32> {
+        self.progress
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|progress| progress.percent as f32)
+    }
+
+    // No job is running
+    fn is_empty(&self) -> bool {
+        self.progress.lock().unwrap().is_none()
+    }
+}
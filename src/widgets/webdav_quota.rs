@@ -0,0 +1,225 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::{WebDavAccount, WidgetId};
+use crate::i3_status::CONFIG;
+use crate::icons::Icon;
+use crate::replay::http_agent;
+use crate::signals::is_low_power;
+use crate::theme::Color;
+use crate::utils::base64::basic_authorization;
+use crate::utils::secrets::resolve_secret;
+use crate::utils::stop::{stop_signal, StopHandle};
+use crate::utils::template::{number, render};
+use crate::widgets::{Widget, WidgetError};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const QUOTA_THRESHOLD: f64 = 10.0;
+const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+// Placeholders: {icon}, {name}, {used}, {available} and {total} in GiB, {percent} of available space
+// Without a quota, {available} and {total} are "∞"
+const FORMAT: &str = "{name}: {available:.1} GiB";
+// See RFC 4331
+const PROPFIND_BODY: &str = r#"<?xml version="1.0"?>
+<d:propfind xmlns:d="DAV:">
+  <d:prop>
+    <d:quota-used-bytes/>
+    <d:quota-available-bytes/>
+  </d:prop>
+</d:propfind>"#;
+
+struct Quota {
+    used: u64,
+    // None if the account has no quota
+    available: Option<u64>,
+}
+
+// Content of the first element with the given name, regardless of its namespace prefix
+// e.g. "123" for "<d:quota-used-bytes>123</d:quota-used-bytes>"
+fn element_text<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = xml;
+    while let Some(index) = rest.find(name) {
+        let (before, after) = (&rest[..index], &rest[index + name.len()..]);
+        rest = after;
+        let tag = &before[before.rfind('<')? + 1..];
+        // Closing tags and other elements that end with the same name are skipped
+        let is_opening_tag = tag.is_empty() || (tag.ends_with(':') && !tag.contains(['/', ' ']));
+        if !is_opening_tag || !after.starts_with(['>', ' ', '/']) {
+            continue;
+        }
+        let tag_end = after.find('>')?;
+        // Empty elements, e.g. "<d:quota-used-bytes/>"
+        if after[..tag_end].ends_with('/') {
+            return None;
+        }
+        let content = &after[tag_end + 1..];
+        return Some(content[..content.find('<')?].trim());
+    }
+
+    None
+}
+
+#[derive(Serialize)]
+pub struct WebDavQuota {
+    // Name of the widget
+    name: &'static str,
+    #[serde(skip_serializing)]
+    // "webdav:<instance>"
+    id: WidgetId,
+    // Text that will be shown in the status bar
+    full_text: Option<String>,
+    // Color of the text
+    color: Color,
+    #[serde(skip_serializing)]
+    label: String,
+    #[serde(skip_serializing)]
+    // Latest quota of the account, updated by the request thread
+    quota: Arc<Mutex<Option<Result<Quota, String>>>>,
+    #[serde(skip_serializing)]
+    // Stops the request thread when the widget is dropped
+    stop: StopHandle,
+}
+
+impl WebDavQuota {
+    pub fn new(id: WidgetId, label: String, account: WebDavAccount) -> Self {
+        let quota = Arc::new(Mutex::new(None));
+        let thread_quota = Arc::clone(&quota);
+        let (stop, signal) = stop_signal();
+        thread::spawn(move || {
+            let agent = http_agent(REQUEST_TIMEOUT);
+            loop {
+                if !is_low_power() {
+                    let result = resolve_secret(&account.password)
+                        .map_err(WidgetError::from)
+                        .and_then(|password| Self::request_quota(&agent, &account, &password));
+                    if let Err(error) = &result {
+                        log::warn!("Could not get the quota of {}: {}", account.url, error);
+                    }
+                    *thread_quota.lock().unwrap() = Some(result.map_err(|error| error.to_string()));
+                }
+                if !signal.sleep(Duration::from_secs(CONFIG.webdav_interval())) {
+                    return;
+                }
+            }
+        });
+
+        Self {
+            name: "webdav",
+            id,
+            full_text: None,
+            color: Color::Neutral,
+            label,
+            quota,
+            stop,
+        }
+    }
+
+    fn request_quota(
+        agent: &ureq::Agent,
+        account: &WebDavAccount,
+        password: &str,
+    ) -> Result<Quota, WidgetError> {
+        let response = agent
+            .request("PROPFIND", &account.url)
+            .set(
+                "Authorization",
+                &basic_authorization(&account.username, password),
+            )
+            .set("Depth", "0")
+            .set("Content-Type", "application/xml")
+            .send_string(PROPFIND_BODY)?
+            .into_string()?;
+
+        let used = element_text(&response, "quota-used-bytes")
+            .ok_or_else(|| WidgetError::new(String::from("The server does not report quotas")))?
+            .parse()?;
+        // Nextcloud uses negative values for unknown and unlimited quotas
+        let available = element_text(&response, "quota-available-bytes")
+            .and_then(|available| available.parse::<i64>().ok())
+            .and_then(|available| u64::try_from(available).ok());
+
+        Ok(Quota { used, available })
+    }
+
+    fn format_quota(&self, quota: &Quota) -> String {
+        let used = quota.used as f64 / GIB;
+        let (available, total) = match quota.available {
+            Some(available) => {
+                let available = available as f64 / GIB;
+                (number(available), number(used + available))
+            }
+            None => ("∞".into(), "∞".into()),
+        };
+        let percent = Self::available_percentage(quota).unwrap_or(100.0);
+
+        render(
+            CONFIG
+                .format(&self.id.to_string())
+                .as_deref()
+                .unwrap_or(FORMAT),
+            &[
+                ("icon", Icon::Disk.glyph().into()),
+                ("name", self.label.clone().into()),
+                ("used", number(used)),
+                ("available", available),
+                ("total", total),
+                ("percent", number(percent)),
+            ],
+        )
+    }
+
+    // Available space in percent, None without a quota
+    fn available_percentage(quota: &Quota) -> Option<f64> {
+        let available = quota.available? as f64;
+        let total = quota.used as f64 + available;
+        (total > 0.0).then(|| available / total * 100.0)
+    }
+}
+
+impl Drop for WebDavQuota {
+    fn drop(&mut self) {
+        self.stop.stop();
+    }
+}
+
+impl Widget for WebDavQuota {
+    fn update(&mut self) {
+        let text = match &*self.quota.lock().unwrap() {
+            Some(Ok(quota)) => {
+                self.color = match Self::available_percentage(quota) {
+                    Some(percentage) if percentage < QUOTA_THRESHOLD => Color::Critical,
+                    _ => Color::Neutral,
+                };
+                self.format_quota(quota)
+            }
+            Some(Err(_)) => {
+                self.color = Color::Critical;
+                format!("{}: ?", self.label)
+            }
+            // The first request is still running
+            None => format!("{}: ...", self.label),
+        };
+        self.full_text = Some(text);
+    }
+
+    fn display_text(&self) -> Result<Value, WidgetError> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn value(&self) -> Option<f32> {
+        match &*self.quota.lock().unwrap() {
+            Some(Ok(quota)) => {
+                Self::available_percentage(quota).map(|percentage| percentage as f32)
+            }
+            _ => None,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        matches!(*self.quota.lock().unwrap(), Some(Err(_)))
+    }
+}
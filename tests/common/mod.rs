@@ -0,0 +1,231 @@
+// Emulates i3bar: starts i3rustus, reads the status stream and sends click events and signals
+// Every line of the stream is checked against the protocol, so a stray print to stdout
+// fails the test that reads it
+// See https://i3wm.org/docs/i3bar-protocol.html
+
+// Every test binary uses only a part of the harness
+#![allow(dead_code)]
+
+use std::fs::{create_dir_all, remove_dir_all, write};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, ExitStatus, Output, Stdio};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+// Widgets are updated every second, so this leaves room for a slow CI machine
+const LINE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Directory with a config file, tests run in parallel so every test needs its own
+fn config_directory(name: &str, config: &str) -> PathBuf {
+    let directory =
+        std::env::temp_dir().join(format!("i3rustus-test-{}-{}", name, std::process::id()));
+    create_dir_all(directory.join("i3rustus")).unwrap();
+    write(directory.join("i3rustus").join("config.toml"), config).unwrap();
+    directory
+}
+
+// i3rustus with every file it writes (state, IPC socket) in the directory of the test and
+// without the session bus, so tests neither see nor change the session of the user
+fn command(directory: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_i3rustus"));
+    command
+        .env("XDG_CONFIG_HOME", directory)
+        .env("XDG_STATE_HOME", directory)
+        .env("XDG_RUNTIME_DIR", directory)
+        .env_remove("DBUS_SESSION_BUS_ADDRESS");
+    command
+}
+
+// Run i3rustus --check-config for a config file
+pub fn check_config(name: &str, config: &str) -> Output {
+    let directory = config_directory(name, config);
+    let output = command(&directory)
+        .arg("--check-config")
+        .output()
+        .expect("Could not start i3rustus");
+    let _ = remove_dir_all(&directory);
+    output
+}
+
+pub fn examples_directory() -> &'static Path {
+    Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/examples"))
+}
+
+pub struct I3bar {
+    child: Child,
+    stdin: ChildStdin,
+    // Lines of stdout, read by a thread so a hanging bar fails the test instead of blocking it
+    lines: Receiver<String>,
+    // Holds the config file of this bar
+    directory: PathBuf,
+    header: Option<Value>,
+    // Whether the opening bracket of the endless array was read
+    array_opened: bool,
+    statuses: usize,
+}
+
+impl I3bar {
+    // Start the bar with a config file
+    pub fn start(name: &str, config: &str) -> Self {
+        let directory = config_directory(name, config);
+
+        let mut child = command(&directory)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Could not start i3rustus");
+
+        let mut stdin = child.stdin.take().unwrap();
+        // Click events are an endless array as well
+        writeln!(stdin, "[").unwrap();
+
+        let stdout = child.stdout.take().unwrap();
+        let (sender, lines) = channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    return;
+                }
+            }
+        });
+
+        Self {
+            child,
+            stdin,
+            lines,
+            directory,
+            header: None,
+            array_opened: false,
+            statuses: 0,
+        }
+    }
+
+    fn next_line(&mut self) -> Option<String> {
+        match self.lines.recv_timeout(LINE_TIMEOUT) {
+            Ok(line) => Some(line),
+            Err(RecvTimeoutError::Timeout) => panic!("No output for {:?}", LINE_TIMEOUT),
+            Err(RecvTimeoutError::Disconnected) => None,
+        }
+    }
+
+    // The header is the first line, a JSON object with at least the protocol version
+    pub fn header(&mut self) -> Value {
+        if let Some(header) = &self.header {
+            return header.clone();
+        }
+        let line = self.next_line().expect("Bar exited before the header");
+        let header: Value = serde_json::from_str(&line)
+            .unwrap_or_else(|error| panic!("Invalid header {:?}: {}", line, error));
+        assert_eq!(header["version"], 1, "Unexpected header {}", header);
+        self.header = Some(header.clone());
+        header
+    }
+
+    // Read the next status line and check that it is a valid array of blocks
+    pub fn next_blocks(&mut self) -> Vec<Value> {
+        self.header();
+        if !self.array_opened {
+            let line = self.next_line().expect("Bar exited before the array");
+            assert_eq!(line, "[", "The status array has to start on its own line");
+            self.array_opened = true;
+        }
+
+        let line = self
+            .next_line()
+            .expect("Bar exited during the status stream");
+        // Every status but the first is separated from the previous one by a comma
+        let status = if self.statuses == 0 {
+            line.as_str()
+        } else {
+            line.strip_prefix(',')
+                .unwrap_or_else(|| panic!("Status line {:?} does not start with a comma", line))
+        };
+        let blocks: Vec<Value> = serde_json::from_str(status)
+            .unwrap_or_else(|error| panic!("Invalid status line {:?}: {}", line, error));
+        for block in &blocks {
+            check_block(block);
+        }
+        self.statuses += 1;
+
+        blocks
+    }
+
+    // Read status lines until a block matches, fails if it takes too long
+    pub fn wait_for_block<F>(&mut self, matches: F) -> Value
+    where
+        F: Fn(&Value) -> bool,
+    {
+        let deadline = Instant::now() + LINE_TIMEOUT;
+        while Instant::now() < deadline {
+            if let Some(block) = self.next_blocks().into_iter().find(&matches) {
+                return block;
+            }
+        }
+        panic!("No matching block within {:?}", LINE_TIMEOUT);
+    }
+
+    pub fn click(&mut self, name: &str, instance: Option<&str>, button: u8) {
+        let event = json!({ "name": name, "instance": instance, "button": button });
+        writeln!(self.stdin, ",{}", event).unwrap();
+    }
+
+    // Send a command over the IPC socket and return the answer, see src/ipc.rs
+    pub fn ipc(&mut self, command: Value) -> Value {
+        // The socket is bound once the widgets are running
+        self.next_blocks();
+        let mut stream = UnixStream::connect(self.directory.join("i3rustus.sock"))
+            .expect("Could not connect to the IPC socket");
+        writeln!(stream, "{}", command).unwrap();
+        let mut answer = String::new();
+        BufReader::new(stream).read_line(&mut answer).unwrap();
+        serde_json::from_str(&answer)
+            .unwrap_or_else(|error| panic!("Invalid answer {:?}: {}", answer, error))
+    }
+
+    pub fn signal(&self, signal: i32) {
+        // SAFETY: kill only sends a signal to the child we started
+        let result = unsafe { libc::kill(self.child.id() as i32, signal) };
+        assert_eq!(result, 0, "Could not send signal {}", signal);
+    }
+
+    // Wait until the bar exits, returns the rest of its output and its exit status
+    pub fn finish(mut self) -> (Vec<String>, ExitStatus) {
+        let mut rest = Vec::new();
+        while let Some(line) = self.next_line() {
+            rest.push(line);
+        }
+        (rest, self.child.wait().unwrap())
+    }
+}
+
+// Every block needs a name and a text, colors have to be hex values
+fn check_block(block: &Value) {
+    assert!(block["name"].is_string(), "Block without name: {}", block);
+    assert!(
+        block["full_text"].is_string(),
+        "Block without text: {}",
+        block
+    );
+    if let Some(color) = block.get("color") {
+        let color = color.as_str().unwrap_or_default();
+        assert!(
+            color.len() == 7 && color.starts_with('#'),
+            "Invalid color in {}",
+            block
+        );
+    }
+}
+
+impl Drop for I3bar {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = remove_dir_all(&self.directory);
+    }
+}
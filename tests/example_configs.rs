@@ -0,0 +1,193 @@
+mod common;
+
+use std::fs::{read_dir, read_to_string};
+
+use common::{check_config, examples_directory};
+
+#[test]
+fn examples_are_valid() {
+    let mut checked = 0;
+    for entry in read_dir(examples_directory()).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|extension| extension != "toml") {
+            continue;
+        }
+        let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+        let output = check_config(&name, &read_to_string(&path).unwrap());
+        assert!(
+            output.status.success(),
+            "{} is invalid:\n{}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        checked += 1;
+    }
+    assert!(
+        checked > 0,
+        "No examples in {}",
+        examples_directory().display()
+    );
+}
+
+fn check_errors(name: &str, config: &str) -> String {
+    let output = check_config(name, config);
+    assert!(!output.status.success(), "{} was accepted", config);
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn misspelled_key_is_rejected_with_suggestion() {
+    let errors = check_errors(
+        "key",
+        "[widgets.\"script:x\"]\ncommand = \"true\"\nintervall = 5\n",
+    );
+    assert!(errors.contains("unknown field `intervall`"), "{}", errors);
+    assert!(errors.contains("did you mean `interval`?"), "{}", errors);
+}
+
+#[test]
+fn misspelled_widget_is_rejected_with_suggestion() {
+    let errors = check_errors("widget", "order = [\"memroy\"]\n");
+    assert!(errors.contains("did you mean `memory`?"), "{}", errors);
+}
+
+#[test]
+fn misspelled_theme_is_rejected_with_suggestion() {
+    let errors = check_errors("theme", "theme = \"grvubox\"\n");
+    assert!(errors.contains("did you mean `gruvbox`?"), "{}", errors);
+}
+
+#[test]
+fn unrelated_key_gets_no_suggestion() {
+    let errors = check_errors("unrelated", "[widgets.time]\ncolour_scheme = 1\n");
+    assert!(
+        errors.contains("unknown field `colour_scheme`"),
+        "{}",
+        errors
+    );
+    assert!(!errors.contains("did you mean"), "{}", errors);
+}
+
+#[test]
+fn invalid_format_of_time_instance_is_rejected() {
+    let errors = check_errors(
+        "time_instance",
+        "order = [\"time\"]\n\n[widgets.\"time:utc\"]\nformat = \"%H:%M %!\"\n",
+    );
+    assert!(errors.contains("time:utc"), "{}", errors);
+    assert!(errors.contains("Invalid strftime specifier"), "{}", errors);
+}
+
+#[test]
+fn invalid_block_color_is_rejected() {
+    let errors = check_errors(
+        "block_color",
+        "[widgets.\"disk:home\"]\nbackground = \"red\"\n",
+    );
+    assert!(errors.contains("Invalid color red"), "{}", errors);
+}
+
+#[test]
+fn invalid_oid_is_rejected() {
+    let errors = check_errors(
+        "oid",
+        "order = [\"snmp:router\"]\n\n[widgets.\"snmp:router\"]\naddress = \"localhost:161\"\noids = [{ label = \"x\", oid = \"1.3.six\" }]\n",
+    );
+    assert!(errors.contains("Invalid OID"), "{}", errors);
+}
+
+#[test]
+fn misspelled_recorded_widget_is_rejected_with_suggestion() {
+    let errors = check_errors("recorded", "recorded_widgets = [\"disc:home\"]\n");
+    assert!(errors.contains("recorded_widgets"), "{}", errors);
+    assert!(errors.contains("did you mean `disk`?"), "{}", errors);
+}
+
+#[test]
+fn missing_plugin_is_rejected() {
+    let errors = check_errors("plugin", "order = [\"plugin:weather\"]\n");
+    assert!(errors.contains("Could not read plugin"), "{}", errors);
+}
+
+#[test]
+fn invalid_day_names_of_time_instance_are_rejected() {
+    let errors = check_errors(
+        "day-names",
+        "[widgets.\"time:utc\"]\nday_names = [\"Mo\", \"Di\"]\n",
+    );
+    assert!(
+        errors.contains("time:utc: day_names needs 7 names"),
+        "{}",
+        errors
+    );
+}
+
+#[test]
+fn invalid_alarm_is_rejected() {
+    let errors = check_errors(
+        "alarm",
+        "[widgets.alarm]\nalarms = [{ label = \"Wake up\", time = \"7 am\" }]\n",
+    );
+    assert!(errors.contains("[widgets.\"alarm\"]"), "{}", errors);
+    assert!(errors.contains("alarms need times like"), "{}", errors);
+    let errors = check_errors(
+        "alarm-weekday",
+        "[widgets.alarm]\nalarms = [{ label = \"Gym\", time = \"18:00\", weekdays = [\"tues\"] }]\n",
+    );
+    assert!(errors.contains("Unknown weekday tues"), "{}", errors);
+}
+
+#[test]
+fn derived_widget_without_expression_is_rejected() {
+    let errors = check_errors(
+        "derived",
+        "order = [\"derived:busy\"]\n\n[widgets.\"derived:busy\"]\nlabel = \"busy\"\n",
+    );
+    assert!(
+        errors.contains("derived:busy needs an expression"),
+        "{}",
+        errors
+    );
+}
+
+#[test]
+fn alert_sink_without_url_is_rejected() {
+    let errors = check_errors(
+        "alert-sink",
+        "alert_sinks = [{ webhook = \"example.com/alerts\" }]\n",
+    );
+    assert!(
+        errors.contains("alert_sinks: Invalid URL example.com/alerts"),
+        "{}",
+        errors
+    );
+}
+
+#[test]
+fn sound_alert_of_misspelled_widget_is_rejected_with_suggestion() {
+    let errors = check_errors(
+        "sound-alert",
+        "sound_alerts = [{ widget = \"batery:BAT1\", threshold = { above = 99 }, file = \"full.wav\" }]\n",
+    );
+    assert!(errors.contains("sound_alerts"), "{}", errors);
+    assert!(errors.contains("did you mean `battery`?"), "{}", errors);
+}
+
+#[test]
+fn variable_color_of_misspelled_widget_is_rejected_with_suggestion() {
+    let errors = check_errors(
+        "variable-color",
+        "variable_colors = [{ variable = \"on_call\", value = \"true\", widgets = [\"tme\"], color = \"warning\" }]\n",
+    );
+    assert!(errors.contains("variable_colors"), "{}", errors);
+    assert!(errors.contains("did you mean `time`?"), "{}", errors);
+}
+
+#[test]
+fn zero_output_scale_is_rejected() {
+    let errors = check_errors(
+        "display-scale",
+        "[widgets.display_scale]\nscales = { \"eDP-1\" = 0 }\n",
+    );
+    assert!(errors.contains("Invalid scale 0"), "{}", errors);
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use common::I3bar;
+use serde_json::json;
+
+// A script that shows which button clicked it last
+const CONFIG: &str = r#"
+order = ["time", "script:probe"]
+
+[widgets."script:probe"]
+command = "echo \"button ${BLOCK_BUTTON:-none}\""
+interval = 0
+"#;
+
+fn is_probe(block: &serde_json::Value) -> bool {
+    block["name"] == "script" && block["instance"] == "probe"
+}
+
+#[test]
+fn header_announces_click_events_and_signals() {
+    let mut bar = I3bar::start("header", CONFIG);
+    let header = bar.header();
+
+    assert_eq!(header["click_events"], true);
+    assert!(header["stop_signal"].is_i64());
+    assert!(header["cont_signal"].is_i64());
+}
+
+#[test]
+fn status_stream_is_valid() {
+    let mut bar = I3bar::start("stream", CONFIG);
+
+    // The first lines are validated by next_blocks, later ones should show every widget
+    for _ in 0..3 {
+        bar.next_blocks();
+    }
+    let blocks = bar.next_blocks();
+    assert!(blocks.iter().any(|block| block["name"] == "time"));
+    assert!(blocks.iter().any(is_probe));
+}
+
+#[test]
+fn clicks_reach_the_widget() {
+    let mut bar = I3bar::start("click", CONFIG);
+    bar.wait_for_block(|block| is_probe(block) && block["full_text"] == "button none");
+
+    bar.click("script", Some("probe"), 3);
+
+    bar.wait_for_block(|block| is_probe(block) && block["full_text"] == "button 3");
+}
+
+#[test]
+fn click_action_switches_the_color_of_other_widgets() {
+    let config = format!(
+        "{}\n{}",
+        r#"
+click_actions = [{ widget = "script", button = 2, action = { toggle = "on_call" } }]
+variable_colors = [{ variable = "on_call", value = "true", widgets = ["time"], color = "critical" }]
+"#,
+        CONFIG
+    );
+    let mut bar = I3bar::start("on-call", &config);
+    bar.wait_for_block(|block| block["name"] == "time");
+
+    bar.click("script", Some("probe"), 2);
+
+    bar.wait_for_block(|block| block["name"] == "time" && block["color"] == "#FF0000");
+}
+
+#[test]
+fn set_order_starts_widgets_that_are_not_shown() {
+    let mut bar = I3bar::start("set-order", CONFIG);
+
+    let answer = bar.ipc(json!({ "command": "set-order", "widgets": ["memory", "time"] }));
+
+    assert_eq!(answer["ok"], true, "Unexpected answer {}", answer);
+    bar.wait_for_block(|block| block["name"] == "memory");
+}
+
+#[test]
+fn set_order_names_every_rejected_widget() {
+    let mut bar = I3bar::start("set-order-rejected", CONFIG);
+
+    let answer = bar.ipc(json!({
+        "command": "set-order",
+        "widgets": ["time", "memroy", "script:missing"]
+    }));
+
+    assert_eq!(answer["ok"], false);
+    let error = answer["error"].as_str().unwrap();
+    assert!(error.contains("memroy"), "Unexpected error {}", error);
+    assert!(
+        error.contains("script:missing"),
+        "Unexpected error {}",
+        error
+    );
+}
+
+#[test]
+fn output_continues_after_stop_and_cont_signals() {
+    let mut bar = I3bar::start("signals", CONFIG);
+    let header = bar.header();
+    bar.next_blocks();
+
+    bar.signal(header["stop_signal"].as_i64().unwrap() as i32);
+    bar.signal(header["cont_signal"].as_i64().unwrap() as i32);
+
+    bar.next_blocks();
+}
+
+#[test]
+fn termination_closes_the_status_array() {
+    let mut bar = I3bar::start("terminate", CONFIG);
+    bar.next_blocks();
+
+    bar.signal(libc::SIGTERM);
+
+    let (rest, status) = bar.finish();
+    assert!(status.success(), "Exited with {}", status);
+    // Status lines that were written before the signal was handled can still follow
+    assert_eq!(
+        rest.last().map(String::as_str),
+        Some("]"),
+        "Output ended with {:?}",
+        rest
+    );
+}
+
+#[test]
+fn bar_does_not_start_with_a_status_that_breaks_the_protocol() {
+    // A tab in the full text is a control character, which i3bar can't show
+    // The time is rendered with the first status, the output of a script may come later
+    let config = r#"
+order = ["time"]
+
+[widgets.time]
+format = "a\tb %H"
+"#;
+    let bar = I3bar::start("self-test", config);
+
+    let (lines, status) = bar.finish();
+    assert_eq!(status.code(), Some(1), "Exited with {}", status);
+    assert!(
+        lines.iter().all(|line| !line.contains("full_text")),
+        "Status written before the check: {:?}",
+        lines
+    );
+}